@@ -1,9 +1,11 @@
 use anyhow::bail;
 use anyhow::Result;
 use elerp_common::sql;
-use elerp_common::sql::get_row_from_table;
-use elerp_common::sql::is_exists_in_table;
-use elerp_common::sql::remove_row_from_table;
+use elerp_common::sql::get_active_row_from_table;
+use elerp_common::sql::is_active_in_table;
+use elerp_common::sql::purge_rows_from_table;
+use elerp_common::sql::restore_row_from_table;
+use elerp_common::sql::soft_remove_row_from_table;
 use elerp_common::sql::row_is_duplicate_col_in_table;
 use elerp_common::sql::rows_to_objects;
 use elerp_common::{
@@ -29,7 +31,9 @@ impl AreaModule {
                 name TEXT NOT NULL,
                 description TEXT NOT NULL,
                 color TEXT NULL,
-                text_color TEXT NULL
+                text_color TEXT NULL,
+                price_list_id INT NOT NULL DEFAULT 0,
+                deleted_at INT NULL
             )",
         )
         .execute(conn)
@@ -47,7 +51,7 @@ impl AreaModule {
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM areas;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM areas WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.areas)
     }
 
@@ -56,15 +60,16 @@ impl AreaModule {
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("areas", "id", id, tx).await
+        is_active_in_table("areas", id, tx).await
     }
 
     pub async fn add(&self, mut v: Area, tx: &mut SqliteConnection) -> Result<Area> {
-        let r = sqlx::query("INSERT INTO areas (name, description, color, text_color) VALUES(?, ?, ?, ?)")
+        let r = sqlx::query("INSERT INTO areas (name, description, color, text_color, price_list_id) VALUES(?, ?, ?, ?, ?)")
             .bind(&v.name)
             .bind(&v.description)
             .bind(&v.color)
             .bind(&v.text_color)
+            .bind(v.price_list_id)
             .execute(&mut *tx)
             .await?;
         if r.rows_affected() != 1 {
@@ -76,15 +81,28 @@ impl AreaModule {
     }
 
     pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = remove_row_from_table(id, "areas", tx).await?;
+        let r = soft_remove_row_from_table(id, "areas", self.ps.get_timestamp_seconds() as i64, tx).await?;
         if notice {
             self.ps.notice(WebSocketFlags::RemoveArea(id)).await?;
         }
         Ok(r)
     }
 
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Area>> {
+        if !restore_row_from_table(id, "areas", tx).await? {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::RestoreArea(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Physically delete the areas removed before the date, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        purge_rows_from_table("areas", deleted_before, tx).await
+    }
+
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Area>> {
-        get_row_from_table("areas", "id", id, tx).await
+        get_active_row_from_table("areas", id, tx).await
     }
 
     pub async fn get_multiple(&self, pagination: &Pagination, query: &GetAreasQuery, tx: &mut SqliteConnection) -> Result<Vec<Area>> {
@@ -97,7 +115,8 @@ impl AreaModule {
         areas.name,
         areas.description,
         areas.color,
-        areas.text_color
+        areas.text_color,
+        areas.price_list_id
         FROM areas
         {qw} {ob} LIMIT ? OFFSET ?"
         ))
@@ -130,11 +149,12 @@ impl AreaModule {
     }
 
     pub async fn update(&self, id: i64, mut v: Area, tx: &mut SqliteConnection) -> Result<Option<Area>> {
-        let r = sqlx::query("UPDATE areas SET name=?, description=?, color=?, text_color=? WHERE id=?")
+        let r = sqlx::query("UPDATE areas SET name=?, description=?, color=?, text_color=?, price_list_id=? WHERE id=? AND deleted_at IS NULL")
             .bind(&v.name)
             .bind(&v.description)
             .bind(&v.color)
             .bind(&v.text_color)
+            .bind(v.price_list_id)
             .bind(id)
             .execute(&mut *tx)
             .await?;
@@ -148,7 +168,7 @@ impl AreaModule {
     }
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT id FROM persons WHERE area_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some()
-            || sqlx::query("SELECT id FROM warehouses WHERE area_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+        Ok(sqlx::query("SELECT id FROM persons WHERE area_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM warehouses WHERE area_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some())
     }
 }
@@ -0,0 +1,112 @@
+use anyhow::{bail, Result};
+use elerp_common::{
+    audit_log_module::model::audit_log::{AuditAction, AuditEntity, AuditLog, GetAuditLogsQuery},
+    model::Pagination,
+    sql::rows_to_objects,
+};
+use public_system::PublicSystem;
+use serde::Serialize;
+use sqlx::{Row, SqliteConnection};
+
+#[derive(Debug, Clone)]
+pub struct AuditLogModule {
+    ps: PublicSystem,
+}
+
+impl AuditLogModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.get_conn().begin().await.unwrap();
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS audit_log(
+                id INTEGER PRIMARY KEY,
+                user_id INT NOT NULL,
+                date INT NOT NULL,
+                entity TEXT NOT NULL,
+                entity_id INT NOT NULL,
+                action TEXT NOT NULL,
+                before TEXT NULL,
+                after TEXT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS audit_log_entities
+    ON audit_log(entity, entity_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS audit_log_users
+    ON audit_log(user_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        tx.commit().await.unwrap();
+        Self { ps }
+    }
+
+    /// Record a mutation, must be called with the transaction of the mutation.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add<T: Serialize>(
+        &self,
+        user_id: i64,
+        entity: AuditEntity,
+        entity_id: i64,
+        action: AuditAction,
+        before: Option<&T>,
+        after: Option<&T>,
+        tx: &mut SqliteConnection,
+    ) -> Result<()> {
+        let before = before.map(serde_json::to_string).transpose()?;
+        let after = after.map(serde_json::to_string).transpose()?;
+        let r = sqlx::query("INSERT INTO audit_log (user_id, date, entity, entity_id, action, before, after) VALUES(?, ?, ?, ?, ?, ?, ?)")
+            .bind(user_id)
+            .bind(self.ps.get_timestamp_seconds() as i64)
+            .bind(entity)
+            .bind(entity_id)
+            .bind(action)
+            .bind(before)
+            .bind(after)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't add audit log");
+        }
+        Ok(())
+    }
+
+    pub async fn get_multiple(&self, pagination: &Pagination, query: &GetAuditLogsQuery, tx: &mut SqliteConnection) -> Result<Vec<AuditLog>> {
+        let qw = query.get_where_condition();
+        let ob = query.get_order_condition();
+        let rows = sqlx::query(&format!(
+            "SELECT
+            audit_log.id,
+            audit_log.user_id,
+            audit_log.date,
+            audit_log.entity,
+            audit_log.entity_id,
+            audit_log.action,
+            audit_log.before,
+            audit_log.after
+            FROM audit_log
+            {qw} {ob} LIMIT ? OFFSET ?"
+        ))
+        .bind(pagination.limit())
+        .bind(pagination.offset())
+        .fetch_all(&mut *tx)
+        .await?;
+        rows_to_objects(rows)
+    }
+
+    pub async fn get_count(&self, query: &GetAuditLogsQuery, tx: &mut SqliteConnection) -> Result<i64> {
+        let qw = query.get_where_condition();
+        let row = sqlx::query(&format!("SELECT count(*) as count FROM audit_log {qw}")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+}
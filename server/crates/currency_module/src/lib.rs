@@ -0,0 +1,228 @@
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use elerp_common::{
+    currency_module::{
+        get_rate_sql,
+        model::currency::{Currency, ExchangeRate, GetExchangeRatesQuery},
+    },
+    model::{Pagination, WebSocketFlags},
+    order_module::model::order::OrderCurrency,
+    sql::rows_to_objects,
+};
+use futures::TryStreamExt;
+use public_system::PublicSystem;
+use sqlx::{Row, SqliteConnection};
+
+#[derive(Debug, Clone)]
+pub struct CurrencyModule {
+    ps: PublicSystem,
+}
+
+/// The currencies available before they became data.
+const DEFAULT_CURRENCIES: [(&str, &str, &str); 8] = [
+    ("CNY", "Chinese Yuan", "¥"),
+    ("HKD", "Hong Kong Dollar", "HK$"),
+    ("USD", "US Dollar", "$"),
+    ("GBP", "Pound Sterling", "£"),
+    ("MYR", "Malaysian Ringgit", "RM"),
+    ("IDR", "Indonesian Rupiah", "Rp"),
+    ("INR", "Indian Rupee", "₹"),
+    ("PHP", "Philippine Peso", "₱"),
+];
+
+impl CurrencyModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.begin_tx(true).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS currencies(
+                code TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                symbol TEXT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS exchange_rates(
+                id INTEGER PRIMARY KEY,
+                currency TEXT NOT NULL,
+                date INT NOT NULL,
+                rate REAL NOT NULL,
+                UNIQUE(currency, date)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        let count: i64 = sqlx::query("SELECT COUNT(*) AS count FROM currencies").fetch_one(tx.as_mut()).await.unwrap().get("count");
+        if count == 0 {
+            for (code, name, symbol) in DEFAULT_CURRENCIES {
+                sqlx::query("INSERT INTO currencies (code, name, symbol) VALUES(?, ?, ?)")
+                    .bind(code)
+                    .bind(name)
+                    .bind(symbol)
+                    .execute(tx.as_mut())
+                    .await
+                    .unwrap();
+            }
+        }
+
+        tx.commit().await.unwrap();
+        Self { ps }
+    }
+
+    /// The unknown currency is always valid for the orders without currency.
+    pub async fn is_exists(&self, code: OrderCurrency, tx: &mut SqliteConnection) -> Result<bool> {
+        if code == OrderCurrency::Unknown {
+            return Ok(true);
+        }
+        Ok(sqlx::query("SELECT code FROM currencies WHERE code=?").bind(code).fetch(&mut *tx).try_next().await?.is_some())
+    }
+
+    pub async fn get(&self, code: OrderCurrency, tx: &mut SqliteConnection) -> Result<Option<Currency>> {
+        Ok(sqlx::query_as("SELECT code, name, symbol FROM currencies WHERE code=?").bind(code).fetch_optional(&mut *tx).await?)
+    }
+
+    pub async fn list(&self, tx: &mut SqliteConnection) -> Result<Vec<Currency>> {
+        let rows = sqlx::query("SELECT code, name, symbol FROM currencies ORDER BY code ASC").fetch_all(&mut *tx).await?;
+        rows_to_objects(rows)
+    }
+
+    /// Add the currency or update it if the code exists.
+    pub async fn set(&self, v: Currency, tx: &mut SqliteConnection) -> Result<Currency> {
+        if v.code == OrderCurrency::Unknown {
+            bail!("The unknown currency can't be set");
+        }
+        sqlx::query("INSERT INTO currencies (code, name, symbol) VALUES(?, ?, ?) ON CONFLICT(code) DO UPDATE SET name=excluded.name, symbol=excluded.symbol")
+            .bind(v.code)
+            .bind(&v.name)
+            .bind(&v.symbol)
+            .execute(&mut *tx)
+            .await?;
+        self.ps.notice(WebSocketFlags::UpdateCurrencies).await?;
+        Ok(v)
+    }
+
+    /// Remove the currency and its exchange rates.
+    pub async fn remove(&self, code: OrderCurrency, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM currencies WHERE code=?").bind(code).execute(&mut *tx).await?;
+        if r.rows_affected() != 1 {
+            return Ok(false);
+        }
+        sqlx::query("DELETE FROM exchange_rates WHERE currency=?").bind(code).execute(&mut *tx).await?;
+        self.ps.notice(WebSocketFlags::UpdateCurrencies).await?;
+        Ok(true)
+    }
+
+    pub async fn is_depend_by_another(&self, code: OrderCurrency, tx: &mut SqliteConnection) -> Result<bool> {
+        for table in ["orders", "guest_orders", "purchase_orders", "quotations"] {
+            if sqlx::query(&format!("SELECT id FROM {table} WHERE currency=?")).bind(code).fetch(&mut *tx).try_next().await?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(sqlx::query("SELECT user_id FROM configures WHERE d_order_currency=?").bind(code).fetch(&mut *tx).try_next().await?.is_some())
+    }
+
+    pub async fn get_rate_by_id(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<ExchangeRate>> {
+        Ok(sqlx::query_as("SELECT id, currency, date, rate FROM exchange_rates WHERE id=?").bind(id).fetch_optional(&mut *tx).await?)
+    }
+
+    /// Add the rate or update it if the currency has a rate at the date.
+    pub async fn set_rate(&self, mut v: ExchangeRate, notice: bool, tx: &mut SqliteConnection) -> Result<ExchangeRate> {
+        if !v.rate.is_finite() || v.rate <= 0.0 {
+            bail!("The rate must be positive");
+        }
+        v.id = sqlx::query("INSERT INTO exchange_rates (currency, date, rate) VALUES(?, ?, ?) ON CONFLICT(currency, date) DO UPDATE SET rate=excluded.rate RETURNING id")
+            .bind(v.currency)
+            .bind(v.date)
+            .bind(v.rate)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("id");
+        if notice {
+            self.ps.notice(WebSocketFlags::AddExchangeRate(v.id)).await?;
+        }
+        Ok(v)
+    }
+
+    pub async fn remove_rate(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM exchange_rates WHERE id=?").bind(id).execute(&mut *tx).await?;
+        if r.rows_affected() == 1 {
+            self.ps.notice(WebSocketFlags::RemoveExchangeRate(id)).await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn get_rates(&self, pagination: &Pagination, query: &GetExchangeRatesQuery, tx: &mut SqliteConnection) -> Result<Vec<ExchangeRate>> {
+        let qw = query.get_where_condition();
+        let rows = sqlx::query(&format!("SELECT id, currency, date, rate FROM exchange_rates {qw} ORDER BY date DESC, currency ASC LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?;
+        rows_to_objects(rows)
+    }
+
+    pub async fn get_rates_count(&self, query: &GetExchangeRatesQuery, tx: &mut SqliteConnection) -> Result<i64> {
+        let qw = query.get_where_condition();
+        let row = sqlx::query(&format!("SELECT COUNT(*) AS count FROM exchange_rates {qw}")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
+    /// Rate converting the currency to the base currency at the date, none if the currency has no rate.
+    pub async fn get_rate(&self, currency: OrderCurrency, date: i64, tx: &mut SqliteConnection) -> Result<Option<f64>> {
+        let rate = get_rate_sql(self.ps.get_config().currency.base, "t.currency", "t.date");
+        let row = sqlx::query(&format!("SELECT {rate} AS rate FROM (SELECT ? AS currency, ? AS date) AS t"))
+            .bind(currency)
+            .bind(date)
+            .fetch_one(&mut *tx)
+            .await?;
+        Ok(row.get("rate"))
+    }
+
+    /// Parse the rows of `currency,date,rate`, the date is a timestamp in seconds or `YYYY-MM-DD`.
+    /// The header row and the empty rows are skipped.
+    pub fn parse_rates_csv(content: &str) -> Result<Vec<ExchangeRate>> {
+        let mut arr = vec![];
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line.to_ascii_lowercase().starts_with("currency")) {
+                continue;
+            }
+            let n = i + 1;
+            let cols: Vec<&str> = line.split(',').map(|col| col.trim().trim_matches('"')).collect();
+            if cols.len() != 3 {
+                bail!("Line {n}: expected currency,date,rate");
+            }
+            let Some(currency) = OrderCurrency::new(cols[0]).filter(|v| *v != OrderCurrency::Unknown) else {
+                bail!("Line {n}: invalid currency {}", cols[0]);
+            };
+            let date = match cols[1].parse::<i64>() {
+                Ok(date) => date,
+                Err(_) => match NaiveDate::parse_from_str(cols[1], "%Y-%m-%d") {
+                    Ok(date) => date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp(),
+                    Err(_) => bail!("Line {n}: invalid date {}", cols[1]),
+                },
+            };
+            let rate = match cols[2].parse::<f64>() {
+                Ok(rate) if rate.is_finite() && rate > 0.0 => rate,
+                _ => bail!("Line {n}: invalid rate {}", cols[2]),
+            };
+            arr.push(ExchangeRate { id: 0, currency, date, rate });
+        }
+        Ok(arr)
+    }
+
+    pub async fn import_rates(&self, rates: Vec<ExchangeRate>, tx: &mut SqliteConnection) -> Result<Vec<ExchangeRate>> {
+        let mut arr = Vec::with_capacity(rates.len());
+        for v in rates {
+            arr.push(self.set_rate(v, false, tx).await?);
+        }
+        self.ps.notice(WebSocketFlags::ImportExchangeRates).await?;
+        Ok(arr)
+    }
+}
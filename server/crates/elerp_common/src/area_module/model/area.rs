@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::sql::{get_search_where_condition, get_sort_col_str, get_sorter_str};
+use crate::sql::{get_deleted_condition, get_search_where_condition, get_sort_col_str, get_sorter_str};
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Area {
@@ -15,12 +15,18 @@ pub struct Area {
     pub description: String,
     pub color: Option<String>,
     pub text_color: Option<String>,
+    /// Price list of the persons in the area, 0 means the default price list.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub price_list_id: i64,
 }
 
 #[derive(Debug, Default, Deserialize, ToSchema, IntoParams)]
 pub struct GetAreasQuery {
     pub id: Option<i64>,
     pub name: Option<String>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -34,9 +40,10 @@ impl GetAreasQuery {
             conditions.push(get_search_where_condition("areas.name", v));
         }
 
+        conditions.push(get_deleted_condition("areas", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -58,7 +65,7 @@ impl GetAreasQuery {
         }
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
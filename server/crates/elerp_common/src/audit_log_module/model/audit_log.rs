@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use strum::AsRefStr;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::sql::{get_sort_col_str, get_sorter_str};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum AuditEntity {
+    Area,
+    Person,
+    Warehouse,
+    WarehouseLink,
+    SKUCategory,
+    SKU,
+    ProductTemplate,
+    ReorderPoint,
+    Reservation,
+    Order,
+    GuestOrder,
+    PurchaseOrder,
+    Quotation,
+    OrderCategory,
+    OrderPayment,
+    Currency,
+    ExchangeRate,
+    TaxRate,
+    PriceList,
+    PriceListItem,
+    User,
+    UserConfigure,
+    Role,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum AuditAction {
+    Add,
+    Update,
+    Remove,
+    Confirm,
+    Link,
+    Unlink,
+    Restore,
+    Purge,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, FromRow)]
+pub struct AuditLog {
+    pub id: i64,
+    /// The user who made the change.
+    pub user_id: i64,
+    pub date: i64,
+    pub entity: AuditEntity,
+    pub entity_id: i64,
+    pub action: AuditAction,
+    /// JSON of the object before the change, null when added.
+    pub before: Option<String>,
+    /// JSON of the object after the change, null when removed.
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetAuditLogsQuery {
+    pub user_id: Option<i64>,
+    pub entity: Option<AuditEntity>,
+    pub entity_id: Option<i64>,
+    pub action: Option<AuditAction>,
+    pub date_start: Option<i64>,
+    pub date_end: Option<i64>,
+    pub sorters: Option<Vec<String>>,
+}
+
+impl GetAuditLogsQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(5);
+        if let Some(v) = &self.user_id {
+            conditions.push(format!("audit_log.user_id={v}"));
+        }
+        if let Some(v) = &self.entity {
+            conditions.push(format!("audit_log.entity='{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.entity_id {
+            conditions.push(format!("audit_log.entity_id={v}"));
+        }
+        if let Some(v) = &self.action {
+            conditions.push(format!("audit_log.action='{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.date_start {
+            conditions.push(format!("audit_log.date>={v}"));
+        }
+        if let Some(v) = &self.date_end {
+            conditions.push(format!("audit_log.date<={v}"));
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        } else {
+            "".into()
+        }
+    }
+
+    pub fn get_order_condition(&self) -> String {
+        if self.sorters.is_none() {
+            return "ORDER BY audit_log.id DESC".into();
+        }
+        let mut conditions = vec![];
+        for sorter in self.sorters.as_ref().unwrap() {
+            let col = get_sort_col_str(sorter);
+            let sort = get_sorter_str(sorter);
+            conditions.push(format!("audit_log.{col} {sort}"));
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(", ");
+            format!("ORDER BY {c}")
+        } else {
+            "ORDER BY audit_log.id DESC".into()
+        }
+    }
+}
@@ -5,7 +5,7 @@ use serde::Deserialize;
 use tokio::fs;
 use tracing::warn;
 
-use crate::meta::MetaInfo;
+use crate::{meta::MetaInfo, order_module::model::order::OrderCurrency};
 
 #[derive(Debug, Deserialize)]
 struct AppConfigInternal {
@@ -19,6 +19,14 @@ struct AppConfigInternal {
     pub tls: TLS,
     #[serde(default)]
     pub ws: WS,
+    #[serde(default)]
+    pub token: Token,
+    #[serde(default)]
+    pub valuation: Valuation,
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default)]
+    pub document: Document,
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +38,10 @@ pub struct AppConfig {
     pub limit: Limit,
     pub tls: TLS,
     pub ws: WS,
+    pub token: Token,
+    pub valuation: Valuation,
+    pub currency: Currency,
+    pub document: Document,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -104,6 +116,113 @@ pub struct WS {
     pub ping: Option<usize>,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct Token {
+    /// Seconds the login token is active.
+    #[serde(default = "default_token_ttl")]
+    pub ttl: i64,
+    /// Seconds the refresh token is active. Refresh will get new token and refresh token.
+    #[serde(default = "default_refresh_token_ttl")]
+    pub refresh_ttl: i64,
+}
+
+fn default_token_ttl() -> i64 {
+    86400 // 1 day
+}
+
+fn default_refresh_token_ttl() -> i64 {
+    2592000 // 30 days
+}
+
+impl Default for Token {
+    fn default() -> Self {
+        Self {
+            ttl: default_token_ttl(),
+            refresh_ttl: default_refresh_token_ttl(),
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValuationMethod {
+    /// Every stock in is merged into the average unit cost of the sku.
+    #[default]
+    MovingAverage,
+    /// Stock out consumes the oldest cost layers first.
+    FIFO,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Valuation {
+    /// How the cost of the stock is calculated, change it need a recalculation of the orders.
+    #[serde(default)]
+    pub method: ValuationMethod,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Currency {
+    /// The totals of the statistics and exports are converted to it by the exchange rates.
+    #[serde(default = "default_base_currency")]
+    pub base: OrderCurrency,
+}
+
+fn default_base_currency() -> OrderCurrency {
+    OrderCurrency::USD
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self { base: default_base_currency() }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Company {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub tax_id: String,
+}
+
+/// Template of the order document, the options not set use the default of the document kind.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct DocumentTemplate {
+    pub title: Option<String>,
+    /// Show the prices, the discounts, the tax and the totals.
+    pub show_prices: Option<bool>,
+    pub show_payment_status: Option<bool>,
+    /// Show the lot numbers and the serial numbers of the items.
+    pub show_lots: Option<bool>,
+    /// Text printed after the items, like the payment terms.
+    #[serde(default)]
+    pub footer: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Document {
+    /// Header of the documents.
+    #[serde(default)]
+    pub company: Company,
+    #[serde(default)]
+    pub invoice: DocumentTemplate,
+    #[serde(default)]
+    pub delivery_note: DocumentTemplate,
+    #[serde(default)]
+    pub packing_slip: DocumentTemplate,
+    /// TrueType or OpenType font embedded in the documents, like Noto Sans CJK for the chinese, japanese and korean texts.
+    /// The builtin Helvetica is used if not set, it only renders the latin texts.
+    pub font: Option<PathBuf>,
+    /// Font of the headers, the font above is used if not set.
+    pub bold_font: Option<PathBuf>,
+}
+
 impl AppConfigInternal {
     pub fn new(meta: MetaInfo) -> Self {
         let web = Web::default();
@@ -121,6 +240,10 @@ impl AppConfigInternal {
                 key: meta.tls_key,
             },
             ws: WS { ping: meta.ping },
+            token: Token::default(),
+            valuation: Valuation::default(),
+            currency: Currency::default(),
+            document: Document::default(),
         }
     }
     pub fn overwrite(&mut self, meta: MetaInfo) {
@@ -160,6 +283,10 @@ impl AppConfig {
             limit: value.limit,
             tls: value.tls,
             ws: value.ws,
+            token: value.token,
+            valuation: value.valuation,
+            currency: value.currency,
+            document: value.document,
             data_path,
         }
     }
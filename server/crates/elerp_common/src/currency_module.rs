@@ -0,0 +1,15 @@
+use crate::order_module::model::order::OrderCurrency;
+
+pub mod model;
+
+/// SQL expression of the rate converting the amount of the row to the base currency at the date of the row.
+/// The latest rate on or before the date is used, otherwise the earliest rate after it, NULL if the currency has no rate.
+pub fn get_rate_sql(base: OrderCurrency, currency_col: &str, date_col: &str) -> String {
+    let base = base.as_ref();
+    format!(
+        "(CASE WHEN {currency_col}='{base}' THEN 1.0 ELSE COALESCE(
+            (SELECT rate FROM exchange_rates WHERE exchange_rates.currency={currency_col} AND exchange_rates.date<={date_col} ORDER BY exchange_rates.date DESC LIMIT 1),
+            (SELECT rate FROM exchange_rates WHERE exchange_rates.currency={currency_col} ORDER BY exchange_rates.date ASC LIMIT 1)
+        ) END)"
+    )
+}
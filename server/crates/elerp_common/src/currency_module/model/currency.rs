@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::order_module::model::order::OrderCurrency;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Currency {
+    pub code: OrderCurrency,
+    pub name: String,
+    #[serde(default)]
+    pub symbol: String,
+}
+
+/// Rate of the currency at the date, 1 unit of the currency is worth `rate` units of the base currency.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct ExchangeRate {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    pub currency: OrderCurrency,
+    /// The rate is used by the orders from the date until the next rate.
+    pub date: i64,
+    pub rate: f64,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct GetExchangeRatesQuery {
+    pub currency: Option<OrderCurrency>,
+    pub date_start: Option<i64>,
+    pub date_end: Option<i64>,
+}
+
+impl GetExchangeRatesQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(3);
+        if let Some(v) = &self.currency {
+            conditions.push(format!("exchange_rates.currency='{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.date_start {
+            conditions.push(format!("exchange_rates.date>={v}"));
+        }
+        if let Some(v) = &self.date_end {
+            conditions.push(format!("exchange_rates.date<={v}"));
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        }
+    }
+}
+
@@ -0,0 +1,168 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use ahash::{HashMap, HashMapExt};
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook};
+use sqlx::{Row, SqliteConnection};
+pub use tempfile::TempPath;
+
+use crate::model::export::ExportFormat;
+
+/// The lists are exported page by page with the size.
+pub const EXPORT_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Clone)]
+pub enum ExportValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// Timestamp in seconds written as the UTC date time.
+    Date(i64),
+}
+
+impl From<String> for ExportValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for ExportValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_owned())
+    }
+}
+
+impl From<i64> for ExportValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for ExportValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for ExportValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl ExportValue {
+    fn to_text(&self) -> String {
+        match self {
+            ExportValue::Text(v) => v.clone(),
+            ExportValue::Int(v) => v.to_string(),
+            ExportValue::Float(v) => v.to_string(),
+            ExportValue::Bool(v) => v.to_string(),
+            ExportValue::Date(v) => format_date(*v),
+        }
+    }
+}
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0).map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default()
+}
+
+enum ExportInner {
+    Xlsx(Box<Workbook>),
+    Csv(Box<csv::Writer<BufWriter<File>>>),
+}
+
+/// Write the rows of the list to the xlsx or csv file one by one.
+pub struct ExportWriter {
+    path: TempPath,
+    row: u32,
+    inner: ExportInner,
+}
+
+impl ExportWriter {
+    /// Create the writer of the uniquely named file `exports/{name}/{name}-{timestamp}-{random}.{extension}` in the data path.
+    pub fn new(format: ExportFormat, data_path: &Path, name: &str, timestamp: u64, headers: &[&str]) -> Result<Self> {
+        let dir = data_path.join("exports").join(name);
+        if !dir.is_dir() {
+            std::fs::create_dir_all(&dir)?;
+        }
+        let (file, path) = tempfile::Builder::new()
+            .prefix(&format!("{name}-{timestamp}-"))
+            .suffix(&format!(".{}", format.extension()))
+            .tempfile_in(&dir)?
+            .into_parts();
+        let inner = match format {
+            ExportFormat::Xlsx => {
+                drop(file);
+                let mut workbook = Workbook::new();
+                let header_format = Format::new().set_bold();
+                let worksheet = workbook.add_worksheet();
+                worksheet.write_row_with_format(0, 0, headers.iter().copied(), &header_format)?;
+                worksheet.set_freeze_panes(1, 0)?;
+                ExportInner::Xlsx(Box::new(workbook))
+            }
+            ExportFormat::Csv => {
+                let mut writer = Box::new(csv::Writer::from_writer(BufWriter::new(file)));
+                writer.write_record(headers)?;
+                ExportInner::Csv(writer)
+            }
+        };
+        Ok(Self { path, row: 0, inner })
+    }
+
+    pub fn write_row(&mut self, values: Vec<ExportValue>) -> Result<()> {
+        self.row += 1;
+        match &mut self.inner {
+            ExportInner::Xlsx(workbook) => {
+                let worksheet = workbook.worksheet_from_index(0)?;
+                for (col, value) in values.iter().enumerate() {
+                    let col = col as u16;
+                    match value {
+                        ExportValue::Int(v) => worksheet.write_number(self.row, col, *v as f64)?,
+                        ExportValue::Float(v) => worksheet.write_number(self.row, col, *v)?,
+                        ExportValue::Bool(v) => worksheet.write_boolean(self.row, col, *v)?,
+                        v => worksheet.write_string(self.row, col, v.to_text())?,
+                    };
+                }
+            }
+            ExportInner::Csv(writer) => writer.write_record(values.iter().map(|v| v.to_text()))?,
+        }
+        Ok(())
+    }
+
+    /// Save the file and return its path, the file is removed when the path is dropped.
+    pub fn finish(self) -> Result<TempPath> {
+        match self.inner {
+            ExportInner::Xlsx(mut workbook) => workbook.save(&self.path)?,
+            ExportInner::Csv(mut writer) => writer.flush()?,
+        }
+        Ok(self.path)
+    }
+}
+
+/// Resolve the names of the ids in the tables, the names queried are cached.
+#[derive(Default)]
+pub struct NameCache {
+    names: HashMap<(&'static str, i64), String>,
+}
+
+impl NameCache {
+    pub fn new() -> Self {
+        Self { names: HashMap::new() }
+    }
+
+    /// Empty if the row is not exists.
+    pub async fn get(&mut self, table: &'static str, id: i64, tx: &mut SqliteConnection) -> Result<String> {
+        if let Some(v) = self.names.get(&(table, id)) {
+            return Ok(v.clone());
+        }
+        let name: String = sqlx::query(&format!("SELECT name FROM {table} WHERE id=? LIMIT 1"))
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.get("name"))
+            .unwrap_or_default();
+        self.names.insert((table, id), name.clone());
+        Ok(name)
+    }
+}
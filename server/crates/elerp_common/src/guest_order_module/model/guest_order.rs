@@ -13,19 +13,14 @@ use crate::order_module::model::{
     order::{Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, AsRefStr, Type, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, AsRefStr, Type, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub enum GuestOrderStatus {
     Confirmed,
+    #[default]
     Pending,
     Expired,
 }
 
-impl Default for GuestOrderStatus {
-    fn default() -> Self {
-        Self::Pending
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct GuestOrder {
     #[serde(default)]
@@ -115,6 +110,13 @@ impl From<GuestOrder> for Order {
             order_type: value.order_type,
             is_record: value.is_record,
             non_payment: value.non_payment,
+            to_warehouse_id: 0,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
         }
     }
 }
@@ -143,6 +145,15 @@ impl From<Order> for GuestOrder {
     }
 }
 
+impl GuestOrder {
+    /// Clear the price of items for user can't view order price.
+    pub fn hide_price(&mut self) {
+        if let Some(items) = self.items.as_mut() {
+            items.iter_mut().for_each(|item| item.price = 0.0);
+        }
+    }
+}
+
 impl GetGuestOrdersQuery {
     pub fn get_where_condition(&self) -> String {
         let mut conditions = Vec::with_capacity(5);
@@ -162,7 +173,7 @@ impl GetGuestOrdersQuery {
         }
         if let Some(v) = &self.warehouse_ids {
             let eq = in_or_not(reverse, "warehouse_ids");
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("guest_orders.warehouse_id{eq}({v})"));
         }
         if let Some(v) = &self.person_related_id {
@@ -207,7 +218,7 @@ impl GetGuestOrdersQuery {
         }
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -222,21 +233,21 @@ impl GetGuestOrdersQuery {
             let mut col = get_sort_col_str(sorter);
             let sort = get_sorter_str(sorter);
             if col == "warehouse_id" {
-                col = format!("warehouse_name {sort}").into();
+                col = format!("warehouse_name {sort}");
             } else if col == "person_related_id" {
-                col = format!("person_related_name {sort}").into();
+                col = format!("person_related_name {sort}");
             } else if col == "person_in_charge_id" {
-                col = format!("person_in_charge_name {sort}").into();
+                col = format!("person_in_charge_name {sort}");
             } else if col == "order_status_id" {
-                col = format!("order_status_name {sort}").into();
+                col = format!("order_status_name {sort}");
             } else {
-                col = format!("guest_orders.{col} {sort}").into();
+                col = format!("guest_orders.{col} {sort}");
             }
             conditions.push(col);
         }
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
@@ -0,0 +1,209 @@
+use std::io::Cursor;
+
+use ahash::{HashMap, HashMapExt};
+use anyhow::{anyhow, bail, Result};
+use calamine::{Reader, Xlsx};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use sqlx::{Row, SqliteConnection};
+
+use crate::model::export::ExportFormat;
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// The row is invalid, it is reported with the message instead of failing the import.
+    Row(String),
+    Internal(anyhow::Error),
+}
+
+impl From<String> for ImportError {
+    fn from(value: String) -> Self {
+        Self::Row(value)
+    }
+}
+
+impl From<anyhow::Error> for ImportError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Internal(value)
+    }
+}
+
+impl From<sqlx::Error> for ImportError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Internal(value.into())
+    }
+}
+
+pub type ImportRowResult<T> = std::result::Result<T, ImportError>;
+
+/// Rows of the first worksheet or the csv file, the first row is the headers.
+pub struct ImportTable {
+    headers: HashMap<String, usize>,
+    /// Row number in the spreadsheet and the values.
+    rows: Vec<(usize, Vec<String>)>,
+}
+
+impl ImportTable {
+    pub fn read(format: ExportFormat, buf: &[u8]) -> Result<Self> {
+        let mut rows: Vec<Vec<String>> = match format {
+            ExportFormat::Xlsx => {
+                let mut workbook = Xlsx::new(Cursor::new(buf))?;
+                let range = workbook.worksheet_range_at(0).ok_or(anyhow!("The workbook is empty."))??;
+                range.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+            }
+            ExportFormat::Csv => {
+                let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(buf);
+                let mut rows = vec![];
+                for record in reader.records() {
+                    rows.push(record?.iter().map(|v| v.to_owned()).collect());
+                }
+                rows
+            }
+        };
+        if rows.is_empty() {
+            bail!("The headers are not found.");
+        }
+        let headers = rows.remove(0).into_iter().enumerate().map(|(i, header)| (header.trim().to_owned(), i)).collect();
+        // The rows formatted but without any value are ignored.
+        let rows = rows.into_iter().zip(2..).filter(|(row, _)| row.iter().any(|v| !v.trim().is_empty())).map(|(row, number)| (number, row)).collect();
+        Ok(Self { headers, rows })
+    }
+
+    pub fn has_header(&self, header: &str) -> bool {
+        self.headers.contains_key(header)
+    }
+
+    /// The first header not found.
+    pub fn get_missing_header<'a>(&self, headers: &[&'a str]) -> Option<&'a str> {
+        headers.iter().find(|header| !self.has_header(header)).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = ImportRow<'_>> {
+        self.rows.iter().map(|(number, values)| ImportRow {
+            headers: &self.headers,
+            number: *number,
+            values,
+        })
+    }
+}
+
+pub struct ImportRow<'a> {
+    headers: &'a HashMap<String, usize>,
+    number: usize,
+    values: &'a [String],
+}
+
+impl<'a> ImportRow<'a> {
+    /// Row number in the spreadsheet.
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Empty if the column is not exists.
+    pub fn get(&self, header: &str) -> &'a str {
+        self.headers.get(header).and_then(|i| self.values.get(*i)).map(|v| v.trim()).unwrap_or_default()
+    }
+
+    pub fn get_required(&self, header: &str) -> ImportRowResult<&'a str> {
+        match self.get(header) {
+            "" => Err(format!("{header} is required.").into()),
+            v => Ok(v),
+        }
+    }
+
+    pub fn get_bool(&self, header: &str) -> ImportRowResult<bool> {
+        match self.get(header).to_lowercase().as_str() {
+            "" | "false" | "0" | "no" => Ok(false),
+            "true" | "1" | "yes" => Ok(true),
+            _ => Err(format!("{header} must be true or false.").into()),
+        }
+    }
+
+    /// None if the cell is empty.
+    pub fn get_f64(&self, header: &str) -> ImportRowResult<Option<f64>> {
+        let v = self.get(header);
+        if v.is_empty() {
+            return Ok(None);
+        }
+        match v.parse::<f64>() {
+            Ok(v) if v.is_finite() => Ok(Some(v)),
+            _ => Err(format!("{header} must be a number.").into()),
+        }
+    }
+
+    /// None if the cell is empty.
+    pub fn get_i64(&self, header: &str) -> ImportRowResult<Option<i64>> {
+        let v = self.get(header);
+        if v.is_empty() {
+            return Ok(None);
+        }
+        v.parse::<i64>().map(Some).map_err(|_| format!("{header} must be an integer.").into())
+    }
+
+    /// Timestamp in seconds of the date `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` as the exports or the timestamp itself.
+    /// None if the cell is empty.
+    pub fn get_date(&self, header: &str) -> ImportRowResult<Option<i64>> {
+        let v = self.get(header);
+        if v.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(v) = v.parse::<i64>() {
+            return Ok(Some(v));
+        }
+        if let Ok(v) = NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S") {
+            return Ok(Some(v.and_utc().timestamp()));
+        }
+        match NaiveDate::parse_from_str(v, "%Y-%m-%d") {
+            Ok(v) => Ok(Some(v.and_time(NaiveTime::MIN).and_utc().timestamp())),
+            Err(_) => Err(format!("{header} must be a date of YYYY-MM-DD.").into()),
+        }
+    }
+
+    /// Id of the name in the table, 0 if the cell is empty.
+    pub async fn get_id(&self, header: &str, table: &'static str, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<i64> {
+        let name = self.get(header);
+        if name.is_empty() {
+            return Ok(0);
+        }
+        match ids.get(table, name, tx).await? {
+            Some(id) => Ok(id),
+            None => Err(format!("{header} '{name}' is not found.").into()),
+        }
+    }
+}
+
+/// Resolve the ids of the names in the tables, the ids queried are cached.
+#[derive(Default)]
+pub struct IdCache {
+    ids: HashMap<(&'static str, String), i64>,
+}
+
+impl IdCache {
+    pub fn new() -> Self {
+        Self { ids: HashMap::new() }
+    }
+
+    /// The names are unique in the tables, the row removed is found too.
+    /// The names not found are not cached, they may be added by the rows before.
+    pub async fn get(&mut self, table: &'static str, name: &str, tx: &mut SqliteConnection) -> Result<Option<i64>> {
+        if let Some(v) = self.ids.get(&(table, name.to_owned())) {
+            return Ok(Some(*v));
+        }
+        let id: Option<i64> = sqlx::query(&format!("SELECT id FROM {table} WHERE name=? LIMIT 1"))
+            .bind(name)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.get("id"));
+        if let Some(id) = id {
+            self.ids.insert((table, name.to_owned()), id);
+        }
+        Ok(id)
+    }
+}
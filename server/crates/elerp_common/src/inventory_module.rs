@@ -1,13 +1,18 @@
-use ahash::{HashSet, HashSetExt};
+use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 use anyhow::{bail, Result};
+use std::collections::hash_map::Entry;
 use sqlx::{Row, SqliteConnection};
 use tracing::warn;
 
-use crate::order_module::model::order::{OrderItem, OrderType};
+use crate::{
+    order_module::model::order::{OrderItem, OrderType},
+    round_amount,
+};
 
 use self::model::{inventory::InventoryProduct, virtual_inventory::VirtualInventory};
 
 pub mod model;
+pub mod valuation;
 
 pub fn calc_quantity_by_order_type(mut inventory_quantity: i64, item: &OrderItem, order_type: OrderType) -> i64 {
     match order_type {
@@ -16,7 +21,7 @@ pub fn calc_quantity_by_order_type(mut inventory_quantity: i64, item: &OrderItem
                 inventory_quantity += item.quantity
             }
         }
-        OrderType::StockOut => {
+        OrderType::StockOut | OrderType::Transfer => {
             if !item.exchanged {
                 inventory_quantity -= item.quantity
             }
@@ -38,6 +43,196 @@ pub fn calc_quantity_by_order_type(mut inventory_quantity: i64, item: &OrderItem
     inventory_quantity
 }
 
+/// Lot number to the expiry date and quantity of the lot.
+/// The stock without lot is not tracked here, it is the inventory quantity subtract the lots.
+pub type Lots = HashMap<String, (Option<i64>, i64)>;
+
+/// Same as `calc_quantity_by_order_type` but for the lots of the sku, `reset` will clear the lots before calibration.
+pub fn calc_lots_by_order_type(lots: &mut Lots, item: &OrderItem, order_type: OrderType, reset: bool) {
+    match order_type {
+        OrderType::Calibration | OrderType::CalibrationStrict => {
+            if item.exchanged {
+                return;
+            }
+            if reset {
+                lots.clear();
+            }
+            let lot_number = match item.lot_number.clone() {
+                Some(v) => v,
+                None => return,
+            };
+            let lot = lots.entry(lot_number).or_insert((None, 0));
+            lot.0 = item.expiry_date.or(lot.0);
+            lot.1 += item.quantity;
+        }
+        _ => {
+            let quantity = calc_quantity_by_order_type(0, item, order_type);
+            let lot_number = match item.lot_number.clone() {
+                Some(v) if quantity != 0 => v,
+                _ => return,
+            };
+            let lot = lots.entry(lot_number).or_insert((None, 0));
+            if quantity > 0 {
+                lot.0 = item.expiry_date.or(lot.0);
+            }
+            lot.1 += quantity;
+        }
+    }
+}
+
+pub async fn get_lots(warehouse_id: i64, sku_id: i64, tx: &mut SqliteConnection) -> Result<Lots> {
+    let rows = sqlx::query("SELECT lot_number, expiry_date, quantity FROM inventory_lots WHERE warehouse_id=? AND sku_id=?")
+        .bind(warehouse_id)
+        .bind(sku_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    let mut lots = Lots::with_capacity(rows.len());
+    for row in rows {
+        lots.insert(row.get("lot_number"), (row.get("expiry_date"), row.get("quantity")));
+    }
+    Ok(lots)
+}
+
+/// Replace the lots of the sku, the empty lots will not be saved.
+pub async fn set_lots(warehouse_id: i64, sku_id: i64, lots: &Lots, tx: &mut SqliteConnection) -> Result<()> {
+    sqlx::query("DELETE FROM inventory_lots WHERE warehouse_id=? AND sku_id=?").bind(warehouse_id).bind(sku_id).execute(&mut *tx).await?;
+    for (lot_number, (expiry_date, quantity)) in lots.iter() {
+        if *quantity == 0 {
+            continue;
+        }
+        sqlx::query("INSERT INTO inventory_lots (warehouse_id, sku_id, lot_number, expiry_date, quantity) VALUES (?, ?, ?, ?, ?)")
+            .bind(warehouse_id)
+            .bind(sku_id)
+            .bind(lot_number)
+            .bind(expiry_date)
+            .bind(quantity)
+            .execute(&mut *tx)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn change_lots(warehouse_id: i64, items: &Vec<OrderItem>, order_type: OrderType, tx: &mut SqliteConnection) -> Result<()> {
+    if order_type == OrderType::CalibrationStrict {
+        sqlx::query("DELETE FROM inventory_lots WHERE warehouse_id=?").bind(warehouse_id).execute(&mut *tx).await?;
+    }
+    let mut temp = HashMap::<i64, Lots>::with_capacity(items.len());
+    let mut calibrated = HashSet::with_capacity(items.len());
+    for item in items {
+        if let Entry::Vacant(e) = temp.entry(item.sku_id) {
+            e.insert(get_lots(warehouse_id, item.sku_id, tx).await?);
+        }
+        let reset = !item.exchanged && calibrated.insert(item.sku_id);
+        calc_lots_by_order_type(temp.get_mut(&item.sku_id).unwrap(), item, order_type, reset);
+    }
+    for (sku_id, lots) in temp.iter() {
+        set_lots(warehouse_id, *sku_id, lots, tx).await?;
+    }
+    Ok(())
+}
+
+fn is_outgoing(item: &OrderItem, order_type: OrderType) -> bool {
+    match order_type {
+        OrderType::StockOut | OrderType::Exchange | OrderType::Transfer => !item.exchanged,
+        _ => false,
+    }
+}
+
+/// Split the outgoing items without lot number to the lots in stock, first-expired, first-out.
+/// The lots expired before `now` are skipped, they go out only when the item names the lot.
+/// The quantity can't be allocated to any lot is left without lot number and consume the stock without lot.
+pub async fn allocate_lots(warehouse_id: i64, items: Vec<OrderItem>, order_type: OrderType, now: i64, tx: &mut SqliteConnection) -> Result<Vec<OrderItem>> {
+    // Lots required explicitly can't be allocated again.
+    let mut required = HashMap::<(i64, String), i64>::with_capacity(items.len());
+    for item in items.iter() {
+        if let Some(lot_number) = item.lot_number.as_ref() {
+            if is_outgoing(item, order_type) {
+                *required.entry((item.sku_id, lot_number.clone())).or_insert(0) += item.quantity;
+            }
+        }
+    }
+    let mut remains = HashMap::<i64, Vec<(String, Option<i64>, i64)>>::with_capacity(items.len());
+    let mut arr = Vec::with_capacity(items.len());
+    for item in items {
+        // Serialized units are not split to the lots.
+        if item.lot_number.is_some() || !item.serial_numbers.is_empty() || !is_outgoing(&item, order_type) {
+            arr.push(item);
+            continue;
+        }
+        if let Entry::Vacant(e) = remains.entry(item.sku_id) {
+            let rows = sqlx::query(
+                "SELECT lot_number, expiry_date, quantity FROM inventory_lots WHERE warehouse_id=? AND sku_id=? AND quantity>0 AND (expiry_date IS NULL OR expiry_date >= ?) ORDER BY expiry_date IS NULL, expiry_date, lot_number",
+            )
+            .bind(warehouse_id)
+            .bind(item.sku_id)
+            .bind(now)
+            .fetch_all(&mut *tx)
+            .await?;
+            let lots = rows
+                .into_iter()
+                .map(|row| {
+                    let lot_number: String = row.get("lot_number");
+                    let quantity: i64 = row.get("quantity");
+                    let quantity = quantity - required.get(&(item.sku_id, lot_number.clone())).copied().unwrap_or(0);
+                    (lot_number, row.get("expiry_date"), quantity)
+                })
+                .collect();
+            e.insert(lots);
+        }
+        let mut quantity = item.quantity;
+        // The discount amount is shared by the lots in proportion to their quantities.
+        let mut discount_amount = item.discount_amount;
+        let start = arr.len();
+        for (lot_number, expiry_date, available) in remains.get_mut(&item.sku_id).unwrap().iter_mut() {
+            if quantity == 0 {
+                break;
+            }
+            if *available <= 0 {
+                continue;
+            }
+            let n = quantity.min(*available);
+            *available -= n;
+            quantity -= n;
+            let share = round_amount(item.discount_amount * n as f64 / item.quantity as f64);
+            discount_amount -= share;
+            arr.push(OrderItem {
+                quantity: n,
+                lot_number: Some(lot_number.clone()),
+                expiry_date: *expiry_date,
+                discount_amount: share,
+                ..item.clone()
+            });
+        }
+        if quantity > 0 {
+            arr.push(OrderItem {
+                quantity,
+                discount_amount: round_amount(discount_amount),
+                ..item
+            });
+        } else if let Some(last) = arr[start..].last_mut() {
+            last.discount_amount = round_amount(last.discount_amount + discount_amount);
+        }
+    }
+    Ok(arr)
+}
+
+/// Quantity held by the reservations not expired, the reservations of the guest order or the quotation are excluded if it is not 0.
+pub async fn get_reserved(warehouse_id: i64, sku_id: i64, guest_order_id: i64, quotation_id: i64, now: i64, tx: &mut SqliteConnection) -> Result<i64> {
+    Ok(sqlx::query(
+        "SELECT COALESCE(SUM(quantity), 0) AS reserved FROM inventory_reservations
+        WHERE warehouse_id=? AND sku_id=? AND (expires_at IS NULL OR expires_at>?) AND (guest_order_id=0 OR guest_order_id<>?)
+        AND (quotation_id=0 OR quotation_id<>?)",
+    )
+    .bind(warehouse_id)
+    .bind(sku_id)
+    .bind(now)
+    .bind(guest_order_id)
+    .bind(quotation_id)
+    .fetch_one(&mut *tx)
+    .await?
+    .get("reserved"))
+}
+
 pub fn get_virtual(capicity: usize) -> VirtualInventory {
     VirtualInventory::new(capicity)
 }
@@ -59,6 +254,12 @@ async fn add(warehouse_id: i64, sku_id: i64, sku_category_id: i64, quantity: i64
             sku_id,
             sku_category_id,
             quantity,
+            reserved: 0,
+            available: quantity,
+            value: 0.0,
+            gross_margin: 0.0,
+            unit: String::new(),
+            unit_quantity: 0.0,
         })
     })
 }
@@ -100,6 +301,7 @@ pub async fn change(warehouse_id: i64, items: &Vec<OrderItem>, order_type: Order
                     add(warehouse_id, item.sku_id, sku_row.get("sku_category_id"), produtc_quantity, tx)
                         .await?
                         .expect("Can't add the new inventory!");
+                    inventory.forget(warehouse_id, item.sku_id);
                 }
             }
         } else {
@@ -113,12 +315,19 @@ pub async fn change(warehouse_id: i64, items: &Vec<OrderItem>, order_type: Order
             sku_id,
             sku_category_id: vproduct.sku_category_id,
             quantity: vproduct.latest_quantity(),
+            reserved: 0,
+            available: vproduct.latest_quantity(),
+            value: 0.0,
+            gross_margin: 0.0,
+            unit: String::new(),
+            unit_quantity: 0.0,
         };
         if update(product, tx).await?.is_none() {
             warn!("Can't update the specified product by id {}!", sku_id);
             bail!("Please ensure order is correct!");
         }
     }
+    change_lots(warehouse_id, items, order_type, tx).await?;
 
     Ok(())
 }
@@ -1,2 +1,4 @@
 pub mod inventory;
+pub mod reorder_point;
+pub mod reservation;
 pub mod virtual_inventory;
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use ahash::HashSet;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -5,7 +7,8 @@ use utoipa::{IntoParams, ToSchema};
 
 use crate::{
     set_to_string,
-    sql::{get_sort_col_str, get_sorter_str},
+    sku_module::model::sku::get_attributes_conditions,
+    sql::{get_sort_col_str, get_sorter_str, quote_str},
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, IntoParams, Clone)]
@@ -13,7 +16,46 @@ pub struct InventoryProduct {
     pub warehouse_id: i64,
     pub sku_id: i64,
     pub sku_category_id: i64,
+    /// Quantity on hand.
+    pub quantity: i64,
+    /// Quantity held by the reservations not expired.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub reserved: i64,
+    /// Quantity on hand subtract the reserved.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub available: i64,
+    /// Cost of the stock by the valuation method.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub value: f64,
+    /// Sales amount of the stock out orders subtract the cost of goods sold.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub gross_margin: f64,
+    /// Unit of the quantity in the unit, the unit of the query if the sku defines it, otherwise the base unit of the sku.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub unit: String,
+    /// Quantity on hand in the unit.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub unit_quantity: f64,
+}
+
+/// Stock of the variants of the product template in the warehouse.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct TemplateInventory {
+    pub warehouse_id: i64,
+    pub template_id: i64,
+    /// Count of the variants in stock records.
+    pub variant_count: i64,
     pub quantity: i64,
+    pub reserved: i64,
+    pub available: i64,
+    pub value: f64,
+    pub gross_margin: f64,
 }
 
 #[derive(Debug, Deserialize, ToSchema, FromRow, IntoParams)]
@@ -21,8 +63,14 @@ pub struct GetInventoryQuery {
     pub warehouse_ids: Option<HashSet<i64>>,
     pub sku_ids: Option<HashSet<i64>>,
     pub sku_category_ids: Option<HashSet<i64>>,
+    /// Only the variants of the product templates.
+    pub template_ids: Option<HashSet<i64>>,
+    /// Only the skus having all of the attribute values.
+    pub attributes: Option<BTreeMap<String, String>>,
     pub quantity_start: Option<i64>,
     pub quantity_end: Option<i64>,
+    /// Show the quantities in the unit too, the skus without the unit show the base unit.
+    pub unit: Option<String>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -30,26 +78,34 @@ impl GetInventoryQuery {
     pub fn get_where_condition(&self) -> String {
         let mut conditions = Vec::with_capacity(4);
         if let Some(v) = &self.warehouse_ids {
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("inventory.warehouse_id IN ({v})"));
         }
         if let Some(v) = &self.sku_ids {
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("inventory.sku_id IN ({v})"));
         }
         if let Some(v) = &self.sku_category_ids {
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("inventory.sku_category_id IN ({v})"));
         }
+        if let Some(v) = &self.template_ids {
+            let v = set_to_string(v, ",");
+            conditions.push(format!("sku_list.template_id IN ({v})"));
+        }
+        if let Some(v) = &self.attributes {
+            conditions.extend(get_attributes_conditions("inventory.sku_id", v));
+        }
         if let Some(v) = &self.quantity_start {
             conditions.push(format!("inventory.quantity>={v}"));
         }
         if let Some(v) = &self.quantity_end {
             conditions.push(format!("inventory.quantity<={v}"));
         }
+        conditions.push("warehouses.deleted_at IS NULL AND sku_list.deleted_at IS NULL".into());
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -64,19 +120,83 @@ impl GetInventoryQuery {
             let mut col = get_sort_col_str(sorter);
             let sort = get_sorter_str(sorter);
             if col == "sku_id" {
-                col = format!("sku_name {sort}").into();
+                col = format!("sku_name {sort}");
             } else if col == "sku_category_id" {
-                col = format!("sku_category_name {sort}").into();
+                col = format!("sku_category_name {sort}");
             } else if col == "warehouse_id" {
-                col = format!("warehouse_name {sort}").into();
+                col = format!("warehouse_name {sort}");
+            } else if col == "reserved" || col == "available" {
+                col = format!("{col} {sort}");
             } else {
-                col = format!("inventory.{col} {sort}").into();
+                col = format!("inventory.{col} {sort}");
             }
             conditions.push(col)
         }
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
+        } else {
+            "".into()
+        }
+    }
+}
+
+/// Quantity of the lot in the warehouse.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct InventoryLot {
+    pub warehouse_id: i64,
+    pub sku_id: i64,
+    pub lot_number: String,
+    pub expiry_date: Option<i64>,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetInventoryLotsQuery {
+    pub warehouse_ids: Option<HashSet<i64>>,
+    pub sku_ids: Option<HashSet<i64>>,
+    pub lot_number: Option<String>,
+    /// Only the lots in stock and expiring within the days, include the expired.
+    pub expiring_within_days: Option<i64>,
+    pub sorters: Option<Vec<String>>,
+}
+
+impl GetInventoryLotsQuery {
+    pub fn get_where_condition(&self, now: i64) -> String {
+        let mut conditions = Vec::with_capacity(4);
+        if let Some(v) = &self.warehouse_ids {
+            let v = set_to_string(v, ",");
+            conditions.push(format!("inventory_lots.warehouse_id IN ({v})"));
+        }
+        if let Some(v) = &self.sku_ids {
+            let v = set_to_string(v, ",");
+            conditions.push(format!("inventory_lots.sku_id IN ({v})"));
+        }
+        if let Some(v) = &self.lot_number {
+            conditions.push(format!("inventory_lots.lot_number={}", quote_str(v)));
+        }
+        if let Some(v) = &self.expiring_within_days {
+            let end = now + v * 86400;
+            conditions.push(format!("inventory_lots.expiry_date IS NOT NULL AND inventory_lots.expiry_date<={end} AND inventory_lots.quantity>0"));
+        }
+        conditions.push("warehouses.deleted_at IS NULL AND sku_list.deleted_at IS NULL".into());
+        let c = conditions.join(" AND ");
+        format!("WHERE {c}")
+    }
+
+    pub fn get_order_condition(&self) -> String {
+        if self.sorters.is_none() {
+            return "ORDER BY inventory_lots.expiry_date IS NULL, inventory_lots.expiry_date".into();
+        }
+        let mut conditions = vec![];
+        for sorter in self.sorters.as_ref().unwrap() {
+            let col = get_sort_col_str(sorter);
+            let sort = get_sorter_str(sorter);
+            conditions.push(format!("inventory_lots.{col} {sort}"));
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(", ");
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
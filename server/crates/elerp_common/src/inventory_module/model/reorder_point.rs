@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use strum::AsRefStr;
+use utoipa::{IntoParams, ToSchema};
+
+/// Thresholds of the sku quantity in the warehouse.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct ReorderPoint {
+    pub sku_id: i64,
+    /// 0 means all warehouses, the thresholds of a specific warehouse take precedence.
+    #[serde(default)]
+    pub warehouse_id: i64,
+    /// Alert when the quantity is lower than it.
+    pub min_quantity: i64,
+    /// Suggested quantity to reorder.
+    pub reorder_quantity: i64,
+    /// Alert when the quantity is higher than it.
+    pub max_quantity: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetReorderPointsQuery {
+    pub sku_id: Option<i64>,
+    pub warehouse_id: Option<i64>,
+}
+
+impl GetReorderPointsQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(2);
+        if let Some(v) = &self.sku_id {
+            conditions.push(format!("sku_id={v}"));
+        }
+        if let Some(v) = &self.warehouse_id {
+            conditions.push(format!("warehouse_id={v}"));
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct RemoveReorderPointQuery {
+    pub sku_id: i64,
+    #[serde(default)]
+    pub warehouse_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, AsRefStr, PartialEq, Eq, Clone, Copy)]
+pub enum InventoryAlertType {
+    LowStock,
+    OverStock,
+}
+
+/// The sku in the warehouse crossed its reorder point.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct InventoryAlert {
+    pub warehouse_id: i64,
+    pub sku_id: i64,
+    pub quantity: i64,
+    pub alert_type: InventoryAlertType,
+    pub min_quantity: i64,
+    pub reorder_quantity: i64,
+    pub max_quantity: Option<i64>,
+}
@@ -0,0 +1,75 @@
+use ahash::HashSet;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::set_to_string;
+
+/// Quantity of the sku held in the warehouse, it lowers the available quantity but not the quantity on hand.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Reservation {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub created_by_user_id: i64,
+    /// Date will generated by the system.
+    #[serde(default)]
+    pub date: i64,
+    pub warehouse_id: i64,
+    pub sku_id: i64,
+    pub quantity: i64,
+    /// The guest order holds the quantity, 0 means none.
+    /// It is released when the guest order is confirmed or removed.
+    #[serde(default)]
+    pub guest_order_id: i64,
+    /// The quotation holds the quantity, 0 means none.
+    /// It is released when the quotation is converted or removed.
+    #[serde(default)]
+    pub quotation_id: i64,
+    /// The reservation is released after the date, none means never.
+    /// Default to the valid date of the quotation.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct GetReservationsQuery {
+    pub warehouse_ids: Option<HashSet<i64>>,
+    pub sku_ids: Option<HashSet<i64>>,
+    pub guest_order_id: Option<i64>,
+    pub quotation_id: Option<i64>,
+    pub created_by_user_id: Option<i64>,
+}
+
+impl GetReservationsQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(5);
+        if let Some(v) = &self.warehouse_ids {
+            let v = set_to_string(v, ",");
+            conditions.push(format!("inventory_reservations.warehouse_id IN ({v})"));
+        }
+        if let Some(v) = &self.sku_ids {
+            let v = set_to_string(v, ",");
+            conditions.push(format!("inventory_reservations.sku_id IN ({v})"));
+        }
+        if let Some(v) = &self.guest_order_id {
+            conditions.push(format!("inventory_reservations.guest_order_id={v}"));
+        }
+        if let Some(v) = &self.quotation_id {
+            conditions.push(format!("inventory_reservations.quotation_id={v}"));
+        }
+        if let Some(v) = &self.created_by_user_id {
+            conditions.push(format!("inventory_reservations.created_by_user_id={v}"));
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::hash_map::Entry;
+
 use ahash::{HashMap, HashMapExt};
 use anyhow::Result;
 use sqlx::{Row, SqliteConnection};
@@ -36,31 +38,35 @@ impl VirtualInventory {
         }
     }
 
+    /// Forget the cached product, it will be fetched again by `get_mut()`.
+    pub fn forget(&mut self, warehouse_id: i64, sku_id: i64) {
+        self.inventory.remove(&(warehouse_id, sku_id));
+    }
+
     pub async fn get_mut(
         &mut self,
         warehouse_id: i64,
         sku_id: i64,
         tx: &mut SqliteConnection,
     ) -> Result<Option<&mut VirtualInventoryProduct>> {
-        Ok(if self.inventory.contains_key(&(warehouse_id, sku_id)) {
-            let p = self.inventory.get_mut(&(warehouse_id, sku_id)).unwrap();
-            p.as_mut()
-        } else {
-            let p =sqlx::query("SELECT sku_category_id, quantity FROM inventory WHERE warehouse_id = ? AND sku_id = ? LIMIT 1").bind(warehouse_id).bind(sku_id)
-                .fetch_optional(&mut *tx).await?
-                .map(|p| {
-                    let quantity = p.get("quantity");
-                    VirtualInventoryProduct {
-                        sku_category_id: p.get("sku_category_id"),
-                        quantity,
-                        latest_quantity: quantity,
-                    }
-                });
-            self.inventory.insert((warehouse_id, sku_id), p);
-            self.inventory
-                .get_mut(&(warehouse_id, sku_id))
-                .unwrap()
-                .as_mut()
+        Ok(match self.inventory.entry((warehouse_id, sku_id)) {
+            Entry::Occupied(e) => e.into_mut().as_mut(),
+            Entry::Vacant(e) => {
+                let p = sqlx::query("SELECT sku_category_id, quantity FROM inventory WHERE warehouse_id = ? AND sku_id = ? LIMIT 1")
+                    .bind(warehouse_id)
+                    .bind(sku_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .map(|p| {
+                        let quantity = p.get("quantity");
+                        VirtualInventoryProduct {
+                            sku_category_id: p.get("sku_category_id"),
+                            quantity,
+                            latest_quantity: quantity,
+                        }
+                    });
+                e.insert(p).as_mut()
+            }
         })
     }
 }
@@ -0,0 +1,417 @@
+use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
+use anyhow::Result;
+use sqlx::{Row, SqliteConnection};
+
+use crate::{
+    config::ValuationMethod,
+    currency_module::get_rate_sql,
+    order_module::model::order::{Order, OrderCurrency, OrderItem, OrderType},
+    set_to_string,
+};
+
+use super::calc_quantity_by_order_type;
+
+/// Stock of the sku received at the same unit cost.
+#[derive(Debug, Clone)]
+struct CostLayer {
+    order_id: i64,
+    date: i64,
+    quantity: i64,
+    unit_cost: f64,
+}
+
+/// Fields of the order used by the valuation.
+struct OrderHead {
+    id: i64,
+    date: i64,
+    order_type: OrderType,
+    warehouse_id: i64,
+    to_warehouse_id: i64,
+    in_transit: bool,
+    /// Rate converting the prices to the base currency, 1 if the currency has no rate.
+    rate: f64,
+}
+
+impl OrderHead {
+    /// Columns of the orders table selected by the valuation.
+    fn columns(base: OrderCurrency) -> String {
+        let rate = get_rate_sql(base, "orders.currency", "orders.date");
+        format!("orders.id, orders.date, orders.order_type, orders.warehouse_id, orders.to_warehouse_id, orders.in_transit, COALESCE({rate}, 1.0) AS rate")
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Self {
+        Self {
+            id: row.get("id"),
+            date: row.get("date"),
+            order_type: row.get("order_type"),
+            warehouse_id: row.get("warehouse_id"),
+            to_warehouse_id: row.get("to_warehouse_id"),
+            in_transit: row.get("in_transit"),
+            rate: row.get("rate"),
+        }
+    }
+}
+
+/// Cost layers of the (warehouse_id, sku_id) and the cost of goods sold by the orders.
+struct CostLedger {
+    method: ValuationMethod,
+    /// All layers are in the memory, nothing need to be loaded from the database.
+    full: bool,
+    layers: HashMap<(i64, i64), Vec<CostLayer>>,
+    /// Unit cost of the last consumed layer, used when the layers are empty.
+    last_costs: HashMap<(i64, i64), f64>,
+    loaded_warehouses: HashSet<i64>,
+    /// (order_id, warehouse_id, sku_id, quantity, amount)
+    sold: Vec<(i64, i64, i64, i64, f64)>,
+    /// Only the keys are rebuilt if any, the other keys are not changed.
+    scope: Option<HashSet<(i64, i64)>>,
+}
+
+impl CostLedger {
+    fn new(method: ValuationMethod, full: bool) -> Self {
+        Self {
+            method,
+            full,
+            layers: HashMap::new(),
+            last_costs: HashMap::new(),
+            loaded_warehouses: HashSet::new(),
+            sold: Vec::new(),
+            scope: None,
+        }
+    }
+
+    fn in_scope(&self, key: (i64, i64)) -> bool {
+        self.scope.as_ref().is_none_or(|keys| keys.contains(&key))
+    }
+
+    fn push_row(&mut self, key: (i64, i64), layer: CostLayer) {
+        self.last_costs.insert(key, layer.unit_cost);
+        let layers = self.layers.entry(key).or_default();
+        if layer.quantity != 0 {
+            layers.push(layer);
+        }
+    }
+
+    async fn load(&mut self, key: (i64, i64), tx: &mut SqliteConnection) -> Result<()> {
+        if self.full || self.layers.contains_key(&key) || self.loaded_warehouses.contains(&key.0) {
+            return Ok(());
+        }
+        let rows = sqlx::query("SELECT order_id, date, quantity, unit_cost FROM inventory_cost_layers WHERE warehouse_id=? AND sku_id=? ORDER BY date, rowid")
+            .bind(key.0)
+            .bind(key.1)
+            .fetch_all(&mut *tx)
+            .await?;
+        self.layers.insert(key, Vec::with_capacity(rows.len()));
+        for row in rows {
+            self.push_row(key, Self::row_to_layer(row));
+        }
+        Ok(())
+    }
+
+    async fn load_warehouse(&mut self, warehouse_id: i64, tx: &mut SqliteConnection) -> Result<()> {
+        if self.full || self.loaded_warehouses.contains(&warehouse_id) {
+            return Ok(());
+        }
+        let rows = sqlx::query("SELECT sku_id, order_id, date, quantity, unit_cost FROM inventory_cost_layers WHERE warehouse_id=? ORDER BY date, rowid")
+            .bind(warehouse_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        self.layers.retain(|key, _| key.0 != warehouse_id);
+        for row in rows {
+            let key = (warehouse_id, row.get("sku_id"));
+            self.push_row(key, Self::row_to_layer(row));
+        }
+        self.loaded_warehouses.insert(warehouse_id);
+        Ok(())
+    }
+
+    fn row_to_layer(row: sqlx::sqlite::SqliteRow) -> CostLayer {
+        CostLayer {
+            order_id: row.get("order_id"),
+            date: row.get("date"),
+            quantity: row.get("quantity"),
+            unit_cost: row.get("unit_cost"),
+        }
+    }
+
+    fn quantity(&self, key: (i64, i64)) -> i64 {
+        self.layers.get(&key).map(|layers| layers.iter().map(|l| l.quantity).sum()).unwrap_or(0)
+    }
+
+    fn average_cost(&self, key: (i64, i64)) -> f64 {
+        let quantity = self.quantity(key);
+        if quantity > 0 {
+            let amount: f64 = self.layers[&key].iter().map(|l| l.quantity as f64 * l.unit_cost).sum();
+            amount / quantity as f64
+        } else {
+            self.last_costs.get(&key).copied().unwrap_or(0.0)
+        }
+    }
+
+    fn receive(&mut self, key: (i64, i64), order_id: i64, date: i64, quantity: i64, unit_cost: f64) {
+        let layers = self.layers.entry(key).or_default();
+        match (self.method, layers.first_mut()) {
+            (ValuationMethod::MovingAverage, Some(layer)) => {
+                let total = layer.quantity + quantity;
+                if total > 0 {
+                    layer.unit_cost = (layer.quantity as f64 * layer.unit_cost + quantity as f64 * unit_cost) / total as f64;
+                }
+                layer.quantity = total;
+                layer.order_id = order_id;
+                layer.date = date;
+            }
+            _ => layers.push(CostLayer {
+                order_id,
+                date,
+                quantity,
+                unit_cost,
+            }),
+        }
+    }
+
+    /// Take the quantity from the oldest layers, return the cost of it.
+    fn consume(&mut self, key: (i64, i64), mut quantity: i64) -> f64 {
+        let mut amount = 0.0;
+        let mut last_cost = self.average_cost(key);
+        let layers = self.layers.entry(key).or_default();
+        while quantity > 0 && !layers.is_empty() {
+            let layer = &mut layers[0];
+            let n = quantity.min(layer.quantity);
+            amount += n as f64 * layer.unit_cost;
+            last_cost = layer.unit_cost;
+            layer.quantity -= n;
+            quantity -= n;
+            if layer.quantity <= 0 {
+                layers.remove(0);
+            }
+        }
+        // Stock out more than the layers, the rest is valued at the last cost.
+        amount += quantity as f64 * last_cost;
+        self.last_costs.insert(key, last_cost);
+        amount
+    }
+
+    /// Change the quantity of the layers to the result of the item.
+    fn change(&mut self, key: (i64, i64), order_id: i64, date: i64, item: &OrderItem, order_type: OrderType, unit_cost: Option<f64>) -> f64 {
+        let quantity = self.quantity(key);
+        let diff = calc_quantity_by_order_type(quantity, item, order_type) - quantity;
+        if diff > 0 {
+            let unit_cost = unit_cost.unwrap_or_else(|| self.average_cost(key));
+            self.receive(key, order_id, date, diff, unit_cost);
+            0.0
+        } else if diff < 0 {
+            self.consume(key, -diff)
+        } else {
+            0.0
+        }
+    }
+
+    async fn apply(&mut self, order: &OrderHead, items: &[OrderItem], tx: &mut SqliteConnection) -> Result<()> {
+        let warehouse_id = order.warehouse_id;
+        if order.order_type == OrderType::CalibrationStrict {
+            // The skus not in the items are calibrated to zero.
+            self.load_warehouse(warehouse_id, tx).await?;
+            let skus: HashSet<i64> = items.iter().filter(|item| !item.exchanged).map(|item| item.sku_id).collect();
+            let keys: Vec<(i64, i64)> = self.layers.keys().filter(|key| key.0 == warehouse_id && !skus.contains(&key.1)).copied().collect();
+            for key in keys {
+                let quantity = self.quantity(key);
+                self.consume(key, quantity);
+            }
+        }
+        for item in items {
+            let key = (warehouse_id, item.sku_id);
+            if !self.in_scope(key) {
+                continue;
+            }
+            self.load(key, tx).await?;
+            match order.order_type {
+                OrderType::StockIn => {
+                    self.change(key, order.id, order.date, item, order.order_type, Some(unit_price(item) * order.rate));
+                }
+                OrderType::Calibration | OrderType::CalibrationStrict => {
+                    // The stock found by the calibration is valued at its price if any, e.g. the opening stock.
+                    let price = unit_price(item) * order.rate;
+                    let unit_cost = if price > 0.0 { Some(price) } else { None };
+                    self.change(key, order.id, order.date, item, order.order_type, unit_cost);
+                }
+                OrderType::StockOut => {
+                    let amount = self.change(key, order.id, order.date, item, order.order_type, None);
+                    if !item.exchanged {
+                        self.sold.push((order.id, warehouse_id, item.sku_id, item.quantity, amount));
+                    }
+                }
+                OrderType::Transfer => {
+                    let amount = self.change(key, order.id, order.date, item, order.order_type, None);
+                    let key = (order.to_warehouse_id, item.sku_id);
+                    if !item.exchanged && !order.in_transit && item.quantity > 0 && self.in_scope(key) {
+                        self.load(key, tx).await?;
+                        self.receive(key, order.id, order.date, item.quantity, amount / item.quantity as f64);
+                    }
+                }
+                OrderType::Verification | OrderType::VerificationStrict => (),
+                _ => {
+                    self.change(key, order.id, order.date, item, order.order_type, None);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(&self, tx: &mut SqliteConnection) -> Result<()> {
+        for ((warehouse_id, sku_id), layers) in self.layers.iter() {
+            if !self.full {
+                sqlx::query("DELETE FROM inventory_cost_layers WHERE warehouse_id=? AND sku_id=?")
+                    .bind(warehouse_id)
+                    .bind(sku_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            let mut rows: Vec<CostLayer> = layers.iter().filter(|l| l.quantity != 0).cloned().collect();
+            // Keep the last cost for the sku is out of stock.
+            if rows.is_empty() {
+                match self.last_costs.get(&(*warehouse_id, *sku_id)) {
+                    Some(unit_cost) => rows.push(CostLayer {
+                        order_id: 0,
+                        date: 0,
+                        quantity: 0,
+                        unit_cost: *unit_cost,
+                    }),
+                    None => continue,
+                }
+            }
+            for layer in rows {
+                sqlx::query("INSERT INTO inventory_cost_layers (warehouse_id, sku_id, order_id, date, quantity, unit_cost) VALUES (?, ?, ?, ?, ?, ?)")
+                    .bind(warehouse_id)
+                    .bind(sku_id)
+                    .bind(layer.order_id)
+                    .bind(layer.date)
+                    .bind(layer.quantity)
+                    .bind(layer.unit_cost)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        for (order_id, warehouse_id, sku_id, quantity, amount) in self.sold.iter() {
+            sqlx::query("INSERT INTO cost_of_goods_sold (order_id, warehouse_id, sku_id, quantity, amount) VALUES (?, ?, ?, ?, ?)")
+                .bind(order_id)
+                .bind(warehouse_id)
+                .bind(sku_id)
+                .bind(quantity)
+                .bind(amount)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The discounted price of the base unit, it is the cost of the item received.
+fn unit_price(item: &OrderItem) -> f64 {
+    if item.quantity > 0 {
+        item.get_amount() / item.quantity as f64
+    } else {
+        item.price / item.unit_factor.max(1) as f64
+    }
+}
+
+/// Put the new order into the cost layers, the order must be the latest one.
+pub async fn apply(order: &Order, items: &[OrderItem], method: ValuationMethod, base: OrderCurrency, tx: &mut SqliteConnection) -> Result<()> {
+    if order.is_record {
+        return Ok(());
+    }
+    let columns = OrderHead::columns(base);
+    let row = sqlx::query(&format!("SELECT {columns} FROM orders WHERE id=?")).bind(order.id).fetch_one(&mut *tx).await?;
+    let mut ledger = CostLedger::new(method, false);
+    ledger.apply(&OrderHead::from_row(&row), items, tx).await?;
+    ledger.save(tx).await
+}
+
+/// Apply the orders of the rows by date to the ledger and save it.
+async fn replay(mut ledger: CostLedger, rows: Vec<sqlx::sqlite::SqliteRow>, tx: &mut SqliteConnection) -> Result<()> {
+    for row in rows {
+        let order = OrderHead::from_row(&row);
+        let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id=? ORDER BY rowid").bind(order.id).fetch_all(&mut *tx).await?;
+        ledger.apply(&order, &items, tx).await?;
+    }
+    ledger.save(tx).await
+}
+
+/// Rebuild the cost layers and the cost of goods sold from all orders by date.
+pub async fn rebuild(method: ValuationMethod, base: OrderCurrency, tx: &mut SqliteConnection) -> Result<()> {
+    sqlx::query("DELETE FROM inventory_cost_layers").execute(&mut *tx).await?;
+    sqlx::query("DELETE FROM cost_of_goods_sold").execute(&mut *tx).await?;
+    let columns = OrderHead::columns(base);
+    let rows = sqlx::query(&format!("SELECT {columns} FROM orders WHERE is_record=False AND deleted_at IS NULL ORDER BY date, id"))
+        .fetch_all(&mut *tx)
+        .await?;
+    replay(CostLedger::new(method, true), rows, tx).await
+}
+
+/// The keys the cost flows through by the transfers, the costs moved from the keys since the date and the costs moved into them.
+async fn get_transfer_keys(keys: HashSet<(i64, i64)>, date: i64, tx: &mut SqliteConnection) -> Result<HashSet<(i64, i64)>> {
+    const SELECT: &str = "SELECT DISTINCT orders.warehouse_id, orders.to_warehouse_id FROM orders INNER JOIN order_items ON order_items.order_id=orders.id
+        WHERE orders.order_type='Transfer' AND orders.in_transit=False AND orders.is_record=False AND orders.deleted_at IS NULL AND order_items.sku_id=?";
+    let mut stack: Vec<(i64, i64)> = keys.iter().copied().collect();
+    let mut keys = keys;
+    while let Some((warehouse_id, sku_id)) = stack.pop() {
+        let rows = sqlx::query(&format!("{SELECT} AND ((orders.warehouse_id=? AND orders.date>=?) OR orders.to_warehouse_id=?)"))
+            .bind(sku_id)
+            .bind(warehouse_id)
+            .bind(date)
+            .bind(warehouse_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in rows {
+            for id in [row.get("warehouse_id"), row.get("to_warehouse_id")] {
+                if keys.insert((id, sku_id)) {
+                    stack.push((id, sku_id));
+                }
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Rebuild the cost layers and the cost of goods sold of the (warehouse_id, sku_id) keys changed since the date,
+/// the other keys are kept unless the cost is transferred between them.
+pub async fn rebuild_keys(keys: HashSet<(i64, i64)>, date: i64, method: ValuationMethod, base: OrderCurrency, tx: &mut SqliteConnection) -> Result<()> {
+    let keys = get_transfer_keys(keys, date, tx).await?;
+    if keys.is_empty() {
+        return Ok(());
+    }
+    for (warehouse_id, sku_id) in keys.iter() {
+        sqlx::query("DELETE FROM inventory_cost_layers WHERE warehouse_id=? AND sku_id=?").bind(warehouse_id).bind(sku_id).execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM cost_of_goods_sold WHERE warehouse_id=? AND sku_id=?").bind(warehouse_id).bind(sku_id).execute(&mut *tx).await?;
+    }
+    let warehouses = set_to_string(&keys.iter().map(|key| key.0).collect(), ",");
+    let skus = set_to_string(&keys.iter().map(|key| key.1).collect(), ",");
+    let columns = OrderHead::columns(base);
+    // The strict calibrations change the skus not in the items too.
+    let rows = sqlx::query(&format!(
+        "SELECT {columns} FROM orders WHERE is_record=False AND deleted_at IS NULL AND (warehouse_id IN ({warehouses}) OR to_warehouse_id IN ({warehouses}))
+        AND (order_type='CalibrationStrict' OR id IN (SELECT order_id FROM order_items WHERE sku_id IN ({skus}))) ORDER BY date, id"
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut ledger = CostLedger::new(method, true);
+    ledger.scope = Some(keys);
+    replay(ledger, rows, tx).await
+}
+
+/// Rebuild the cost of the inventory changed by the order since its date.
+pub async fn rebuild_order(order: &Order, items: &[OrderItem], date: i64, method: ValuationMethod, base: OrderCurrency, tx: &mut SqliteConnection) -> Result<()> {
+    let mut keys: HashSet<(i64, i64)> = items.iter().map(|item| (order.warehouse_id, item.sku_id)).collect();
+    match order.order_type {
+        OrderType::Transfer => keys.extend(items.iter().map(|item| (order.to_warehouse_id, item.sku_id))),
+        OrderType::CalibrationStrict => {
+            let rows = sqlx::query("SELECT sku_id FROM inventory WHERE warehouse_id=? UNION SELECT sku_id FROM inventory_cost_layers WHERE warehouse_id=?")
+                .bind(order.warehouse_id)
+                .bind(order.warehouse_id)
+                .fetch_all(&mut *tx)
+                .await?;
+            keys.extend(rows.into_iter().map(|row| (order.warehouse_id, row.get("sku_id"))));
+        }
+        _ => (),
+    }
+    rebuild_keys(keys, date, method, base, tx).await
+}
@@ -1,8 +1,12 @@
 use ahash::HashSet;
 
 pub mod area_module;
+pub mod audit_log_module;
 pub mod config;
+pub mod currency_module;
+pub mod export;
 pub mod guest_order_module;
+pub mod import;
 pub mod inventory_module;
 pub mod meta;
 pub mod model;
@@ -10,9 +14,14 @@ pub mod order_category_module;
 pub mod order_module;
 pub mod order_payment_module;
 pub mod person_module;
+pub mod price_list_module;
+pub mod product_template_module;
+pub mod purchase_order_module;
+pub mod quotation_module;
 pub mod sku_category_module;
 pub mod sku_module;
 pub mod statistical_module;
+pub mod tax_module;
 pub mod user_system;
 pub mod warehouse_module;
 
@@ -20,11 +29,11 @@ pub fn get_test_config() -> config::AppConfig {
     use config::AppConfig;
     use tempfile::tempdir;
 
-    use crate::config::{Limit, Web, TLS, WS};
+    use crate::config::{Currency, Document, Limit, Token, Valuation, Web, TLS, WS};
 
     let tmp_dir = tempdir().expect("Get temp directory failed!");
     AppConfig {
-        data_path: tmp_dir.into_path(),
+        data_path: tmp_dir.keep(),
         web: Web::default(),
         host: "0.0.0.0".to_owned(),
         port: 3345,
@@ -43,6 +52,10 @@ pub fn get_test_config() -> config::AppConfig {
         },
         tls: TLS::default(),
         ws: WS::default(),
+        token: Token::default(),
+        valuation: Valuation::default(),
+        currency: Currency::default(),
+        document: Document::default(),
     }
 }
 
@@ -50,6 +63,11 @@ pub fn i64_safe_max() -> i64 {
     i64::MAX - 1
 }
 
+/// Round the amount to 2 decimals.
+pub fn round_amount(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
 pub fn set_to_string<T: ToString>(set: &HashSet<T>, sep: &str) -> String {
     set.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(sep)
 }
@@ -61,11 +79,16 @@ pub mod sql {
     use anyhow::Result;
     use futures::TryStreamExt;
     use regex::Regex;
-    use sqlx::{sqlite::SqliteRow, Encode, FromRow, Sqlite, SqliteConnection, Type};
+    use sqlx::{sqlite::SqliteRow, Encode, FromRow, Row, Sqlite, SqliteConnection, Type};
 
     const STANDARD_ID_NUM: i64 = 10000;
     static RE: OnceLock<Regex> = OnceLock::new();
 
+    /// The string literal of the value in the conditions, the single quotes are escaped.
+    pub fn quote_str(v: &str) -> String {
+        format!("'{}'", v.replace('\'', "''"))
+    }
+
     pub fn get_search_where_condition(col: &str, query: &str) -> String {
         let re: &Regex = RE.get_or_init(|| Regex::new(r"[\s+\(\)\-\:\@（）]").unwrap());
         let mut tmp = [0u8; 4];
@@ -74,7 +97,7 @@ pub mod sql {
         for q in qs {
             conditions.push(format!("{col} LIKE '%{q}%'"));
         }
-        conditions.join(" AND ").into()
+        conditions.join(" AND ")
     }
 
     pub fn get_sorter_str(sorter: &str) -> &'static str {
@@ -86,58 +109,34 @@ pub mod sql {
     }
 
     pub fn get_sort_col_str(col: &str) -> String {
-        col.replace(":ascend", "").replace(":descend", "").into()
+        col.replace(":ascend", "").replace(":descend", "")
     }
 
     pub fn eq_or_not(reverse: Option<&HashSet<String>>, col: &str) -> &'static str {
         match reverse {
-            Some(reverse) => {
-                if reverse.contains(col) {
-                    "<>"
-                } else {
-                    "="
-                }
-            }
-            None => "=",
+            Some(reverse) if reverse.contains(col) => "<>",
+            _ => "=",
         }
     }
 
     pub fn in_or_not(reverse: Option<&HashSet<String>>, col: &str) -> &'static str {
         match reverse {
-            Some(reverse) => {
-                if reverse.contains(col) {
-                    " NOT IN "
-                } else {
-                    " IN "
-                }
-            }
-            None => " IN ",
+            Some(reverse) if reverse.contains(col) => " NOT IN ",
+            _ => " IN ",
         }
     }
 
     pub fn exists_or_not(reverse: Option<&HashSet<String>>, col: &str) -> &'static str {
         match reverse {
-            Some(reverse) => {
-                if reverse.contains(col) {
-                    " NOT EXISTS "
-                } else {
-                    " EXISTS "
-                }
-            }
-            None => " EXISTS ",
+            Some(reverse) if reverse.contains(col) => " NOT EXISTS ",
+            _ => " EXISTS ",
         }
     }
 
     pub fn like_or_not(reverse: Option<&HashSet<String>>, col: &str) -> &'static str {
         match reverse {
-            Some(reverse) => {
-                if reverse.contains(col) {
-                    " NOT LIKE "
-                } else {
-                    " LIKE "
-                }
-            }
-            None => " LIKE ",
+            Some(reverse) if reverse.contains(col) => " NOT LIKE ",
+            _ => " LIKE ",
         }
     }
 
@@ -194,6 +193,54 @@ pub mod sql {
         Ok(r.rows_affected() == 1)
     }
 
+    /// Condition to filter the soft deleted rows, default only the rows not deleted.
+    pub fn get_deleted_condition(table_name: &str, deleted: Option<bool>) -> String {
+        if deleted.unwrap_or(false) {
+            format!("{table_name}.deleted_at IS NOT NULL")
+        } else {
+            format!("{table_name}.deleted_at IS NULL")
+        }
+    }
+
+    pub async fn is_active_in_table(table_name: &str, row_id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let q = format!("SELECT id FROM {table_name} WHERE id = ? AND deleted_at IS NULL");
+        let mut r = sqlx::query(&q).bind(row_id).fetch(&mut *tx);
+        Ok(r.try_next().await?.is_some())
+    }
+
+    pub async fn get_active_row_from_table<T>(table_name: &str, row_id: i64, tx: &mut SqliteConnection) -> Result<Option<T>>
+    where
+        for<'r> T: FromRow<'r, SqliteRow> + Unpin + Send,
+    {
+        let q = format!("SELECT * FROM {table_name} WHERE id = ? AND deleted_at IS NULL");
+        let mut r = sqlx::query(&q).bind(row_id).fetch(&mut *tx);
+        Ok(if let Some(row) = r.try_next().await? {
+            let v = T::from_row(&row)?;
+            Some(v)
+        } else {
+            None
+        })
+    }
+
+    pub async fn soft_remove_row_from_table(row_id: i64, table_name: &str, deleted_at: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let q = format!("UPDATE {table_name} SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL");
+        let r = sqlx::query(&q).bind(deleted_at).bind(row_id).execute(&mut *tx).await?;
+        Ok(r.rows_affected() == 1)
+    }
+
+    pub async fn restore_row_from_table(row_id: i64, table_name: &str, tx: &mut SqliteConnection) -> Result<bool> {
+        let q = format!("UPDATE {table_name} SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL");
+        let r = sqlx::query(&q).bind(row_id).execute(&mut *tx).await?;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Physically delete the soft deleted rows, return the ids of the rows deleted.
+    pub async fn purge_rows_from_table(table_name: &str, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let q = format!("DELETE FROM {table_name} WHERE deleted_at IS NOT NULL AND deleted_at <= ? RETURNING id");
+        let rows = sqlx::query(&q).bind(deleted_before.unwrap_or(i64::MAX)).fetch_all(&mut *tx).await?;
+        Ok(rows.iter().map(|row| row.get("id")).collect())
+    }
+
     pub async fn try_set_standard_id(creation_id: i64, table_name: &str, tx: &mut SqliteConnection) -> Result<i64> {
         if creation_id == 1 {
             let nid = STANDARD_ID_NUM + 1;
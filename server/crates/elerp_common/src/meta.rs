@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{arg, command, ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand};
 
 #[derive(Debug, Parser, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -46,6 +46,7 @@ pub struct MetaInfo {
 }
 
 impl MetaInfo {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self::parse()
     }
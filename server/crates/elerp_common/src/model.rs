@@ -1,3 +1,5 @@
+pub mod export;
+pub mod import;
 pub mod list_slice;
 pub mod pagination;
 pub mod web_socket_flags;
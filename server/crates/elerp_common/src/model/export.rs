@@ -0,0 +1,40 @@
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Xlsx,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct ExportQuery {
+    /// Xlsx by default.
+    pub format: Option<ExportFormat>,
+    /// One row per order item instead of one row per order, only for the orders.
+    pub with_items: Option<bool>,
+}
+
+impl ExportQuery {
+    pub fn get_format(&self) -> ExportFormat {
+        self.format.unwrap_or_default()
+    }
+}
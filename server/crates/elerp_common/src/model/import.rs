@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::order_module::model::{check_order_result::ItemNotAvailable, order::OrderCurrency};
+
+use super::export::ExportFormat;
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct ImportQuery {
+    /// Format of the file in the body, xlsx by default.
+    pub format: Option<ExportFormat>,
+    /// Validate the rows without saving them.
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ImportOpeningStockQuery {
+    /// Format of the file in the body, xlsx by default.
+    pub format: Option<ExportFormat>,
+    /// Validate the rows without saving them.
+    pub dry_run: Option<bool>,
+    /// Category of the calibration orders.
+    pub order_category_id: i64,
+    /// Currency of the unit costs, the base currency if not set.
+    pub currency: Option<OrderCurrency>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct ImportOrdersQuery {
+    /// Format of the file in the body, xlsx by default.
+    pub format: Option<ExportFormat>,
+    /// Validate the orders without saving them.
+    pub dry_run: Option<bool>,
+    /// Reject all of the orders if any of them is invalid, true by default.
+    /// Otherwise the valid orders are added and the others are skipped.
+    pub all_or_nothing: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema, Clone, PartialEq)]
+pub struct ImportRowError {
+    /// Row number in the spreadsheet, the headers are the row 1.
+    pub row: usize,
+    pub msg: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportResult {
+    pub dry_run: bool,
+    /// The rows are saved only if all of them passed and it is not dry run.
+    pub committed: bool,
+    /// Count of the rows in the file.
+    pub total: usize,
+    pub errors: Vec<ImportRowError>,
+    /// Ids of the rows added, the calibration orders for the opening stock. Empty if not committed.
+    pub ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportOrderResult {
+    /// Value of the `Order` column, the rows with the same value are the lines of the order.
+    pub key: String,
+    /// Row numbers of the lines.
+    pub rows: Vec<usize>,
+    pub valid: bool,
+    /// Id of the order added, 0 if it is not committed.
+    pub id: i64,
+    pub errors: Vec<ImportRowError>,
+    /// Same as the check of the order.
+    pub items_not_available: Vec<ItemNotAvailable>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportOrdersResult {
+    pub dry_run: bool,
+    pub committed: bool,
+    /// Count of the rows in the file.
+    pub total: usize,
+    pub orders: Vec<ImportOrderResult>,
+}
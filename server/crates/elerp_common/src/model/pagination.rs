@@ -54,6 +54,7 @@ impl Pagination {
         self.offset = Some(v);
         self
     }
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> &Self {
         self.index += 1;
         self
@@ -6,33 +6,72 @@ pub enum WebSocketFlags {
     AddArea(i64),
     UpdateArea(i64),
     RemoveArea(i64),
+    RestoreArea(i64),
 
     AddPerson(i64),
     UpdatePerson(i64),
     RemovePerson(i64),
+    RestorePerson(i64),
 
     AddWarehouse(i64),
     UpdateWarehouse(i64),
     RemoveWarehouse(i64),
+    RestoreWarehouse(i64),
     LinkedWarehouse(i64),
     UnlinkedWarehouse(i64),
     
     AddSKUCategory(i64),
     UpdateSKUCategory(i64),
     RemoveSKUCategory(i64),
+    RestoreSKUCategory(i64),
 
     AddSKU(i64),
     UpdateSKU(i64),
     RemoveSKU(i64),
+    RestoreSKU(i64),
+
+    AddProductTemplate(i64),
+    UpdateProductTemplate(i64),
+    RemoveProductTemplate(i64),
 
     AddOrder(i64),
     UpdateOrder(i64),
     RemoveOrder(i64),
+    RestoreOrder(i64),
     AddGuestOrder(i64),
     RemoveGuestOrder(i64),
     ConfirmGuestOrder(i64),
     RecalcOrders,
 
+    /// The sku crossed its reorder point in some warehouse.
+    InventoryAlert(i64),
+    AddReservation(i64),
+    RemoveReservation(i64),
+
+    AddPurchaseOrder(i64),
+    UpdatePurchaseOrder(i64),
+    RemovePurchaseOrder(i64),
+
+    AddQuotation(i64),
+    UpdateQuotation(i64),
+    RemoveQuotation(i64),
+
+    UpdateCurrencies,
+    AddExchangeRate(i64),
+    RemoveExchangeRate(i64),
+    /// Exchange rates imported from a file.
+    ImportExchangeRates,
+
+    AddTaxRate(i64),
+    UpdateTaxRate(i64),
+    RemoveTaxRate(i64),
+
+    AddPriceList(i64),
+    UpdatePriceList(i64),
+    RemovePriceList(i64),
+    /// The items of the price list are changed.
+    UpdatePriceListItems(i64),
+
     ReadyAccess,
     Ping,
     ClearAreas,
@@ -43,13 +82,16 @@ pub enum WebSocketFlags {
     ClearOrders,
     ClearOrderCategories,
     ClearOrderPayments,
+    Purge,
 
     AddOrderCategory(i64),
     UpdateOrderCategory(i64),
     RemoveOrderCategory(i64),
+    RestoreOrderCategory(i64),
 
     AddOrderPayment(i64),
     RemoveOrderPayment(i64),
+    RestoreOrderPayment(i64),
 
     AddUser(i64),
     UpdateUser(i64),
@@ -58,6 +100,10 @@ pub enum WebSocketFlags {
     LinkedUser(i64),
     UnlinkedUser(i64),
 
+    AddRole(i64),
+    UpdateRole(i64),
+    RemoveRole(i64),
+
     UserConnected(i64),
     UserDisconnected(i64),
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::sql::{get_search_where_condition, get_sort_col_str, get_sorter_str};
+use crate::sql::{get_deleted_condition, get_search_where_condition, get_sort_col_str, get_sorter_str};
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
 pub struct OrderCategory {
@@ -20,6 +20,8 @@ pub struct OrderCategory {
 pub struct GetOrderCategoryQuery {
     pub id: Option<i64>,
     pub name: Option<String>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -32,9 +34,10 @@ impl GetOrderCategoryQuery {
         if let Some(v) = &self.name {
             conditions.push(get_search_where_condition("order_categories.name", v));
         }
+        conditions.push(get_deleted_condition("order_categories", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -55,7 +58,7 @@ impl GetOrderCategoryQuery {
 
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
@@ -1,17 +1,23 @@
-use crate::{inventory_module, sql};
-use ahash::{HashMap, HashMapExt};
+use crate::{config::ValuationMethod, inventory_module, sql, tax_module};
+use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 use anyhow::{bail, Result};
 use futures::TryStreamExt;
-use sqlx::{QueryBuilder, Row, SqliteConnection};
+use sqlx::{types::Json, QueryBuilder, Row, SqliteConnection};
 
 use self::model::{
     check_order_result::{CheckOrderResult, ItemNotAvailable},
-    order::{Order, OrderType},
+    order::{Order, OrderCurrency, OrderItem, OrderType},
+    serial_number::{SerialNumberHistory, SerialNumberMovement, SerialNumberStatus},
 };
 
 pub mod model;
 
-pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -> Result<CheckOrderResult> {
+pub async fn check(order: &Order, fast_check: bool, now: i64, tx: &mut SqliteConnection) -> Result<CheckOrderResult> {
+    check_with_quotation(order, 0, fast_check, now, tx).await
+}
+
+/// Check the order converted from the quotation, the quantity reserved by the quotation can be taken by it.
+pub async fn check_with_quotation(order: &Order, quotation_id: i64, fast_check: bool, now: i64, tx: &mut SqliteConnection) -> Result<CheckOrderResult> {
     let mut items_not_available = Vec::new();
     if order.is_record {
         return Ok(CheckOrderResult { items_not_available });
@@ -22,25 +28,81 @@ pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -
         return Ok(CheckOrderResult { items_not_available });
     };
     let mut item_map = HashMap::with_capacity(items.len());
+    let mut lot_map = HashMap::with_capacity(items.len());
     for item in items {
         if !item.exchanged {
             item_map.entry(item.sku_id).and_modify(|q| *q += item.quantity).or_insert(item.quantity);
+            if let Some(lot_number) = item.lot_number.as_ref() {
+                lot_map.entry((item.sku_id, lot_number.clone())).and_modify(|q| *q += item.quantity).or_insert(item.quantity);
+            }
+        }
+    }
+    for item in items {
+        let incoming = match get_item_direction(item.exchanged, order.order_type) {
+            Some(v) => v,
+            None => continue,
+        };
+        for serial_number in item.serial_numbers.iter() {
+            let status = get_serial_number_status(item.sku_id, serial_number, tx).await?;
+            let available = match (incoming, order.order_type) {
+                (false, _) => status == Some((SerialNumberStatus::InStock, order.warehouse_id)),
+                (true, OrderType::StockIn) => !matches!(status, Some((SerialNumberStatus::InStock, _))),
+                (true, _) => matches!(status, Some((SerialNumberStatus::Shipped, _))),
+            };
+            if !available {
+                items_not_available.push(ItemNotAvailable {
+                    sku_id: item.sku_id,
+                    require_quantity: 1,
+                    actual_quantity: 0,
+                    lot_number: None,
+                    serial_number: Some(serial_number.clone()),
+                });
+                if fast_check {
+                    return Ok(CheckOrderResult { items_not_available });
+                }
+            }
         }
     }
     let mut inventory = { inventory_module::get_virtual(items.len()) };
     match order.order_type {
-        OrderType::Exchange | OrderType::StockOut => {
+        OrderType::Exchange | OrderType::StockOut | OrderType::Transfer => {
             for (sku_id, require_quantity) in item_map {
+                // The quantity reserved by others can't be taken.
+                let reserved = inventory_module::get_reserved(order.warehouse_id, sku_id, order.from_guest_order_id, quotation_id, now, tx).await?;
                 let (latest_quantity, actual_quantity) = inventory
                     .get_mut(order.warehouse_id, sku_id, tx)
                     .await?
                     .map(|p| (p.change(p.latest_quantity() - require_quantity), p.quantity()))
                     .unwrap_or((0 - require_quantity, 0));
-                if latest_quantity < 0 {
+                if latest_quantity - reserved < 0 {
+                    items_not_available.push(ItemNotAvailable {
+                        sku_id,
+                        require_quantity,
+                        actual_quantity: actual_quantity - reserved,
+                        lot_number: None,
+                        serial_number: None,
+                    });
+                    if fast_check {
+                        return Ok(CheckOrderResult { items_not_available });
+                    }
+                }
+            }
+            for ((sku_id, lot_number), require_quantity) in lot_map {
+                let actual_quantity = sqlx::query("SELECT quantity FROM inventory_lots WHERE warehouse_id=? AND sku_id=? AND lot_number=? LIMIT 1")
+                    .bind(order.warehouse_id)
+                    .bind(sku_id)
+                    .bind(&lot_number)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .map(|row| row.get("quantity"))
+                    .unwrap_or(0);
+                if actual_quantity < require_quantity {
                     items_not_available.push(ItemNotAvailable {
                         sku_id,
                         require_quantity,
                         actual_quantity,
+                        lot_number: Some(lot_number),
+                        serial_number: None,
                     });
                     if fast_check {
                         return Ok(CheckOrderResult { items_not_available });
@@ -57,6 +119,8 @@ pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -
                         sku_id,
                         require_quantity,
                         actual_quantity,
+                        lot_number: None,
+                        serial_number: None,
                     });
                     if fast_check {
                         return Ok(CheckOrderResult { items_not_available });
@@ -74,6 +138,8 @@ pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -
                         sku_id,
                         require_quantity,
                         actual_quantity,
+                        lot_number: None,
+                        serial_number: None,
                     });
                     if fast_check {
                         return Ok(CheckOrderResult { items_not_available });
@@ -95,6 +161,8 @@ pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -
                         sku_id: row.get("sku_id"),
                         require_quantity: 0,
                         actual_quantity: row.get("quantity"),
+                        lot_number: None,
+                        serial_number: None,
                     });
                     return Ok(CheckOrderResult { items_not_available });
                 }
@@ -106,6 +174,8 @@ pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -
                         sku_id: row.get("sku_id"),
                         require_quantity: 0,
                         actual_quantity: row.get("quantity"),
+                        lot_number: None,
+                        serial_number: None,
                     });
                 }
             }
@@ -114,31 +184,144 @@ pub async fn check(order: &Order, fast_check: bool, tx: &mut SqliteConnection) -
     Ok(CheckOrderResult { items_not_available })
 }
 
-pub async fn add(mut order: Order, tx: &mut SqliteConnection) -> Result<Order> {
-    let items = order.items.as_ref();
-    if let Some(items) = items {
+/// Some(true) when the item is moved into the warehouse by the order, Some(false) when moved out.
+pub fn get_item_direction(exchanged: bool, order_type: OrderType) -> Option<bool> {
+    match order_type {
+        OrderType::StockIn | OrderType::Return => (!exchanged).then_some(true),
+        OrderType::StockOut | OrderType::Transfer => (!exchanged).then_some(false),
+        OrderType::Exchange => Some(exchanged),
+        _ => None,
+    }
+}
+
+/// The serialized skus must name the serial number of each unit and the others must not.
+pub async fn is_serial_numbers_matched(items: &[OrderItem], order_type: OrderType, tx: &mut SqliteConnection) -> Result<bool> {
+    let mut serial_numbers = HashSet::new();
+    for item in items {
+        if get_item_direction(item.exchanged, order_type).is_none() {
+            continue;
+        }
+        let serialized: bool = sqlx::query("SELECT serialized FROM sku_list WHERE id=? LIMIT 1")
+            .bind(item.sku_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.get("serialized"))
+            .unwrap_or(false);
+        let expected = if serialized { item.quantity } else { 0 };
+        if item.serial_numbers.len() as i64 != expected {
+            return Ok(false);
+        }
+        for serial_number in item.serial_numbers.iter() {
+            if !serial_numbers.insert((item.sku_id, serial_number)) {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Get the movements of the serial number in the orders not removed, one history for each sku.
+pub async fn get_serial_number_histories(sku_id: Option<i64>, serial_number: &str, tx: &mut SqliteConnection) -> Result<Vec<SerialNumberHistory>> {
+    let sku_condition = sku_id.map(|v| format!("AND order_items.sku_id={v}")).unwrap_or_default();
+    let rows = sqlx::query(&format!(
+        "SELECT order_items.sku_id, order_items.exchanged, orders.id, orders.order_type, orders.warehouse_id, orders.to_warehouse_id, orders.in_transit, orders.date, orders.is_record
+        FROM order_items
+        INNER JOIN orders ON orders.id=order_items.order_id, json_each(order_items.serial_numbers)
+        WHERE json_each.value=? AND orders.deleted_at IS NULL {sku_condition}
+        ORDER BY order_items.sku_id, orders.date, orders.id"
+    ))
+    .bind(serial_number)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut arr: Vec<SerialNumberHistory> = Vec::new();
+    for row in rows {
+        let sku_id: i64 = row.get("sku_id");
+        let order_type: OrderType = row.get("order_type");
+        let incoming = match get_item_direction(row.get("exchanged"), order_type) {
+            Some(v) => v,
+            None => continue,
+        };
+        let movement = SerialNumberMovement {
+            order_id: row.get("id"),
+            order_type,
+            warehouse_id: row.get("warehouse_id"),
+            date: row.get("date"),
+            incoming,
+            is_record: row.get("is_record"),
+        };
+        if arr.last().map(|h| h.sku_id) != Some(sku_id) {
+            arr.push(SerialNumberHistory {
+                sku_id,
+                serial_number: serial_number.to_owned(),
+                status: None,
+                warehouse_id: None,
+                movements: Vec::new(),
+            });
+        }
+        let history = arr.last_mut().unwrap();
+        // The received transfer moves the serial number into the destination warehouse as well.
+        let received = (order_type == OrderType::Transfer && !row.get::<bool, _>("in_transit")).then(|| SerialNumberMovement {
+            warehouse_id: row.get("to_warehouse_id"),
+            incoming: true,
+            ..movement.clone()
+        });
+        for movement in std::iter::once(movement).chain(received) {
+            if !movement.is_record {
+                history.status = Some(if movement.incoming { SerialNumberStatus::InStock } else { SerialNumberStatus::Shipped });
+                history.warehouse_id = Some(movement.warehouse_id);
+            }
+            history.movements.push(movement);
+        }
+    }
+    Ok(arr)
+}
+
+/// Status and the warehouse of the serial number, none if it is unknown.
+pub async fn get_serial_number_status(sku_id: i64, serial_number: &str, tx: &mut SqliteConnection) -> Result<Option<(SerialNumberStatus, i64)>> {
+    Ok(get_serial_number_histories(Some(sku_id), serial_number, tx).await?.pop().and_then(|h| h.status.zip(h.warehouse_id)))
+}
+
+pub async fn add(mut order: Order, valuation: ValuationMethod, base: OrderCurrency, now: i64, tx: &mut SqliteConnection) -> Result<Order> {
+    // Before the items are split to the lots.
+    order.calc_discounts();
+    if let Some(items) = order.items.take() {
         if !order.is_record {
-            inventory_module::change(order.warehouse_id, items, order.order_type, tx).await?;
+            let items = inventory_module::allocate_lots(order.warehouse_id, items, order.order_type, now, tx).await?;
+            inventory_module::change(order.warehouse_id, &items, order.order_type, tx).await?;
+            if order.order_type == OrderType::Transfer && !order.in_transit {
+                inventory_module::change(order.to_warehouse_id, &items, OrderType::StockIn, tx).await?;
+            }
+            order.items = Some(items);
+        } else {
+            order.items = Some(items);
         }
     }
-    let r = sqlx::query("INSERT INTO orders (from_guest_order_id, created_by_user_id, updated_by_user_id, warehouse_id, currency, total_amount, person_related_id, person_in_charge_id, date, last_updated_date, description, order_type, is_record, non_payment, order_category_id, total_amount_settled, order_payment_status) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+    tax_module::apply(&mut order, tx).await?;
+    let r = sqlx::query("INSERT INTO orders (from_guest_order_id, created_by_user_id, updated_by_user_id, warehouse_id, currency, total_amount, person_related_id, person_in_charge_id, date, last_updated_date, description, order_type, is_record, non_payment, order_category_id, total_amount_settled, order_payment_status, to_warehouse_id, in_transit, tax_inclusive, net_amount, tax_amount, discount, discount_amount) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
         .bind(order.from_guest_order_id)
         .bind(order.created_by_user_id)
         .bind(order.updated_by_user_id)
             .bind(order.warehouse_id)
-            .bind(&order.currency)
+            .bind(order.currency)
             .bind(order.total_amount)
             .bind(order.person_related_id)
             .bind(order.person_in_charge_id)
             .bind(order.date)
             .bind(order.last_updated_date)
             .bind(&order.description)
-            .bind(&order.order_type)
+            .bind(order.order_type)
             .bind(order.is_record)
             .bind(order.non_payment)
             .bind(order.order_category_id)
             .bind(0)
             .bind(order.order_payment_status)
+            .bind(order.to_warehouse_id)
+            .bind(order.in_transit)
+            .bind(order.tax_inclusive)
+            .bind(order.net_amount)
+            .bind(order.tax_amount)
+            .bind(Json(&order.discount))
+            .bind(order.discount_amount)
             .execute(&mut *tx)
             .await?;
     if r.rows_affected() != 1 {
@@ -148,16 +331,47 @@ pub async fn add(mut order: Order, tx: &mut SqliteConnection) -> Result<Order> {
     order.id = sql::try_set_standard_id(r.last_insert_rowid(), "orders", tx).await?;
 
     add_order_items(&order, tx).await?;
+    if let Some(items) = order.items.as_ref() {
+        inventory_module::valuation::apply(&order, items, valuation, base, tx).await?;
+    }
+    add_revision(order.id, order.created_by_user_id, order.date, tx).await?;
 
     Ok(order)
 }
 
+/// Save the current state of the order and its items as the next revision.
+pub async fn add_revision(order_id: i64, user_id: i64, revision_date: i64, tx: &mut SqliteConnection) -> Result<()> {
+    let r = sqlx::query(
+        "INSERT INTO order_revisions (order_id, revision, user_id, revision_date, date, warehouse_id, person_related_id, person_in_charge_id, order_category_id, currency, order_type, description, total_amount)
+        SELECT id, (SELECT COALESCE(MAX(revision), 0) + 1 FROM order_revisions WHERE order_id = ?), ?, ?, date, warehouse_id, person_related_id, person_in_charge_id, order_category_id, currency, order_type, description, total_amount
+        FROM orders WHERE id = ?",
+    )
+    .bind(order_id)
+    .bind(user_id)
+    .bind(revision_date)
+    .bind(order_id)
+    .execute(&mut *tx)
+    .await?;
+    if r.rows_affected() != 1 {
+        bail!("Can't add the order revision!");
+    }
+    sqlx::query(
+        "INSERT INTO order_revision_items (revision_id, sku_id, quantity, price, exchanged, lot_number, expiry_date, serial_numbers, tax_rate, tax_amount, discount, discount_amount, unit, unit_factor)
+        SELECT ?, sku_id, quantity, price, exchanged, lot_number, expiry_date, serial_numbers, tax_rate, tax_amount, discount, discount_amount, unit, unit_factor FROM order_items WHERE order_id = ?",
+    )
+        .bind(r.last_insert_rowid())
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
+
 async fn add_order_items(order: &Order, tx: &mut SqliteConnection) -> Result<()> {
     let items = order.items.as_ref();
-    if items.is_none() || items.as_ref().unwrap().len() == 0 {
+    if items.is_none() || items.as_ref().unwrap().is_empty() {
         return Ok(());
     }
-    let mut query_builder = QueryBuilder::new("INSERT INTO order_items (order_id, sku_id, sku_category_id, quantity, price, exchanged, amount) ");
+    let mut query_builder = QueryBuilder::new("INSERT INTO order_items (order_id, sku_id, sku_category_id, quantity, price, exchanged, amount, lot_number, expiry_date, serial_numbers, tax_rate, tax_amount, discount, discount_amount, unit, unit_factor) ");
     query_builder.push_values(items.unwrap(), |mut b, item| {
         b.push_bind(order.id)
             .push_bind(item.sku_id)
@@ -165,7 +379,16 @@ async fn add_order_items(order: &Order, tx: &mut SqliteConnection) -> Result<()>
             .push_bind(item.quantity)
             .push_bind(item.price)
             .push_bind(item.exchanged)
-            .push_bind(item.quantity as f64 * item.price);
+            .push_bind(item.get_amount())
+            .push_bind(&item.lot_number)
+            .push_bind(item.expiry_date)
+            .push_bind(Json(&item.serial_numbers))
+            .push_bind(item.tax_rate.unwrap_or(0.0))
+            .push_bind(item.tax_amount)
+            .push_bind(Json(&item.discount))
+            .push_bind(item.discount_amount)
+            .push_bind(&item.unit)
+            .push_bind(item.unit_factor.max(1));
     });
     let query = query_builder.build();
     query.execute(&mut *tx).await?;
@@ -1,2 +1,5 @@
 pub mod check_order_result;
+pub mod document;
 pub mod order;
+pub mod order_revision;
+pub mod serial_number;
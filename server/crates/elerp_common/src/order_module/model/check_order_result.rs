@@ -6,6 +6,10 @@ pub struct ItemNotAvailable {
     pub sku_id: i64,
     pub require_quantity: i64,
     pub actual_quantity: i64,
+    /// The lot is not enough, null when the sku is not enough.
+    pub lot_number: Option<String>,
+    /// The serial number is unknown or can't be moved.
+    pub serial_number: Option<String>,
 }
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CheckOrderResult {
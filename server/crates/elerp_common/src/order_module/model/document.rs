@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    /// Prices, totals and payment status of the order.
+    Invoice,
+    /// Items delivered to the person without the prices.
+    DeliveryNote,
+    /// Items with their lots and serial numbers to pack.
+    PackingSlip,
+}
+
+impl DocumentKind {
+    pub fn default_title(&self) -> &'static str {
+        match self {
+            DocumentKind::Invoice => "Invoice",
+            DocumentKind::DeliveryNote => "Delivery Note",
+            DocumentKind::PackingSlip => "Packing Slip",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetDocumentQuery {
+    pub kind: DocumentKind,
+}
@@ -1,15 +1,21 @@
 use ahash::HashSet;
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
-use strum::AsRefStr;
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    prelude::FromRow,
+    sqlite::{SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef},
+    Sqlite,
+};
+use strum::{AsRefStr, EnumString};
 use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    set_to_string,
-    sql::{eq_or_not, exists_or_not, get_sort_col_str, get_sorter_str, in_or_not, like_or_not},
+    round_amount, set_to_string,
+    sql::{eq_or_not, exists_or_not, get_deleted_condition, get_sort_col_str, get_sorter_str, in_or_not, like_or_not},
 };
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, EnumString, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum OrderType {
     StockIn,
     StockOut,
@@ -19,34 +25,92 @@ pub enum OrderType {
     CalibrationStrict,
     Verification,
     VerificationStrict,
+    /// Move the items from the warehouse to the destination warehouse.
+    Transfer,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, strum::Display)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, strum::Display, Default)]
 pub enum OrderPaymentStatus {
     Settled,
+    #[default]
     Unsettled,
     PartialSettled,
     None,
 }
 
-impl Default for OrderPaymentStatus {
-    fn default() -> Self {
-        Self::Unsettled
+#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, AsRefStr, PartialEq, Eq, Clone, Copy)]
+pub enum DiscountType {
+    /// The value is the percent of the amount, 10 means 10% off.
+    Percentage,
+    /// The value is the amount off.
+    Fixed,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+pub struct Discount {
+    pub discount_type: DiscountType,
+    pub value: f64,
+    /// Reason code of the discount, such as "PROMO" or "DAMAGED".
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl Discount {
+    /// Amount off from the amount, the fixed discount can't exceed the amount.
+    pub fn calc(&self, amount: f64) -> f64 {
+        match self.discount_type {
+            DiscountType::Percentage => amount * self.value / 100.0,
+            DiscountType::Fixed => self.value.min(amount),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let max = match self.discount_type {
+            DiscountType::Percentage => 100.0,
+            DiscountType::Fixed => f64::MAX,
+        };
+        self.value.is_finite() && (0.0..=max).contains(&self.value)
     }
 }
 
-#[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-pub enum OrderCurrency {
-    CNY,
-    HKD,
-    USD,
-    GBP,
-    MYR,
-    IDR,
-    INR,
-    PHP,
-    Unknown,
+/// ISO 4217 code of the currency, the available codes are stored in the currencies table.
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct OrderCurrency([u8; 3]);
+
+impl OrderCurrency {
+    pub const CNY: Self = Self(*b"CNY");
+    pub const HKD: Self = Self(*b"HKD");
+    pub const USD: Self = Self(*b"USD");
+    pub const GBP: Self = Self(*b"GBP");
+    pub const MYR: Self = Self(*b"MYR");
+    pub const IDR: Self = Self(*b"IDR");
+    pub const INR: Self = Self(*b"INR");
+    pub const PHP: Self = Self(*b"PHP");
+    #[allow(non_upper_case_globals)]
+    pub const Unknown: Self = Self([0; 3]);
+
+    /// Parse the three letters code, "Unknown" is kept for the orders without currency.
+    pub fn new(code: &str) -> Option<Self> {
+        if code == "Unknown" {
+            return Some(Self::Unknown);
+        }
+        let bytes: [u8; 3] = code.as_bytes().try_into().ok()?;
+        if bytes.iter().all(u8::is_ascii_alphabetic) {
+            Some(Self(bytes.map(|b| b.to_ascii_uppercase())))
+        } else {
+            None
+        }
+    }
+}
+
+impl AsRef<str> for OrderCurrency {
+    fn as_ref(&self) -> &str {
+        if *self == Self::Unknown {
+            "Unknown"
+        } else {
+            std::str::from_utf8(&self.0).unwrap_or("Unknown")
+        }
+    }
 }
 
 impl Default for OrderCurrency {
@@ -55,6 +119,63 @@ impl Default for OrderCurrency {
     }
 }
 
+impl std::fmt::Display for OrderCurrency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl std::fmt::Debug for OrderCurrency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl Serialize for OrderCurrency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderCurrency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Self::new(&code).ok_or_else(|| serde::de::Error::custom(format!("invalid currency code: {code}")))
+    }
+}
+
+impl<'s> ToSchema<'s> for OrderCurrency {
+    fn schema() -> (&'s str, utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>) {
+        (
+            "OrderCurrency",
+            utoipa::openapi::ObjectBuilder::new()
+                .schema_type(utoipa::openapi::SchemaType::String)
+                .description(Some("ISO 4217 currency code"))
+                .example(Some("USD".into()))
+                .into(),
+        )
+    }
+}
+
+impl sqlx::Type<Sqlite> for OrderCurrency {
+    fn type_info() -> SqliteTypeInfo {
+        <String as sqlx::Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Sqlite> for OrderCurrency {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        <String as sqlx::Encode<Sqlite>>::encode(self.as_ref().to_owned(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for OrderCurrency {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let code = <&str as sqlx::Decode<Sqlite>>::decode(value)?;
+        Ok(Self::new(code).unwrap_or_default())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Order {
     /// Id will generated by the system.
@@ -86,12 +207,28 @@ pub struct Order {
     pub currency: OrderCurrency,
     #[serde(default)]
     pub items: Option<Vec<OrderItem>>,
+    /// Gross amount including the tax, the payments settle it.
     #[serde(default)]
     pub total_amount: f64,
+    /// Discount of the order, shared by the items in proportion to their amounts after their own discounts.
+    #[serde(default)]
+    pub discount: Option<Discount>,
+    /// Total discount amount of the order and its items will generated by the system.
+    #[serde(default)]
+    pub discount_amount: f64,
     #[serde(default)]
     pub total_amount_settled: f64,
     #[serde(default)]
     pub order_payment_status: OrderPaymentStatus,
+    /// The prices of the items include the tax.
+    #[serde(default)]
+    pub tax_inclusive: bool,
+    /// Amount excluding the tax will generated by the system.
+    #[serde(default)]
+    pub net_amount: f64,
+    /// Tax amount will generated by the system.
+    #[serde(default)]
+    pub tax_amount: f64,
     #[serde(default)]
     pub warehouse_id: i64,
     #[serde(default)]
@@ -103,18 +240,146 @@ pub struct Order {
     pub is_record: bool,
     #[serde(default)]
     pub non_payment: bool,
+    /// Destination warehouse of the transfer order, 0 for the other order types.
+    #[serde(default)]
+    pub to_warehouse_id: i64,
+    /// The transfer order is shipped from the warehouse but not yet received by the destination warehouse.
+    #[serde(default)]
+    pub in_transit: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone, IntoParams, FromRow)]
 pub struct OrderItem {
+    /// Id of the sku, it can be 0 if the sku code is set.
+    #[serde(default)]
     pub sku_id: i64,
+    /// Code or barcode of the sku in place of the sku id, it is resolved when the order is added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(skip)]
+    pub sku_code: Option<String>,
+    /// Quantity in the base unit of the sku.
+    /// If the unit is entered without the factor, it is the quantity in the unit and converted by the system.
     pub quantity: i64,
+    /// Unit the item is entered in, the price is the price of the unit. None means the base unit.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub unit: Option<String>,
+    /// Quantity of the base unit in one of the unit will generated by the system, it must be the factor of the sku if it is sent.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub unit_factor: i64,
+    /// List price of the sku, it is filled by the price lists when it is omitted.
+    #[serde(default = "OrderItem::price_not_set")]
     pub price: f64,
     #[serde(default)]
     pub exchanged: bool,
+    /// Lot of the sku. Stock out without lot number will consume the lots first-expired, first-out.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub lot_number: Option<String>,
+    /// Expiry date of the lot, only used when the lot is stocked in.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub expiry_date: Option<i64>,
+    /// Serial number of each unit, required by the serialized sku when it is moved.
+    #[serde(default)]
+    #[sqlx(default, json)]
+    pub serial_numbers: Vec<String>,
+    /// Tax rate in percent overriding the default of the sku or its category.
+    /// The default is filled when the order is added.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub tax_rate: Option<f64>,
+    /// Tax amount of the item will generated by the system.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub tax_amount: f64,
+    /// Discount of the item, the price is kept as the list price.
+    #[serde(default)]
+    #[sqlx(default, json)]
+    pub discount: Option<Discount>,
+    /// Discount amount of the item including its share of the order discount will generated by the system.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub discount_amount: f64,
+}
+
+impl OrderItem {
+    fn price_not_set() -> f64 {
+        f64::NAN
+    }
+
+    pub fn is_price_set(&self) -> bool {
+        !self.price.is_nan()
+    }
+
+    /// Amount of the list price subtract the discount.
+    pub fn get_amount(&self) -> f64 {
+        self.get_list_amount() - self.discount_amount
+    }
+
+    /// Amount of the list price without the discount.
+    pub fn get_list_amount(&self) -> f64 {
+        self.get_unit_quantity() * self.price
+    }
+
+    /// Quantity in the unit the item is entered in.
+    pub fn get_unit_quantity(&self) -> f64 {
+        self.quantity as f64 / self.unit_factor.max(1) as f64
+    }
+}
+
+impl Order {
+    /// Clear the amounts and the price of items for user can't view order price.
+    pub fn hide_price(&mut self) {
+        self.total_amount = 0.0;
+        self.total_amount_settled = 0.0;
+        self.net_amount = 0.0;
+        self.tax_amount = 0.0;
+        self.discount = None;
+        self.discount_amount = 0.0;
+        if let Some(items) = self.items.as_mut() {
+            items.iter_mut().for_each(|item| {
+                item.price = 0.0;
+                item.tax_amount = 0.0;
+                item.discount = None;
+                item.discount_amount = 0.0;
+            });
+        }
+    }
+
+    /// Calculate the discount amount of each item, the exchanged items are not discounted.
+    pub fn calc_discounts(&mut self) {
+        let Some(items) = self.items.as_mut() else {
+            self.discount_amount = 0.0;
+            return;
+        };
+        for item in items.iter_mut() {
+            let amount = item.get_list_amount();
+            item.discount_amount = match (&item.discount, item.exchanged) {
+                (Some(discount), false) => round_amount(discount.calc(amount)),
+                _ => 0.0,
+            };
+        }
+        if let Some(discount) = &self.discount {
+            let amounts: Vec<(usize, f64)> = items.iter().enumerate().filter(|(_, item)| !item.exchanged).map(|(i, item)| (i, item.get_amount())).collect();
+            let total: f64 = amounts.iter().map(|(_, amount)| amount).sum();
+            if total > 0.0 {
+                let order_discount = round_amount(discount.calc(total));
+                let mut remain = order_discount;
+                for (n, (i, amount)) in amounts.iter().enumerate() {
+                    // The last item takes the remain to avoid the rounding difference.
+                    let share = if n + 1 == amounts.len() { round_amount(remain) } else { round_amount(order_discount * amount / total) };
+                    items[*i].discount_amount = round_amount(items[*i].discount_amount + share);
+                    remain -= share;
+                }
+            }
+        }
+        self.discount_amount = round_amount(items.iter().map(|item| item.discount_amount).sum());
+    }
 }
 
-#[derive(Debug, Default, Deserialize, ToSchema, IntoParams)]
+#[derive(Debug, Default, Deserialize, ToSchema, IntoParams, Clone)]
 pub struct GetOrdersQuery {
     pub id: Option<i64>,
     pub created_by_user_id: Option<i64>,
@@ -135,6 +400,8 @@ pub struct GetOrdersQuery {
     pub date_end: Option<i64>,
     pub last_updated_date_start: Option<i64>,
     pub last_updated_date_end: Option<i64>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
     pub reverse: Option<HashSet<String>>,
 }
@@ -163,6 +430,7 @@ impl GetOrdersQuery {
             last_updated_date_start: None,
             last_updated_date_end: None,
             non_payment: None,
+            deleted: None,
         }
     }
     pub fn get_where_condition(&self) -> String {
@@ -183,21 +451,23 @@ impl GetOrdersQuery {
         }
         if let Some(v) = &self.fuzzy {
             let eq = like_or_not(reverse, "fuzzy");
-            conditions.push(format!("CAST(orders.id AS TEXT) {eq} '%{v}%' OR persons_related.name {eq} '%{v}%' OR persons_in_charge.name {eq} '%{v}%' OR order_status_list.name {eq} '%{v}%' OR warehouses.name {eq} '%{v}%'"));
+            conditions.push(format!("(CAST(orders.id AS TEXT) {eq} '%{v}%' OR persons_related.name {eq} '%{v}%' OR persons_in_charge.name {eq} '%{v}%' OR order_status_list.name {eq} '%{v}%' OR warehouses.name {eq} '%{v}%')"));
         }
         if let Some(v) = &self.warehouse_ids {
             let eq = in_or_not(reverse, "warehouse_ids");
-            let v = set_to_string(&v, ",");
-            conditions.push(format!("orders.warehouse_id{eq}({v})"));
+            let v = set_to_string(v, ",");
+            // The transfer orders belong to the destination warehouse too.
+            let or = if eq.contains("NOT") { "AND" } else { "OR" };
+            conditions.push(format!("(orders.warehouse_id{eq}({v}) {or} orders.to_warehouse_id{eq}({v}))"));
         }
         if let Some(v) = &self.items {
             let ext = exists_or_not(reverse, "items");
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("{ext} (SELECT 1 FROM order_items oi WHERE oi.order_id=orders.id AND oi.sku_id IN ({v}))"));
         }
         if let Some(v) = &self.item_categories {
             let ext = exists_or_not(reverse, "item_categories");
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("{ext} (SELECT 1 FROM order_items oi WHERE oi.order_id=orders.id AND oi.sku_category_id IN ({v}))"));
         }
         if let Some(v) = &self.person_related_id {
@@ -245,9 +515,10 @@ impl GetOrdersQuery {
         if let Some(v) = &self.last_updated_date_end {
             conditions.push(format!("orders.last_updated_date_end<={v}"));
         }
+        conditions.push(get_deleted_condition("orders", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -262,21 +533,21 @@ impl GetOrdersQuery {
             let mut col = get_sort_col_str(sorter);
             let sort = get_sorter_str(sorter);
             if col == "warehouse_id" {
-                col = format!("warehouse_name {sort}").into();
+                col = format!("warehouse_name {sort}");
             } else if col == "person_related_id" {
-                col = format!("person_related_name {sort}").into();
+                col = format!("person_related_name {sort}");
             } else if col == "person_in_charge_id" {
-                col = format!("person_in_charge_name {sort}").into();
+                col = format!("person_in_charge_name {sort}");
             } else if col == "order_category_id" {
-                col = format!("order_status_name {sort}").into();
+                col = format!("order_status_name {sort}");
             } else {
-                col = format!("orders.{col} {sort}").into();
+                col = format!("orders.{col} {sort}");
             }
             conditions.push(col);
         }
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
@@ -0,0 +1,210 @@
+use ahash::{HashMap, HashMapExt};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::order::{OrderCurrency, OrderItem, OrderType};
+
+/// Snapshot of the order and its items after it is added or updated.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct OrderRevision {
+    pub id: i64,
+    pub order_id: i64,
+    /// Revision number of the order, start from 1.
+    pub revision: i64,
+    /// The user who made the revision.
+    pub user_id: i64,
+    /// Date of the revision made.
+    pub revision_date: i64,
+    pub date: i64,
+    pub warehouse_id: i64,
+    pub person_related_id: i64,
+    pub person_in_charge_id: i64,
+    pub order_category_id: i64,
+    pub currency: OrderCurrency,
+    pub order_type: OrderType,
+    pub description: String,
+    pub total_amount: f64,
+    pub items: Vec<OrderItem>,
+    /// Changes from the previous revision, null for the first revision.
+    pub diff: Option<OrderRevisionDiff>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
+pub struct OrderRevisionDiff {
+    pub fields: Vec<OrderFieldChange>,
+    pub items: Vec<OrderItemChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+pub struct OrderFieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Quantity or price change of the sku, null quantity and price mean the item is not exists in that side.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+pub struct OrderItemChange {
+    pub sku_id: i64,
+    pub exchanged: bool,
+    pub before_quantity: Option<i64>,
+    pub after_quantity: Option<i64>,
+    pub before_price: Option<f64>,
+    pub after_price: Option<f64>,
+    /// Changes of the unit, the discount and the tax of the item exists in both sides.
+    pub fields: Vec<OrderFieldChange>,
+}
+
+/// The items of the same sku summed up to compare.
+struct ItemSummary {
+    quantity: i64,
+    price: f64,
+    unit: String,
+    unit_factor: i64,
+    discount: String,
+    discount_amount: f64,
+    tax_rate: f64,
+    tax_amount: f64,
+}
+
+impl ItemSummary {
+    fn new(item: &OrderItem) -> Self {
+        Self {
+            quantity: item.quantity,
+            price: item.price,
+            unit: item.unit.clone().unwrap_or_default(),
+            unit_factor: item.unit_factor.max(1),
+            discount: serde_json::to_string(&item.discount).unwrap_or_default(),
+            discount_amount: item.discount_amount,
+            // The tax rate is stored as 0 if it is not set.
+            tax_rate: item.tax_rate.unwrap_or(0.0),
+            tax_amount: item.tax_amount,
+        }
+    }
+
+    fn changes_from(&self, prev: &ItemSummary) -> Vec<OrderFieldChange> {
+        let mut fields = Vec::new();
+        let mut push = |field: &str, before: String, after: String| {
+            if before != after {
+                fields.push(OrderFieldChange {
+                    field: field.to_owned(),
+                    before,
+                    after,
+                });
+            }
+        };
+        push("unit", prev.unit.clone(), self.unit.clone());
+        push("unit_factor", prev.unit_factor.to_string(), self.unit_factor.to_string());
+        push("discount", prev.discount.clone(), self.discount.clone());
+        push("discount_amount", prev.discount_amount.to_string(), self.discount_amount.to_string());
+        push("tax_rate", prev.tax_rate.to_string(), self.tax_rate.to_string());
+        push("tax_amount", prev.tax_amount.to_string(), self.tax_amount.to_string());
+        fields
+    }
+}
+
+impl OrderRevision {
+    /// Compare with the previous revision.
+    pub fn changes_from(&self, prev: &OrderRevision) -> OrderRevisionDiff {
+        let mut fields = Vec::new();
+        let mut push = |field: &str, before: String, after: String| {
+            if before != after {
+                fields.push(OrderFieldChange {
+                    field: field.to_owned(),
+                    before,
+                    after,
+                });
+            }
+        };
+        push("date", prev.date.to_string(), self.date.to_string());
+        push("warehouse_id", prev.warehouse_id.to_string(), self.warehouse_id.to_string());
+        push("person_related_id", prev.person_related_id.to_string(), self.person_related_id.to_string());
+        push("person_in_charge_id", prev.person_in_charge_id.to_string(), self.person_in_charge_id.to_string());
+        push("order_category_id", prev.order_category_id.to_string(), self.order_category_id.to_string());
+        push("currency", prev.currency.as_ref().to_owned(), self.currency.as_ref().to_owned());
+        push("order_type", prev.order_type.as_ref().to_owned(), self.order_type.as_ref().to_owned());
+        push("description", prev.description.clone(), self.description.clone());
+        push("total_amount", prev.total_amount.to_string(), self.total_amount.to_string());
+
+        let before = Self::group_items(&prev.items);
+        let after = Self::group_items(&self.items);
+        let mut items = Vec::new();
+        for (key, b) in before.iter() {
+            match after.get(key) {
+                Some(a) => {
+                    let fields = a.changes_from(b);
+                    if a.quantity != b.quantity || a.price != b.price || !fields.is_empty() {
+                        items.push(OrderItemChange {
+                            sku_id: key.0,
+                            exchanged: key.1,
+                            before_quantity: Some(b.quantity),
+                            after_quantity: Some(a.quantity),
+                            before_price: Some(b.price),
+                            after_price: Some(a.price),
+                            fields,
+                        });
+                    }
+                }
+                None => items.push(OrderItemChange {
+                    sku_id: key.0,
+                    exchanged: key.1,
+                    before_quantity: Some(b.quantity),
+                    after_quantity: None,
+                    before_price: Some(b.price),
+                    after_price: None,
+                    fields: vec![],
+                }),
+            }
+        }
+        for (key, a) in after.iter() {
+            if !before.contains_key(key) {
+                items.push(OrderItemChange {
+                    sku_id: key.0,
+                    exchanged: key.1,
+                    before_quantity: None,
+                    after_quantity: Some(a.quantity),
+                    before_price: None,
+                    after_price: Some(a.price),
+                    fields: vec![],
+                });
+            }
+        }
+        items.sort_by_key(|item| (item.sku_id, item.exchanged));
+        OrderRevisionDiff { fields, items }
+    }
+
+    /// Sum the quantity and the amounts of the same sku, the others are the first one.
+    fn group_items(items: &[OrderItem]) -> HashMap<(i64, bool), ItemSummary> {
+        let mut map: HashMap<(i64, bool), ItemSummary> = HashMap::with_capacity(items.len());
+        for item in items {
+            map.entry((item.sku_id, item.exchanged))
+                .and_modify(|v| {
+                    v.quantity += item.quantity;
+                    v.discount_amount += item.discount_amount;
+                    v.tax_amount += item.tax_amount;
+                })
+                .or_insert_with(|| ItemSummary::new(item));
+        }
+        map
+    }
+
+    /// Clear the amount and the prices for user can't view order price.
+    pub fn hide_price(&mut self) {
+        self.total_amount = 0.0;
+        self.items.iter_mut().for_each(|item| {
+            item.price = 0.0;
+            item.discount = None;
+            item.discount_amount = 0.0;
+            item.tax_amount = 0.0;
+        });
+        if let Some(diff) = self.diff.as_mut() {
+            diff.fields.retain(|f| f.field != "total_amount");
+            diff.items.retain_mut(|item| {
+                item.before_price = item.before_price.map(|_| 0.0);
+                item.after_price = item.after_price.map(|_| 0.0);
+                item.fields.retain(|f| !matches!(f.field.as_str(), "discount" | "discount_amount" | "tax_amount"));
+                item.before_quantity != item.after_quantity || !item.fields.is_empty()
+            });
+        }
+    }
+}
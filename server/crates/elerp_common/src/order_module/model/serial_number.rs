@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use strum::AsRefStr;
+use utoipa::{IntoParams, ToSchema};
+
+use super::order::OrderType;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, AsRefStr, PartialEq, Eq, Clone, Copy)]
+pub enum SerialNumberStatus {
+    InStock,
+    Shipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct SerialNumberMovement {
+    pub order_id: i64,
+    pub order_type: OrderType,
+    pub warehouse_id: i64,
+    pub date: i64,
+    /// Moved into the warehouse, otherwise moved out.
+    pub incoming: bool,
+    /// Record orders are not changed the status of the serial number.
+    pub is_record: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct SerialNumberHistory {
+    pub sku_id: i64,
+    pub serial_number: String,
+    /// Null when the serial number is only in the record orders.
+    pub status: Option<SerialNumberStatus>,
+    /// The warehouse of the last movement.
+    pub warehouse_id: Option<i64>,
+    pub movements: Vec<SerialNumberMovement>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetSerialNumberQuery {
+    pub serial_number: String,
+    pub sku_id: Option<i64>,
+}
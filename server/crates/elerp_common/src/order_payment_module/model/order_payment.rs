@@ -1,5 +1,5 @@
 use ahash::HashSet;
-use crate::{set_to_string, sql::{get_sort_col_str, get_sorter_str}};
+use crate::{set_to_string, sql::{get_deleted_condition, get_sort_col_str, get_sorter_str}};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use utoipa::{IntoParams, ToSchema};
@@ -37,6 +37,8 @@ pub struct GetOrderPaymentsQuery {
     pub creation_date_end: Option<i64>,
     pub actual_date_start: Option<i64>,
     pub actual_date_end: Option<i64>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -47,7 +49,7 @@ impl GetOrderPaymentsQuery {
             conditions.push(format!("order_payments.id = {v}"));
         }
         if let Some(v) = &self.warehouse_ids {
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             conditions.push(format!("orders.warehouse_id IN ({v})"));
         }
         if let Some(v) = &self.person_in_charge_id {
@@ -71,9 +73,10 @@ impl GetOrderPaymentsQuery {
         if let Some(v) = &self.actual_date_end {
             conditions.push(format!("order_payments.actual_date<={v}"));
         }
+        conditions.push(get_deleted_condition("order_payments", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -88,15 +91,15 @@ impl GetOrderPaymentsQuery {
             let mut col = get_sort_col_str(sorter);
             let sort = get_sorter_str(sorter);
             if col == "person_id" {
-                col = format!("person_name {sort}").into();
+                col = format!("person_name {sort}");
             } else {
-                col = format!("order_payments.{col} {sort}").into();
+                col = format!("order_payments.{col} {sort}");
             }
             conditions.push(col);
         }
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
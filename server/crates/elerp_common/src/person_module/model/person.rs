@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::sql::{get_search_where_condition, get_sort_col_str, get_sorter_str};
+use crate::sql::{get_deleted_condition, get_search_where_condition, get_sort_col_str, get_sorter_str};
 
 #[derive(Debug, Deserialize, Serialize, ToSchema, FromRow)]
 pub struct Person {
@@ -25,6 +25,10 @@ pub struct Person {
     pub email: String,
     pub color: Option<String>,
     pub text_color: Option<String>,
+    /// Price list of the person, 0 means the price list of its area.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub price_list_id: i64,
 }
 
 #[derive(Debug, Default, Deserialize, ToSchema, IntoParams)]
@@ -36,6 +40,8 @@ pub struct GetPersonsQuery {
     pub person_in_charge_id: Option<i64>,
     pub contact: Option<String>,
     pub email: Option<String>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -43,32 +49,33 @@ impl GetPersonsQuery {
     pub fn get_where_condition(&self) -> String {
         let mut conditions = Vec::with_capacity(4);
         if let Some(v) = &self.id {
-            conditions.push(format!("persons.id = {v}").into());
+            conditions.push(format!("persons.id = {v}"));
         }
         if let Some(v) = &self.name {
             conditions.push(get_search_where_condition("persons.name", v));
         }
         if let Some(v) = &self.address {
             let v = v.trim();
-            conditions.push(format!("persons.address LIKE '%{v}%'").into());
+            conditions.push(format!("persons.address LIKE '%{v}%'"));
         }
         if let Some(v) = &self.area_id {
-            conditions.push(format!("persons.area_id={v}").into());
+            conditions.push(format!("persons.area_id={v}"));
         }
         if let Some(v) = &self.person_in_charge_id {
-            conditions.push(format!("persons.person_in_charge_id={v}").into());
+            conditions.push(format!("persons.person_in_charge_id={v}"));
         }
         if let Some(v) = &self.contact {
             let v = v.trim();
-            conditions.push(format!("persons.contact LIKE '%{v}%'").into());
+            conditions.push(format!("persons.contact LIKE '%{v}%'"));
         }
         if let Some(v) = &self.email {
             let v = v.trim();
-            conditions.push(format!("persons.email LIKE '%{v}%'").into());
+            conditions.push(format!("persons.email LIKE '%{v}%'"));
         }
+        conditions.push(get_deleted_condition("persons", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -84,11 +91,11 @@ impl GetPersonsQuery {
                 let mut col = get_sort_col_str(sorter);
                 let sort = get_sorter_str(sorter);
                 if col == "person_in_charge_id" {
-                    col = format!("person_in_charge_name {sort}").into();
+                    col = format!("person_in_charge_name {sort}");
                 } else if col == "area_id" {
-                    col = format!("area_name {sort}").into();
+                    col = format!("area_name {sort}");
                 } else {
-                    col = format!("persons.{col} {sort}").into();
+                    col = format!("persons.{col} {sort}");
                 }
 
                 conditions.push(col);
@@ -97,7 +104,7 @@ impl GetPersonsQuery {
 
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use sqlx::SqliteConnection;
+
+use crate::order_module::model::order::{OrderCurrency, OrderItem};
+
+use self::model::price_list::AppliedPrice;
+
+pub mod model;
+
+/// Price of the sku for the person at the date, the person's list first, then its area's list, then the default list.
+/// A list without the valid price of the sku falls back to the next one.
+pub async fn get_price(person_id: i64, sku_id: i64, currency: OrderCurrency, date: i64, tx: &mut SqliteConnection) -> Result<Option<AppliedPrice>> {
+    Ok(sqlx::query_as(
+        "WITH lists AS (
+            SELECT price_list_id AS id, 0 AS priority FROM persons WHERE id=?1 AND price_list_id<>0
+            UNION ALL
+            SELECT areas.price_list_id AS id, 1 AS priority FROM areas INNER JOIN persons ON persons.area_id=areas.id WHERE persons.id=?1 AND areas.price_list_id<>0
+            UNION ALL
+            SELECT id, 2 AS priority FROM price_lists WHERE is_default
+        )
+        SELECT price_list_items.price_list_id, price_list_items.id AS price_list_item_id, price_list_items.price
+        FROM price_list_items
+        INNER JOIN lists ON lists.id=price_list_items.price_list_id
+        WHERE price_list_items.sku_id=?2 AND price_list_items.currency=?3
+        AND (price_list_items.valid_from IS NULL OR price_list_items.valid_from<=?4)
+        AND (price_list_items.valid_to IS NULL OR price_list_items.valid_to>=?4)
+        ORDER BY lists.priority ASC, price_list_items.valid_from DESC
+        LIMIT 1",
+    )
+    .bind(person_id)
+    .bind(sku_id)
+    .bind(currency)
+    .bind(date)
+    .fetch_optional(&mut *tx)
+    .await?)
+}
+
+/// Fill the prices of the items without the price from the price lists of the person at the date.
+/// The prices of the lists are the prices of the base units. Return the skus without any price.
+pub async fn fill_prices(person_id: i64, currency: OrderCurrency, date: i64, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+    let mut not_found = Vec::new();
+    for item in items.iter_mut().filter(|item| !item.is_price_set()) {
+        match get_price(person_id, item.sku_id, currency, date, tx).await? {
+            Some(v) => item.price = v.price * item.unit_factor.max(1) as f64,
+            None => not_found.push(item.sku_id),
+        }
+    }
+    Ok(not_found)
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::order_module::model::order::OrderCurrency;
+
+/// Prices of the skus, it is assigned to the persons or the areas, the default one is used by the others.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct PriceList {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Only one price list is the default.
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct PriceListItem {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    pub price_list_id: i64,
+    pub sku_id: i64,
+    pub currency: OrderCurrency,
+    pub price: f64,
+    /// The price is valid from the date, null means no start.
+    #[serde(default)]
+    pub valid_from: Option<i64>,
+    /// The price is valid until the date, null means no end.
+    #[serde(default)]
+    pub valid_to: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct GetPriceListItemsQuery {
+    pub price_list_id: Option<i64>,
+    pub sku_id: Option<i64>,
+    pub currency: Option<OrderCurrency>,
+}
+
+impl GetPriceListItemsQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(3);
+        if let Some(v) = &self.price_list_id {
+            conditions.push(format!("price_list_items.price_list_id={v}"));
+        }
+        if let Some(v) = &self.sku_id {
+            conditions.push(format!("price_list_items.sku_id={v}"));
+        }
+        if let Some(v) = &self.currency {
+            conditions.push(format!("price_list_items.currency='{}'", v.as_ref()));
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetPriceQuery {
+    pub person_id: i64,
+    pub sku_id: i64,
+    pub currency: OrderCurrency,
+    /// Now by default.
+    pub date: Option<i64>,
+}
+
+/// Price from the most specific price list, the list of the person first, then the list of its area, then the default list.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone, PartialEq)]
+pub struct AppliedPrice {
+    pub price_list_id: i64,
+    pub price_list_item_id: i64,
+    pub price: f64,
+}
@@ -0,0 +1 @@
+pub mod product_template;
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::sql::get_search_where_condition;
+
+/// Template of the skus differing only in the attributes, such as the colour and the size of a T-shirt.
+/// Each combination of the attribute values is a variant sku of the template.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct ProductTemplate {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    /// Category of the variants generated.
+    pub sku_category_id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Axes of the variants, in the order of the names of the variants.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub attributes: Vec<TemplateAttribute>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+pub struct TemplateAttribute {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl ProductTemplate {
+    /// Attributes of all the variants, each one is a combination of the values of the attributes.
+    pub fn get_variants(&self) -> Vec<BTreeMap<String, String>> {
+        let mut variants = vec![BTreeMap::new()];
+        for attribute in self.attributes.iter() {
+            variants = variants
+                .into_iter()
+                .flat_map(|variant| {
+                    attribute.values.iter().map(move |value| {
+                        let mut v = variant.clone();
+                        v.insert(attribute.name.clone(), value.clone());
+                        v
+                    })
+                })
+                .collect();
+        }
+        variants
+    }
+
+    /// The template name followed by the values of the variant, such as `T-shirt red M`.
+    pub fn get_variant_name(&self, attributes: &BTreeMap<String, String>) -> String {
+        let mut name = self.name.clone();
+        for attribute in self.attributes.iter() {
+            if let Some(v) = attributes.get(&attribute.name) {
+                name.push(' ');
+                name.push_str(v);
+            }
+        }
+        name
+    }
+
+    /// The variant has one of the values of each attribute and nothing else.
+    pub fn is_valid_variant(&self, attributes: &BTreeMap<String, String>) -> bool {
+        attributes.len() == self.attributes.len()
+            && self.attributes.iter().all(|attribute| attributes.get(&attribute.name).is_some_and(|v| attribute.values.contains(v)))
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct GetProductTemplatesQuery {
+    pub sku_category_id: Option<i64>,
+    pub name: Option<String>,
+}
+
+impl GetProductTemplatesQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(2);
+        if let Some(v) = self.sku_category_id {
+            conditions.push(format!("product_templates.sku_category_id={v}"));
+        }
+        if let Some(v) = &self.name {
+            conditions.push(get_search_where_condition("product_templates.name", v.trim()));
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        }
+    }
+}
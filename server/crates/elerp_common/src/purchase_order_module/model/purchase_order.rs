@@ -0,0 +1,282 @@
+use ahash::{HashMap, HashMapExt, HashSet};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use strum::AsRefStr;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    order_module::model::order::{Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    set_to_string,
+    sql::{eq_or_not, get_sort_col_str, get_sorter_str, in_or_not},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, AsRefStr, Type, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum PurchaseOrderStatus {
+    /// Can be updated or removed, nothing can be received.
+    #[default]
+    Draft,
+    /// Waiting for the goods.
+    Approved,
+    PartiallyReceived,
+    Received,
+    /// Nothing can be received anymore.
+    Closed,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, FromRow)]
+pub struct PurchaseOrderItem {
+    pub sku_id: i64,
+    pub quantity: i64,
+    pub price: f64,
+    /// Quantity received by the stock in orders, generated by the system.
+    #[serde(default)]
+    pub received_quantity: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct PurchaseOrder {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub created_by_user_id: i64,
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub updated_by_user_id: i64,
+    /// Date will generated by the system.
+    #[serde(default)]
+    pub date: i64,
+    /// Last updated date will generated by the system.
+    #[serde(default)]
+    pub last_updated_date: i64,
+    /// Person in charge of the supplier, generated by the system.
+    #[serde(default)]
+    pub person_in_charge_id: i64,
+    /// The person supplies the items.
+    pub supplier_id: i64,
+    /// The warehouse receives the items.
+    pub warehouse_id: i64,
+    /// Order category of the stock in orders.
+    pub order_category_id: i64,
+    #[serde(default)]
+    pub currency: OrderCurrency,
+    #[serde(default)]
+    pub description: String,
+    /// Status will generated by the system.
+    #[serde(default)]
+    pub status: PurchaseOrderStatus,
+    /// Total amount will generated by the system.
+    #[serde(default)]
+    pub total_amount: f64,
+    #[serde(default)]
+    pub items: Option<Vec<PurchaseOrderItem>>,
+    /// Stock in orders received against the purchase order, generated by the system.
+    #[serde(default)]
+    pub order_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct PurchaseOrderReceiptItem {
+    pub sku_id: i64,
+    pub quantity: i64,
+    #[serde(default)]
+    pub lot_number: Option<String>,
+    #[serde(default)]
+    pub expiry_date: Option<i64>,
+    #[serde(default)]
+    pub serial_numbers: Vec<String>,
+}
+
+/// Items received against the purchase order, the price is taken from the purchase order.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PurchaseOrderReceipt {
+    pub items: Vec<PurchaseOrderReceiptItem>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PurchaseOrderReceived {
+    pub purchase_order: PurchaseOrder,
+    /// The stock in order created by the receipt.
+    pub order: Order,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct GetPurchaseOrdersQuery {
+    pub id: Option<i64>,
+    pub created_by_user_id: Option<i64>,
+    pub supplier_id: Option<i64>,
+    pub warehouse_ids: Option<HashSet<i64>>,
+    pub order_category_id: Option<i64>,
+    pub currency: Option<OrderCurrency>,
+    pub status: Option<PurchaseOrderStatus>,
+    pub date_start: Option<i64>,
+    pub date_end: Option<i64>,
+    pub sorters: Option<Vec<String>>,
+    pub reverse: Option<HashSet<String>>,
+}
+
+impl PurchaseOrderItem {
+    pub fn outstanding_quantity(&self) -> i64 {
+        (self.quantity - self.received_quantity).max(0)
+    }
+}
+
+impl PurchaseOrder {
+    /// Clear the amounts and the price of items for user can't view order price.
+    pub fn hide_price(&mut self) {
+        self.total_amount = 0.0;
+        if let Some(items) = self.items.as_mut() {
+            items.iter_mut().for_each(|item| item.price = 0.0);
+        }
+    }
+
+    /// All items of the receipt are in the purchase order and not more than the outstanding quantities.
+    pub fn is_receivable(&self, receipt: &PurchaseOrderReceipt) -> bool {
+        let mut outstanding = HashMap::new();
+        for item in self.items.iter().flatten() {
+            *outstanding.entry(item.sku_id).or_insert(0) += item.outstanding_quantity();
+        }
+        for item in receipt.items.iter() {
+            match outstanding.get_mut(&item.sku_id) {
+                Some(quantity) if item.quantity > 0 && item.quantity <= *quantity => *quantity -= item.quantity,
+                _ => return false,
+            }
+        }
+        !receipt.items.is_empty()
+    }
+
+    /// The stock in order of the receipt, the user fields are generated when it is added.
+    pub fn to_receipt_order(&self, receipt: PurchaseOrderReceipt) -> Order {
+        let prices: HashMap<i64, f64> = self.items.iter().flatten().map(|item| (item.sku_id, item.price)).collect();
+        let items = receipt
+            .items
+            .into_iter()
+            .map(|item| OrderItem {
+                sku_id: item.sku_id,
+                quantity: item.quantity,
+                price: prices.get(&item.sku_id).copied().unwrap_or(0.0),
+                exchanged: false,
+                lot_number: item.lot_number,
+                expiry_date: item.expiry_date,
+                serial_numbers: item.serial_numbers,
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            })
+            .collect();
+        let description = if receipt.description.is_empty() {
+            format!("Received against the purchase order #{}", self.id)
+        } else {
+            receipt.description
+        };
+        Order {
+            id: 0,
+            created_by_user_id: 0,
+            updated_by_user_id: 0,
+            date: 0,
+            last_updated_date: 0,
+            person_in_charge_id: self.person_in_charge_id,
+            order_category_id: self.order_category_id,
+            from_guest_order_id: 0,
+            currency: self.currency,
+            items: Some(items),
+            total_amount: 0.0,
+            total_amount_settled: 0.0,
+            order_payment_status: OrderPaymentStatus::Unsettled,
+            warehouse_id: self.warehouse_id,
+            person_related_id: self.supplier_id,
+            description,
+            order_type: OrderType::StockIn,
+            is_record: false,
+            non_payment: false,
+            to_warehouse_id: 0,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+        }
+    }
+}
+
+impl GetPurchaseOrdersQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(5);
+        let reverse = self.reverse.as_ref();
+
+        if let Some(v) = &self.id {
+            let eq = eq_or_not(reverse, "id");
+            conditions.push(format!("purchase_orders.id{eq}{v}"));
+        }
+        if let Some(v) = &self.created_by_user_id {
+            let eq = eq_or_not(reverse, "created_by_user_id");
+            conditions.push(format!("purchase_orders.created_by_user_id{eq}{v}"));
+        }
+        if let Some(v) = &self.supplier_id {
+            let eq = eq_or_not(reverse, "supplier_id");
+            conditions.push(format!("purchase_orders.supplier_id{eq}{v}"));
+        }
+        if let Some(v) = &self.warehouse_ids {
+            let eq = in_or_not(reverse, "warehouse_ids");
+            let v = set_to_string(v, ",");
+            conditions.push(format!("purchase_orders.warehouse_id{eq}({v})"));
+        }
+        if let Some(v) = &self.order_category_id {
+            let eq = eq_or_not(reverse, "order_category_id");
+            conditions.push(format!("purchase_orders.order_category_id{eq}{v}"));
+        }
+        if let Some(v) = &self.currency {
+            let eq = eq_or_not(reverse, "currency");
+            conditions.push(format!("purchase_orders.currency{eq}'{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.status {
+            let eq = eq_or_not(reverse, "status");
+            conditions.push(format!("purchase_orders.status{eq}'{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.date_start {
+            conditions.push(format!("purchase_orders.date>={v}"));
+        }
+        if let Some(v) = &self.date_end {
+            conditions.push(format!("purchase_orders.date<={v}"));
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        } else {
+            "".into()
+        }
+    }
+
+    pub fn get_order_condition(&self) -> String {
+        if self.sorters.is_none() {
+            return "".into();
+        }
+        let mut conditions = vec![];
+        for sorter in self.sorters.as_ref().unwrap() {
+            let col = get_sort_col_str(sorter);
+            let sort = get_sorter_str(sorter);
+            let col = if col == "warehouse_id" {
+                format!("warehouse_name {sort}")
+            } else if col == "supplier_id" {
+                format!("supplier_name {sort}")
+            } else {
+                format!("purchase_orders.{col} {sort}")
+            };
+            conditions.push(col);
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(", ");
+            format!("ORDER BY {c}")
+        } else {
+            "".into()
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use ahash::HashSet;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use strum::AsRefStr;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    order_module::model::{
+        check_order_result::CheckOrderResult,
+        order::{Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    },
+    set_to_string,
+    sql::{eq_or_not, get_sort_col_str, get_sorter_str, in_or_not},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, AsRefStr, Type, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum QuotationStatus {
+    #[default]
+    Draft,
+    /// The quotation document is sent to the customer.
+    Sent,
+    /// The stock out order is created from the quotation.
+    Converted,
+    /// Valid date is passed before it is converted.
+    Expired,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, FromRow)]
+pub struct QuotationItem {
+    pub sku_id: i64,
+    pub quantity: i64,
+    pub price: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct Quotation {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub created_by_user_id: i64,
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub updated_by_user_id: i64,
+    /// Date will generated by the system.
+    #[serde(default)]
+    pub date: i64,
+    /// Last updated date will generated by the system.
+    #[serde(default)]
+    pub last_updated_date: i64,
+    /// Person in charge will generated by the system.
+    #[serde(default)]
+    pub person_in_charge_id: i64,
+    /// The customer of the quotation.
+    pub person_related_id: i64,
+    /// The warehouse ships the items after the quotation is converted.
+    pub warehouse_id: i64,
+    pub order_category_id: i64,
+    #[serde(default)]
+    pub currency: OrderCurrency,
+    #[serde(default)]
+    pub description: String,
+    /// The quotation is expired after the date.
+    pub valid_until: i64,
+    /// Status will generated by the system.
+    #[serde(default)]
+    pub status: QuotationStatus,
+    /// Count of the revisions, generated by the system.
+    #[serde(default)]
+    pub revision: i64,
+    /// The order converted from the quotation, generated by the system.
+    #[serde(default)]
+    pub order_id: i64,
+    /// Total amount will generated by the system.
+    #[serde(default)]
+    pub total_amount: f64,
+    #[serde(default)]
+    pub items: Option<Vec<QuotationItem>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QuotationConvert {
+    pub check_result: CheckOrderResult,
+    /// None if some items are not available.
+    pub order: Option<Order>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
+pub struct GetQuotationsQuery {
+    pub id: Option<i64>,
+    pub created_by_user_id: Option<i64>,
+    pub person_related_id: Option<i64>,
+    pub warehouse_ids: Option<HashSet<i64>>,
+    pub currency: Option<OrderCurrency>,
+    pub status: Option<QuotationStatus>,
+    pub date_start: Option<i64>,
+    pub date_end: Option<i64>,
+    pub valid_until_start: Option<i64>,
+    pub valid_until_end: Option<i64>,
+    pub sorters: Option<Vec<String>>,
+    pub reverse: Option<HashSet<String>>,
+}
+
+impl Quotation {
+    /// Clear the amounts and the price of items for user can't view order price.
+    pub fn hide_price(&mut self) {
+        self.total_amount = 0.0;
+        if let Some(items) = self.items.as_mut() {
+            items.iter_mut().for_each(|item| item.price = 0.0);
+        }
+    }
+
+    /// The stock out order of the quotation, the user fields are generated when it is added.
+    pub fn to_order(&self) -> Order {
+        let items = self
+            .items
+            .iter()
+            .flatten()
+            .map(|item| OrderItem {
+                sku_id: item.sku_id,
+                quantity: item.quantity,
+                price: item.price,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            })
+            .collect();
+        Order {
+            id: 0,
+            created_by_user_id: 0,
+            updated_by_user_id: 0,
+            date: 0,
+            last_updated_date: 0,
+            person_in_charge_id: self.person_in_charge_id,
+            order_category_id: self.order_category_id,
+            from_guest_order_id: 0,
+            currency: self.currency,
+            items: Some(items),
+            total_amount: 0.0,
+            total_amount_settled: 0.0,
+            order_payment_status: OrderPaymentStatus::Unsettled,
+            warehouse_id: self.warehouse_id,
+            person_related_id: self.person_related_id,
+            description: self.description.clone(),
+            order_type: OrderType::StockOut,
+            is_record: false,
+            non_payment: false,
+            to_warehouse_id: 0,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+        }
+    }
+}
+
+impl GetQuotationsQuery {
+    pub fn get_where_condition(&self) -> String {
+        let mut conditions = Vec::with_capacity(5);
+        let reverse = self.reverse.as_ref();
+
+        if let Some(v) = &self.id {
+            let eq = eq_or_not(reverse, "id");
+            conditions.push(format!("quotations.id{eq}{v}"));
+        }
+        if let Some(v) = &self.created_by_user_id {
+            let eq = eq_or_not(reverse, "created_by_user_id");
+            conditions.push(format!("quotations.created_by_user_id{eq}{v}"));
+        }
+        if let Some(v) = &self.person_related_id {
+            let eq = eq_or_not(reverse, "person_related_id");
+            conditions.push(format!("quotations.person_related_id{eq}{v}"));
+        }
+        if let Some(v) = &self.warehouse_ids {
+            let eq = in_or_not(reverse, "warehouse_ids");
+            let v = set_to_string(v, ",");
+            conditions.push(format!("quotations.warehouse_id{eq}({v})"));
+        }
+        if let Some(v) = &self.currency {
+            let eq = eq_or_not(reverse, "currency");
+            conditions.push(format!("quotations.currency{eq}'{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.status {
+            let eq = eq_or_not(reverse, "status");
+            conditions.push(format!("quotations.status{eq}'{}'", v.as_ref()));
+        }
+        if let Some(v) = &self.date_start {
+            conditions.push(format!("quotations.date>={v}"));
+        }
+        if let Some(v) = &self.date_end {
+            conditions.push(format!("quotations.date<={v}"));
+        }
+        if let Some(v) = &self.valid_until_start {
+            conditions.push(format!("quotations.valid_until>={v}"));
+        }
+        if let Some(v) = &self.valid_until_end {
+            conditions.push(format!("quotations.valid_until<={v}"));
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(" AND ");
+            format!("WHERE {c}")
+        } else {
+            "".into()
+        }
+    }
+
+    pub fn get_order_condition(&self) -> String {
+        if self.sorters.is_none() {
+            return "".into();
+        }
+        let mut conditions = vec![];
+        for sorter in self.sorters.as_ref().unwrap() {
+            let col = get_sort_col_str(sorter);
+            let sort = get_sorter_str(sorter);
+            let col = if col == "warehouse_id" {
+                format!("warehouse_name {sort}")
+            } else if col == "person_related_id" {
+                format!("person_related_name {sort}")
+            } else {
+                format!("quotations.{col} {sort}")
+            };
+            conditions.push(col);
+        }
+        if !conditions.is_empty() {
+            let c = conditions.join(", ");
+            format!("ORDER BY {c}")
+        } else {
+            "".into()
+        }
+    }
+}
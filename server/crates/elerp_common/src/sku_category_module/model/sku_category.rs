@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::sql::{get_search_where_condition, get_sort_col_str, get_sorter_str};
+use crate::sql::{get_deleted_condition, get_search_where_condition, get_sort_col_str, get_sorter_str};
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
 pub struct SKUCategory {
@@ -15,12 +15,18 @@ pub struct SKUCategory {
     pub description: String,
     pub color: Option<String>,
     pub text_color: Option<String>,
+    /// Default tax rate of the skus in the category, 0 means tax free.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub tax_rate_id: i64,
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
 pub struct GetSKUCategoriesQuery {
     pub id: Option<i64>,
     pub name: Option<String>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -28,14 +34,15 @@ impl GetSKUCategoriesQuery {
     pub fn get_where_condition(&self) -> String {
         let mut conditions = Vec::with_capacity(1);
         if let Some(v) = &self.id {
-            conditions.push(format!("sku_categories.id = {v}").into());
+            conditions.push(format!("sku_categories.id = {v}"));
         }
         if let Some(v) = &self.name {
             conditions.push(get_search_where_condition("sku_categories.name", v));
         }
+        conditions.push(get_deleted_condition("sku_categories", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -56,7 +63,7 @@ impl GetSKUCategoriesQuery {
 
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
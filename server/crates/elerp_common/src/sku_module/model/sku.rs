@@ -1,8 +1,10 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::sql::{get_search_where_condition, get_sort_col_str, get_sorter_str};
+use crate::sql::{get_deleted_condition, get_search_where_condition, get_sort_col_str, get_sorter_str, quote_str};
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow)]
@@ -18,13 +20,95 @@ pub struct SKU {
     pub description: String,
     pub color: Option<String>,
     pub text_color: Option<String>,
+    /// Each unit of the sku is tracked by the serial number.
+    #[serde(default)]
+    pub serialized: bool,
+    /// Default tax rate of the sku, 0 means the default of its category.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub tax_rate_id: i64,
+    /// Internal code of the sku, unique if it is not empty.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub code: String,
+    /// Base unit of measure, such as `pcs` or `kg`, the quantities of the inventory are counted in it.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub unit: String,
+    /// Other units of the sku with their conversion to the base unit, such as the carton of 24 pcs.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub units: Vec<SKUUnit>,
+    /// EAN/UPC barcodes of the sku, each one is unique.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub barcodes: Vec<String>,
+    /// Product template of the variant, 0 means the sku is not a variant.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub template_id: i64,
+    /// Attribute values of the sku, such as `colour=red`, the variants have one value of each attribute of the template.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub attributes: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+pub struct SKUUnit {
+    pub name: String,
+    /// Quantity of the base unit in one of the unit.
+    pub factor: i64,
+}
+
+impl SKU {
+    /// Base units in one of the unit, 1 for the base unit or an empty unit.
+    pub fn get_unit_factor(&self, unit: &str) -> Option<i64> {
+        if unit.is_empty() || unit == self.unit {
+            return Some(1);
+        }
+        self.units.iter().find(|v| v.name == unit).map(|v| v.factor)
+    }
+
+    /// The first barcode with the wrong length or check digit.
+    pub fn get_invalid_barcode(&self) -> Option<&str> {
+        self.barcodes.iter().find(|v| !is_valid_barcode(v)).map(|v| v.as_str())
+    }
+}
+
+/// EAN-8, UPC-A, EAN-13 or GTIN-14 with the right check digit.
+pub fn is_valid_barcode(code: &str) -> bool {
+    if !matches!(code.len(), 8 | 12 | 13 | 14) || !code.bytes().all(|v| v.is_ascii_digit()) {
+        return false;
+    }
+    let digits: Vec<u32> = code.bytes().map(|v| (v - b'0') as u32).collect();
+    let (check, digits) = digits.split_last().unwrap();
+    // The weights are 3 and 1 alternately from the digit next to the check digit.
+    let sum: u32 = digits.iter().rev().enumerate().map(|(i, v)| if i % 2 == 0 { v * 3 } else { *v }).sum();
+    (10 - sum % 10) % 10 == *check
+}
+
+/// Conditions of the skus in the column having all of the attribute values.
+pub fn get_attributes_conditions(sku_id_col: &str, attributes: &BTreeMap<String, String>) -> Vec<String> {
+    attributes
+        .iter()
+        .map(|(name, value)| {
+            let (name, value) = (quote_str(name), quote_str(value));
+            format!("EXISTS (SELECT 1 FROM sku_attributes WHERE sku_attributes.sku_id={sku_id_col} AND sku_attributes.name={name} AND sku_attributes.value={value})")
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Default)]
 pub struct GetSKUsQuery {
     pub id: Option<i64>,
     pub sku_category_id: Option<i64>,
     pub name: Option<String>,
+    pub code: Option<String>,
+    pub template_id: Option<i64>,
+    /// Only the skus having all of the attribute values.
+    pub attributes: Option<BTreeMap<String, String>>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -32,18 +116,29 @@ impl GetSKUsQuery {
     pub fn get_where_condition(&self) -> String {
         let mut conditions = Vec::with_capacity(2);
         if let Some(v) = &self.id {
-            conditions.push(format!("sku_list.id = {v}").into());
+            conditions.push(format!("sku_list.id = {v}"));
         }
         if let Some(v) = self.sku_category_id {
-            conditions.push(format!("sku_list.sku_category_id={v}").into());
+            conditions.push(format!("sku_list.sku_category_id={v}"));
         }
         if let Some(v) = &self.name {
             let v = v.trim();
             conditions.push(get_search_where_condition("sku_list.name", v));
         }
+        if let Some(v) = &self.code {
+            let v = v.trim();
+            conditions.push(get_search_where_condition("sku_list.code", v));
+        }
+        if let Some(v) = self.template_id {
+            conditions.push(format!("sku_list.template_id={v}"));
+        }
+        if let Some(v) = &self.attributes {
+            conditions.extend(get_attributes_conditions("sku_list.id", v));
+        }
+        conditions.push(get_deleted_condition("sku_list", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -56,12 +151,12 @@ impl GetSKUsQuery {
         }
         if let Some(sorters) = self.sorters.as_ref() {
             for sorter in sorters {
-                let mut col = get_sort_col_str(&sorter);
+                let mut col = get_sort_col_str(sorter);
                 let sort = get_sorter_str(sorter);
                 if col == "sku_category_id" {
-                    col = format!("sku_category_name {sort}").into();
+                    col = format!("sku_category_name {sort}");
                 } else {
-                    col = format!("sku_list.{col} {sort}").into();
+                    col = format!("sku_list.{col} {sort}");
                 }
                 conditions.push(col)
             }
@@ -69,7 +164,7 @@ impl GetSKUsQuery {
 
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
@@ -3,14 +3,17 @@ use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::order_module::model::order::{GetOrdersQuery, OrderCurrency};
+use crate::order_module::model::order::{GetOrdersQuery, OrderCurrency, OrderType};
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone, PartialOrd)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
 pub struct PopularSKU {
     pub id: i64,
     pub currency: OrderCurrency,
     pub order_count: i64,
+    /// Average of the prices after the discounts.
     pub average_price: f64,
+    /// Average of the list prices before the discounts.
+    pub average_list_price: f64,
     pub total_out: i64,
 }
 impl PartialEq for PopularSKU {
@@ -20,6 +23,12 @@ impl PartialEq for PopularSKU {
 }
 impl Eq for PopularSKU {}
 
+impl PartialOrd for PopularSKU {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for PopularSKU {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.total_out {
@@ -30,12 +39,18 @@ impl Ord for PopularSKU {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone, PartialOrd)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
 pub struct SalesAmountWithCurrency {
     pub any: f64,
     pub settled: f64,
     pub unsettled: f64,
     pub partial_settled: f64,
+    /// Tax included in the amount.
+    pub tax: f64,
+    /// Cost of the items sold by the orders not record.
+    pub cost_of_goods_sold: f64,
+    /// Net amount of the orders not record subtract the cost of goods sold.
+    pub gross_margin: f64,
     pub currency: OrderCurrency,
 }
 
@@ -46,6 +61,12 @@ impl PartialEq for SalesAmountWithCurrency {
 }
 impl Eq for SalesAmountWithCurrency {}
 
+impl PartialOrd for SalesAmountWithCurrency {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for SalesAmountWithCurrency {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.any {
@@ -60,6 +81,10 @@ impl Ord for SalesAmountWithCurrency {
 pub struct StatisticalOrderData {
     pub total_count: StatisticalOrderCountData,
     pub total_amount: Vec<SalesAmountWithCurrency>,
+    /// Total amount of all the currencies converted to the base currency at the rate of the order date.
+    pub total_amount_in_base_currency: SalesAmountWithCurrency,
+    /// Count of the orders excluded from the converted total amount because their currency has no rate.
+    pub unconverted_count: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
@@ -73,6 +98,7 @@ pub struct StatisticalOrderCountData {
     pub calibration_strict_count: i64,
     pub verification_count: i64,
     pub verification_strict_count: i64,
+    pub transfer_count: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
@@ -86,6 +112,8 @@ pub struct StatisticalData {
     pub order_category_count: i64,
 
     pub most_popular_skus: Vec<PopularSKU>,
+    /// The popular skus summed by the product templates of the variants, the id is the template id.
+    pub most_popular_templates: Vec<PopularSKU>,
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams, Clone, PartialEq, Eq)]
@@ -120,3 +148,57 @@ impl GetStatisticalDataQuery {
         q
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaxSummaryPeriod {
+    Day,
+    #[default]
+    Month,
+    Year,
+}
+
+impl TaxSummaryPeriod {
+    pub fn get_format(&self) -> &'static str {
+        match self {
+            TaxSummaryPeriod::Day => "%Y-%m-%d",
+            TaxSummaryPeriod::Month => "%Y-%m",
+            TaxSummaryPeriod::Year => "%Y",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams, Clone, PartialEq, Eq)]
+pub struct GetTaxSummaryQuery {
+    pub date_start: Option<i64>,
+    pub date_end: Option<i64>,
+    pub warehouse_ids: Option<HashSet<i64>>,
+    pub currency: Option<OrderCurrency>,
+    pub order_type: Option<OrderType>,
+    /// Month by default.
+    pub period: Option<TaxSummaryPeriod>,
+}
+
+impl GetTaxSummaryQuery {
+    pub fn get_order_query(&self) -> GetOrdersQuery {
+        let mut q = GetOrdersQuery::empty();
+        q.date_start = self.date_start;
+        q.date_end = self.date_end;
+        q.warehouse_ids = self.warehouse_ids.clone();
+        q.currency = self.currency;
+        q.order_type = self.order_type;
+        q
+    }
+}
+
+/// Amounts of the items with the same tax rate in a period, the exchanged items are not counted.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct TaxSummary {
+    /// Formatted as YYYY-MM-DD, YYYY-MM or YYYY by the period in UTC.
+    pub period: String,
+    pub currency: OrderCurrency,
+    pub order_type: OrderType,
+    pub tax_rate: f64,
+    pub net_amount: f64,
+    pub tax_amount: f64,
+    pub gross_amount: f64,
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use sqlx::{Row, SqliteConnection};
+
+use crate::{
+    order_module::model::order::{Order, OrderItem},
+    round_amount,
+};
+
+pub mod model;
+
+/// Tax rate of the sku in percent, the rate of the sku first, then the rate of its category, 0 if none.
+pub async fn get_default_rate(sku_id: i64, tx: &mut SqliteConnection) -> Result<f64> {
+    Ok(sqlx::query(
+        "SELECT COALESCE(
+            (SELECT rate FROM tax_rates WHERE tax_rates.id=sku_list.tax_rate_id),
+            (SELECT rate FROM tax_rates INNER JOIN sku_categories ON sku_categories.tax_rate_id=tax_rates.id WHERE sku_categories.id=sku_list.sku_category_id),
+            0.0
+        ) AS rate FROM sku_list WHERE id=?",
+    )
+    .bind(sku_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .map(|row| row.get("rate"))
+    .unwrap_or(0.0))
+}
+
+/// Calculate the tax of each item, return the net, tax and gross amount of them.
+/// The exchanged items are not counted, the item without the tax rate is tax free, the tax is based on the discounted amount.
+pub fn calc_amounts(items: &mut [OrderItem], tax_inclusive: bool) -> (f64, f64, f64) {
+    let (mut net, mut tax, mut gross) = (0.0, 0.0, 0.0);
+    for item in items.iter_mut() {
+        if item.exchanged {
+            item.tax_amount = 0.0;
+            continue;
+        }
+        let amount = item.get_amount();
+        let rate = item.tax_rate.unwrap_or(0.0) / 100.0;
+        item.tax_amount = if tax_inclusive { round_amount(amount - amount / (1.0 + rate)) } else { round_amount(amount * rate) };
+        let (item_net, item_gross) = if tax_inclusive { (amount - item.tax_amount, amount) } else { (amount, amount + item.tax_amount) };
+        net += item_net;
+        tax += item.tax_amount;
+        gross += item_gross;
+    }
+    (net, tax, gross)
+}
+
+/// Fill the tax rates of the items without override by the defaults, then recalculate the amounts of the order.
+pub async fn apply(order: &mut Order, tx: &mut SqliteConnection) -> Result<()> {
+    let Some(items) = order.items.as_mut() else {
+        return Ok(());
+    };
+    for item in items.iter_mut() {
+        if item.tax_rate.is_none() {
+            item.tax_rate = Some(get_default_rate(item.sku_id, tx).await?);
+        }
+    }
+    (order.net_amount, order.tax_amount, order.total_amount) = calc_amounts(items, order.tax_inclusive);
+    Ok(())
+}
@@ -0,0 +1 @@
+pub mod tax_rate;
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Tax such as VAT or GST, it is the default of the skus or the categories linked to it.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct TaxRate {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    /// Rate in percent, 7.5 means 7.5%.
+    pub rate: f64,
+    #[serde(default)]
+    pub description: String,
+}
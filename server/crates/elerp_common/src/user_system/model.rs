@@ -1,3 +1,5 @@
 pub mod user_info;
 pub mod user_permission;
 pub mod user_configure;
+pub mod user_session;
+pub mod role;
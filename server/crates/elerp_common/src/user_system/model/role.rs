@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user_permission::Permission;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct Role {
+    /// Id will generated by the system.
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub permissions: Vec<Permission>,
+}
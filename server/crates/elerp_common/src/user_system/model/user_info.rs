@@ -5,6 +5,8 @@ use utoipa::{IntoParams, ToSchema};
 
 use crate::model::action_type::ActionType;
 
+use super::user_permission::Permission;
+
 #[derive(
     Debug,
     Serialize,
@@ -30,9 +32,18 @@ pub struct UserInfo {
     pub id: i64,
     pub alias: String,
     pub username: String,
+    /// Only used to receive the password. Never return the stored password hash.
+    #[serde(default, skip_serializing)]
     pub password: String,
     pub user_type: UserType,
-    pub permission: i64,
+    /// Id of the roles the user hold. The roles are kept if it is none when update the user.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub roles: Option<Vec<i64>>,
+    /// Permissions granted by the roles. It is ignored when add or update the user.
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub permissions: Vec<Permission>,
     #[serde(default)]
     pub is_connected: bool,
 }
@@ -1,3 +1,8 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter, IntoEnumIterator};
+use utoipa::ToSchema;
+
+// Legacy permission bitmask of the users, only used to migrate the users to roles.
 pub const MANAGE_AREA: i64 = 1;
 pub const MANAGE_PERSON: i64 = 2;
 pub const MANAGE_WAREHOUSE: i64 = 4;
@@ -8,3 +13,57 @@ pub const UPDATE_REMOVE_ORDER: i64 = 64;
 pub const MANAGE_ORDER_CATEGORY: i64 = 128;
 pub const ADD_ORDER_PAYMENT: i64 = 256;
 pub const UPDATE_REMOVE_ORDER_PAYMENT: i64 = 512;
+
+/// Permission bundled by the roles. Admin have all permissions.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Hash, sqlx::Type, AsRefStr, EnumIter, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Permission {
+    ManageArea,
+    ManagePerson,
+    ManageWarehouse,
+    ManageSKU,
+    ManageSKUCategory,
+    ManageOrderCategory,
+    AddOrder,
+    UpdateRemoveOrder,
+    AddOrderPayment,
+    UpdateRemoveOrderPayment,
+
+    ViewArea,
+    ViewPerson,
+    ViewWarehouse,
+    ViewSKU,
+    ViewSKUCategory,
+    ViewOrderCategory,
+    ViewOrder,
+    /// Without it the price and amount of orders will be hidden.
+    ViewOrderPrice,
+    ViewOrderPayment,
+    ViewInventory,
+    ViewStatistics,
+}
+
+impl Permission {
+    pub fn is_view(&self) -> bool {
+        self.as_ref().starts_with("View")
+    }
+
+    pub fn views() -> Vec<Permission> {
+        Permission::iter().filter(|p| p.is_view()).collect()
+    }
+
+    /// Legacy permission bit with the name of role will be migrated to.
+    pub fn legacy() -> [(i64, Permission, &'static str); 10] {
+        [
+            (MANAGE_AREA, Permission::ManageArea, "Manage area"),
+            (MANAGE_PERSON, Permission::ManagePerson, "Manage person"),
+            (MANAGE_WAREHOUSE, Permission::ManageWarehouse, "Manage warehouse"),
+            (MANAGE_SKU, Permission::ManageSKU, "Manage SKU"),
+            (MANAGE_SKU_CATEGORY, Permission::ManageSKUCategory, "Manage SKU category"),
+            (ADD_ORDER, Permission::AddOrder, "Add order"),
+            (UPDATE_REMOVE_ORDER, Permission::UpdateRemoveOrder, "Update and remove order"),
+            (MANAGE_ORDER_CATEGORY, Permission::ManageOrderCategory, "Manage order category"),
+            (ADD_ORDER_PAYMENT, Permission::AddOrderPayment, "Add order payment"),
+            (UPDATE_REMOVE_ORDER_PAYMENT, Permission::UpdateRemoveOrderPayment, "Update and remove order payment"),
+        ]
+    }
+}
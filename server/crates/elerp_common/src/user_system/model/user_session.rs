@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+/// Active login session of the user. Token values are never stored, only their hash.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct UserSession {
+    pub id: i64,
+    pub user_id: i64,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub refresh_expires_at: i64,
+    pub is_connected: bool,
+}
+
+/// Tokens issued to the user when login or refresh.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct UserToken {
+    pub session_id: i64,
+    pub token: String,
+    pub expires_at: i64,
+    pub refresh_token: String,
+    pub refresh_expires_at: i64,
+}
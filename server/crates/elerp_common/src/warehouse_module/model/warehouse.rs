@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::sql::{get_search_where_condition, get_sort_col_str, get_sorter_str};
+use crate::sql::{get_deleted_condition, get_search_where_condition, get_sort_col_str, get_sorter_str};
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Warehouse {
@@ -30,6 +30,8 @@ pub struct GetWarehousesQuery {
     pub person_in_charge_id: Option<i64>,
     pub area_id: Option<i64>,
     pub address: Option<String>,
+    /// Get the removed rows instead of the others.
+    pub deleted: Option<bool>,
     pub sorters: Option<Vec<String>>,
 }
 
@@ -42,25 +44,26 @@ impl GetWarehousesQuery {
     pub fn get_where_condition(&self) -> String {
         let mut conditions = Vec::with_capacity(3);
         if let Some(v) = &self.id {
-            conditions.push(format!("warehouses.id = {v}").into());
+            conditions.push(format!("warehouses.id = {v}"));
         }
         if let Some(v) = &self.name {
             let v = v.trim();
             conditions.push(get_search_where_condition("warehouses.name", v));
         }
         if let Some(v) = &self.person_in_charge_id {
-            conditions.push(format!("warehouses.person_in_charge_id={v}").into());
+            conditions.push(format!("warehouses.person_in_charge_id={v}"));
         }
         if let Some(v) = &self.area_id {
-            conditions.push(format!("warehouses.area_id={v}").into());
+            conditions.push(format!("warehouses.area_id={v}"));
         }
         if let Some(v) = &self.address {
             let v = v.trim();
-            conditions.push(format!("warehouses.address LIKE '%{v}%'").into());
+            conditions.push(format!("warehouses.address LIKE '%{v}%'"));
         }
+        conditions.push(get_deleted_condition("warehouses", self.deleted));
         if !conditions.is_empty() {
             let c = conditions.join(" AND ");
-            format!("WHERE {c}").into()
+            format!("WHERE {c}")
         } else {
             "".into()
         }
@@ -76,11 +79,11 @@ impl GetWarehousesQuery {
                 let mut col = get_sort_col_str(sorter);
                 let sort = get_sorter_str(sorter);
                 if col == "person_in_charge_id" {
-                    col = format!("person_in_charge_name {sort}").into();
+                    col = format!("person_in_charge_name {sort}");
                 } else if col == "area_id" {
-                    col = format!("area_name {sort}").into();
+                    col = format!("area_name {sort}");
                 } else {
-                    col = format!("warehouses.{col} {sort}").into();
+                    col = format!("warehouses.{col} {sort}");
                 }
                 conditions.push(col)
             }
@@ -88,7 +91,7 @@ impl GetWarehousesQuery {
 
         if !conditions.is_empty() {
             let c = conditions.join(", ");
-            format!("ORDER BY {c}").into()
+            format!("ORDER BY {c}")
         } else {
             "".into()
         }
@@ -1,4 +1,7 @@
+use std::borrow::Cow;
+
 use axum::{http::StatusCode, response::IntoResponse, Json};
+use elerp_common::import::ImportError;
 use serde_json::json;
 use strum::AsRefStr;
 use tracing::error;
@@ -10,13 +13,38 @@ pub enum CustomErrorCode {
     WarehouseNotFound,
     SKUCategoryNotFound,
     SKUNotFound,
+    InvalidBarcode,
+    InvalidUnit,
+    ProductTemplateNotFound,
+    InvalidVariant,
     OrderNotFound,
     OrderCategoryNotFound,
     OrderPaymentSettled,
     OrderPaymentNotFound,
     OrderPaymentIsNone,
     OrderItemsIsEmpty,
+    SerialNumbersMismatch,
+    ReorderPointNotFound,
+    InvalidReorderPoint,
+    ReservationNotFound,
+    AvailableQuantityExceeded,
+    PurchaseOrderNotFound,
+    PurchaseOrderStatusNotAllowed,
+    ReceivedQuantityExceeded,
+    QuotationNotFound,
+    QuotationStatusNotAllowed,
+    CurrencyNotFound,
+    ExchangeRateNotFound,
+    InvalidExchangeRate,
+    TaxRateNotFound,
+    InvalidTaxRate,
+    InvalidDiscount,
+    InvalidImportFile,
+    PriceListNotFound,
+    PriceListItemNotFound,
+    PriceNotFound,
     UserNotFound,
+    RoleNotFound,
     WrongPassword,
     NoPermission,
     SameObject,
@@ -30,6 +58,8 @@ pub enum CustomErrorCode {
     GuestOrderConfirmed,
     GuestOrderExpired,
     FromGuestOrder,
+    FromPurchaseOrder,
+    QuotationExpired,
     UserLimitExceeded,
     WarehouseLimitExceeded,
     AreaLimitExceeded,
@@ -45,7 +75,7 @@ pub enum CustomErrorCode {
 pub enum AppErrorType {
     Internal(anyhow::Error),
     InternalSQL(sqlx::Error),
-    Custom(CustomErrorCode, &'static str),
+    Custom(CustomErrorCode, Cow<'static, str>),
 }
 pub struct AppError {
     err: AppErrorType,
@@ -59,6 +89,15 @@ impl From<anyhow::Error> for AppError {
     }
 }
 
+impl From<ImportError> for AppError {
+    fn from(value: ImportError) -> Self {
+        match value {
+            ImportError::Row(msg) => AppError::custom(CustomErrorCode::CheckFailed, msg),
+            ImportError::Internal(err) => err.into(),
+        }
+    }
+}
+
 impl From<sqlx::Error> for AppError {
     fn from(value: sqlx::Error) -> Self {
         AppError {
@@ -107,9 +146,17 @@ impl AppError {
         Err(self)
     }
 
-    pub fn custom(code: CustomErrorCode, msg: &'static str) -> Self {
+    pub fn custom(code: CustomErrorCode, msg: impl Into<Cow<'static, str>>) -> Self {
         Self {
-            err: AppErrorType::Custom(code, msg),
+            err: AppErrorType::Custom(code, msg.into()),
+        }
+    }
+
+    /// Message of the custom error, None if it is internal.
+    pub fn get_custom_msg(&self) -> Option<&str> {
+        match &self.err {
+            AppErrorType::Custom(_, msg) => Some(msg),
+            _ => None,
         }
     }
 }
@@ -1,16 +1,23 @@
 use std::sync::Arc;
 
 use area_module::AreaModule;
+use audit_log_module::AuditLogModule;
+use currency_module::CurrencyModule;
 use guest_order_module::GuestOrderModule;
 use inventory_module::InventoryModule;
 use order_category_module::OrderCategoryModule;
 use order_module::OrderModule;
 use order_payment_module::OrderPaymentModule;
 use person_module::PersonModule;
+use price_list_module::PriceListModule;
+use product_template_module::ProductTemplateModule;
+use purchase_order_module::PurchaseOrderModule;
 use public_system::PublicSystem;
+use quotation_module::QuotationModule;
 use sku_category_module::SKUCategoryModule;
 use sku_module::SKUModule;
 use statistical_module::StatisticalModule;
+use tax_module::TaxModule;
 use warehouse_module::WarehouseModule;
 
 type Result<T> = anyhow::Result<T>;
@@ -25,10 +32,17 @@ pub struct ERP {
     pub sku: Arc<SKUModule>,
     pub order: Arc<OrderModule>,
     pub guest_order: Arc<GuestOrderModule>,
+    pub purchase_order: Arc<PurchaseOrderModule>,
+    pub quotation: Arc<QuotationModule>,
+    pub currency: Arc<CurrencyModule>,
+    pub tax: Arc<TaxModule>,
+    pub price_list: Arc<PriceListModule>,
+    pub product_template: Arc<ProductTemplateModule>,
     pub order_category: Arc<OrderCategoryModule>,
     pub order_payment: Arc<OrderPaymentModule>,
     pub inventory: Arc<InventoryModule>,
     pub statistical: Arc<StatisticalModule>,
+    pub audit_log: Arc<AuditLogModule>,
 }
 
 impl ERP {
@@ -43,8 +57,15 @@ impl ERP {
         let order_payment = Arc::new(OrderPaymentModule::new(ps.clone()).await);
         let order = Arc::new(OrderModule::new(ps.clone()).await);
         let guest_order = Arc::new(GuestOrderModule::new(ps.clone()).await);
+        let purchase_order = Arc::new(PurchaseOrderModule::new(ps.clone()).await);
+        let quotation = Arc::new(QuotationModule::new(ps.clone()).await);
+        let currency = Arc::new(CurrencyModule::new(ps.clone()).await);
+        let tax = Arc::new(TaxModule::new(ps.clone()).await);
+        let price_list = Arc::new(PriceListModule::new(ps.clone()).await);
+        let product_template = Arc::new(ProductTemplateModule::new(ps.clone()).await);
         let inventory = Arc::new(InventoryModule::new(ps.clone()).await);
         let statistical = Arc::new(StatisticalModule::new(ps.clone()).await);
+        let audit_log = Arc::new(AuditLogModule::new(ps.clone()).await);
 
         ERP {
             area,
@@ -54,10 +75,17 @@ impl ERP {
             sku_category,
             order,
             guest_order,
+            purchase_order,
+            quotation,
+            currency,
+            tax,
+            price_list,
+            product_template,
             order_category,
             order_payment,
             inventory,
             statistical,
+            audit_log,
         }
     }
 
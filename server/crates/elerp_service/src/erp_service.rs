@@ -1,22 +1,26 @@
 use crate::custom_error::{AppError, CustomErrorCode};
 
 use axum::{
-    body::Body,
+    body::{Body, Bytes},
     extract::{Path, State},
     http::{header, HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
 };
-use elerp_common::{area_module::model::area::{Area, GetAreasQuery}, guest_order_module::model::guest_order::{GetGuestOrdersQuery, GuestOrder, GuestOrderConfirm, GuestOrderStatus}, inventory_module::model::inventory::{GetInventoryQuery, InventoryProduct}, model::{action_type::ActionType, ListSlice, Pagination, WebSocketFlags}, order_category_module::model::order_category::{GetOrderCategoryQuery, OrderCategory}, order_module::model::{check_order_result::CheckOrderResult, order::{GetOrdersQuery, Order, OrderItem, OrderPaymentStatus, OrderType}}, order_payment_module::model::order_payment::{GetOrderPaymentsQuery, OrderPayment}, person_module::model::person::{GetPersonsQuery, Person}, sku_category_module::model::sku_category::{GetSKUCategoriesQuery, SKUCategory}, sku_module::model::sku::{GetSKUsQuery, SKU}, statistical_module::model::statistical_data::{GetStatisticalDataQuery, StatisticalData}, user_system::model::user_info::{UserInfo, UserType}, warehouse_module::model::{fn_argument::WarehouseIsFrom, warehouse::{GetWarehousesQuery, Warehouse, WarehouseToLinkQuery}}};
+use elerp_common::{area_module::model::area::{Area, GetAreasQuery}, audit_log_module::model::audit_log::{AuditAction, AuditEntity, AuditLog, GetAuditLogsQuery}, currency_module::model::currency::{Currency, ExchangeRate, GetExchangeRatesQuery}, export::TempPath, guest_order_module::model::guest_order::{GetGuestOrdersQuery, GuestOrder, GuestOrderConfirm, GuestOrderStatus}, inventory_module::model::{inventory::{GetInventoryLotsQuery, GetInventoryQuery, InventoryLot, InventoryProduct, TemplateInventory}, reorder_point::{GetReorderPointsQuery, InventoryAlert, InventoryAlertType, RemoveReorderPointQuery, ReorderPoint}, reservation::{GetReservationsQuery, Reservation}}, import::{IdCache, ImportRow, ImportTable}, model::{action_type::ActionType, export::{ExportFormat, ExportQuery}, import::{ImportOpeningStockQuery, ImportOrderResult, ImportOrdersQuery, ImportOrdersResult, ImportQuery, ImportResult, ImportRowError}, ListSlice, Pagination, WebSocketFlags}, order_category_module::model::order_category::{GetOrderCategoryQuery, OrderCategory}, order_module::model::{check_order_result::CheckOrderResult, document::{DocumentKind, GetDocumentQuery}, order::{Discount, DiscountType, GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType}, order_revision::{OrderFieldChange, OrderItemChange, OrderRevision, OrderRevisionDiff}, serial_number::{GetSerialNumberQuery, SerialNumberHistory, SerialNumberMovement, SerialNumberStatus}}, order_payment_module::model::order_payment::{GetOrderPaymentsQuery, OrderPayment}, person_module::model::person::{GetPersonsQuery, Person}, price_list_module::model::price_list::{AppliedPrice, GetPriceListItemsQuery, GetPriceQuery, PriceList, PriceListItem}, product_template_module::model::product_template::{GetProductTemplatesQuery, ProductTemplate, TemplateAttribute}, purchase_order_module::model::purchase_order::{GetPurchaseOrdersQuery, PurchaseOrder, PurchaseOrderItem, PurchaseOrderReceipt, PurchaseOrderReceiptItem, PurchaseOrderReceived, PurchaseOrderStatus}, quotation_module::model::quotation::{GetQuotationsQuery, Quotation, QuotationConvert, QuotationItem, QuotationStatus}, sku_category_module::model::sku_category::{GetSKUCategoriesQuery, SKUCategory}, sku_module::model::sku::{GetSKUsQuery, SKUUnit, SKU}, statistical_module::model::statistical_data::{GetStatisticalDataQuery, GetTaxSummaryQuery, StatisticalData, TaxSummary, TaxSummaryPeriod}, tax_module::model::tax_rate::TaxRate, user_system::model::{user_info::{UserInfo, UserType}, user_permission::Permission}, warehouse_module::model::{fn_argument::WarehouseIsFrom, warehouse::{GetWarehousesQuery, Warehouse, WarehouseToLinkQuery}}};
+use currency_module::CurrencyModule;
+use futures::StreamExt;
+use guest_order_module::GuestOrderModule;
 use serde_qs::axum::QsQuery as Query;
 
+use public_system::SqliteSafeTransaction;
 use sqlx::SqliteConnection;
 use tokio_util::io::ReaderStream;
 
 use utoipa::OpenApi;
 
-use super::{model::{authenticated_user::AuthenticatedUser, clear_result::ClearResult}, AppState};
+use super::{model::{authenticated_user::AuthenticatedUser, clear_result::ClearResult, purge::{PurgeQuery, PurgeResult}}, AppState};
 
 type Result<T> = core::result::Result<T, AppError>;
 
@@ -27,52 +31,133 @@ type Result<T> = core::result::Result<T, AppError>;
         remove_person,
         get_person,
         get_persons,
+        export_persons,
+        import_persons,
         update_person,
+        restore_person,
 
         add_area,
         remove_area,
         get_area,
         get_areas,
         update_area,
+        restore_area,
             
         add_warehouse,
         remove_warehouse,
         get_warehouse,
         get_warehouses,
+        import_warehouses,
         update_warehouse,
+        restore_warehouse,
 
         inventory_list,
         inventory_list_excel,
+        inventory_lots_list,
+        get_inventory_alerts,
+        get_template_inventory,
+        get_reorder_points,
+        set_reorder_point,
+        remove_reorder_point,
+        get_reservations,
+        add_reservation,
+        remove_reservation,
+
+        get_currencies,
+        set_currency,
+        remove_currency,
+        get_exchange_rates,
+        set_exchange_rate,
+        remove_exchange_rate,
+        import_exchange_rates,
+
+        get_tax_rates,
+        add_tax_rate,
+        update_tax_rate,
+        remove_tax_rate,
+        get_tax_summary,
+        get_price_lists,
+        add_price_list,
+        update_price_list,
+        remove_price_list,
+        get_price_list_items,
+        add_price_list_item,
+        update_price_list_item,
+        remove_price_list_item,
+        get_price,
 
         add_order,
         remove_order,
         update_order,
         get_orders,
         get_orders,
+        export_orders,
+        import_orders,
+        import_opening_stock,
+        export_guest_orders,
         check_order,
+        restore_order,
+        get_order_revisions,
+        receive_order,
+        get_order_document,
+        get_serial_numbers,
+
+        add_purchase_order,
+        get_purchase_order,
+        get_purchase_orders,
+        update_purchase_order,
+        remove_purchase_order,
+        approve_purchase_order,
+        close_purchase_order,
+        receive_purchase_order,
+        add_quotation,
+        get_quotation,
+        get_quotations,
+        update_quotation,
+        remove_quotation,
+        send_quotation,
+        get_quotation_document,
+        convert_quotation,
 
         add_order_category,
         remove_order_category,
         update_order,
         get_order_category,
         get_order_categories,
+        restore_order_category,
 
         add_order_payment,
         remove_order_payment,
         get_order_payment,
         get_order_payments,
+        export_order_payments,
+        restore_order_payment,
 
         add_sku,
         remove_sku,
         get_sku,
+        get_sku_by_barcode,
         get_skus,
+        export_skus,
+        import_skus,
         update_sku,
+        restore_sku,
+        get_product_templates,
+        add_product_template,
+        get_product_template,
+        update_product_template,
+        remove_product_template,
+        generate_product_template_variants,
 
         add_sku_category,
         remove_sku_category,
         get_sku_category,
         get_sku_categories,
         update_sku_category,
+        restore_sku_category,
+
+        get_audit_logs,
+        purge,
     ),
     tags(
         (name = "ERP", description = "ERP API")
@@ -86,15 +171,80 @@ type Result<T> = core::result::Result<T, AppError>;
             Order,
             OrderType,
             CheckOrderResult,
+            DocumentKind,
+            GetDocumentQuery,
+            OrderRevision,
+            OrderRevisionDiff,
+            OrderFieldChange,
+            OrderItemChange,
+            SerialNumberHistory,
+            SerialNumberMovement,
+            SerialNumberStatus,
+            GetSerialNumberQuery,
             Warehouse,
             OrderItem,
+            Discount,
+            DiscountType,
             SKU,
+            SKUUnit,
+            ProductTemplate,
+            TemplateAttribute,
+            GetProductTemplatesQuery,
+            TemplateInventory,
             SKUCategory,
             InventoryProduct,
+            InventoryLot,
+            PurchaseOrder,
+            PurchaseOrderItem,
+            PurchaseOrderStatus,
+            PurchaseOrderReceipt,
+            PurchaseOrderReceiptItem,
+            PurchaseOrderReceived,
+            GetPurchaseOrdersQuery,
+            Quotation,
+            QuotationItem,
+            QuotationStatus,
+            QuotationConvert,
+            GetQuotationsQuery,
+            ReorderPoint,
+            GetReorderPointsQuery,
+            RemoveReorderPointQuery,
+            Reservation,
+            GetReservationsQuery,
+            Currency,
+            ExchangeRate,
+            GetExchangeRatesQuery,
+            TaxRate,
+            TaxSummary,
+            TaxSummaryPeriod,
+            GetTaxSummaryQuery,
+            ExportFormat,
+            ExportQuery,
+            ImportQuery,
+            ImportOpeningStockQuery,
+            ImportOrdersQuery,
+            ImportOrderResult,
+            ImportOrdersResult,
+            ImportRowError,
+            ImportResult,
+            PriceList,
+            PriceListItem,
+            GetPriceListItemsQuery,
+            GetPriceQuery,
+            AppliedPrice,
+            InventoryAlert,
+            InventoryAlertType,
+            GetInventoryLotsQuery,
             GetWarehousesQuery,
             GetOrdersQuery,
             GetSKUCategoriesQuery,
             GetSKUsQuery,
+            AuditLog,
+            AuditEntity,
+            AuditAction,
+            GetAuditLogsQuery,
+            PurgeQuery,
+            PurgeResult,
         )
     )
 )]
@@ -104,23 +254,31 @@ pub fn get_services() -> Router<AppState> {
     Router::new()
         .route("/statistical_data", get(get_statistical_data))
         .route("/clear_cache", post(clear_cache))
+        .route("/audit_log", get(get_audit_logs))
+        .route("/purge", post(purge))
         .route("/persons", post(add_person).get(get_persons).delete(clear_persons))
+        .route("/persons/export", get(export_persons))
+        .route("/persons/import", post(import_persons))
         .route(
             "/persons/:id",
             delete(remove_person).get(get_person).put(update_person),
         )
+        .route("/persons/:id/restore", post(restore_person))
         .route("/areas", post(add_area).get(get_areas).delete(clear_areas))
         .route(
             "/areas/:id",
             delete(remove_area).get(get_area).put(update_area),
         )
+        .route("/areas/:id/restore", post(restore_area))
         .route("/warehouses", post(add_warehouse).get(get_warehouses).delete(clear_warehouses))
+        .route("/warehouses/import", post(import_warehouses))
         .route(
             "/warehouses/:id",
             delete(remove_warehouse)
                 .get(get_warehouse)
                 .put(update_warehouse),
         )
+        .route("/warehouses/:id/restore", post(restore_warehouse))
         .route(
             "/warehouse_link/:id",
             delete(unlink_warehouse)
@@ -129,18 +287,59 @@ pub fn get_services() -> Router<AppState> {
         )
         .route("/inventory", get(inventory_list))
         .route("/inventory_excel", get(inventory_list_excel))
+        .route("/inventory_lots", get(inventory_lots_list))
+        .route("/inventory/alerts", get(get_inventory_alerts))
+        .route("/inventory/templates", get(get_template_inventory))
+        .route("/reorder_points", get(get_reorder_points).put(set_reorder_point).delete(remove_reorder_point))
+        .route("/reservations", get(get_reservations).post(add_reservation))
+        .route("/reservations/:id", delete(remove_reservation))
+        .route("/currencies", get(get_currencies).put(set_currency))
+        .route("/currencies/:code", delete(remove_currency))
+        .route("/exchange_rates", get(get_exchange_rates).put(set_exchange_rate))
+        .route("/exchange_rates/:id", delete(remove_exchange_rate))
+        .route("/exchange_rates/import", post(import_exchange_rates))
+        .route("/tax_rates", get(get_tax_rates).post(add_tax_rate))
+        .route("/tax_rates/:id", delete(remove_tax_rate).put(update_tax_rate))
+        .route("/tax_summary", get(get_tax_summary))
+        .route("/price_lists", get(get_price_lists).post(add_price_list))
+        .route("/price_lists/:id", delete(remove_price_list).put(update_price_list))
+        .route("/price_list_items", get(get_price_list_items).post(add_price_list_item))
+        .route("/price_list_items/:id", delete(remove_price_list_item).put(update_price_list_item))
+        .route("/price", get(get_price))
         .route("/check_order", post(check_order))
         .route("/orders", post(add_order).get(get_orders).delete(clear_orders))
+        .route("/orders/export", get(export_orders))
+        .route("/orders/import", post(import_orders))
+        .route("/orders/opening_stock", post(import_opening_stock))
         .route("/guest_orders", post(add_guest_order).get(get_guest_orders).delete(clear_guest_orders))
+        .route("/guest_orders/export", get(export_guest_orders))
         .route("/order_categories", post(add_order_category).get(get_order_categories).delete(clear_order_categories))
         .route("/order_categories/:id", delete(remove_order_category).get(get_order_category).put(update_order_category))
+        .route("/order_categories/:id/restore", post(restore_order_category))
         .route("/order_payments", post(add_order_payment).get(get_order_payments).delete(clear_order_payments))
+        .route("/order_payments/export", get(export_order_payments))
         .route("/order_payments/:id", delete(remove_order_payment).get(get_order_payment))
+        .route("/order_payments/:id/restore", post(restore_order_payment))
         .route("/recalc_orders", post(recalc_orders))
         .route(
             "/orders/:id",
             delete(remove_order).get(get_order).put(update_order),
         )
+        .route("/orders/:id/restore", post(restore_order))
+        .route("/orders/:id/revisions", get(get_order_revisions))
+        .route("/orders/:id/receive", post(receive_order))
+        .route("/orders/:id/pdf", get(get_order_document))
+        .route("/serial_numbers", get(get_serial_numbers))
+        .route("/purchase_orders", post(add_purchase_order).get(get_purchase_orders))
+        .route("/purchase_orders/:id", delete(remove_purchase_order).get(get_purchase_order).put(update_purchase_order))
+        .route("/purchase_orders/:id/approve", post(approve_purchase_order))
+        .route("/purchase_orders/:id/close", post(close_purchase_order))
+        .route("/purchase_orders/:id/receive", post(receive_purchase_order))
+        .route("/quotations", post(add_quotation).get(get_quotations))
+        .route("/quotations/:id", delete(remove_quotation).get(get_quotation).put(update_quotation))
+        .route("/quotations/:id/send", post(send_quotation))
+        .route("/quotations/:id/document", get(get_quotation_document))
+        .route("/quotations/:id/convert", post(convert_quotation))
         .route(
             "/order_items/:id",
             get(get_order_items),
@@ -150,7 +349,14 @@ pub fn get_services() -> Router<AppState> {
             delete(remove_guest_order).get(get_guest_order).put(confirm_guest_order),
         )
         .route("/skus", post(add_sku).get(get_skus).delete(clear_skus))
+        .route("/skus/export", get(export_skus))
+        .route("/skus/import", post(import_skus))
+        .route("/skus/by_barcode/:code", get(get_sku_by_barcode))
         .route("/skus/:id", delete(remove_sku).get(get_sku).put(update_sku))
+        .route("/skus/:id/restore", post(restore_sku))
+        .route("/product_templates", get(get_product_templates).post(add_product_template))
+        .route("/product_templates/:id", get(get_product_template).put(update_product_template).delete(remove_product_template))
+        .route("/product_templates/:id/variants", post(generate_product_template_variants))
         .route(
             "/sku_categories",
             post(add_sku_category).get(get_sku_categories).delete(clear_sku_categories),
@@ -161,6 +367,7 @@ pub fn get_services() -> Router<AppState> {
                 .get(get_sku_category)
                 .put(update_sku_category),
         )
+        .route("/sku_categories/:id/restore", post(restore_sku_category))
 }
 
 /// Get statistics data
@@ -172,6 +379,7 @@ pub fn get_services() -> Router<AppState> {
     ),
 )]
 async fn get_statistical_data(State(s): State<AppState>,Query(q): Query<GetStatisticalDataQuery>, authenticated: AuthenticatedUser,) -> Result<Json<StatisticalData>> {
+    authenticated.check_permission(Permission::ViewStatistics)?;
     let mut tx = s.ps.begin_tx(false).await?;
     let data = s.erp.statistical.get(&q, authenticated.user.as_action_type(false), tx.as_mut()).await?;
     tx.commit().await?;
@@ -193,6 +401,84 @@ async fn clear_cache(State(s): State<AppState>, authenticated: AuthenticatedUser
     Ok(StatusCode::OK)
 }
 
+/// Get audit log of the mutations.
+#[utoipa::path(
+    get,
+    path = "/audit_log",
+    responses(
+        (status = 200, description = "get audit log successfully", body = ListSlice<AuditLog>)
+    ),
+    params(
+        Pagination,
+        GetAuditLogsQuery,
+    )
+)]
+async fn get_audit_logs(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(query): Query<GetAuditLogsQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<AuditLog>>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.audit_log.get_multiple(&pagination.correct(), &query, tx.as_mut()).await?;
+    let count = s.erp.audit_log.get_count(&query, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { count, items }))
+}
+
+/// Physically delete the removed rows, can't restore them anymore.
+#[utoipa::path(
+    post,
+    path = "/purge",
+    responses(
+        (status = 200, description = "purge removed rows successfully", body = PurgeResult)
+    ),
+    params(
+        PurgeQuery,
+    )
+)]
+async fn purge(
+    State(s): State<AppState>,
+    Query(q): Query<PurgeQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<PurgeResult>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = q.deleted_before;
+    let purged = [
+        (AuditEntity::OrderPayment, s.erp.order_payment.purge(before, tx.as_mut()).await?),
+        (AuditEntity::Order, s.erp.order.purge(before, tx.as_mut()).await?),
+        (AuditEntity::SKU, s.erp.sku.purge(before, tx.as_mut()).await?),
+        (AuditEntity::SKUCategory, s.erp.sku_category.purge(before, tx.as_mut()).await?),
+        (AuditEntity::Warehouse, s.erp.warehouse.purge(before, tx.as_mut()).await?),
+        (AuditEntity::Person, s.erp.person.purge(before, tx.as_mut()).await?),
+        (AuditEntity::Area, s.erp.area.purge(before, tx.as_mut()).await?),
+        (AuditEntity::OrderCategory, s.erp.order_category.purge(before, tx.as_mut()).await?),
+    ];
+    let mut r = PurgeResult::default();
+    for (entity, ids) in purged {
+        for id in ids.iter() {
+            s.erp.audit_log.add::<()>(authenticated.user.id, entity, *id, AuditAction::Purge, None, None, tx.as_mut()).await?;
+        }
+        let count = ids.len() as i64;
+        match entity {
+            AuditEntity::OrderPayment => r.order_payments = count,
+            AuditEntity::Order => r.orders = count,
+            AuditEntity::SKU => r.skus = count,
+            AuditEntity::SKUCategory => r.sku_categories = count,
+            AuditEntity::Warehouse => r.warehouses = count,
+            AuditEntity::Person => r.persons = count,
+            AuditEntity::Area => r.areas = count,
+            AuditEntity::OrderCategory => r.order_categories = count,
+            _ => (),
+        }
+    }
+    tx.commit().await?;
+    s.ps.notice(WebSocketFlags::Purge).await?;
+    Ok(Json(r))
+}
+
 async fn check_token_exists(s: AppState, authenticated: Option<&AuthenticatedUser>, headers: &HeaderMap, tx: &mut SqliteConnection) -> Result<()> {
     if authenticated.is_none() {
         match headers.get("X-Sub-Authorization") {
@@ -218,6 +504,9 @@ async fn check_person(s: AppState, p: &Person, prev: Option<i64>, tx: &mut Sqlit
     if !s.erp.area.is_exists(p.area_id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::AreaNotFound, "Area is not exists.").into_err();
     }
+    if p.price_list_id != 0 && !s.erp.price_list.is_exists(p.price_list_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::PriceListNotFound, "Price list is not exists.").into_err();
+    }
     if p.person_in_charge_id > 0 && !s.erp
             .person
             .is_exists(p.person_in_charge_id, &mut *tx)
@@ -246,22 +535,75 @@ async fn add_person(
     authenticated: AuthenticatedUser,
     Json(p): Json<Person>,
 ) -> Result<Json<Person>> {
-    authenticated.is_manage_person()?;
+    authenticated.check_permission(Permission::ManagePerson)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.person.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::PersonLimitExceeded, "Person count limit exceeded!").into_err();
     }
     check_person(s.clone(), &p, None, tx.as_mut()).await?;
-    let r = s.erp.person.add(p, tx.as_mut()).await?;
+    let r = s.erp.person.add(p, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Person, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
-async fn remove_person_core(s: AppState, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+/// import persons from the xlsx or csv file in the body, the columns are the same as the export.
+/// Nothing is imported if any row is invalid.
+#[utoipa::path(
+    post,
+    path = "/persons/import",
+    request_body(content = Vec<u8>, description = "The headers are Name, Area, Person In Charge, Address, Contact, Email, Price List and Description.", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "import persons successfully", body = ImportResult)
+    ),
+    params(
+        ImportQuery,
+    )
+)]
+async fn import_persons(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Query(q): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportResult>> {
+    authenticated.check_permission(Permission::ManagePerson)?;
+    let table = read_import_table(q.format, &body, &["Name", "Area"])?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let mut cache = IdCache::new();
+    let mut errors = vec![];
+    let mut ids = Vec::with_capacity(table.len());
+    for row in table.rows() {
+        let v = match s.erp.person.parse_import_row(&row, &mut cache, tx.as_mut()).await {
+            Ok(v) => v,
+            Err(err) => {
+                push_import_error(&mut errors, row.number(), err.into())?;
+                continue;
+            }
+        };
+        if s.erp.person.is_limit_reached(tx.as_mut()).await? {
+            return AppError::custom(CustomErrorCode::PersonLimitExceeded, "Person count limit exceeded!").into_err();
+        }
+        if let Err(err) = check_person(s.clone(), &v, None, tx.as_mut()).await {
+            push_import_error(&mut errors, row.number(), err)?;
+            continue;
+        }
+        let r = s.erp.person.add(v, false, tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Person, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+        ids.push(r.id);
+    }
+    finish_import(&s, tx, q.dry_run.unwrap_or(false), table.len(), errors, ids, WebSocketFlags::AddPerson).await
+}
+
+async fn remove_person_core(s: AppState, user: &UserInfo, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
     if s.erp.person.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the person.").into_err();
     }
-    Ok(s.erp.person.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.person.get(id, ActionType::System, &mut *tx).await?;
+    let r = s.erp.person.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::Person, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove person
@@ -280,9 +622,9 @@ async fn remove_person(
     authenticated: AuthenticatedUser,
     Path(id): Path<i64>,
 ) -> Result<StatusCode> {
-    authenticated.is_manage_person()?;
+    authenticated.check_permission(Permission::ManagePerson)?;
     let mut tx = s.ps.begin_tx(true).await?;
-    if remove_person_core(s.clone(), id, true, tx.as_mut()).await? {
+    if remove_person_core(s.clone(), &authenticated.user, id, true, tx.as_mut()).await? {
         tx.commit().await?;
         Ok(StatusCode::OK)
     } else {
@@ -290,6 +632,37 @@ async fn remove_person(
     }
 }
 
+/// restore person
+#[utoipa::path(
+    post,
+    path = "/persons/{id}/restore",
+    responses(
+        (status = 200, description = "restore person successfully", body = Person)
+    ),
+    params(
+        ("id" = i64, Path, description = "person id")
+    )
+)]
+async fn restore_person(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Person>> {
+    authenticated.check_permission(Permission::ManagePerson)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.person.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::PersonLimitExceeded, "Person count limit exceeded!").into_err();
+    }
+    if let Some(v) = s.erp.person.restore(id, tx.as_mut()).await? {
+        check_person(s.clone(), &v, Some(id), tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Person, id, AuditAction::Restore, None, Some(&v), tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::PersonNotFound, "Removed person is not exists.").into_err()
+    }
+}
+
 /// clear persons
 #[utoipa::path(
     delete,
@@ -307,15 +680,15 @@ async fn clear_persons(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetPersonsQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_manage_person()?;
+    authenticated.check_permission(Permission::ManagePerson)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.person.get_multiple_ids(&pagination, &q, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
-            match remove_person_core(s.clone(), id, false, tx.as_mut()).await {
+            match remove_person_core(s.clone(), &authenticated.user, id, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
                 Err(_) => failed += 1,
             }
@@ -347,8 +720,9 @@ async fn get_person(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<Person>> {
+    authenticated.check_permission(Permission::ViewPerson)?;
     let mut tx = s.ps.begin_tx(false).await?;
-    let action = authenticated.user.as_action_type(authenticated.is_manage_person().is_ok());
+    let action = authenticated.user.as_action_type(authenticated.has_permission(Permission::ManagePerson));
     let r = s.erp.person.get(id, action, tx.as_mut()).await?;
     if let Some(p) = r {
         tx.commit().await?;
@@ -376,14 +750,41 @@ async fn get_persons(
     Query(query): Query<GetPersonsQuery>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<Person>>> {
+    authenticated.check_permission(Permission::ViewPerson)?;
     let mut tx = s.ps.begin_tx(false).await?;
-    let action = authenticated.user.as_action_type(authenticated.is_manage_person().is_ok());
+    let action = authenticated.user.as_action_type(authenticated.has_permission(Permission::ManagePerson));
     let items = s.erp.person.get_multiple(&pagination.correct(), &query, action, tx.as_mut()).await?;
     let count = s.erp.person.get_count(&query, tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { count, items }))
 }
 
+/// export persons in xlsx or csv file.
+#[utoipa::path(
+    get,
+    path = "/persons/export",
+    responses(
+        (status = 200, description = "export persons successfully", body = Response)
+    ),
+    params(
+        GetPersonsQuery,
+        ExportQuery,
+    )
+)]
+async fn export_persons(
+    State(s): State<AppState>,
+    Query(q): Query<GetPersonsQuery>,
+    Query(export): Query<ExportQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewPerson)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(authenticated.has_permission(Permission::ManagePerson));
+    let path = s.erp.person.get_export(&q, export.get_format(), action, tx.as_mut()).await?;
+    tx.commit().await?;
+    export_response(path, "Persons", export.get_format()).await
+}
+
 /// update person
 #[utoipa::path(
     put,
@@ -401,12 +802,14 @@ async fn update_person(
     authenticated: AuthenticatedUser,
     Json(body): Json<Person>,
 ) -> Result<Json<Person>> {
-    authenticated.is_manage_person()?;
+    authenticated.check_permission(Permission::ManagePerson)?;
     let mut tx = s.ps.begin_tx(true).await?;
     check_person(s.clone(), &body, Some(id),tx.as_mut()).await?;
 
+    let before = s.erp.person.get(id, ActionType::System, tx.as_mut()).await?;
     let r = s.erp.person.update(id, body, tx.as_mut()).await?;
     if let Some(v) = r {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Person, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut()).await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
@@ -422,6 +825,9 @@ async fn check_area(s: AppState, v: &Area, prev: Option<i64>, tx: &mut SqliteCon
         )
         .into_err();
     }
+    if v.price_list_id != 0 && !s.erp.price_list.is_exists(v.price_list_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::PriceListNotFound, "Price list is not exists.").into_err();
+    }
 
     Ok(())
 }
@@ -439,22 +845,28 @@ async fn add_area(
     authenticated: AuthenticatedUser,
     Json(area): Json<Area>,
 ) -> Result<Json<Area>> {
-    authenticated.is_manage_area()?;
+    authenticated.check_permission(Permission::ManageArea)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.area.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::AreaLimitExceeded, "Area count limit exceeded!").into_err();
     }
     check_area(s.clone(), &area, None, tx.as_mut()).await?;
     let r = s.erp.area.add(area, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Area, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
-async fn remove_area_core(s: AppState, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+async fn remove_area_core(s: AppState, user: &UserInfo, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
     if s.erp.area.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the area.").into_err();
     }
-    Ok(s.erp.area.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.area.get(id, &mut *tx).await?;
+    let r = s.erp.area.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::Area, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove area
@@ -473,9 +885,9 @@ async fn remove_area(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<StatusCode> {
-    authenticated.is_manage_area()?;
+    authenticated.check_permission(Permission::ManageArea)?;
     let mut tx = s.ps.begin_tx(true).await?;
-    if remove_area_core(s.clone(), id, true, tx.as_mut()).await? {
+    if remove_area_core(s.clone(), &authenticated.user, id, true, tx.as_mut()).await? {
         tx.commit().await?;
         Ok(StatusCode::OK)
     } else {
@@ -483,6 +895,37 @@ async fn remove_area(
     }
 }
 
+/// restore area
+#[utoipa::path(
+    post,
+    path = "/areas/{id}/restore",
+    responses(
+        (status = 200, description = "restore area successfully", body = Area)
+    ),
+    params(
+        ("id" = i64, Path, description = "area id")
+    )
+)]
+async fn restore_area(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Area>> {
+    authenticated.check_permission(Permission::ManageArea)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.area.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::AreaLimitExceeded, "Area count limit exceeded!").into_err();
+    }
+    if let Some(v) = s.erp.area.restore(id, tx.as_mut()).await? {
+        check_area(s.clone(), &v, Some(id), tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Area, id, AuditAction::Restore, None, Some(&v), tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::AreaNotFound, "Removed area is not exists.").into_err()
+    }
+}
+
 /// clear areas
 #[utoipa::path(
     delete,
@@ -500,15 +943,15 @@ async fn clear_areas(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetAreasQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_manage_area()?;
+    authenticated.check_permission(Permission::ManageArea)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.area.get_multiple_ids(&pagination, &q, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
-            match remove_area_core(s.clone(), id, false, tx.as_mut()).await {
+            match remove_area_core(s.clone(), &authenticated.user, id, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
                 Err(_) => failed += 1,
             }
@@ -538,8 +981,9 @@ async fn clear_areas(
 async fn get_area(
     State(s): State<AppState>,
     Path(id): Path<i64>,
-    _: AuthenticatedUser,
+    authenticated: AuthenticatedUser,
 ) -> Result<Json<Area>> {
+    authenticated.check_permission(Permission::ViewArea)?;
     let mut tx = s.ps.begin_tx(false).await?;
     if !s.erp.area.is_exists(id, tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::AreaNotFound, "Area is not exists.").into_err();
@@ -563,11 +1007,12 @@ async fn get_areas(
     State(s): State<AppState>,
     Query(pagination): Query<Pagination>,
     Query(query): Query<GetAreasQuery>,
-    _: AuthenticatedUser,
+    authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<Area>>> {
+    authenticated.check_permission(Permission::ViewArea)?;
     let mut tx = s.ps.begin_tx(false).await?;
     let items = s.erp.area.get_multiple(&pagination.correct(), &query, tx.as_mut()).await?;
-    let count = s.erp.area.get_count(&query, &mut *tx).await?;
+    let count = s.erp.area.get_count(&query, &mut tx).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { count, items }))
 }
@@ -589,11 +1034,13 @@ async fn update_area(
     authenticated: AuthenticatedUser,
     Json(body): Json<Area>,
 ) -> Result<Json<Area>> {
-    authenticated.is_manage_area()?;
+    authenticated.check_permission(Permission::ManageArea)?;
     let mut tx = s.ps.begin_tx(true).await?;
     check_area(s.clone(), &body, Some(id), tx.as_mut()).await?;
+    let before = s.erp.area.get(id, tx.as_mut()).await?;
     let r = s.erp.area.update(id, body, tx.as_mut()).await?;
     if let Some(v) = r {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Area, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut()).await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
@@ -635,17 +1082,65 @@ async fn add_warehouse(
     authenticated: AuthenticatedUser,
     Json(body): Json<Warehouse>,
 ) -> Result<Json<Warehouse>> {
-    authenticated.is_manage_warehouse()?;
+    authenticated.check_permission(Permission::ManageWarehouse)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.warehouse.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::WarehouseLimitExceeded, "Warehouse count limit exceeded!").into_err();
     }
     check_warehouse(s.clone(), &body, None, tx.as_mut()).await?;
-    let v = s.erp.warehouse.add(body, tx.as_mut()).await?;
+    let v = s.erp.warehouse.add(body, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Warehouse, v.id, AuditAction::Add, None, Some(&v), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(v))
 }
 
+/// import warehouses from the xlsx or csv file in the body.
+/// Nothing is imported if any row is invalid.
+#[utoipa::path(
+    post,
+    path = "/warehouses/import",
+    request_body(content = Vec<u8>, description = "The headers are Name, Area, Person In Charge, Address and Description.", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "import warehouses successfully", body = ImportResult)
+    ),
+    params(
+        ImportQuery,
+    )
+)]
+async fn import_warehouses(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Query(q): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportResult>> {
+    authenticated.check_permission(Permission::ManageWarehouse)?;
+    let table = read_import_table(q.format, &body, &["Name", "Area", "Person In Charge"])?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let mut cache = IdCache::new();
+    let mut errors = vec![];
+    let mut ids = Vec::with_capacity(table.len());
+    for row in table.rows() {
+        let v = match s.erp.warehouse.parse_import_row(&row, &mut cache, tx.as_mut()).await {
+            Ok(v) => v,
+            Err(err) => {
+                push_import_error(&mut errors, row.number(), err.into())?;
+                continue;
+            }
+        };
+        if s.erp.warehouse.is_limit_reached(tx.as_mut()).await? {
+            return AppError::custom(CustomErrorCode::WarehouseLimitExceeded, "Warehouse count limit exceeded!").into_err();
+        }
+        if let Err(err) = check_warehouse(s.clone(), &v, None, tx.as_mut()).await {
+            push_import_error(&mut errors, row.number(), err)?;
+            continue;
+        }
+        let r = s.erp.warehouse.add(v, false, tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Warehouse, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+        ids.push(r.id);
+    }
+    finish_import(&s, tx, q.dry_run.unwrap_or(false), table.len(), errors, ids, WebSocketFlags::AddWarehouse).await
+}
+
 async fn remove_warehouse_core(s: AppState, id: i64, notice: bool, user: &UserInfo, tx: &mut SqliteConnection) -> Result<bool> {
     if s.erp.warehouse.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the warehouse.").into_err();
@@ -654,7 +1149,12 @@ async fn remove_warehouse_core(s: AppState, id: i64, notice: bool, user: &UserIn
         return AppError::custom(CustomErrorCode::NotLinked, "You not linked to the warehouse!").into_err();
     }
 
-    Ok(s.erp.warehouse.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.warehouse.get(id, &mut *tx).await?;
+    let r = s.erp.warehouse.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::Warehouse, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 /// remove warehouse
 #[utoipa::path(
@@ -672,7 +1172,7 @@ async fn remove_warehouse(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<StatusCode> {
-    authenticated.is_manage_warehouse()?;
+    authenticated.check_permission(Permission::ManageWarehouse)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if remove_warehouse_core(s.clone(), id, true, &authenticated.user, tx.as_mut()).await? {
         tx.commit().await?;
@@ -686,6 +1186,40 @@ async fn remove_warehouse(
     }
 }
 
+/// restore warehouse
+#[utoipa::path(
+    post,
+    path = "/warehouses/{id}/restore",
+    responses(
+        (status = 200, description = "restore warehouse successfully", body = Warehouse)
+    ),
+    params(
+        ("id" = i64, Path, description = "warehouse id")
+    )
+)]
+async fn restore_warehouse(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Warehouse>> {
+    authenticated.check_permission(Permission::ManageWarehouse)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.warehouse.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::WarehouseLimitExceeded, "Warehouse count limit exceeded!").into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to the warehouse!").into_err();
+    }
+    if let Some(v) = s.erp.warehouse.restore(id, tx.as_mut()).await? {
+        check_warehouse(s.clone(), &v, Some(id), tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Warehouse, id, AuditAction::Restore, None, Some(&v), tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::WarehouseNotFound, "Removed warehouse is not exists.").into_err()
+    }
+}
+
 /// clear warehouses
 #[utoipa::path(
     delete,
@@ -703,14 +1237,14 @@ async fn clear_warehouses(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetWarehousesQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_manage_warehouse()?;
+    authenticated.check_permission(Permission::ManageWarehouse)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let mut pagination = Pagination::new(0, 100);
     let action = authenticated.user.as_action_type(true);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.warehouse.get_multiple_ids(&pagination, &q, action, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
             match remove_warehouse_core(s.clone(), id, false, &authenticated.user, tx.as_mut()).await {
                 Ok(_) => success += 1,
@@ -744,6 +1278,7 @@ async fn get_warehouse(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<Warehouse>> {
+    authenticated.check_permission(Permission::ViewWarehouse)?;
     let mut tx = s.ps.begin_tx(false).await?;
     if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(id), (&authenticated.user).into(), tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::NotLinked, "You not linked to the warehouse!").into_err();
@@ -778,10 +1313,11 @@ async fn get_warehouses(
     Query(query): Query<GetWarehousesQuery>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<Warehouse>>> {
+    authenticated.check_permission(Permission::ViewWarehouse)?;
     let mut tx = s.ps.begin_tx(false).await?;
-    let action = authenticated.user.as_action_type(authenticated.is_manage_warehouse().is_ok());
+    let action = authenticated.user.as_action_type(authenticated.has_permission(Permission::ManageWarehouse));
     let items = s.erp.warehouse.get_multiple(&pagination.correct(), &query, action, tx.as_mut()).await?;
-    let count = s.erp.warehouse.get_count(&query, action, &mut *tx).await?;
+    let count = s.erp.warehouse.get_count(&query, action, &mut tx).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { items, count }))
 }
@@ -804,10 +1340,12 @@ async fn update_warehouse(
 
     Json(body): Json<Warehouse>,
 ) -> Result<Json<Warehouse>> {
-    authenticated.is_manage_warehouse()?;
+    authenticated.check_permission(Permission::ManageWarehouse)?;
     let mut tx = s.ps.begin_tx(true).await?;
     check_warehouse(s.clone(), &body, Some(id), tx.as_mut()).await?;
+    let before = s.erp.warehouse.get(id, tx.as_mut()).await?;
     if let Some(v) = s.erp.warehouse.update(id, body, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Warehouse, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut()).await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
@@ -857,6 +1395,7 @@ async fn link_warehouse(
     let mut tx = s.ps.begin_tx(true).await?;
     check_link_warehouse(s.clone(), &authenticated.user, id, &q, true, tx.as_mut()).await?;
     Ok(if s.erp.warehouse.link(id, q.user_id, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::WarehouseLink, id, AuditAction::Link, None, Some(&q), tx.as_mut()).await?;
         tx.commit().await?;
         StatusCode::OK
     } else {
@@ -882,6 +1421,7 @@ async fn unlink_warehouse(
     let mut tx = s.ps.begin_tx(true).await?;
     check_link_warehouse(s.clone(), &authenticated.user, id, &q, false, tx.as_mut()).await?;
     Ok(if s.erp.warehouse.unlink(id, q.user_id, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::WarehouseLink, id, AuditAction::Unlink, None, Some(&q), tx.as_mut()).await?;
         tx.commit().await?;
         StatusCode::OK
     } else {
@@ -934,22 +1474,28 @@ async fn add_order_category(
     authenticated: AuthenticatedUser,
     Json(body): Json<OrderCategory>,
 ) -> Result<Json<OrderCategory>> {
-    authenticated.is_manage_order_category()?;
+    authenticated.check_permission(Permission::ManageOrderCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.order_category.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::OrderCategoryLimitExceeded, "Order category count limit exceeded!").into_err();
     }
     check_order_category(s.clone(), &body, None, tx.as_mut()).await?;
     let r = s.erp.order_category.add(body, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::OrderCategory, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
-async fn remove_order_category_core(s: AppState, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+async fn remove_order_category_core(s: AppState, user: &UserInfo, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
     if s.erp.order_category.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the order category.").into_err();
     }
-    Ok(s.erp.order_category.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.order_category.get(id, &mut *tx).await?;
+    let r = s.erp.order_category.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::OrderCategory, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove order category
@@ -968,9 +1514,9 @@ async fn remove_order_category(
     authenticated: AuthenticatedUser,
     Path(id): Path<i64>,
 ) -> Result<StatusCode> {
-    authenticated.is_manage_order_category()?;
+    authenticated.check_permission(Permission::ManageOrderCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
-    if remove_order_category_core(s.clone(), id, true, tx.as_mut()).await? {
+    if remove_order_category_core(s.clone(), &authenticated.user, id, true, tx.as_mut()).await? {
         tx.commit().await?;
         Ok(StatusCode::OK)
     } else {
@@ -978,6 +1524,37 @@ async fn remove_order_category(
     }
 }
 
+/// restore order category
+#[utoipa::path(
+    post,
+    path = "/order_categories/{id}/restore",
+    responses(
+        (status = 200, description = "restore order category successfully", body = OrderCategory)
+    ),
+    params(
+        ("id" = i64, Path, description = "order category id")
+    )
+)]
+async fn restore_order_category(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<OrderCategory>> {
+    authenticated.check_permission(Permission::ManageOrderCategory)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.order_category.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::OrderCategoryLimitExceeded, "Order category count limit exceeded!").into_err();
+    }
+    if let Some(v) = s.erp.order_category.restore(id, tx.as_mut()).await? {
+        check_order_category(s.clone(), &v, Some(id), tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::OrderCategory, id, AuditAction::Restore, None, Some(&v), tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::OrderCategoryNotFound, "Removed order category is not found.").into_err()
+    }
+}
+
 /// clear order category
 #[utoipa::path(
     delete,
@@ -995,15 +1572,15 @@ async fn clear_order_categories(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetOrderCategoryQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_manage_order_category()?;
+    authenticated.check_permission(Permission::ManageOrderCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.order_category.get_multiple_ids(&pagination, &q, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
-            match remove_order_category_core(s.clone(), id, false, tx.as_mut()).await {
+            match remove_order_category_core(s.clone(), &authenticated.user, id, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
                 Err(_) => failed += 1,
             }
@@ -1033,8 +1610,9 @@ async fn clear_order_categories(
 async fn get_order_category(
     State(s): State<AppState>,
     Path(id): Path<i64>,
-    _: AuthenticatedUser,
+    authenticated: AuthenticatedUser,
 ) -> Result<Json<OrderCategory>> {
+    authenticated.check_permission(Permission::ViewOrderCategory)?;
     let mut tx = s.ps.begin_tx(false).await?;
     if let Some(v) = s.erp.order_category.get(id, tx.as_mut()).await? {
         tx.commit().await?;
@@ -1064,11 +1642,12 @@ async fn get_order_categories(
     State(s): State<AppState>,
     Query(pagination): Query<Pagination>,
     Query(q): Query<GetOrderCategoryQuery>,
-    _: AuthenticatedUser,
+    authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<OrderCategory>>> {
+    authenticated.check_permission(Permission::ViewOrderCategory)?;
     let mut tx = s.ps.begin_tx(false).await?;
-    let items = s.erp.order_category.get_multiple(&pagination.correct(), &q, &mut *tx).await?;
-    let count = s.erp.order_category.get_count(&q, &mut *tx).await?;
+    let items = s.erp.order_category.get_multiple(&pagination.correct(), &q, &mut tx).await?;
+    let count = s.erp.order_category.get_count(&q, &mut tx).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { items, count }))
 }
@@ -1090,9 +1669,11 @@ async fn update_order_category(
     authenticated: AuthenticatedUser,
     Json(body): Json<OrderCategory>,
 ) -> Result<Json<OrderCategory>> {
-    authenticated.is_manage_order_category()?;
+    authenticated.check_permission(Permission::ManageOrderCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.erp.order_category.get(id, tx.as_mut()).await?;
     if let Some(v) = s.erp.order_category.update(id, body, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::OrderCategory, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut()).await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
@@ -1146,7 +1727,7 @@ async fn add_order_payment(
     authenticated: AuthenticatedUser,
     Json(mut body): Json<OrderPayment>,
 ) -> Result<Json<OrderPayment>> {
-    authenticated.is_add_order_payment()?;
+    authenticated.check_permission(Permission::AddOrderPayment)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.order_payment.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::OrderPaymentLimitExceeded, "Order payment count limit exceeded!").into_err();
@@ -1154,12 +1735,13 @@ async fn add_order_payment(
     s.erp.order_payment.preprocess(&mut body, &authenticated.user);
     check_order_payment(s.clone(), &authenticated.user, &body, tx.as_mut()).await?;
     let r = s.erp.order_payment.add(body, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::OrderPayment, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
 async fn remove_order_payment_core(s: AppState, user: &UserInfo, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-    if !s.erp.order_payment.can_access(id, &user, &mut *tx).await? {
+    if !s.erp.order_payment.can_access(id, user, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::NoPermission, "You not the owner or admin!").into_err();
     }
     if !s.erp.order_payment.is_exists(id, &mut *tx).await? {
@@ -1168,7 +1750,12 @@ async fn remove_order_payment_core(s: AppState, user: &UserInfo, id: i64, notice
     if !s.erp.warehouse.is_linked(WarehouseIsFrom::OrderPayment(id), user.into(), &mut *tx).await? {
         return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
     }
-    Ok(s.erp.order_payment.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.order_payment.get(id, &mut *tx).await?;
+    let r = s.erp.order_payment.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::OrderPayment, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove order payment
@@ -1187,13 +1774,48 @@ async fn remove_order_payment(
     authenticated: AuthenticatedUser,
     Path(id): Path<i64>,
 ) -> Result<StatusCode> {
-    authenticated.is_update_remove_order_payment()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrderPayment)?;
     let mut tx = s.ps.begin_tx(true).await?;
     remove_order_payment_core(s.clone(), &authenticated.user, id, true, tx.as_mut()).await?;
     tx.commit().await?;
     Ok(StatusCode::OK)
 }
 
+/// restore order payment
+#[utoipa::path(
+    post,
+    path = "/order_payments/{id}/restore",
+    responses(
+        (status = 200, description = "restore order payment successfully", body = OrderPayment)
+    ),
+    params(
+        ("id"=i64, Path, description = "order payment id")
+    )
+)]
+async fn restore_order_payment(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<Json<OrderPayment>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrderPayment)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.order_payment.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::OrderPaymentLimitExceeded, "Order payment count limit exceeded!").into_err();
+    }
+    if !s.erp.order_payment.can_access(id, &authenticated.user, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NoPermission, "You not the owner or admin!").into_err();
+    }
+    let op = match s.erp.order_payment.get_removed(id, tx.as_mut()).await? {
+        Some(op) => op,
+        None => return AppError::custom(CustomErrorCode::OrderPaymentNotFound, "Removed order payment is not found.").into_err(),
+    };
+    check_order_payment(s.clone(), &authenticated.user, &op, tx.as_mut()).await?;
+    let r = s.erp.order_payment.restore(id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::OrderPayment, id, AuditAction::Restore, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
 /// clear order payments
 #[utoipa::path(
     delete,
@@ -1211,14 +1833,14 @@ async fn clear_order_payments(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetOrderPaymentsQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_update_remove_order_payment()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrderPayment)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let action = authenticated.user.as_action_type(false);
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.order_payment.get_multiple_ids(&pagination, &q, action, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
             match remove_order_payment_core(s.clone(), &authenticated.user, id, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
@@ -1252,6 +1874,7 @@ async fn get_order_payment(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<OrderPayment>> {
+    authenticated.check_permission(Permission::ViewOrderPayment)?;
     let mut tx = s.ps.begin_tx(false).await?;
     if !s.erp.order_payment.is_exists(id, tx.as_mut()).await? {
         return AppError::custom(
@@ -1284,14 +1907,41 @@ async fn get_order_payments(
     Query(q): Query<GetOrderPaymentsQuery>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<OrderPayment>>> {
+    authenticated.check_permission(Permission::ViewOrderPayment)?;
     let mut tx = s.ps.begin_tx(false).await?;
     let action = authenticated.user.as_action_type(false);
-    let items = s.erp.order_payment.get_multiple(&pagination.correct(), &q, action, &mut *tx).await?;
-    let count = s.erp.order_payment.get_count(&q, action, &mut *tx).await?;
+    let items = s.erp.order_payment.get_multiple(&pagination.correct(), &q, action, &mut tx).await?;
+    let count = s.erp.order_payment.get_count(&q, action, &mut tx).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { items, count }))
 }
 
+/// export order payments in xlsx or csv file.
+#[utoipa::path(
+    get,
+    path = "/order_payments/export",
+    responses(
+        (status = 200, description = "export order payments successfully", body = Response)
+    ),
+    params(
+        GetOrderPaymentsQuery,
+        ExportQuery,
+    )
+)]
+async fn export_order_payments(
+    State(s): State<AppState>,
+    Query(q): Query<GetOrderPaymentsQuery>,
+    Query(export): Query<ExportQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewOrderPayment)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let path = s.erp.order_payment.get_export(&q, export.get_format(), action, tx.as_mut()).await?;
+    tx.commit().await?;
+    export_response(path, "OrderPayments", export.get_format()).await
+}
+
 /// check order
 #[utoipa::path(
     post,
@@ -1307,12 +1957,27 @@ async fn check_order(
 ) -> Result<Json<CheckOrderResult>> {
     let mut tx = s.ps.begin_tx(false).await?;
     check_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await?;
-    let r = elerp_common::order_module::check(&order, false, tx.as_mut()).await?;
+    let r = elerp_common::order_module::check(&order, false, s.ps.get_timestamp_seconds() as i64, tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
+/// Get the order with items, used to record the audit log.
+async fn get_order_with_items(s: AppState, id: i64, tx: &mut SqliteConnection) -> Result<Option<Order>> {
+    Ok(match s.erp.order.get(id, &mut *tx).await? {
+        Some(mut order) => {
+            order.items = Some(s.erp.order.get_order_items(id, &Pagination::max(), &mut *tx).await?);
+            Some(order)
+        }
+        None => None,
+    })
+}
+
 async fn check_order_and_preprocess(s: AppState, authenticated: &AuthenticatedUser, initial: bool, order: &mut Order, tx: &mut SqliteConnection) -> Result<()> {
+    if let Some(items) = order.items.as_mut() {
+        fill_sku_ids(s.clone(), items, &mut *tx).await?;
+        fill_units(s.clone(), items, &mut *tx).await?;
+    }
     if initial {
         if !s.erp.warehouse.is_exists(order.warehouse_id, &mut *tx).await? {
             return AppError::custom(
@@ -1324,37 +1989,120 @@ async fn check_order_and_preprocess(s: AppState, authenticated: &AuthenticatedUs
         if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), (&authenticated.user).into(), &mut *tx).await? {
             return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
         }
-        if order.items.is_none() || order.items.as_ref().unwrap().len() == 0 {
+        if order.order_type == OrderType::Transfer {
+            if order.to_warehouse_id == order.warehouse_id {
+                return AppError::custom(CustomErrorCode::SameObject, "Can't transfer to the same warehouse!").into_err();
+            }
+            if !s.erp.warehouse.is_exists(order.to_warehouse_id, &mut *tx).await? {
+                return AppError::custom(CustomErrorCode::WarehouseNotFound, "Destination warehouse is not found.").into_err();
+            }
+            if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.to_warehouse_id), (&authenticated.user).into(), &mut *tx).await? {
+                return AppError::custom(CustomErrorCode::NotLinked, "You not linked to destination warehouse!").into_err();
+            }
+        }
+        if order.items.is_none() || order.items.as_ref().unwrap().is_empty() {
             return AppError::custom(CustomErrorCode::OrderItemsIsEmpty, "Items is empty!").into_err();
         }
+        if !s.erp.order.is_serial_numbers_matched(order.items.as_ref().unwrap(), order.order_type, &mut *tx).await? {
+            return AppError::custom(CustomErrorCode::SerialNumbersMismatch, "Serial numbers are not matched with the items!").into_err();
+        }
+        check_item_tax_rates(order.items.as_ref().unwrap())?;
+        check_item_discounts(order.items.as_ref().unwrap())?;
+    }
+    if order.discount.as_ref().is_some_and(|discount| !discount.is_valid()) {
+        return AppError::custom(CustomErrorCode::InvalidDiscount, "Discount must not be negative and the percentage can't exceed 100!").into_err();
     }
     if !s.erp.order_category.is_exists(order.order_category_id, tx).await? {
         return AppError::custom(CustomErrorCode::OrderCategoryNotFound, "Order category is not found.").into_err();
     }
+    if !s.erp.currency.is_exists(order.currency, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CurrencyNotFound, "Currency is not found.").into_err();
+    }
     if let Some(person) = s.erp.person.get(order.person_related_id, ActionType::System, &mut *tx).await? {
+        if let Some(items) = order.items.as_mut() {
+            fill_prices(s.clone(), person.id, order.currency, order.date, items, &mut *tx).await?;
+        }
         s.erp.order.preprocess(order, &authenticated.user, initial, person.person_in_charge_id);
-        
     } else {
         return AppError::custom(CustomErrorCode::PersonNotFound, "Person is not found.").into_err();
     }
     Ok(())
 }
 
-async fn check_guest_order_and_preprocess(s: AppState, authenticated: &AuthenticatedUser, order: &mut GuestOrder, tx: &mut SqliteConnection) -> Result<()> {
-    if !s.erp.warehouse.is_exists(order.warehouse_id, &mut *tx).await? {
-        return AppError::custom(
-            CustomErrorCode::WarehouseNotFound,
-            "Warehouse is not found.",
-        )
-        .into_err();
+/// Fill the sku ids of the items by the sku codes or barcodes.
+async fn fill_sku_ids(s: AppState, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<()> {
+    for item in items.iter_mut().filter(|item| item.sku_id == 0) {
+        let Some(code) = item.sku_code.as_deref() else {
+            continue;
+        };
+        match s.erp.sku.get_id_by_code(code.trim(), &mut *tx).await? {
+            Some(id) => item.sku_id = id,
+            None => return AppError::custom(CustomErrorCode::SKUNotFound, format!("The sku of the code {code} is not found.")).into_err(),
+        }
     }
-    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), (&authenticated.user).into(), &mut *tx).await? {
-        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    Ok(())
+}
+
+/// Convert the quantities entered in the units of the skus to the base units.
+async fn fill_units(s: AppState, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<()> {
+    if let Some((sku_id, unit)) = s.erp.sku.fill_units(items, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::InvalidUnit, format!("The unit {unit} of the sku {sku_id} is not found or its factor is not matched.")).into_err();
     }
-    if !s.erp.order_category.is_exists(order.order_category_id, tx).await? {
+    Ok(())
+}
+
+/// Fill the prices omitted by the price lists of the person.
+async fn fill_prices(s: AppState, person_id: i64, currency: OrderCurrency, date: i64, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<()> {
+    let not_found = s.erp.price_list.fill_prices(person_id, currency, date, items, &mut *tx).await?;
+    if let Some(sku_id) = not_found.first() {
+        return AppError::custom(CustomErrorCode::PriceNotFound, format!("Price of the sku {sku_id} is not found in the price lists.")).into_err();
+    }
+    Ok(())
+}
+
+fn is_valid_tax_rate(rate: f64) -> bool {
+    rate.is_finite() && (0.0..=100.0).contains(&rate)
+}
+
+fn check_item_tax_rates(items: &[OrderItem]) -> Result<()> {
+    if items.iter().any(|item| item.tax_rate.is_some_and(|rate| !is_valid_tax_rate(rate))) {
+        return AppError::custom(CustomErrorCode::InvalidTaxRate, "Tax rate must be between 0 and 100!").into_err();
+    }
+    Ok(())
+}
+
+fn check_item_discounts(items: &[OrderItem]) -> Result<()> {
+    if items.iter().any(|item| item.discount.as_ref().is_some_and(|discount| !discount.is_valid())) {
+        return AppError::custom(CustomErrorCode::InvalidDiscount, "Discount must not be negative and the percentage can't exceed 100!").into_err();
+    }
+    Ok(())
+}
+
+async fn check_guest_order_and_preprocess(s: AppState, authenticated: &AuthenticatedUser, order: &mut GuestOrder, tx: &mut SqliteConnection) -> Result<()> {
+    if order.order_type == OrderType::Transfer {
+        return AppError::custom(CustomErrorCode::NotAllowed, "Guest can't make the transfer order!").into_err();
+    }
+    if !s.erp.warehouse.is_exists(order.warehouse_id, &mut *tx).await? {
+        return AppError::custom(
+            CustomErrorCode::WarehouseNotFound,
+            "Warehouse is not found.",
+        )
+        .into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), (&authenticated.user).into(), &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.order_category.is_exists(order.order_category_id, tx).await? {
         return AppError::custom(CustomErrorCode::OrderCategoryNotFound, "Order category is not found.").into_err();
     }
+    if !s.erp.currency.is_exists(order.currency, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CurrencyNotFound, "Currency is not found.").into_err();
+    }
     if let Some(person) = s.erp.person.get(order.person_related_id, ActionType::System, &mut *tx).await? {
+        if let Some(mut items) = order.items.take() {
+            check_guest_order_items(s.clone(), order, &mut items, &mut *tx).await?;
+            order.items = Some(items);
+        }
         s.erp.guest_order.preprocess(order, &authenticated.user, person.person_in_charge_id);
     } else {
         return AppError::custom(CustomErrorCode::PersonNotFound, "Person is not found.").into_err();
@@ -1362,6 +2110,15 @@ async fn check_guest_order_and_preprocess(s: AppState, authenticated: &Authentic
     Ok(())
 }
 
+/// Check the items of the guest order and fill the omitted by the person of the guest order, the same as the items of the orders.
+async fn check_guest_order_items(s: AppState, order: &GuestOrder, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<()> {
+    fill_sku_ids(s.clone(), items, &mut *tx).await?;
+    fill_units(s.clone(), items, &mut *tx).await?;
+    check_item_tax_rates(items)?;
+    check_item_discounts(items)?;
+    fill_prices(s.clone(), order.person_related_id, order.currency, order.date, items, &mut *tx).await
+}
+
 /// add order
 #[utoipa::path(
     post,
@@ -1375,21 +2132,225 @@ async fn add_order(
     authenticated: AuthenticatedUser,
     Json(mut order): Json<Order>,
 ) -> Result<Json<Order>> {
-    authenticated.is_add_order()?;
+    authenticated.check_permission(Permission::AddOrder)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.order.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::OrderLimitExceeded, "Order count limit exceeded!").into_err();
     }
+    s.erp.inventory.release_expired_reservations(tx.as_mut()).await?;
     check_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await?;
     if !s.erp.order.is_check_pass(&order, tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::CheckFailed, "Order can't pass the check!")
             .into_err();
     }
-    let r = s.erp.order.add(order, tx.as_mut()).await?;
+    let r = s.erp.order.add(order, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
+/// import the opening stock from the xlsx or csv file in the body, a calibration order is added for each warehouse.
+/// The person related of the orders is the person in charge of the warehouse, the price is the unit cost of the stock. Nothing is imported if any row is invalid.
+#[utoipa::path(
+    post,
+    path = "/orders/opening_stock",
+    request_body(content = Vec<u8>, description = "The headers are Warehouse, SKU, SKU Category, Quantity, Price, Lot Number, Expiry Date and Serial Numbers.", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "import opening stock successfully", body = ImportResult)
+    ),
+    params(
+        ImportOpeningStockQuery,
+    )
+)]
+async fn import_opening_stock(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Query(q): Query<ImportOpeningStockQuery>,
+    body: Bytes,
+) -> Result<Json<ImportResult>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let table = read_import_table(q.format, &body, &["Warehouse", "SKU", "Quantity"])?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let mut cache = IdCache::new();
+    let mut errors = vec![];
+    // Warehouse id, the first row of the warehouse and its items, in the order of the file.
+    let mut warehouses: Vec<(i64, usize, Vec<OrderItem>)> = vec![];
+    for row in table.rows() {
+        let (warehouse_id, item) = match s.erp.order.parse_opening_stock_row(&row, &mut cache, tx.as_mut()).await {
+            Ok(v) => v,
+            Err(err) => {
+                push_import_error(&mut errors, row.number(), err.into())?;
+                continue;
+            }
+        };
+        match warehouses.iter_mut().find(|(id, _, _)| *id == warehouse_id) {
+            // The calibration sets the quantity of the sku, so it can't be split to several rows.
+            Some((_, _, items)) if items.iter().any(|v| v.sku_id == item.sku_id) => {
+                errors.push(ImportRowError {
+                    row: row.number(),
+                    msg: format!("SKU '{}' is already in the rows of the warehouse.", row.get("SKU")),
+                });
+            }
+            Some((_, _, items)) => items.push(item),
+            None => warehouses.push((warehouse_id, row.number(), vec![item])),
+        }
+    }
+    let currency = q.currency.unwrap_or(s.ps.get_config().currency.base);
+    let mut ids = Vec::with_capacity(warehouses.len());
+    for (warehouse_id, row, items) in warehouses {
+        let person_related_id = match s.erp.warehouse.get(warehouse_id, tx.as_mut()).await? {
+            Some(v) => v.person_in_charge_id,
+            None => {
+                push_import_error(&mut errors, row, AppError::custom(CustomErrorCode::WarehouseNotFound, "Warehouse is not found."))?;
+                continue;
+            }
+        };
+        let mut order = Order {
+            id: 0,
+            created_by_user_id: 0,
+            updated_by_user_id: 0,
+            date: 0,
+            last_updated_date: 0,
+            person_in_charge_id: 0,
+            order_category_id: q.order_category_id,
+            from_guest_order_id: 0,
+            currency,
+            items: Some(items),
+            total_amount: 0.0,
+            total_amount_settled: 0.0,
+            order_payment_status: OrderPaymentStatus::None,
+            warehouse_id,
+            person_related_id,
+            description: "Opening stock".to_owned(),
+            order_type: OrderType::Calibration,
+            is_record: false,
+            non_payment: true,
+            to_warehouse_id: 0,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+        };
+        if s.erp.order.is_limit_reached(tx.as_mut()).await? {
+            return AppError::custom(CustomErrorCode::OrderLimitExceeded, "Order count limit exceeded!").into_err();
+        }
+        if let Err(err) = check_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await {
+            push_import_error(&mut errors, row, err)?;
+            continue;
+        }
+        if !s.erp.order.is_check_pass(&order, tx.as_mut()).await? {
+            push_import_error(&mut errors, row, AppError::custom(CustomErrorCode::CheckFailed, "Order can't pass the check!"))?;
+            continue;
+        }
+        let r = s.erp.order.add(order, false, tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+        ids.push(r.id);
+    }
+    finish_import(&s, tx, q.dry_run.unwrap_or(false), table.len(), errors, ids, WebSocketFlags::AddOrder).await
+}
+
+/// import the orders from the xlsx or csv file in the body, each row is a line of the order in the `Order` column.
+/// The columns of the order are read from its first row, the lines are checked as adding the order.
+#[utoipa::path(
+    post,
+    path = "/orders/import",
+    request_body(content = Vec<u8>, description = "The headers are Order, Order Type, Person, Warehouse, To Warehouse, Order Category, Currency, Tax Inclusive, Non Payment, Description, SKU, SKU Category, Quantity, Price, Tax Rate, Exchanged, Lot Number, Expiry Date and Serial Numbers.", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "import orders successfully", body = ImportOrdersResult)
+    ),
+    params(
+        ImportOrdersQuery,
+    )
+)]
+async fn import_orders(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Query(q): Query<ImportOrdersQuery>,
+    body: Bytes,
+) -> Result<Json<ImportOrdersResult>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let table = read_import_table(q.format, &body, &["Order", "Order Type", "Person", "Warehouse", "Order Category", "SKU", "Quantity"])?;
+    let mut groups: Vec<(String, Vec<ImportRow>)> = vec![];
+    for row in table.rows() {
+        let key = row.get("Order").to_owned();
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, rows)) => rows.push(row),
+            None => groups.push((key, vec![row])),
+        }
+    }
+    let mut tx = s.ps.begin_tx(true).await?;
+    let mut cache = IdCache::new();
+    let mut orders = Vec::with_capacity(groups.len());
+    for (key, rows) in groups {
+        let first = rows[0].number();
+        let mut errors = vec![];
+        if key.is_empty() {
+            errors.push(ImportRowError { row: first, msg: "Order is required.".to_owned() });
+        }
+        let order = match s.erp.order.parse_import_order(&rows[0], &mut cache, tx.as_mut()).await {
+            Ok(v) => Some(v),
+            Err(err) => {
+                push_import_error(&mut errors, first, err.into())?;
+                None
+            }
+        };
+        let mut items = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            match s.erp.order.parse_import_item(row, &mut cache, tx.as_mut()).await {
+                Ok(v) => items.push(v),
+                Err(err) => push_import_error(&mut errors, row.number(), err.into())?,
+            }
+        }
+        let mut items_not_available = vec![];
+        let mut id = 0;
+        if let (Some(mut order), true) = (order, errors.is_empty()) {
+            order.items = Some(items);
+            if s.erp.order.is_limit_reached(tx.as_mut()).await? {
+                return AppError::custom(CustomErrorCode::OrderLimitExceeded, "Order count limit exceeded!").into_err();
+            }
+            match check_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await {
+                Ok(()) => items_not_available = elerp_common::order_module::check(&order, false, s.ps.get_timestamp_seconds() as i64, tx.as_mut()).await?.items_not_available,
+                Err(err) => push_import_error(&mut errors, first, err)?,
+            }
+            // The valid orders are added at once, so the orders after them are checked with their stock.
+            if errors.is_empty() && items_not_available.is_empty() {
+                let r = s.erp.order.add(order, false, tx.as_mut()).await?;
+                s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+                id = r.id;
+            }
+        }
+        orders.push(ImportOrderResult {
+            key,
+            rows: rows.iter().map(|row| row.number()).collect(),
+            valid: id > 0,
+            id,
+            errors,
+            items_not_available,
+        });
+    }
+    let dry_run = q.dry_run.unwrap_or(false);
+    let committed = !dry_run && (!q.all_or_nothing.unwrap_or(true) || orders.iter().all(|v| v.valid));
+    if committed {
+        tx.commit().await?;
+        // The orders are noticed only after they are committed.
+        for v in orders.iter().filter(|v| v.valid) {
+            s.ps.notice(WebSocketFlags::AddOrder(v.id)).await?;
+        }
+    } else {
+        for v in orders.iter_mut() {
+            v.id = 0;
+        }
+    }
+    Ok(Json(ImportOrdersResult {
+        dry_run,
+        committed,
+        total: table.len(),
+        orders,
+    }))
+}
+
 /// add guest order
 #[utoipa::path(
     post,
@@ -1403,7 +2364,7 @@ async fn add_guest_order(
     authenticated: AuthenticatedUser,
     Json(mut order): Json<GuestOrder>,
 ) -> Result<Json<GuestOrder>> {
-    authenticated.is_add_order()?;
+    authenticated.check_permission(Permission::AddOrder)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.guest_order.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::GuestOrderLimitExceeded, "Guest order count limit exceeded!").into_err();
@@ -1411,6 +2372,7 @@ async fn add_guest_order(
     check_guest_order_and_preprocess(s.clone(), &authenticated, &mut order, tx.as_mut()).await?;
     let sub_token = s.us.get_sub_token(&authenticated.user, tx.as_mut()).await?;
     let r = s.erp.guest_order.add(&sub_token, order, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::GuestOrder, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
@@ -1430,7 +2392,7 @@ async fn confirm_guest_order(
     State(s): State<AppState>,
     Path(id): Path<i64>,
     headers: HeaderMap,
-    Json(body): Json<GuestOrder>,
+    Json(mut body): Json<GuestOrder>,
 ) -> Result<Json<GuestOrderConfirm>> {
     let mut tx = s.ps.begin_tx(true).await?;
     if !s.erp.guest_order.is_exists(id, tx.as_mut()).await? {
@@ -1449,9 +2411,10 @@ async fn confirm_guest_order(
             return AppError::custom(CustomErrorCode::NoPermission, "You no permission to confirm!").into_err();
         }
     };
-    if s.us.get_sub_token_owner(token, tx.as_mut()).await?.is_none() {
-        return AppError::custom(CustomErrorCode::UserNotFound, "Can't found the owner of your token!").into_err();
-    }
+    let owner = match s.us.get_sub_token_owner(token, tx.as_mut()).await? {
+        Some(owner) => owner,
+        None => return AppError::custom(CustomErrorCode::UserNotFound, "Can't found the owner of your token!").into_err(),
+    };
     match s.erp.guest_order.get_status(id, tx.as_mut()).await? {
     GuestOrderStatus::Confirmed => {
         return AppError::custom(CustomErrorCode::GuestOrderConfirmed, "Guest order is confirmed already!").into_err();
@@ -1462,8 +2425,24 @@ async fn confirm_guest_order(
     GuestOrderStatus::Pending => ()
 };
 
+let before = s.erp.guest_order.get(id, tx.as_mut()).await?;
+if let (Some(go), Some(items)) = (before.as_ref(), body.items.as_mut()) {
+    check_guest_order_items(s.clone(), go, items, tx.as_mut()).await?;
+}
+if let (Some(go), Some(items)) = (before.as_ref(), body.items.as_ref()) {
+    if !s.erp.order.is_serial_numbers_matched(items, go.order_type, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SerialNumbersMismatch, "Serial numbers are not matched with the items!").into_err();
+    }
+}
 match s.erp.guest_order.confirm(id, body, tx.as_mut()).await? {
- Some(result) => Ok(Json(result)),
+ Some(result) => {
+    if let Some(order) = result.order.as_ref() {
+        s.erp.audit_log.add(owner.id, AuditEntity::GuestOrder, id, AuditAction::Confirm, before.as_ref(), Some(order), tx.as_mut()).await?;
+        s.erp.inventory.release_reservations(id, 0, tx.as_mut()).await?;
+    }
+    tx.commit().await?;
+    Ok(Json(result))
+ },
  None => AppError::custom(CustomErrorCode::OrderNotFound, "Order is not found.").into_err()
 }
 }
@@ -1477,16 +2456,24 @@ async fn remove_order_core(s: AppState, user: &UserInfo, id: i64, recall: bool,
     if s.erp.order.is_from_guest_order(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::FromGuestOrder, "This order is created from guest order!").into_err();
     }
+    if s.erp.purchase_order.is_receipt_order(id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::FromPurchaseOrder, "This order is received against the purchase order!").into_err();
+    }
     if !s.erp.warehouse.is_linked(WarehouseIsFrom::Order(id), user.into(), &mut *tx).await? {
         return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
     }
-    if !s.erp.order.can_access(id, &user, &mut *tx).await? {
+    if !s.erp.order.can_access(id, user, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::NoPermission, "You not the order's owner or admin!").into_err();
     }
     if s.erp.order.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the order.").into_err();
     }
-    Ok(s.erp.order.remove(id, recall, notice, action, &mut *tx).await?)
+    let before = get_order_with_items(s.clone(), id, &mut *tx).await?;
+    let r = s.erp.order.remove(id, recall, notice, action, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::Order, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove order
@@ -1505,13 +2492,80 @@ async fn remove_order(
     authenticated: AuthenticatedUser,
     Path(id): Path<i64>,
 ) -> Result<StatusCode> {
-    authenticated.is_update_remove_order()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
     let mut tx = s.ps.begin_tx(true).await?;
     remove_order_core(s.clone(), &authenticated.user, id, true, true, tx.as_mut()).await?;
     tx.commit().await?;
     Ok(StatusCode::OK)
 }
 
+async fn check_removed_order(s: AppState, order: &Order, tx: &mut SqliteConnection) -> Result<()> {
+    if !s.erp.warehouse.is_exists(order.warehouse_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::WarehouseNotFound, "Warehouse is not found.").into_err();
+    }
+    if !s.erp.order_category.is_exists(order.order_category_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::OrderCategoryNotFound, "Order category is not found.").into_err();
+    }
+    if !s.erp.person.is_exists(order.person_related_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::PersonNotFound, "Person is not found.").into_err();
+    }
+    if order.person_in_charge_id > 0 && !s.erp.person.is_exists(order.person_in_charge_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::PersonNotFound, "Person in charge is not found.").into_err();
+    }
+    for item in order.items.iter().flatten() {
+        if !s.erp.sku.is_exists(item.sku_id, &mut *tx).await? {
+            return AppError::custom(CustomErrorCode::SKUNotFound, "The sku of items is not found!").into_err();
+        }
+    }
+    if !order.is_record && !s.erp.order.is_check_pass(order, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Order can't pass the check!").into_err();
+    }
+    Ok(())
+}
+
+/// restore order
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/restore",
+    responses(
+        (status = 200, description = "restore order successfully", body = Order)
+    ),
+    params(
+        ("id"=i64, Path, description = "order id")
+    )
+)]
+async fn restore_order(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<Json<Order>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.order.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::OrderLimitExceeded, "Order count limit exceeded!").into_err();
+    }
+    let mut order = match s.erp.order.get_removed(id, tx.as_mut()).await? {
+        Some(order) => order,
+        None => return AppError::custom(CustomErrorCode::OrderNotFound, "Removed order is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.order.can_access(id, &authenticated.user, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NoPermission, "You not the order's owner or admin!").into_err();
+    }
+    order.items = Some(s.erp.order.get_order_items(id, &Pagination::max(), tx.as_mut()).await?);
+    check_removed_order(s.clone(), &order, tx.as_mut()).await?;
+    let action = authenticated.user.as_action_type(false);
+    let mut r = s.erp.order.restore(id, action, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, id, AuditAction::Restore, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        r.hide_price();
+    }
+    Ok(Json(r))
+}
+
 /// clear orders
 #[utoipa::path(
     delete,
@@ -1529,14 +2583,14 @@ async fn clear_orders(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetOrdersQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_update_remove_order()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let action = authenticated.user.as_action_type(false);
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.order.get_multiple_ids(&pagination, &q, action, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
             match remove_order_core(s.clone(), &authenticated.user, id, false, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
@@ -1569,15 +2623,21 @@ async fn remove_guest_order_core(s: AppState, user: &UserInfo, id: i64, recall:
         return AppError::custom(CustomErrorCode::NoPermission, "You not the order's owner or admin!").into_err();
     }
     let order_id = s.erp.guest_order.get_order_id(id, &mut *tx).await?.unwrap();
-    if order_id > 0 {
-        if s.erp.order.is_depend_by_another(order_id, &mut *tx).await? {
-            return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "The guest order has the order id is depent to the order.").into_err();
-        }
+    if order_id > 0 && s.erp.order.is_depend_by_another(order_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "The guest order has the order id is depent to the order.").into_err();
     }
     
+    let before = s.erp.guest_order.get(id, &mut *tx).await?;
     let r = s.erp.guest_order.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::GuestOrder, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+        s.erp.inventory.release_reservations(id, 0, &mut *tx).await?;
+    }
         if order_id>0 {
-            s.erp.order.remove(order_id, recall,notice,  action, &mut *tx).await?;
+            let before = get_order_with_items(s.clone(), order_id, &mut *tx).await?;
+            if s.erp.order.remove(order_id, recall,notice,  action, &mut *tx).await? {
+                s.erp.audit_log.add(user.id, AuditEntity::Order, order_id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+            }
         }
         Ok(r)
 }
@@ -1598,7 +2658,7 @@ async fn remove_guest_order(
     authenticated: AuthenticatedUser,
     Path(id): Path<i64>,
 ) -> Result<StatusCode> {
-    authenticated.is_update_remove_order()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
     let mut tx = s.ps.begin_tx(true).await?;
     remove_guest_order_core(s.clone(), &authenticated.user, id, true, true, tx.as_mut()).await?;
     tx.commit().await?;
@@ -1622,14 +2682,14 @@ async fn clear_guest_orders(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetGuestOrdersQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_update_remove_order()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
     let mut tx = s.ps.begin_tx(false).await?;
     let action = authenticated.user.as_action_type(false);
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.guest_order.get_multiple_ids(&pagination, &q, action, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for guest_id in ids {
             match remove_guest_order_core(s.clone(), &authenticated.user, guest_id, false, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
@@ -1686,6 +2746,7 @@ async fn get_order(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<Order>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
     let mut tx = s.ps.begin_tx(false).await?;
     if !s.erp.order.is_exists(id, tx.as_mut()).await? {
         return AppError::custom(
@@ -1697,7 +2758,48 @@ async fn get_order(
     if !s.erp.warehouse.is_linked(WarehouseIsFrom::Order(id), (&authenticated.user).into(), tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
     }
-    Ok(Json(s.erp.order.get(id, tx.as_mut()).await?.unwrap()))
+    let mut order = s.erp.order.get(id, tx.as_mut()).await?.unwrap();
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        order.hide_price();
+    }
+    Ok(Json(order))
+}
+
+/// order document in pdf file, the invoice, the delivery note or the packing slip.
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/pdf",
+    responses(
+        (status = 200, description = "get order document successfully", body = Response)
+    ),
+    params(
+        ("id"=i64, Path, description = "order id"),
+        GetDocumentQuery,
+    )
+)]
+async fn get_order_document(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    Query(q): Query<GetDocumentQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    if !s.erp.order.is_exists(id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::OrderNotFound, "Order is not found.").into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::Order(id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    let show_price = authenticated.has_permission(Permission::ViewOrderPrice);
+    let path = s.erp.order.get_document(id, q.kind, show_price, tx.as_mut()).await?.unwrap();
+    tx.commit().await?;
+    let file = tokio::fs::File::open(path).await.map_err(anyhow::Error::from)?;
+    let stream = ReaderStream::new(file);
+    let body = Body::from_stream(stream);
+    let disposition = format!("attachment; filename=\"{}-{id}.pdf\"", q.kind.default_title().replace(' ', ""));
+    let headers = [(header::CONTENT_TYPE, "application/pdf".to_owned()), (header::CONTENT_DISPOSITION, disposition)];
+    Ok((headers, body).into_response())
 }
 
 /// get order items
@@ -1716,6 +2818,9 @@ async fn get_order_items(
     Path(id): Path<i64>,
     authenticated: Option<AuthenticatedUser>,
 ) -> Result<Json<Vec<OrderItem>>> {
+    if let Some(authenticated) = authenticated.as_ref() {
+        authenticated.check_permission(Permission::ViewOrder)?;
+    }
     let mut tx = s.ps.begin_tx(false).await?;
     if !s.erp.order.is_exists(id, tx.as_mut()).await? {
         return AppError::custom(
@@ -1724,11 +2829,100 @@ async fn get_order_items(
         )
         .into_err();
     }
-    if !s.erp.order.is_from_guest_order(id, tx.as_mut()).await? && authenticated.is_none() &&
-        !s.erp.warehouse.is_linked(WarehouseIsFrom::Order(id), (&authenticated.unwrap().user).into(), tx.as_mut()).await? {
+    // The items of the guest order can be got without login.
+    if !s.erp.order.is_from_guest_order(id, tx.as_mut()).await? {
+        match authenticated.as_ref() {
+            Some(authenticated) => {
+                if !s.erp.warehouse.is_linked(WarehouseIsFrom::Order(id), (&authenticated.user).into(), tx.as_mut()).await? {
+                    return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+                }
+            }
+            None => return AppError::custom(CustomErrorCode::NoPermission, "Please login to continue...").into_err(),
+        }
+    }
+    let mut items = s.erp.order.get_order_items(id, &Pagination::max(), tx.as_mut()).await?;
+    if authenticated.is_some_and(|authenticated| !authenticated.has_permission(Permission::ViewOrderPrice)) {
+        items.iter_mut().for_each(|item| item.price = 0.0);
+    }
+    Ok(Json(items))
+}
+
+/// get order revisions
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/revisions",
+    responses(
+        (status = 200, description = "get order revisions successfully", body = Vec<OrderRevision>)
+    ),
+    params(
+        ("id" = i64, Path, description = "order id")
+    )
+)]
+async fn get_order_revisions(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Vec<OrderRevision>>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let warehouse_id = match s.erp.order.get(id, tx.as_mut()).await? {
+        Some(order) => order.warehouse_id,
+        None => match s.erp.order.get_removed(id, tx.as_mut()).await? {
+            Some(order) => order.warehouse_id,
+            None => return AppError::custom(CustomErrorCode::OrderNotFound, "Order is not found.").into_err(),
+        },
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
     }
-    Ok(Json(s.erp.order.get_order_items(id, &Pagination::max(), tx.as_mut()).await?))
+    let mut revisions = s.erp.order.get_revisions(id, tx.as_mut()).await?;
+    tx.commit().await?;
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        revisions.iter_mut().for_each(|revision| revision.hide_price());
+    }
+    Ok(Json(revisions))
+}
+
+/// get serial number histories
+#[utoipa::path(
+    get,
+    path = "/serial_numbers",
+    responses(
+        (status = 200, description = "get serial number histories successfully", body = Vec<SerialNumberHistory>)
+    ),
+    params(
+        GetSerialNumberQuery,
+    )
+)]
+async fn get_serial_numbers(
+    State(s): State<AppState>,
+    Query(q): Query<GetSerialNumberQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Vec<SerialNumberHistory>>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let mut histories = s.erp.order.get_serial_number_histories(&q, tx.as_mut()).await?;
+    let mut linked: Vec<(i64, bool)> = Vec::new();
+    for history in histories.iter_mut() {
+        let mut movements = Vec::with_capacity(history.movements.len());
+        for movement in history.movements.drain(..) {
+            let is_linked = match linked.iter().find(|(id, _)| *id == movement.warehouse_id) {
+                Some((_, v)) => *v,
+                None => {
+                    let v = s.erp.warehouse.is_linked(WarehouseIsFrom::ID(movement.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await?;
+                    linked.push((movement.warehouse_id, v));
+                    v
+                }
+            };
+            if is_linked {
+                movements.push(movement);
+            }
+        }
+        history.movements = movements;
+    }
+    tx.commit().await?;
+    histories.retain(|history| !history.movements.is_empty());
+    Ok(Json(histories))
 }
 
 /// get guest order
@@ -1748,6 +2942,9 @@ async fn get_guest_order(
     authenticated: Option<AuthenticatedUser>,
     headers: HeaderMap,
 ) -> Result<Json<GuestOrder>> {
+    if let Some(authenticated) = authenticated.as_ref() {
+        authenticated.check_permission(Permission::ViewOrder)?;
+    }
     let mut tx = s.ps.begin_tx(false).await?;
     if !s.erp.guest_order.is_exists(id, tx.as_mut()).await? {
         return AppError::custom(
@@ -1768,7 +2965,11 @@ async fn get_guest_order(
             }
           };
     }
-    Ok(Json(s.erp.guest_order.get(id, tx.as_mut()).await?.unwrap()))
+    let mut order = s.erp.guest_order.get(id, tx.as_mut()).await?.unwrap();
+    if authenticated.is_some_and(|authenticated| !authenticated.has_permission(Permission::ViewOrderPrice)) {
+        order.hide_price();
+    }
+    Ok(Json(order))
 }
 
 /// get orders
@@ -1789,14 +2990,45 @@ async fn get_orders(
     Query(q): Query<GetOrdersQuery>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<Order>>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
     let mut tx = s.ps.begin_tx(false).await?;
     let action = authenticated.user.as_action_type(false);
-    let items = s.erp.order.get_multiple(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    let mut items = s.erp.order.get_multiple(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        items.iter_mut().for_each(|order| order.hide_price());
+    }
     let count = s.erp.order.get_count(&q, action, tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { count, items }))
 }
 
+/// export orders in xlsx or csv file, one row per order item if `with_items`, the file with items can be imported again.
+#[utoipa::path(
+    get,
+    path = "/orders/export",
+    responses(
+        (status = 200, description = "export orders successfully", body = Response)
+    ),
+    params(
+        GetOrdersQuery,
+        ExportQuery,
+    )
+)]
+async fn export_orders(
+    State(s): State<AppState>,
+    Query(q): Query<GetOrdersQuery>,
+    Query(export): Query<ExportQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let show_price = authenticated.has_permission(Permission::ViewOrderPrice);
+    let path = s.erp.order.get_export(&q, &export, show_price, action, tx.as_mut()).await?;
+    tx.commit().await?;
+    export_response(path, "Orders", export.get_format()).await
+}
+
 /// get guest orders
 #[utoipa::path(
     get,
@@ -1815,14 +3047,44 @@ async fn get_guest_orders(
     Query(q): Query<GetGuestOrdersQuery>,
     authenticated: AuthenticatedUser,
 ) -> Result<Json<ListSlice<GuestOrder>>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
     let mut tx = s.ps.begin_tx(false).await?;
     let action = authenticated.user.as_action_type(false);
-    let items = s.erp.guest_order.get_multiple(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    let mut items = s.erp.guest_order.get_multiple(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        items.iter_mut().for_each(|order| order.hide_price());
+    }
     let count = s.erp.guest_order.get_count(&q, action, tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(ListSlice { count, items }))
 }
 
+/// export guest orders in xlsx or csv file.
+#[utoipa::path(
+    get,
+    path = "/guest_orders/export",
+    responses(
+        (status = 200, description = "export guest orders successfully", body = Response)
+    ),
+    params(
+        GetGuestOrdersQuery,
+        ExportQuery,
+    )
+)]
+async fn export_guest_orders(
+    State(s): State<AppState>,
+    Query(q): Query<GetGuestOrdersQuery>,
+    Query(export): Query<ExportQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let path = s.erp.guest_order.get_export(&q, export.get_format(), action, tx.as_mut()).await?;
+    tx.commit().await?;
+    export_response(path, "GuestOrders", export.get_format()).await
+}
+
 /// update order
 #[utoipa::path(
     put,
@@ -1840,7 +3102,7 @@ async fn update_order(
     authenticated: AuthenticatedUser,
     Json(mut body): Json<Order>,
 ) -> Result<Json<Order>> {
-    authenticated.is_update_remove_order()?;
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if !s.erp.order.is_exists(id, tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::OrderNotFound, "Order is not found.").into_err();
@@ -1852,73 +3114,1638 @@ async fn update_order(
         return AppError::custom(CustomErrorCode::NoPermission, "You not the order's owner or admin!").into_err();
     }
     check_order_and_preprocess(s.clone(), &authenticated, false, &mut body, tx.as_mut()).await?;
+    let before = get_order_with_items(s.clone(), id, tx.as_mut()).await?;
     let r = s.erp.order.update(id, body, authenticated.user.as_action_type(false), tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, id, AuditAction::Update, before.as_ref(), Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
-/// inventory list
+/// receive the transfer order in transit
 #[utoipa::path(
-    get,
-    path = "/inventory",
+    post,
+    path = "/orders/{id}/receive",
     responses(
-        (status = 200, description = "get inventory list successfully", body = ListSlice<InventoryProduct>)
+        (status = 200, description = "receive transfer order successfully", body = Order)
     ),
     params(
-        Pagination,
-        GetInventoryQuery,
+        ("id"=i64, Path, description = "order id")
     )
 )]
-async fn inventory_list(
+async fn receive_order(
     State(s): State<AppState>,
-    Query(pagination): Query<Pagination>,
-    Query(q): Query<GetInventoryQuery>,
+    Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
-) -> Result<Json<ListSlice<InventoryProduct>>> {
-    let mut tx = s.ps.begin_tx(false).await?;
-    let action = authenticated.user.as_action_type(false);
+) -> Result<Json<Order>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match get_order_with_items(s.clone(), id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::OrderNotFound, "Order is not found.").into_err(),
+    };
+    if before.order_type != OrderType::Transfer || !before.in_transit {
+        return AppError::custom(CustomErrorCode::NotAllowed, "Order is not a transfer in transit!").into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(before.to_warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to destination warehouse!").into_err();
+    }
+    let r = s.erp.order.receive(id, authenticated.user.id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+async fn check_purchase_order_and_preprocess(s: AppState, authenticated: &AuthenticatedUser, initial: bool, order: &mut PurchaseOrder, tx: &mut SqliteConnection) -> Result<()> {
+    if !s.erp.warehouse.is_exists(order.warehouse_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::WarehouseNotFound, "Warehouse is not found.").into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), (&authenticated.user).into(), &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.order_category.is_exists(order.order_category_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::OrderCategoryNotFound, "Order category is not found.").into_err();
+    }
+    if !s.erp.currency.is_exists(order.currency, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CurrencyNotFound, "Currency is not found.").into_err();
+    }
+    match order.items.as_ref() {
+        Some(items) if !items.is_empty() => {
+            let mut skus = Vec::with_capacity(items.len());
+            for item in items {
+                if item.quantity <= 0 || item.price < 0.0 {
+                    return AppError::custom(CustomErrorCode::CheckFailed, "Quantity must be greater than 0 and price can't be negative!").into_err();
+                }
+                if skus.contains(&item.sku_id) {
+                    return AppError::custom(CustomErrorCode::SameObject, "SKU is repeated in the items!").into_err();
+                }
+                if !s.erp.sku.is_exists(item.sku_id, &mut *tx).await? {
+                    return AppError::custom(CustomErrorCode::SKUNotFound, "SKU is not found.").into_err();
+                }
+                skus.push(item.sku_id);
+            }
+        }
+        Some(_) => return AppError::custom(CustomErrorCode::OrderItemsIsEmpty, "Items is empty!").into_err(),
+        None if initial => return AppError::custom(CustomErrorCode::OrderItemsIsEmpty, "Items is empty!").into_err(),
+        None => (),
+    }
+    if let Some(person) = s.erp.person.get(order.supplier_id, ActionType::System, &mut *tx).await? {
+        s.erp.purchase_order.preprocess(order, &authenticated.user, initial, person.person_in_charge_id);
+    } else {
+        return AppError::custom(CustomErrorCode::PersonNotFound, "Supplier is not found.").into_err();
+    }
+    Ok(())
+}
+
+/// Get the purchase order can be changed by the user.
+async fn get_accessible_purchase_order(s: AppState, user: &UserInfo, id: i64, tx: &mut SqliteConnection) -> Result<PurchaseOrder> {
+    let order = match s.erp.purchase_order.get(id, &mut *tx).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::PurchaseOrderNotFound, "Purchase order is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), user.into(), &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.purchase_order.can_access(id, user, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NoPermission, "You not the order's owner or admin!").into_err();
+    }
+    Ok(order)
+}
+
+/// add purchase order
+#[utoipa::path(
+    post,
+    path = "/purchase_orders",
+    responses(
+        (status = 200, description = "add purchase order successfully", body = PurchaseOrder)
+    ),
+)]
+async fn add_purchase_order(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(mut order): Json<PurchaseOrder>,
+) -> Result<Json<PurchaseOrder>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_purchase_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await?;
+    let r = s.erp.purchase_order.add(order, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PurchaseOrder, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// get purchase order
+#[utoipa::path(
+    get,
+    path = "/purchase_orders/{id}",
+    responses(
+        (status = 200, description = "get purchase order successfully", body = PurchaseOrder)
+    ),
+    params(
+        ("id"=i64, Path, description = "purchase order id")
+    )
+)]
+async fn get_purchase_order(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<PurchaseOrder>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let mut order = match s.erp.purchase_order.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::PurchaseOrderNotFound, "Purchase order is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(order.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        order.hide_price();
+    }
+    tx.commit().await?;
+    Ok(Json(order))
+}
+
+/// get purchase orders
+#[utoipa::path(
+    get,
+    path = "/purchase_orders",
+    responses(
+        (status = 200, description = "get purchase orders successfully", body = ListSlice<PurchaseOrder>)
+    ),
+    params(
+        Pagination,
+        GetPurchaseOrdersQuery,
+    )
+)]
+async fn get_purchase_orders(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetPurchaseOrdersQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<PurchaseOrder>>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let mut items = s.erp.purchase_order.get_multiple(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        items.iter_mut().for_each(|order| order.hide_price());
+    }
+    let count = s.erp.purchase_order.get_count(&q, action, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { count, items }))
+}
+
+/// update the draft purchase order
+#[utoipa::path(
+    put,
+    path = "/purchase_orders/{id}",
+    responses(
+        (status = 200, description = "update purchase order successfully", body = PurchaseOrder)
+    ),
+    params(
+        ("id"=i64, Path, description = "purchase order id")
+    )
+)]
+async fn update_purchase_order(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+    Json(mut body): Json<PurchaseOrder>,
+) -> Result<Json<PurchaseOrder>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_purchase_order(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if before.status != PurchaseOrderStatus::Draft {
+        return AppError::custom(CustomErrorCode::PurchaseOrderStatusNotAllowed, "Only the draft purchase order can be updated!").into_err();
+    }
+    check_purchase_order_and_preprocess(s.clone(), &authenticated, false, &mut body, tx.as_mut()).await?;
+    let r = s.erp.purchase_order.update(id, body, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PurchaseOrder, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove the purchase order has nothing received
+#[utoipa::path(
+    delete,
+    path = "/purchase_orders/{id}",
+    responses(
+        (status = 200, description = "remove purchase order successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "purchase order id")
+    )
+)]
+async fn remove_purchase_order(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_purchase_order(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if !before.order_ids.is_empty() {
+        return AppError::custom(CustomErrorCode::PurchaseOrderStatusNotAllowed, "Purchase order has received items, close it instead!").into_err();
+    }
+    s.erp.purchase_order.remove(id, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PurchaseOrder, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// approve the draft purchase order to receive the items
+#[utoipa::path(
+    post,
+    path = "/purchase_orders/{id}/approve",
+    responses(
+        (status = 200, description = "approve purchase order successfully", body = PurchaseOrder)
+    ),
+    params(
+        ("id"=i64, Path, description = "purchase order id")
+    )
+)]
+async fn approve_purchase_order(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<PurchaseOrder>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_purchase_order(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if before.status != PurchaseOrderStatus::Draft {
+        return AppError::custom(CustomErrorCode::PurchaseOrderStatusNotAllowed, "Only the draft purchase order can be approved!").into_err();
+    }
+    let r = s.erp.purchase_order.set_status(id, PurchaseOrderStatus::Approved, authenticated.user.id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PurchaseOrder, id, AuditAction::Confirm, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// close the purchase order, the outstanding items will not be received
+#[utoipa::path(
+    post,
+    path = "/purchase_orders/{id}/close",
+    responses(
+        (status = 200, description = "close purchase order successfully", body = PurchaseOrder)
+    ),
+    params(
+        ("id"=i64, Path, description = "purchase order id")
+    )
+)]
+async fn close_purchase_order(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<PurchaseOrder>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_purchase_order(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if matches!(before.status, PurchaseOrderStatus::Draft | PurchaseOrderStatus::Closed) {
+        return AppError::custom(CustomErrorCode::PurchaseOrderStatusNotAllowed, "Draft or closed purchase order can't be closed!").into_err();
+    }
+    let r = s.erp.purchase_order.set_status(id, PurchaseOrderStatus::Closed, authenticated.user.id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PurchaseOrder, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// receive items against the purchase order, a stock in order is created for them
+#[utoipa::path(
+    post,
+    path = "/purchase_orders/{id}/receive",
+    request_body = PurchaseOrderReceipt,
+    responses(
+        (status = 200, description = "receive purchase order successfully", body = PurchaseOrderReceived)
+    ),
+    params(
+        ("id"=i64, Path, description = "purchase order id")
+    )
+)]
+async fn receive_purchase_order(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+    Json(receipt): Json<PurchaseOrderReceipt>,
+) -> Result<Json<PurchaseOrderReceived>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.purchase_order.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::PurchaseOrderNotFound, "Purchase order is not found.").into_err(),
+    };
+    if !matches!(before.status, PurchaseOrderStatus::Approved | PurchaseOrderStatus::PartiallyReceived) {
+        return AppError::custom(CustomErrorCode::PurchaseOrderStatusNotAllowed, "Only the approved purchase order can be received!").into_err();
+    }
+    if !before.is_receivable(&receipt) {
+        return AppError::custom(CustomErrorCode::ReceivedQuantityExceeded, "Items are not in the purchase order or more than the outstanding quantities!").into_err();
+    }
+    if s.erp.order.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::OrderLimitExceeded, "Order count limit exceeded!").into_err();
+    }
+    let mut order = before.to_receipt_order(receipt);
+    check_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await?;
+    let order = s.erp.order.add(order, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, order.id, AuditAction::Add, None, Some(&order), tx.as_mut()).await?;
+    let r = s.erp.purchase_order.receive(id, &order, authenticated.user.id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PurchaseOrder, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(PurchaseOrderReceived { purchase_order: r, order }))
+}
+
+async fn check_quotation_and_preprocess(s: AppState, authenticated: &AuthenticatedUser, initial: bool, quotation: &mut Quotation, tx: &mut SqliteConnection) -> Result<()> {
+    if !s.erp.warehouse.is_exists(quotation.warehouse_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::WarehouseNotFound, "Warehouse is not found.").into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(quotation.warehouse_id), (&authenticated.user).into(), &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.order_category.is_exists(quotation.order_category_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::OrderCategoryNotFound, "Order category is not found.").into_err();
+    }
+    if !s.erp.currency.is_exists(quotation.currency, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CurrencyNotFound, "Currency is not found.").into_err();
+    }
+    if quotation.valid_until <= s.ps.get_timestamp_seconds() as i64 {
+        return AppError::custom(CustomErrorCode::QuotationExpired, "Valid date of the quotation is passed!").into_err();
+    }
+    match quotation.items.as_ref() {
+        Some(items) if !items.is_empty() => {
+            let mut skus = Vec::with_capacity(items.len());
+            for item in items {
+                if item.quantity <= 0 || item.price < 0.0 {
+                    return AppError::custom(CustomErrorCode::CheckFailed, "Quantity must be greater than 0 and price can't be negative!").into_err();
+                }
+                if skus.contains(&item.sku_id) {
+                    return AppError::custom(CustomErrorCode::SameObject, "SKU is repeated in the items!").into_err();
+                }
+                if !s.erp.sku.is_exists(item.sku_id, &mut *tx).await? {
+                    return AppError::custom(CustomErrorCode::SKUNotFound, "SKU is not found.").into_err();
+                }
+                skus.push(item.sku_id);
+            }
+        }
+        Some(_) => return AppError::custom(CustomErrorCode::OrderItemsIsEmpty, "Items is empty!").into_err(),
+        None if initial => return AppError::custom(CustomErrorCode::OrderItemsIsEmpty, "Items is empty!").into_err(),
+        None => (),
+    }
+    if let Some(person) = s.erp.person.get(quotation.person_related_id, ActionType::System, &mut *tx).await? {
+        s.erp.quotation.preprocess(quotation, &authenticated.user, initial, person.person_in_charge_id);
+    } else {
+        return AppError::custom(CustomErrorCode::PersonNotFound, "Person is not found.").into_err();
+    }
+    Ok(())
+}
+
+/// Get the quotation can be changed by the user.
+async fn get_accessible_quotation(s: AppState, user: &UserInfo, id: i64, tx: &mut SqliteConnection) -> Result<Quotation> {
+    let quotation = match s.erp.quotation.get(id, &mut *tx).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::QuotationNotFound, "Quotation is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(quotation.warehouse_id), user.into(), &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.quotation.can_access(id, user, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::NoPermission, "You not the quotation's owner or admin!").into_err();
+    }
+    Ok(quotation)
+}
+
+/// add quotation
+#[utoipa::path(
+    post,
+    path = "/quotations",
+    responses(
+        (status = 200, description = "add quotation successfully", body = Quotation)
+    ),
+)]
+async fn add_quotation(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(mut quotation): Json<Quotation>,
+) -> Result<Json<Quotation>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_quotation_and_preprocess(s.clone(), &authenticated, true, &mut quotation, tx.as_mut()).await?;
+    let r = s.erp.quotation.add(quotation, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Quotation, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// get quotation
+#[utoipa::path(
+    get,
+    path = "/quotations/{id}",
+    responses(
+        (status = 200, description = "get quotation successfully", body = Quotation)
+    ),
+    params(
+        ("id"=i64, Path, description = "quotation id")
+    )
+)]
+async fn get_quotation(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Quotation>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let mut quotation = match s.erp.quotation.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::QuotationNotFound, "Quotation is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(quotation.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        quotation.hide_price();
+    }
+    tx.commit().await?;
+    Ok(Json(quotation))
+}
+
+/// get quotations
+#[utoipa::path(
+    get,
+    path = "/quotations",
+    responses(
+        (status = 200, description = "get quotations successfully", body = ListSlice<Quotation>)
+    ),
+    params(
+        Pagination,
+        GetQuotationsQuery,
+    )
+)]
+async fn get_quotations(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetQuotationsQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<Quotation>>> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let mut items = s.erp.quotation.get_multiple(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    if !authenticated.has_permission(Permission::ViewOrderPrice) {
+        items.iter_mut().for_each(|quotation| quotation.hide_price());
+    }
+    let count = s.erp.quotation.get_count(&q, action, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { count, items }))
+}
+
+/// revise the quotation, it is changed to draft again
+#[utoipa::path(
+    put,
+    path = "/quotations/{id}",
+    responses(
+        (status = 200, description = "revise quotation successfully", body = Quotation)
+    ),
+    params(
+        ("id"=i64, Path, description = "quotation id")
+    )
+)]
+async fn update_quotation(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+    Json(mut body): Json<Quotation>,
+) -> Result<Json<Quotation>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_quotation(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if before.status == QuotationStatus::Converted {
+        return AppError::custom(CustomErrorCode::QuotationStatusNotAllowed, "Converted quotation can't be revised!").into_err();
+    }
+    check_quotation_and_preprocess(s.clone(), &authenticated, false, &mut body, tx.as_mut()).await?;
+    let r = s.erp.quotation.update(id, body, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Quotation, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove the quotation not converted
+#[utoipa::path(
+    delete,
+    path = "/quotations/{id}",
+    responses(
+        (status = 200, description = "remove quotation successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "quotation id")
+    )
+)]
+async fn remove_quotation(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_quotation(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if before.status == QuotationStatus::Converted {
+        return AppError::custom(CustomErrorCode::QuotationStatusNotAllowed, "Converted quotation can't be removed!").into_err();
+    }
+    s.erp.quotation.remove(id, true, tx.as_mut()).await?;
+    s.erp.inventory.release_reservations(0, id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Quotation, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// mark the quotation is sent to the customer
+#[utoipa::path(
+    post,
+    path = "/quotations/{id}/send",
+    responses(
+        (status = 200, description = "send quotation successfully", body = Quotation)
+    ),
+    params(
+        ("id"=i64, Path, description = "quotation id")
+    )
+)]
+async fn send_quotation(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Quotation>> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_quotation(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    if before.status != QuotationStatus::Draft {
+        return AppError::custom(CustomErrorCode::QuotationStatusNotAllowed, "Only the draft quotation can be sent!").into_err();
+    }
+    let r = s.erp.quotation.set_status(id, QuotationStatus::Sent, authenticated.user.id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Quotation, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// quotation document in excel file to send to the customer.
+#[utoipa::path(
+    get,
+    path = "/quotations/{id}/document",
+    responses(
+        (status = 200, description = "get quotation document successfully", body = Response)
+    ),
+    params(
+        ("id"=i64, Path, description = "quotation id")
+    )
+)]
+async fn get_quotation_document(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewOrder)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let quotation = match s.erp.quotation.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::QuotationNotFound, "Quotation is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(quotation.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    let show_price = authenticated.has_permission(Permission::ViewOrderPrice);
+    let path = s.erp.quotation.get_document(id, show_price, tx.as_mut()).await?.unwrap();
+    tx.commit().await?;
+    let file = tokio::fs::File::open(path).await.map_err(anyhow::Error::from)?;
+    let stream = ReaderStream::new(file);
+    let body = Body::from_stream(stream);
+    let disposition = format!("attachment; filename=\"Quotation-{id}.xlsx\"");
+    let headers = [
+        (header::CONTENT_TYPE, "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_owned()),
+        (header::CONTENT_DISPOSITION, disposition),
+    ];
+    Ok((headers, body).into_response())
+}
+
+/// convert the quotation to the stock out order, the order is not created if some items are not available
+#[utoipa::path(
+    post,
+    path = "/quotations/{id}/convert",
+    responses(
+        (status = 200, description = "convert quotation successfully", body = QuotationConvert)
+    ),
+    params(
+        ("id"=i64, Path, description = "quotation id")
+    )
+)]
+async fn convert_quotation(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<QuotationConvert>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = get_accessible_quotation(s.clone(), &authenticated.user, id, tx.as_mut()).await?;
+    match before.status {
+        QuotationStatus::Expired => return AppError::custom(CustomErrorCode::QuotationExpired, "Quotation is expired!").into_err(),
+        QuotationStatus::Converted => return AppError::custom(CustomErrorCode::QuotationStatusNotAllowed, "Quotation is already converted!").into_err(),
+        QuotationStatus::Draft | QuotationStatus::Sent => (),
+    }
+    if s.erp.order.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::OrderLimitExceeded, "Order count limit exceeded!").into_err();
+    }
+    let mut order = before.to_order();
+    check_order_and_preprocess(s.clone(), &authenticated, true, &mut order, tx.as_mut()).await?;
+    // The quantity reserved by the quotation can be taken by the order.
+    let check_result = elerp_common::order_module::check_with_quotation(&order, id, false, s.ps.get_timestamp_seconds() as i64, tx.as_mut()).await?;
+    if !check_result.items_not_available.is_empty() {
+        return Ok(Json(QuotationConvert { check_result, order: None }));
+    }
+    let order = s.erp.order.add(order, true, tx.as_mut()).await?;
+    s.erp.inventory.release_reservations(0, id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Order, order.id, AuditAction::Add, None, Some(&order), tx.as_mut()).await?;
+    let r = s.erp.quotation.convert(id, order.id, authenticated.user.id, tx.as_mut()).await?.unwrap();
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Quotation, id, AuditAction::Update, Some(&before), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(QuotationConvert { check_result, order: Some(order) }))
+}
+
+/// inventory list
+#[utoipa::path(
+    get,
+    path = "/inventory",
+    responses(
+        (status = 200, description = "get inventory list successfully", body = ListSlice<InventoryProduct>)
+    ),
+    params(
+        Pagination,
+        GetInventoryQuery,
+    )
+)]
+async fn inventory_list(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetInventoryQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<InventoryProduct>>> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
     let items = s.erp.inventory.list(&pagination.correct(), &q, action, tx.as_mut()).await?;
     let count = s.erp.inventory.get_count(&q, action, tx.as_mut()).await?;
     tx.commit().await?;
-    Ok(Json(ListSlice { items, count }))
+    Ok(Json(ListSlice { items, count }))
+}
+
+/// inventory lots list
+#[utoipa::path(
+    get,
+    path = "/inventory_lots",
+    responses(
+        (status = 200, description = "get inventory lots list successfully", body = ListSlice<InventoryLot>)
+    ),
+    params(
+        Pagination,
+        GetInventoryLotsQuery,
+    )
+)]
+async fn inventory_lots_list(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetInventoryLotsQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<InventoryLot>>> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let items = s.erp.inventory.list_lots(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    let count = s.erp.inventory.get_lots_count(&q, action, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { items, count }))
+}
+
+/// Stream the exported file as the attachment, the file is removed once the body is sent or dropped.
+async fn export_response(path: TempPath, name: &str, format: ExportFormat) -> Result<Response> {
+    let file = tokio::fs::File::open(&path).await.map_err(anyhow::Error::from)?;
+    let stream = ReaderStream::new(file).map(move |chunk| {
+        let _ = &path;
+        chunk
+    });
+    let body = Body::from_stream(stream);
+    let disposition = format!("attachment; filename=\"{name}.{}\"", format.extension());
+    let headers = [(header::CONTENT_TYPE, format.content_type().to_owned()), (header::CONTENT_DISPOSITION, disposition)];
+    Ok((headers, body).into_response())
+}
+
+/// Read the rows of the file uploaded, the columns required must be found.
+fn read_import_table(format: Option<ExportFormat>, body: &[u8], required: &[&str]) -> Result<ImportTable> {
+    let table = match ImportTable::read(format.unwrap_or_default(), body) {
+        Ok(v) => v,
+        Err(err) => return AppError::custom(CustomErrorCode::InvalidImportFile, format!("Can't read the file: {err}")).into_err(),
+    };
+    if let Some(header) = table.get_missing_header(required) {
+        return AppError::custom(CustomErrorCode::InvalidImportFile, format!("Column {header} is required.")).into_err();
+    }
+    Ok(table)
+}
+
+/// Report the custom error of the row, the internal errors fail the import.
+fn push_import_error(errors: &mut Vec<ImportRowError>, row: usize, err: AppError) -> Result<()> {
+    match err.get_custom_msg() {
+        Some(msg) => {
+            errors.push(ImportRowError { row, msg: msg.to_owned() });
+            Ok(())
+        }
+        None => Err(err),
+    }
+}
+
+/// Commit the rows imported only if all of them passed and it is not dry run.
+/// The rows are added without notice, they are noticed by `flag` after the commit.
+async fn finish_import(
+    s: &AppState,
+    tx: SqliteSafeTransaction<'_>,
+    dry_run: bool,
+    total: usize,
+    errors: Vec<ImportRowError>,
+    ids: Vec<i64>,
+    flag: fn(i64) -> WebSocketFlags,
+) -> Result<Json<ImportResult>> {
+    let committed = !dry_run && errors.is_empty();
+    if committed {
+        tx.commit().await?;
+        for id in ids.iter() {
+            s.ps.notice(flag(*id)).await?;
+        }
+    }
+    Ok(Json(ImportResult {
+        dry_run,
+        committed,
+        total,
+        errors,
+        ids: if committed { ids } else { vec![] },
+    }))
+}
+
+/// inventory list in excel file.
+#[utoipa::path(
+    get,
+    path = "/inventory_excel",
+    responses(
+        (status = 200, description = "get inventory excel successfully", body = Response)
+    ),
+    params(
+        GetInventoryQuery,
+    )
+)]
+async fn inventory_list_excel(
+    State(s): State<AppState>,
+    Query(q): Query<GetInventoryQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let path = s.erp.inventory.get_excel(&q, authenticated.user.as_action_type(false), tx.as_mut()).await?;
+    tx.commit().await?;
+    // `File` implements `AsyncRead`
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(anyhow::Error::from)?;
+    let stream = ReaderStream::new(file);
+    let body = Body::from_stream(stream);
+    let headers = [
+        (
+            header::CONTENT_TYPE,
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"Inventory.xlsx\"",
+        ),
+    ];
+    Ok((headers, body).into_response())
+}
+
+/// inventory items crossed their reorder points.
+#[utoipa::path(
+    get,
+    path = "/inventory/alerts",
+    responses(
+        (status = 200, description = "get inventory alerts successfully", body = Vec<InventoryAlert>)
+    ),
+)]
+async fn get_inventory_alerts(State(s): State<AppState>, authenticated: AuthenticatedUser) -> Result<Json<Vec<InventoryAlert>>> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let r = s.erp.inventory.list_alerts(authenticated.user.as_action_type(false), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// inventory summed by the product templates of the variants.
+#[utoipa::path(
+    get,
+    path = "/inventory/templates",
+    responses(
+        (status = 200, description = "get template inventory successfully", body = Vec<TemplateInventory>)
+    ),
+    params(
+        GetInventoryQuery,
+    )
+)]
+async fn get_template_inventory(
+    State(s): State<AppState>,
+    Query(q): Query<GetInventoryQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Vec<TemplateInventory>>> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let r = s.erp.inventory.list_templates(&q, authenticated.user.as_action_type(false), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// reorder points list
+#[utoipa::path(
+    get,
+    path = "/reorder_points",
+    responses(
+        (status = 200, description = "get reorder points successfully", body = Vec<ReorderPoint>)
+    ),
+    params(
+        GetReorderPointsQuery,
+    )
+)]
+async fn get_reorder_points(
+    State(s): State<AppState>,
+    Query(q): Query<GetReorderPointsQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Vec<ReorderPoint>>> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let r = s.erp.inventory.list_reorder_points(&q, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// add or update the reorder point of the sku, warehouse id 0 means all warehouses.
+#[utoipa::path(
+    put,
+    path = "/reorder_points",
+    request_body = ReorderPoint,
+    responses(
+        (status = 200, description = "set reorder point successfully", body = ReorderPoint)
+    ),
+)]
+async fn set_reorder_point(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<ReorderPoint>,
+) -> Result<Json<ReorderPoint>> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    if v.min_quantity < 0 || v.reorder_quantity < 0 || v.max_quantity.is_some_and(|max| max < v.min_quantity) {
+        return AppError::custom(
+            CustomErrorCode::InvalidReorderPoint,
+            "Quantities can't be negative and the maximum can't be lower than the minimum.",
+        )
+        .into_err();
+    }
+    let mut tx = s.ps.begin_tx(true).await?;
+    if !s.erp.sku.is_exists(v.sku_id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SKUNotFound, "SKU is not found!").into_err();
+    }
+    if v.warehouse_id != 0 && !s.erp.warehouse.is_exists(v.warehouse_id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::WarehouseNotFound, "Warehouse is not found!").into_err();
+    }
+    let before = s.erp.inventory.get_reorder_point(v.sku_id, v.warehouse_id, tx.as_mut()).await?;
+    let r = s.erp.inventory.set_reorder_point(&v, tx.as_mut()).await?;
+    let action = if before.is_some() { AuditAction::Update } else { AuditAction::Add };
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::ReorderPoint, r.sku_id, action, before.as_ref(), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove the reorder point of the sku.
+#[utoipa::path(
+    delete,
+    path = "/reorder_points",
+    responses(
+        (status = 200, description = "remove reorder point successfully")
+    ),
+    params(
+        RemoveReorderPointQuery,
+    )
+)]
+async fn remove_reorder_point(
+    State(s): State<AppState>,
+    Query(q): Query<RemoveReorderPointQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.erp.inventory.get_reorder_point(q.sku_id, q.warehouse_id, tx.as_mut()).await?;
+    if s.erp.inventory.remove_reorder_point(q.sku_id, q.warehouse_id, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::ReorderPoint, q.sku_id, AuditAction::Remove, before.as_ref(), None, tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(StatusCode::OK)
+    } else {
+        AppError::custom(CustomErrorCode::ReorderPointNotFound, "Reorder point is not exists.").into_err()
+    }
+}
+
+/// reservations list, the expired are released.
+#[utoipa::path(
+    get,
+    path = "/reservations",
+    responses(
+        (status = 200, description = "get reservations successfully", body = ListSlice<Reservation>)
+    ),
+    params(
+        Pagination,
+        GetReservationsQuery,
+    )
+)]
+async fn get_reservations(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetReservationsQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<Reservation>>> {
+    authenticated.check_permission(Permission::ViewInventory)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let action = authenticated.user.as_action_type(false);
+    let items = s.erp.inventory.list_reservations(&pagination.correct(), &q, action, tx.as_mut()).await?;
+    let count = s.erp.inventory.get_reservations_count(&q, action, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { count, items }))
+}
+
+/// reserve the available quantity of the sku for the pending guest order or quotation.
+#[utoipa::path(
+    post,
+    path = "/reservations",
+    request_body = Reservation,
+    responses(
+        (status = 200, description = "add reservation successfully", body = Reservation)
+    ),
+)]
+async fn add_reservation(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(mut v): Json<Reservation>,
+) -> Result<Json<Reservation>> {
+    authenticated.check_permission(Permission::AddOrder)?;
+    let now = s.ps.get_timestamp_seconds() as i64;
+    if v.quantity <= 0 || v.expires_at.is_some_and(|date| date <= now) {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Quantity must be greater than 0 and the expiry date must be later than now!").into_err();
+    }
+    let mut tx = s.ps.begin_tx(true).await?;
+    if !s.erp.warehouse.is_exists(v.warehouse_id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::WarehouseNotFound, "Warehouse is not found.").into_err();
+    }
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(v.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if !s.erp.sku.is_exists(v.sku_id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SKUNotFound, "SKU is not found.").into_err();
+    }
+    s.erp.inventory.release_expired_reservations(tx.as_mut()).await?;
+    if v.guest_order_id != 0 {
+        let guest_order = match s.erp.guest_order.get(v.guest_order_id, tx.as_mut()).await? {
+            Some(go) if go.warehouse_id == v.warehouse_id => go,
+            _ => return AppError::custom(CustomErrorCode::OrderNotFound, "Guest order of the warehouse is not found.").into_err(),
+        };
+        if guest_order.guest_order_status != GuestOrderStatus::Pending {
+            return AppError::custom(CustomErrorCode::NotAllowed, "Only the pending guest order can reserve!").into_err();
+        }
+        let expiry_date = guest_order.date + GuestOrderModule::EXPIRY_SECONDS;
+        v.expires_at = Some(v.expires_at.map_or(expiry_date, |date| date.min(expiry_date)));
+    }
+    if v.quotation_id != 0 {
+        let quotation = match s.erp.quotation.get(v.quotation_id, tx.as_mut()).await? {
+            Some(q) if q.warehouse_id == v.warehouse_id => q,
+            _ => return AppError::custom(CustomErrorCode::QuotationNotFound, "Quotation of the warehouse is not found.").into_err(),
+        };
+        if !matches!(quotation.status, QuotationStatus::Draft | QuotationStatus::Sent) {
+            return AppError::custom(CustomErrorCode::QuotationStatusNotAllowed, "Only the draft or sent quotation can reserve!").into_err();
+        }
+        v.expires_at = Some(v.expires_at.map_or(quotation.valid_until, |date| date.min(quotation.valid_until)));
+    }
+    if s.erp.inventory.get_available(v.warehouse_id, v.sku_id, 0, tx.as_mut()).await? < v.quantity {
+        return AppError::custom(CustomErrorCode::AvailableQuantityExceeded, "Available quantity is not enough to reserve!").into_err();
+    }
+    v.created_by_user_id = authenticated.user.id;
+    v.date = now;
+    let r = s.erp.inventory.add_reservation(v, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Reservation, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// release the reservation.
+#[utoipa::path(
+    delete,
+    path = "/reservations/{id}",
+    responses(
+        (status = 200, description = "remove reservation successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "reservation id")
+    )
+)]
+async fn remove_reservation(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.check_permission(Permission::UpdateRemoveOrder)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.inventory.get_reservation(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::ReservationNotFound, "Reservation is not found.").into_err(),
+    };
+    if !s.erp.warehouse.is_linked(WarehouseIsFrom::ID(before.warehouse_id), (&authenticated.user).into(), tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::NotLinked, "You not linked to warehouse!").into_err();
+    }
+    if authenticated.user.user_type != UserType::Admin && before.created_by_user_id != authenticated.user.id {
+        return AppError::custom(CustomErrorCode::NoPermission, "You not the reservation's owner or admin!").into_err();
+    }
+    s.erp.inventory.remove_reservation(id, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Reservation, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// currencies list.
+#[utoipa::path(
+    get,
+    path = "/currencies",
+    responses(
+        (status = 200, description = "get currencies successfully", body = Vec<Currency>)
+    ),
+)]
+async fn get_currencies(State(s): State<AppState>, _authenticated: AuthenticatedUser) -> Result<Json<Vec<Currency>>> {
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.currency.list(tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(items))
+}
+
+/// add the currency or update it by the code.
+#[utoipa::path(
+    put,
+    path = "/currencies",
+    request_body = Currency,
+    responses(
+        (status = 200, description = "set currency successfully", body = Currency)
+    ),
+)]
+async fn set_currency(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<Currency>,
+) -> Result<Json<Currency>> {
+    authenticated.fail_if_not_admin()?;
+    if v.code == OrderCurrency::Unknown || v.name.is_empty() {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Currency code and name are required!").into_err();
+    }
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.erp.currency.get(v.code, tx.as_mut()).await?;
+    let action = if before.is_some() { AuditAction::Update } else { AuditAction::Add };
+    let r = s.erp.currency.set(v, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Currency, 0, action, before.as_ref(), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove the currency with its exchange rates.
+#[utoipa::path(
+    delete,
+    path = "/currencies/{code}",
+    responses(
+        (status = 200, description = "remove currency successfully")
+    ),
+    params(
+        ("code"=String, Path, description = "currency code")
+    )
+)]
+async fn remove_currency(
+    State(s): State<AppState>,
+    Path(code): Path<OrderCurrency>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.fail_if_not_admin()?;
+    if code == s.ps.get_config().currency.base {
+        return AppError::custom(CustomErrorCode::NotAllowed, "Base currency can't be removed!").into_err();
+    }
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.currency.get(code, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::CurrencyNotFound, "Currency is not found.").into_err(),
+    };
+    if s.erp.currency.is_depend_by_another(code, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Someone depends on the currency!").into_err();
+    }
+    s.erp.currency.remove(code, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::Currency, 0, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// exchange rates list, the latest date first.
+#[utoipa::path(
+    get,
+    path = "/exchange_rates",
+    responses(
+        (status = 200, description = "get exchange rates successfully", body = ListSlice<ExchangeRate>)
+    ),
+    params(
+        Pagination,
+        GetExchangeRatesQuery,
+    )
+)]
+async fn get_exchange_rates(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetExchangeRatesQuery>,
+    _authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<ExchangeRate>>> {
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.currency.get_rates(&pagination.correct(), &q, tx.as_mut()).await?;
+    let count = s.erp.currency.get_rates_count(&q, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { count, items }))
+}
+
+async fn check_exchange_rate(s: AppState, v: &ExchangeRate, tx: &mut SqliteConnection) -> Result<()> {
+    if v.currency == OrderCurrency::Unknown || !s.erp.currency.is_exists(v.currency, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CurrencyNotFound, format!("Currency {} is not found.", v.currency)).into_err();
+    }
+    if v.currency == s.ps.get_config().currency.base {
+        return AppError::custom(CustomErrorCode::InvalidExchangeRate, "Base currency is always 1!").into_err();
+    }
+    if !v.rate.is_finite() || v.rate <= 0.0 {
+        return AppError::custom(CustomErrorCode::InvalidExchangeRate, "Rate must be greater than 0!").into_err();
+    }
+    Ok(())
+}
+
+/// add the exchange rate or update it by the currency and date.
+#[utoipa::path(
+    put,
+    path = "/exchange_rates",
+    request_body = ExchangeRate,
+    responses(
+        (status = 200, description = "set exchange rate successfully", body = ExchangeRate)
+    ),
+)]
+async fn set_exchange_rate(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<ExchangeRate>,
+) -> Result<Json<ExchangeRate>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_exchange_rate(s.clone(), &v, tx.as_mut()).await?;
+    let r = s.erp.currency.set_rate(v, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::ExchangeRate, r.id, AuditAction::Update, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove the exchange rate.
+#[utoipa::path(
+    delete,
+    path = "/exchange_rates/{id}",
+    responses(
+        (status = 200, description = "remove exchange rate successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "exchange rate id")
+    )
+)]
+async fn remove_exchange_rate(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.currency.get_rate_by_id(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::ExchangeRateNotFound, "Exchange rate is not found.").into_err(),
+    };
+    s.erp.currency.remove_rate(id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::ExchangeRate, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// import the exchange rates from the csv rows of `currency,date,rate`, the date is a timestamp or `YYYY-MM-DD`.
+/// Nothing is imported if any row is invalid.
+#[utoipa::path(
+    post,
+    path = "/exchange_rates/import",
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (status = 200, description = "import exchange rates successfully", body = Vec<ExchangeRate>)
+    ),
+)]
+async fn import_exchange_rates(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    body: String,
+) -> Result<Json<Vec<ExchangeRate>>> {
+    authenticated.fail_if_not_admin()?;
+    let rates = match CurrencyModule::parse_rates_csv(&body) {
+        Ok(rates) => rates,
+        Err(err) => return AppError::custom(CustomErrorCode::InvalidExchangeRate, err.to_string()).into_err(),
+    };
+    let mut tx = s.ps.begin_tx(true).await?;
+    for v in &rates {
+        check_exchange_rate(s.clone(), v, tx.as_mut()).await?;
+    }
+    let arr = s.erp.currency.import_rates(rates, tx.as_mut()).await?;
+    for r in &arr {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::ExchangeRate, r.id, AuditAction::Update, None, Some(r), tx.as_mut()).await?;
+    }
+    tx.commit().await?;
+    Ok(Json(arr))
+}
+
+/// tax rates list.
+#[utoipa::path(
+    get,
+    path = "/tax_rates",
+    responses(
+        (status = 200, description = "get tax rates successfully", body = Vec<TaxRate>)
+    ),
+)]
+async fn get_tax_rates(State(s): State<AppState>, _authenticated: AuthenticatedUser) -> Result<Json<Vec<TaxRate>>> {
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.tax.list(tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(items))
+}
+
+fn check_tax_rate(v: &TaxRate) -> Result<()> {
+    if v.name.is_empty() {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Tax rate name is required!").into_err();
+    }
+    if !is_valid_tax_rate(v.rate) {
+        return AppError::custom(CustomErrorCode::InvalidTaxRate, "Tax rate must be between 0 and 100!").into_err();
+    }
+    Ok(())
+}
+
+/// add tax rate.
+#[utoipa::path(
+    post,
+    path = "/tax_rates",
+    request_body = TaxRate,
+    responses(
+        (status = 200, description = "add tax rate successfully", body = TaxRate)
+    ),
+)]
+async fn add_tax_rate(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<TaxRate>,
+) -> Result<Json<TaxRate>> {
+    authenticated.fail_if_not_admin()?;
+    check_tax_rate(&v)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let r = s.erp.tax.add(v, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::TaxRate, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// update tax rate, the orders added keep their tax.
+#[utoipa::path(
+    put,
+    path = "/tax_rates/{id}",
+    request_body = TaxRate,
+    responses(
+        (status = 200, description = "update tax rate successfully", body = TaxRate)
+    ),
+    params(
+        ("id"=i64, Path, description = "tax rate id")
+    )
+)]
+async fn update_tax_rate(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+    Json(v): Json<TaxRate>,
+) -> Result<Json<TaxRate>> {
+    authenticated.fail_if_not_admin()?;
+    check_tax_rate(&v)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.erp.tax.get(id, tx.as_mut()).await?;
+    let r = match s.erp.tax.update(id, v, tx.as_mut()).await? {
+        Some(r) => r,
+        None => return AppError::custom(CustomErrorCode::TaxRateNotFound, "Tax rate is not found.").into_err(),
+    };
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::TaxRate, id, AuditAction::Update, before.as_ref(), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove tax rate.
+#[utoipa::path(
+    delete,
+    path = "/tax_rates/{id}",
+    responses(
+        (status = 200, description = "remove tax rate successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "tax rate id")
+    )
+)]
+async fn remove_tax_rate(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.tax.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::TaxRateNotFound, "Tax rate is not found.").into_err(),
+    };
+    if s.erp.tax.is_depend_by_another(id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Someone depends on the tax rate!").into_err();
+    }
+    s.erp.tax.remove(id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::TaxRate, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Get the net, tax and gross amounts grouped by period, currency, order type and tax rate.
+#[utoipa::path(
+    get,
+    path = "/tax_summary",
+    responses(
+        (status = 200, description = "get tax summary successfully", body = Vec<TaxSummary>)
+    ),
+)]
+async fn get_tax_summary(State(s): State<AppState>, Query(q): Query<GetTaxSummaryQuery>, authenticated: AuthenticatedUser) -> Result<Json<Vec<TaxSummary>>> {
+    authenticated.check_permission(Permission::ViewStatistics)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let arr = s.erp.statistical.get_tax_summary(&q, authenticated.user.as_action_type(false), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(arr))
+}
+
+/// price lists list.
+#[utoipa::path(
+    get,
+    path = "/price_lists",
+    responses(
+        (status = 200, description = "get price lists successfully", body = Vec<PriceList>)
+    ),
+)]
+async fn get_price_lists(State(s): State<AppState>, _authenticated: AuthenticatedUser) -> Result<Json<Vec<PriceList>>> {
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.price_list.list(tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(items))
+}
+
+fn check_price_list(v: &PriceList) -> Result<()> {
+    if v.name.is_empty() {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Price list name is required!").into_err();
+    }
+    Ok(())
+}
+
+/// add price list, the others are not the default if it is the default.
+#[utoipa::path(
+    post,
+    path = "/price_lists",
+    request_body = PriceList,
+    responses(
+        (status = 200, description = "add price list successfully", body = PriceList)
+    ),
+)]
+async fn add_price_list(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<PriceList>,
+) -> Result<Json<PriceList>> {
+    authenticated.fail_if_not_admin()?;
+    check_price_list(&v)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let r = s.erp.price_list.add(v, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PriceList, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// update price list, the others are not the default if it is the default.
+#[utoipa::path(
+    put,
+    path = "/price_lists/{id}",
+    request_body = PriceList,
+    responses(
+        (status = 200, description = "update price list successfully", body = PriceList)
+    ),
+    params(
+        ("id"=i64, Path, description = "price list id")
+    )
+)]
+async fn update_price_list(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+    Json(v): Json<PriceList>,
+) -> Result<Json<PriceList>> {
+    authenticated.fail_if_not_admin()?;
+    check_price_list(&v)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.erp.price_list.get(id, tx.as_mut()).await?;
+    let r = match s.erp.price_list.update(id, v, tx.as_mut()).await? {
+        Some(r) => r,
+        None => return AppError::custom(CustomErrorCode::PriceListNotFound, "Price list is not found.").into_err(),
+    };
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PriceList, id, AuditAction::Update, before.as_ref(), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove price list with its items.
+#[utoipa::path(
+    delete,
+    path = "/price_lists/{id}",
+    responses(
+        (status = 200, description = "remove price list successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "price list id")
+    )
+)]
+async fn remove_price_list(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.price_list.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::PriceListNotFound, "Price list is not found.").into_err(),
+    };
+    if s.erp.price_list.is_depend_by_another(id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Someone depends on the price list!").into_err();
+    }
+    s.erp.price_list.remove(id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PriceList, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// price list items list.
+#[utoipa::path(
+    get,
+    path = "/price_list_items",
+    responses(
+        (status = 200, description = "get price list items successfully", body = ListSlice<PriceListItem>)
+    ),
+    params(
+        Pagination,
+        GetPriceListItemsQuery,
+    )
+)]
+async fn get_price_list_items(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetPriceListItemsQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<PriceListItem>>> {
+    authenticated.check_permission(Permission::ViewOrderPrice)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.price_list.get_items(&pagination.correct(), &q, tx.as_mut()).await?;
+    let count = s.erp.price_list.get_items_count(&q, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { count, items }))
+}
+
+async fn check_price_list_item(s: AppState, v: &PriceListItem, tx: &mut SqliteConnection) -> Result<()> {
+    if !s.erp.price_list.is_exists(v.price_list_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::PriceListNotFound, "Price list is not exists.").into_err();
+    }
+    if !s.erp.sku.is_exists(v.sku_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::SKUNotFound, "SKU is not exists.").into_err();
+    }
+    if !s.erp.currency.is_exists(v.currency, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::CurrencyNotFound, "Currency is not exists.").into_err();
+    }
+    if !v.price.is_finite() || v.price < 0.0 {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Price must not be negative!").into_err();
+    }
+    if let (Some(from), Some(to)) = (v.valid_from, v.valid_to) {
+        if from > to {
+            return AppError::custom(CustomErrorCode::CheckFailed, "The start of the validity period must not be after its end!").into_err();
+        }
+    }
+    Ok(())
+}
+
+/// add price list item.
+#[utoipa::path(
+    post,
+    path = "/price_list_items",
+    request_body = PriceListItem,
+    responses(
+        (status = 200, description = "add price list item successfully", body = PriceListItem)
+    ),
+)]
+async fn add_price_list_item(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<PriceListItem>,
+) -> Result<Json<PriceListItem>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_price_list_item(s.clone(), &v, tx.as_mut()).await?;
+    let r = s.erp.price_list.add_item(v, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PriceListItem, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// update price list item, the orders added keep their prices.
+#[utoipa::path(
+    put,
+    path = "/price_list_items/{id}",
+    request_body = PriceListItem,
+    responses(
+        (status = 200, description = "update price list item successfully", body = PriceListItem)
+    ),
+    params(
+        ("id"=i64, Path, description = "price list item id")
+    )
+)]
+async fn update_price_list_item(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+    Json(v): Json<PriceListItem>,
+) -> Result<Json<PriceListItem>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_price_list_item(s.clone(), &v, tx.as_mut()).await?;
+    let before = s.erp.price_list.get_item(id, tx.as_mut()).await?;
+    let r = match s.erp.price_list.update_item(id, v, tx.as_mut()).await? {
+        Some(r) => r,
+        None => return AppError::custom(CustomErrorCode::PriceListItemNotFound, "Price list item is not found.").into_err(),
+    };
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PriceListItem, id, AuditAction::Update, before.as_ref(), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
 }
 
-/// inventory list in excel file.
+/// remove price list item.
 #[utoipa::path(
-    get,
-    path = "/inventory_excel",
+    delete,
+    path = "/price_list_items/{id}",
     responses(
-        (status = 200, description = "get inventory excel successfully", body = Response)
+        (status = 200, description = "remove price list item successfully")
     ),
     params(
-        GetInventoryQuery,
+        ("id"=i64, Path, description = "price list item id")
     )
 )]
-async fn inventory_list_excel(
+async fn remove_price_list_item(
     State(s): State<AppState>,
-    Query(q): Query<GetInventoryQuery>,
     authenticated: AuthenticatedUser,
-) -> Result<impl IntoResponse> {
+    Path(id): Path<i64>,
+) -> Result<()> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.price_list.get_item(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::PriceListItemNotFound, "Price list item is not found.").into_err(),
+    };
+    s.erp.price_list.remove_item(id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::PriceListItem, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Get the price applied to the person, the sku and the date from the most specific price list.
+#[utoipa::path(
+    get,
+    path = "/price",
+    responses(
+        (status = 200, description = "get price successfully", body = AppliedPrice)
+    ),
+)]
+async fn get_price(State(s): State<AppState>, Query(q): Query<GetPriceQuery>, authenticated: AuthenticatedUser) -> Result<Json<AppliedPrice>> {
+    authenticated.check_permission(Permission::ViewOrderPrice)?;
     let mut tx = s.ps.begin_tx(false).await?;
-    let path = s.erp.inventory.get_excel(&q, authenticated.user.as_action_type(false), tx.as_mut()).await?;
+    let r = s.erp.price_list.get_price(&q, tx.as_mut()).await?;
     tx.commit().await?;
-    // `File` implements `AsyncRead`
-    let file = tokio::fs::File::open(path)
-        .await
-        .map_err(anyhow::Error::from)?;
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
-    let headers = [
-        (
-            header::CONTENT_TYPE,
-            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
-        ),
-        (
-            header::CONTENT_DISPOSITION,
-            "attachment; filename=\"Inventory.xlsx\"",
-        ),
-    ];
-    Ok((headers, body).into_response())
+    match r {
+        Some(r) => Ok(Json(r)),
+        None => AppError::custom(CustomErrorCode::PriceNotFound, "Price is not found in the price lists.").into_err(),
+    }
 }
 
 async fn check_sku(s: AppState, v: &SKU, prev: Option<i64>, tx: &mut SqliteConnection) -> Result<()> {
@@ -1929,6 +4756,9 @@ async fn check_sku(s: AppState, v: &SKU, prev: Option<i64>, tx: &mut SqliteConne
         )
         .into_err();
     }
+    if v.tax_rate_id != 0 && !s.erp.tax.is_exists(v.tax_rate_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::TaxRateNotFound, "Sku's tax rate id is not found.").into_err();
+    }
 
     if s.erp
         .sku
@@ -1941,6 +4771,44 @@ async fn check_sku(s: AppState, v: &SKU, prev: Option<i64>, tx: &mut SqliteConne
         )
         .into_err();
     }
+    if !v.code.is_empty() && s.erp.sku.is_exists_code(&v.code, prev, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::SameObject, "Already contains the sku's code.").into_err();
+    }
+    if v.units.iter().any(|unit| unit.name.trim().is_empty() || unit.name == v.unit || unit.factor < 1) {
+        return AppError::custom(CustomErrorCode::InvalidUnit, "The unit must be named other than the base unit and its factor must be positive!").into_err();
+    }
+    if v.units.iter().enumerate().any(|(i, unit)| v.units[..i].iter().any(|prev| prev.name == unit.name)) {
+        return AppError::custom(CustomErrorCode::SameObject, "The units of the sku are repeated.").into_err();
+    }
+    if let Some(barcode) = v.get_invalid_barcode() {
+        return AppError::custom(CustomErrorCode::InvalidBarcode, format!("The barcode {barcode} is not a valid EAN/UPC code.")).into_err();
+    }
+    if v.barcodes.iter().enumerate().any(|(i, barcode)| v.barcodes[..i].contains(barcode)) {
+        return AppError::custom(CustomErrorCode::SameObject, "The barcodes of the sku are repeated.").into_err();
+    }
+    if let Some(barcode) = s.erp.sku.get_exists_barcode(&v.barcodes, prev, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::SameObject, format!("The barcode {barcode} is used by another sku.")).into_err();
+    }
+    if v.template_id != 0 {
+        let template = match s.erp.product_template.get(v.template_id, &mut *tx).await? {
+            Some(v) => v,
+            None => return AppError::custom(CustomErrorCode::ProductTemplateNotFound, "Sku's product template id is not found.").into_err(),
+        };
+        if !template.is_valid_variant(&v.attributes) {
+            return AppError::custom(CustomErrorCode::InvalidVariant, "The variant must have one value of each attribute of its product template!").into_err();
+        }
+        let q = GetSKUsQuery {
+            template_id: Some(v.template_id),
+            attributes: Some(v.attributes.clone()),
+            ..Default::default()
+        };
+        let ids = s.erp.sku.get_multiple_ids(&Pagination::new(0, 2), &q, &mut *tx).await?;
+        if ids.iter().any(|id| Some(*id) != prev) {
+            return AppError::custom(CustomErrorCode::SameObject, "Already contains the variant of the attributes.").into_err();
+        }
+    } else if v.attributes.iter().any(|(name, value)| name.trim().is_empty() || value.trim().is_empty()) {
+        return AppError::custom(CustomErrorCode::CheckFailed, "The attribute name and value are required!").into_err();
+    }
 
     Ok(())
 }
@@ -1958,22 +4826,75 @@ async fn add_sku(
     authenticated: AuthenticatedUser,
     Json(body): Json<SKU>,
 ) -> Result<Json<SKU>> {
-    authenticated.is_manage_sku()?;
+    authenticated.check_permission(Permission::ManageSKU)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.sku.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::SKULimitExceeded, "SKU count limit exceeded!").into_err();
     }
     check_sku(s.clone(), &body, None, tx.as_mut()).await?;
-    let r = s.erp.sku.add(body, tx.as_mut()).await?;
+    let r = s.erp.sku.add(body, true, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKU, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
-async fn remove_sku_core(s: AppState, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+/// import skus from the xlsx or csv file in the body, the columns are the same as the export.
+/// Nothing is imported if any row is invalid.
+#[utoipa::path(
+    post,
+    path = "/skus/import",
+    request_body(content = Vec<u8>, description = "The headers are Name, SKU Category, Serialized, Tax Rate and Description.", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "import skus successfully", body = ImportResult)
+    ),
+    params(
+        ImportQuery,
+    )
+)]
+async fn import_skus(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Query(q): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportResult>> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let table = read_import_table(q.format, &body, &["Name", "SKU Category"])?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let mut cache = IdCache::new();
+    let mut errors = vec![];
+    let mut ids = Vec::with_capacity(table.len());
+    for row in table.rows() {
+        let v = match s.erp.sku.parse_import_row(&row, &mut cache, tx.as_mut()).await {
+            Ok(v) => v,
+            Err(err) => {
+                push_import_error(&mut errors, row.number(), err.into())?;
+                continue;
+            }
+        };
+        if s.erp.sku.is_limit_reached(tx.as_mut()).await? {
+            return AppError::custom(CustomErrorCode::SKULimitExceeded, "SKU count limit exceeded!").into_err();
+        }
+        if let Err(err) = check_sku(s.clone(), &v, None, tx.as_mut()).await {
+            push_import_error(&mut errors, row.number(), err)?;
+            continue;
+        }
+        let r = s.erp.sku.add(v, false, tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKU, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+        ids.push(r.id);
+    }
+    finish_import(&s, tx, q.dry_run.unwrap_or(false), table.len(), errors, ids, WebSocketFlags::AddSKU).await
+}
+
+async fn remove_sku_core(s: AppState, user: &UserInfo, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
     if s.erp.sku.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the sku.").into_err();
     }
-    Ok(s.erp.sku.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.sku.get(id, &mut *tx).await?;
+    let r = s.erp.sku.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::SKU, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove sku
@@ -1992,9 +4913,9 @@ async fn remove_sku(
     Path(id): Path<i64>,
     authenticated: AuthenticatedUser,
 ) -> Result<StatusCode> {
-    authenticated.is_manage_sku()?;
+    authenticated.check_permission(Permission::ManageSKU)?;
     let mut tx = s.ps.begin_tx(true).await?;
-    if remove_sku_core(s.clone(), id, true, tx.as_mut()).await? {
+    if remove_sku_core(s.clone(), &authenticated.user, id, true, tx.as_mut()).await? {
         tx.commit().await?;
         Ok(StatusCode::OK)
     } else {
@@ -2002,6 +4923,37 @@ async fn remove_sku(
     }
 }
 
+/// restore sku
+#[utoipa::path(
+    post,
+    path = "/skus/{id}/restore",
+    responses(
+        (status = 200, description = "restore sku successfully", body = SKU)
+    ),
+    params(
+        ("id" = i64, Path, description = "sku id")
+    )
+)]
+async fn restore_sku(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<SKU>> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.sku.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SKULimitExceeded, "SKU count limit exceeded!").into_err();
+    }
+    if let Some(v) = s.erp.sku.restore(id, tx.as_mut()).await? {
+        check_sku(s.clone(), &v, Some(id), tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKU, id, AuditAction::Restore, None, Some(&v), tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::SKUNotFound, "The removed sku is not found!").into_err()
+    }
+}
+
 /// clear skus
 #[utoipa::path(
     delete,
@@ -2019,15 +4971,15 @@ async fn clear_skus(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetSKUsQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_manage_sku()?;
+    authenticated.check_permission(Permission::ManageSKU)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.sku.get_multiple_ids(&pagination, &q, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
-            match remove_sku_core(s.clone(), id, false, tx.as_mut()).await {
+            match remove_sku_core(s.clone(), &authenticated.user, id, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
                 Err(_) => failed += 1,
             }
@@ -2060,6 +5012,9 @@ async fn get_sku(
     authenticated: Option<AuthenticatedUser>,
     headers: HeaderMap,
 ) -> Result<Json<SKU>> {
+    if let Some(authenticated) = authenticated.as_ref() {
+        authenticated.check_permission(Permission::ViewSKU)?;
+    }
     let mut tx = s.ps.begin_tx(false).await?;
     check_token_exists(s.clone(), authenticated.as_ref(), &headers, tx.as_mut()).await?;
     if let Some(v) = s.erp.sku.get(id, tx.as_mut()).await? {
@@ -2070,6 +5025,33 @@ async fn get_sku(
     }
 }
 
+/// get sku by the barcode or the sku code
+#[utoipa::path(
+    get,
+    path = "/skus/by_barcode/{code}",
+    responses(
+        (status = 200, description = "get sku successfully", body = SKU)
+    ),
+    params(
+        ("code"=String, Path, description = "barcode or sku code")
+    )
+)]
+async fn get_sku_by_barcode(
+    State(s): State<AppState>,
+    Path(code): Path<String>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<SKU>> {
+    authenticated.check_permission(Permission::ViewSKU)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    if let Some(id) = s.erp.sku.get_id_by_code(code.trim(), tx.as_mut()).await? {
+        if let Some(v) = s.erp.sku.get(id, tx.as_mut()).await? {
+            tx.commit().await?;
+            return Ok(Json(v));
+        }
+    }
+    AppError::custom(CustomErrorCode::SKUNotFound, "Sku is not found.").into_err()
+}
+
 /// get skus
 #[utoipa::path(
     get,
@@ -2089,6 +5071,9 @@ async fn get_skus(
     authenticated: Option<AuthenticatedUser>,
     headers: HeaderMap,
 ) -> Result<Json<ListSlice<SKU>>> {
+    if let Some(authenticated) = authenticated.as_ref() {
+        authenticated.check_permission(Permission::ViewSKU)?;
+    }
     let mut tx = s.ps.begin_tx(false).await?;
     check_token_exists(s.clone(), authenticated.as_ref(), &headers, tx.as_mut()).await?;
 
@@ -2098,6 +5083,31 @@ async fn get_skus(
     Ok(Json(ListSlice { items, count }))
 }
 
+/// export skus in xlsx or csv file.
+#[utoipa::path(
+    get,
+    path = "/skus/export",
+    responses(
+        (status = 200, description = "export skus successfully", body = Response)
+    ),
+    params(
+        GetSKUsQuery,
+        ExportQuery,
+    )
+)]
+async fn export_skus(
+    State(s): State<AppState>,
+    Query(q): Query<GetSKUsQuery>,
+    Query(export): Query<ExportQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<impl IntoResponse> {
+    authenticated.check_permission(Permission::ViewSKU)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let path = s.erp.sku.get_export(&q, export.get_format(), tx.as_mut()).await?;
+    tx.commit().await?;
+    export_response(path, "SKUs", export.get_format()).await
+}
+
 /// update sku
 #[utoipa::path(
     put,
@@ -2115,10 +5125,12 @@ async fn update_sku(
     authenticated: AuthenticatedUser,
     Json(body): Json<SKU>,
 ) -> Result<Json<SKU>> {
-    authenticated.is_manage_sku()?;
+    authenticated.check_permission(Permission::ManageSKU)?;
     let mut tx = s.ps.begin_tx(true).await?;
     check_sku(s.clone(), &body, Some(id), tx.as_mut()).await?;
+    let before = s.erp.sku.get(id, tx.as_mut()).await?;
     if let Some(v) = s.erp.sku.update(id, body, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKU, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut()).await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
@@ -2126,7 +5138,242 @@ async fn update_sku(
     }
 }
 
+async fn check_product_template(s: AppState, v: &ProductTemplate, prev: Option<i64>, tx: &mut SqliteConnection) -> Result<()> {
+    if v.name.trim().is_empty() {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Product template name is required!").into_err();
+    }
+    if !s.erp.sku_category.is_exists(v.sku_category_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::SKUCategoryNotFound, "Product template's category id is not found.").into_err();
+    }
+    if s.erp.product_template.is_exists_name(&v.name, prev, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::SameObject, "Already contains the product template's name.").into_err();
+    }
+    if v.attributes.is_empty()
+        || v.attributes.iter().any(|attribute| attribute.name.trim().is_empty() || attribute.values.is_empty() || attribute.values.iter().any(|value| value.trim().is_empty()))
+    {
+        return AppError::custom(CustomErrorCode::CheckFailed, "The product template must have the attributes with the names and the values!").into_err();
+    }
+    if v.attributes.iter().enumerate().any(|(i, attribute)| {
+        v.attributes[..i].iter().any(|prev| prev.name == attribute.name) || attribute.values.iter().enumerate().any(|(i, value)| attribute.values[..i].contains(value))
+    }) {
+        return AppError::custom(CustomErrorCode::SameObject, "The attributes or their values are repeated.").into_err();
+    }
+    if let Some(id) = prev {
+        let q = GetSKUsQuery {
+            template_id: Some(id),
+            ..Default::default()
+        };
+        let variants = s.erp.sku.get_multiple(&Pagination::max(), &q, &mut *tx).await?;
+        if variants.iter().any(|sku| !v.is_valid_variant(&sku.attributes)) {
+            return AppError::custom(CustomErrorCode::InvalidVariant, "The attributes and the values used by the variants can't be removed!").into_err();
+        }
+    }
+    Ok(())
+}
+
+/// product templates list
+#[utoipa::path(
+    get,
+    path = "/product_templates",
+    responses(
+        (status = 200, description = "get product templates successfully", body = ListSlice<ProductTemplate>)
+    ),
+    params(
+        Pagination,
+        GetProductTemplatesQuery,
+    )
+)]
+async fn get_product_templates(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    Query(q): Query<GetProductTemplatesQuery>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<ProductTemplate>>> {
+    authenticated.check_permission(Permission::ViewSKU)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.erp.product_template.get_multiple(&pagination.correct(), &q, tx.as_mut()).await?;
+    let count = s.erp.product_template.get_count(&q, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { items, count }))
+}
+
+/// add product template
+#[utoipa::path(
+    post,
+    path = "/product_templates",
+    request_body = ProductTemplate,
+    responses(
+        (status = 200, description = "add product template successfully", body = ProductTemplate)
+    ),
+)]
+async fn add_product_template(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(v): Json<ProductTemplate>,
+) -> Result<Json<ProductTemplate>> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_product_template(s.clone(), &v, None, tx.as_mut()).await?;
+    let r = s.erp.product_template.add(v, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::ProductTemplate, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// get product template
+#[utoipa::path(
+    get,
+    path = "/product_templates/{id}",
+    responses(
+        (status = 200, description = "get product template successfully", body = ProductTemplate)
+    ),
+    params(
+        ("id"=i64, Path, description = "product template id")
+    )
+)]
+async fn get_product_template(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ProductTemplate>> {
+    authenticated.check_permission(Permission::ViewSKU)?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    match s.erp.product_template.get(id, tx.as_mut()).await? {
+        Some(v) => {
+            tx.commit().await?;
+            Ok(Json(v))
+        }
+        None => AppError::custom(CustomErrorCode::ProductTemplateNotFound, "Product template is not found.").into_err(),
+    }
+}
+
+/// update product template, the attribute values used by its variants are kept.
+#[utoipa::path(
+    put,
+    path = "/product_templates/{id}",
+    request_body = ProductTemplate,
+    responses(
+        (status = 200, description = "update product template successfully", body = ProductTemplate)
+    ),
+    params(
+        ("id"=i64, Path, description = "product template id")
+    )
+)]
+async fn update_product_template(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+    Json(v): Json<ProductTemplate>,
+) -> Result<Json<ProductTemplate>> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_product_template(s.clone(), &v, Some(id), tx.as_mut()).await?;
+    let before = s.erp.product_template.get(id, tx.as_mut()).await?;
+    let r = match s.erp.product_template.update(id, v, tx.as_mut()).await? {
+        Some(r) => r,
+        None => return AppError::custom(CustomErrorCode::ProductTemplateNotFound, "Product template is not found.").into_err(),
+    };
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::ProductTemplate, id, AuditAction::Update, before.as_ref(), Some(&r), tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(r))
+}
+
+/// remove product template without the variants.
+#[utoipa::path(
+    delete,
+    path = "/product_templates/{id}",
+    responses(
+        (status = 200, description = "remove product template successfully")
+    ),
+    params(
+        ("id"=i64, Path, description = "product template id")
+    )
+)]
+async fn remove_product_template(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let before = match s.erp.product_template.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::ProductTemplateNotFound, "Product template is not found.").into_err(),
+    };
+    if s.erp.product_template.is_depend_by_another(id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some variants depend on the product template!").into_err();
+    }
+    s.erp.product_template.remove(id, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::ProductTemplate, id, AuditAction::Remove, Some(&before), None, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// add the variants of the product template not added yet, one sku of each combination of the attribute values.
+/// The skus are named by the template and the values, such as `T-shirt red M`.
+#[utoipa::path(
+    post,
+    path = "/product_templates/{id}/variants",
+    responses(
+        (status = 200, description = "generate product template variants successfully", body = Vec<SKU>)
+    ),
+    params(
+        ("id"=i64, Path, description = "product template id")
+    )
+)]
+async fn generate_product_template_variants(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<SKU>>> {
+    authenticated.check_permission(Permission::ManageSKU)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let template = match s.erp.product_template.get(id, tx.as_mut()).await? {
+        Some(v) => v,
+        None => return AppError::custom(CustomErrorCode::ProductTemplateNotFound, "Product template is not found.").into_err(),
+    };
+    let q = GetSKUsQuery {
+        template_id: Some(id),
+        ..Default::default()
+    };
+    let variants = s.erp.sku.get_multiple(&Pagination::max(), &q, tx.as_mut()).await?;
+    let mut arr = Vec::new();
+    for attributes in template.get_variants() {
+        if variants.iter().any(|sku| sku.attributes == attributes) {
+            continue;
+        }
+        if s.erp.sku.is_limit_reached(tx.as_mut()).await? {
+            return AppError::custom(CustomErrorCode::SKULimitExceeded, "SKU count limit exceeded!").into_err();
+        }
+        let sku = SKU {
+            id: 0,
+            sku_category_id: template.sku_category_id,
+            name: template.get_variant_name(&attributes),
+            description: template.description.clone(),
+            color: None,
+            text_color: None,
+            serialized: false,
+            tax_rate_id: 0,
+            code: String::new(),
+            unit: String::new(),
+            units: vec![],
+            barcodes: vec![],
+            template_id: id,
+            attributes,
+        };
+        check_sku(s.clone(), &sku, None, tx.as_mut()).await?;
+        let r = s.erp.sku.add(sku, true, tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKU, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
+        arr.push(r);
+    }
+    tx.commit().await?;
+    Ok(Json(arr))
+}
+
 async fn check_sku_category(s: AppState, v: &SKUCategory, prev: Option<i64>, tx: &mut SqliteConnection) -> Result<()> {
+    if v.tax_rate_id != 0 && !s.erp.tax.is_exists(v.tax_rate_id, &mut *tx).await? {
+        return AppError::custom(CustomErrorCode::TaxRateNotFound, "Sku category's tax rate id is not found.").into_err();
+    }
     if s.erp.sku_category.is_exists_name(&v.name, prev, &mut *tx).await? {
         return AppError::custom(
             CustomErrorCode::SameObject,
@@ -2149,22 +5396,28 @@ async fn add_sku_category(
     authenticated: AuthenticatedUser,
     Json(body): Json<SKUCategory>,
 ) -> Result<Json<SKUCategory>> {
-    authenticated.is_manage_sku_category()?;
+    authenticated.check_permission(Permission::ManageSKUCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
     if s.erp.sku_category.is_limit_reached(tx.as_mut()).await? {
         return AppError::custom(CustomErrorCode::SKUCategoryLimitExceeded, "SKU category count limit exceeded!").into_err();
     }
     check_sku_category(s.clone(), &body, None, tx.as_mut()).await?;
     let r = s.erp.sku_category.add(body, tx.as_mut()).await?;
+    s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKUCategory, r.id, AuditAction::Add, None, Some(&r), tx.as_mut()).await?;
     tx.commit().await?;
     Ok(Json(r))
 }
 
-async fn remove_sku_category_core(s: AppState, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+async fn remove_sku_category_core(s: AppState, user: &UserInfo, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
     if s.erp.sku_category.is_depend_by_another(id, &mut *tx).await? {
         return AppError::custom(CustomErrorCode::SomeoneIsDepentIt, "Some one is depent to the sku category.").into_err();
     }
-    Ok(s.erp.sku_category.remove(id, notice, &mut *tx).await?)
+    let before = s.erp.sku_category.get(id, &mut *tx).await?;
+    let r = s.erp.sku_category.remove(id, notice, &mut *tx).await?;
+    if r {
+        s.erp.audit_log.add(user.id, AuditEntity::SKUCategory, id, AuditAction::Remove, before.as_ref(), None, &mut *tx).await?;
+    }
+    Ok(r)
 }
 
 /// remove sku category
@@ -2183,9 +5436,9 @@ async fn remove_sku_category(
     authenticated: AuthenticatedUser,
     Path(id): Path<i64>,
 ) -> Result<StatusCode> {
-    authenticated.is_manage_sku_category()?;
+    authenticated.check_permission(Permission::ManageSKUCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
-    if remove_sku_category_core(s.clone(), id, true, tx.as_mut()).await? {
+    if remove_sku_category_core(s.clone(), &authenticated.user, id, true, tx.as_mut()).await? {
         tx.commit().await?;
         Ok(StatusCode::OK)
     } else {
@@ -2193,6 +5446,37 @@ async fn remove_sku_category(
     }
 }
 
+/// restore sku category
+#[utoipa::path(
+    post,
+    path = "/sku_categories/{id}/restore",
+    responses(
+        (status = 200, description = "restore sku category successfully", body = SKUCategory)
+    ),
+    params(
+        ("id" = i64, Path, description = "sku category id")
+    )
+)]
+async fn restore_sku_category(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<SKUCategory>> {
+    authenticated.check_permission(Permission::ManageSKUCategory)?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if s.erp.sku_category.is_limit_reached(tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::SKUCategoryLimitExceeded, "SKU category count limit exceeded!").into_err();
+    }
+    if let Some(v) = s.erp.sku_category.restore(id, tx.as_mut()).await? {
+        check_sku_category(s.clone(), &v, Some(id), tx.as_mut()).await?;
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKUCategory, id, AuditAction::Restore, None, Some(&v), tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::SKUCategoryNotFound, "The removed sku category is not found!").into_err()
+    }
+}
+
 /// clear sku categories
 #[utoipa::path(
     delete,
@@ -2210,15 +5494,15 @@ async fn clear_sku_categories(
     authenticated: AuthenticatedUser,
     Query(q): Query<GetSKUCategoriesQuery>,
 ) -> Result<Json<ClearResult>> {
-    authenticated.is_manage_sku_category()?;
+    authenticated.check_permission(Permission::ManageSKUCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
     let mut pagination = Pagination::new(0, 100);
     let mut success = 0;
     let mut failed = 0;
     let mut ids = s.erp.sku_category.get_multiple_ids(&pagination, &q, tx.as_mut()).await?;
-    while ids.len() > 0 {
+    while !ids.is_empty() {
         for id in ids {
-            match remove_sku_category_core(s.clone(), id, false, tx.as_mut()).await {
+            match remove_sku_category_core(s.clone(), &authenticated.user, id, false, tx.as_mut()).await {
                 Ok(_) => success += 1,
                 Err(_) => failed += 1,
             }
@@ -2251,6 +5535,9 @@ async fn get_sku_category(
     authenticated: Option<AuthenticatedUser>,
     headers: HeaderMap,
 ) -> Result<Json<SKUCategory>> {
+    if let Some(authenticated) = authenticated.as_ref() {
+        authenticated.check_permission(Permission::ViewSKUCategory)?;
+    }
     let mut tx = s.ps.begin_tx(false).await?;
     check_token_exists(s.clone(), authenticated.as_ref(), &headers, tx.as_mut()).await?;
     if let Some(v) = s.erp.sku_category.get(id, tx.as_mut()).await? {
@@ -2284,6 +5571,9 @@ async fn get_sku_categories(
     authenticated: Option<AuthenticatedUser>,
     headers: HeaderMap,
 ) -> Result<Json<ListSlice<SKUCategory>>> {
+    if let Some(authenticated) = authenticated.as_ref() {
+        authenticated.check_permission(Permission::ViewSKUCategory)?;
+    }
     let mut tx = s.ps.begin_tx(false).await?;
     check_token_exists(s.clone(), authenticated.as_ref(), &headers, tx.as_mut()).await?;
     let items = s.erp.sku_category.get_multiple(&pagination.correct(), &q, tx.as_mut()).await?;
@@ -2309,9 +5599,11 @@ async fn update_sku_category(
     authenticated: AuthenticatedUser,
     Json(body): Json<SKUCategory>,
 ) -> Result<Json<SKUCategory>> {
-    authenticated.is_manage_sku_category()?;
+    authenticated.check_permission(Permission::ManageSKUCategory)?;
     let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.erp.sku_category.get(id, tx.as_mut()).await?;
     if let Some(v) = s.erp.sku_category.update(id, body, tx.as_mut()).await? {
+        s.erp.audit_log.add(authenticated.user.id, AuditEntity::SKUCategory, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut()).await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
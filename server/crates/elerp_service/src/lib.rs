@@ -60,7 +60,7 @@ pub async fn serve(config: AppConfig) {
     us_openapi.servers = Some(vec![utoipa::openapi::Server::new("/api/us")]);
 
     let api_router = Router::new().nest("/erp", erp_service::get_services()).nest("/us", user_service::get_services());
-    let serve_dir = ServeDir::new(&config.web.dist).not_found_service(ServeFile::new(&config.web.dist.join(&config.web.index)));
+    let serve_dir = ServeDir::new(&config.web.dist).not_found_service(ServeFile::new(config.web.dist.join(&config.web.index)));
     let app = Router::new()
         .nest_service("/", serve_dir)
         .merge(RapiDoc::with_openapi("/crm-api-docs/openapi.json", erp_openapi).path("/rapidoc/erp"))
@@ -1,3 +1,4 @@
 pub mod authenticated_user;
 pub mod web_socket_flag_json;
 pub mod clear_result;
+pub mod purge;
\ No newline at end of file
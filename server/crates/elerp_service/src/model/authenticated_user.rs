@@ -7,9 +7,8 @@ use elerp_common::{
     model::action_type::ActionType,
     user_system::model::{
         user_info::{UserInfo, UserType},
-        user_permission::{
-            ADD_ORDER, ADD_ORDER_PAYMENT, MANAGE_AREA, MANAGE_ORDER_CATEGORY, MANAGE_PERSON, MANAGE_SKU, MANAGE_SKU_CATEGORY, MANAGE_WAREHOUSE, UPDATE_REMOVE_ORDER, UPDATE_REMOVE_ORDER_PAYMENT
-        },
+        user_permission::Permission,
+        user_session::UserToken,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -31,6 +30,17 @@ pub struct GetTokenQuery {
 pub struct AuthenticatedUser {
     pub user: UserInfo,
     pub token: String,
+    /// Only returned when login or refresh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Only returned when login or refresh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshTokenBody {
+    pub refresh_token: String,
 }
 
 #[async_trait]
@@ -48,16 +58,10 @@ where
         let path = MatchedPath::from_request_parts(parts, s)
             .await
             .map(|path| path.as_str().to_string())
-            .map_err(|err| anyhow::Error::from(err))?;
+            .map_err(anyhow::Error::from)?;
 
         let auth = match parts.headers.get("X-authorization") {
-            Some(v) => {
-                if let Ok(auth) = v.to_str() {
-                    Some(auth)
-                } else {
-                    None
-                }
-            }
+            Some(v) => v.to_str().ok(),
             None => {
                 let mut r = None;
                 if let Some(a) = parts.headers.get("Sec-Websocket-Protocol") {
@@ -79,7 +83,7 @@ where
                 .await?;
             if let Some(user) = user {
                 if path.starts_with("/api/erp")
-                    && !app.us.is_socket_connected(user.id, tx.as_mut()).await?
+                    && !app.us.is_socket_connected(auth, tx.as_mut()).await?
                 {
                     return AppError::custom(
                         CustomErrorCode::NoPermission,
@@ -91,6 +95,8 @@ where
                 return Ok(AuthenticatedUser {
                     user,
                     token: auth.to_string(),
+                    refresh_token: None,
+                    expires_at: None,
                 });
             }
             return AppError::custom(
@@ -105,136 +111,53 @@ where
 }
 
 impl AuthenticatedUser {
-    pub fn fail_if_not_admin(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin {
-            AppError::custom(CustomErrorCode::NoPermission, "Only admin can access.").into_err()?;
-        }
-        Ok(())
-    }
-
-    pub fn is_manage_area(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & MANAGE_AREA != MANAGE_AREA
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to manage area.",
-            )
-            .into_err();
-        }
-        Ok(())
-    }
-
-    pub fn is_manage_person(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & MANAGE_PERSON != MANAGE_PERSON
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to manage person.",
-            )
-            .into_err();
-        }
-        Ok(())
-    }
-
-    pub fn is_manage_warehouse(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & MANAGE_WAREHOUSE != MANAGE_WAREHOUSE
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to manage warehouse.",
-            )
-            .into_err();
+    pub fn with_token(user: UserInfo, token: UserToken) -> Self {
+        Self {
+            user,
+            token: token.token,
+            refresh_token: Some(token.refresh_token),
+            expires_at: Some(token.expires_at),
         }
-        Ok(())
-    }
-
-    pub fn is_manage_sku(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin && self.user.permission & MANAGE_SKU != MANAGE_SKU
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to manage sku.",
-            )
-            .into_err();
-        }
-        Ok(())
     }
 
-    pub fn is_manage_order_category(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & MANAGE_ORDER_CATEGORY != MANAGE_ORDER_CATEGORY
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to manage order status.",
-            )
-            .into_err();
-        }
-        Ok(())
-    }
-
-    pub fn is_add_order_payment(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & ADD_ORDER_PAYMENT != ADD_ORDER_PAYMENT
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to add order payment.",
-            )
-            .into_err();
-        }
-        Ok(())
-    }
-
-    pub fn is_update_remove_order_payment(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & UPDATE_REMOVE_ORDER_PAYMENT != UPDATE_REMOVE_ORDER_PAYMENT
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to remove order payment.",
-            )
-            .into_err();
-        }
-        Ok(())
-    }
-
-    pub fn is_manage_sku_category(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & MANAGE_SKU_CATEGORY != MANAGE_SKU_CATEGORY
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to manage sku category.",
-            )
-            .into_err();
+    pub fn fail_if_not_admin(&self) -> Result<(), AppError> {
+        if self.user.user_type != UserType::Admin {
+            AppError::custom(CustomErrorCode::NoPermission, "Only admin can access.").into_err()?;
         }
         Ok(())
     }
 
-    pub fn is_add_order(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin && self.user.permission & ADD_ORDER != ADD_ORDER {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to add order.",
-            )
-            .into_err();
-        }
-        Ok(())
+    /// Admin have all permissions, general user have the permissions granted by their roles.
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.user.user_type == UserType::Admin || self.user.permissions.contains(&permission)
     }
 
-    pub fn is_update_remove_order(&self) -> Result<(), AppError> {
-        if self.user.user_type != UserType::Admin
-            && self.user.permission & UPDATE_REMOVE_ORDER != UPDATE_REMOVE_ORDER
-        {
-            return AppError::custom(
-                CustomErrorCode::NoPermission,
-                "You don't have permission to update/remove order.",
-            )
-            .into_err();
+    pub fn check_permission(&self, permission: Permission) -> Result<(), AppError> {
+        if !self.has_permission(permission) {
+            let msg = match permission {
+                Permission::ManageArea => "You don't have permission to manage area.",
+                Permission::ManagePerson => "You don't have permission to manage person.",
+                Permission::ManageWarehouse => "You don't have permission to manage warehouse.",
+                Permission::ManageSKU => "You don't have permission to manage sku.",
+                Permission::ManageSKUCategory => "You don't have permission to manage sku category.",
+                Permission::ManageOrderCategory => "You don't have permission to manage order category.",
+                Permission::AddOrder => "You don't have permission to add order.",
+                Permission::UpdateRemoveOrder => "You don't have permission to update/remove order.",
+                Permission::AddOrderPayment => "You don't have permission to add order payment.",
+                Permission::UpdateRemoveOrderPayment => "You don't have permission to remove order payment.",
+                Permission::ViewArea => "You don't have permission to view area.",
+                Permission::ViewPerson => "You don't have permission to view person.",
+                Permission::ViewWarehouse => "You don't have permission to view warehouse.",
+                Permission::ViewSKU => "You don't have permission to view sku.",
+                Permission::ViewSKUCategory => "You don't have permission to view sku category.",
+                Permission::ViewOrderCategory => "You don't have permission to view order category.",
+                Permission::ViewOrder => "You don't have permission to view order.",
+                Permission::ViewOrderPrice => "You don't have permission to view order price.",
+                Permission::ViewOrderPayment => "You don't have permission to view order payment.",
+                Permission::ViewInventory => "You don't have permission to view inventory.",
+                Permission::ViewStatistics => "You don't have permission to view statistics.",
+            };
+            return AppError::custom(CustomErrorCode::NoPermission, msg).into_err();
         }
         Ok(())
     }
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct PurgeQuery {
+    /// Only purge the rows removed before or at the date, default all removed rows.
+    pub deleted_before: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PurgeResult {
+    pub areas: i64,
+    pub persons: i64,
+    pub warehouses: i64,
+    pub sku_categories: i64,
+    pub skus: i64,
+    pub order_categories: i64,
+    pub orders: i64,
+    pub order_payments: i64,
+}
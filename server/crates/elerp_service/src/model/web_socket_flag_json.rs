@@ -13,41 +13,75 @@ impl From<WebSocketFlags> for WebSocketFlagsJson {
             WebSocketFlags::AddArea(id)
             | WebSocketFlags::UpdateArea(id)
             | WebSocketFlags::RemoveArea(id)
+            | WebSocketFlags::RestoreArea(id)
             | WebSocketFlags::AddPerson(id)
             | WebSocketFlags::UpdatePerson(id)
             | WebSocketFlags::RemovePerson(id)
+            | WebSocketFlags::RestorePerson(id)
             | WebSocketFlags::AddWarehouse(id)
             | WebSocketFlags::UpdateWarehouse(id)
             | WebSocketFlags::RemoveWarehouse(id)
+            | WebSocketFlags::RestoreWarehouse(id)
             | WebSocketFlags::LinkedWarehouse(id)
             | WebSocketFlags::UnlinkedWarehouse(id)
             | WebSocketFlags::AddSKUCategory(id)
             | WebSocketFlags::UpdateSKUCategory(id)
             | WebSocketFlags::RemoveSKUCategory(id)
+            | WebSocketFlags::RestoreSKUCategory(id)
             | WebSocketFlags::AddSKU(id)
             | WebSocketFlags::UpdateSKU(id)
             | WebSocketFlags::RemoveSKU(id)
+            | WebSocketFlags::RestoreSKU(id)
+            | WebSocketFlags::AddProductTemplate(id)
+            | WebSocketFlags::UpdateProductTemplate(id)
+            | WebSocketFlags::RemoveProductTemplate(id)
             | WebSocketFlags::AddOrder(id)
             | WebSocketFlags::UpdateOrder(id)
             | WebSocketFlags::RemoveOrder(id)
+            | WebSocketFlags::RestoreOrder(id)
             | WebSocketFlags::AddGuestOrder(id)
             | WebSocketFlags::ConfirmGuestOrder(id)
             | WebSocketFlags::RemoveGuestOrder(id)
+            | WebSocketFlags::InventoryAlert(id)
+            | WebSocketFlags::AddReservation(id)
+            | WebSocketFlags::RemoveReservation(id)
+            | WebSocketFlags::AddPurchaseOrder(id)
+            | WebSocketFlags::UpdatePurchaseOrder(id)
+            | WebSocketFlags::RemovePurchaseOrder(id)
+            | WebSocketFlags::AddQuotation(id)
+            | WebSocketFlags::UpdateQuotation(id)
+            | WebSocketFlags::RemoveQuotation(id)
+            | WebSocketFlags::AddExchangeRate(id)
+            | WebSocketFlags::RemoveExchangeRate(id)
+            | WebSocketFlags::AddTaxRate(id)
+            | WebSocketFlags::UpdateTaxRate(id)
+            | WebSocketFlags::RemoveTaxRate(id)
+            | WebSocketFlags::AddPriceList(id)
+            | WebSocketFlags::UpdatePriceList(id)
+            | WebSocketFlags::RemovePriceList(id)
+            | WebSocketFlags::UpdatePriceListItems(id)
             | WebSocketFlags::AddUser(id)
             | WebSocketFlags::UpdateUser(id)
             | WebSocketFlags::RemoveUser(id)
             | WebSocketFlags::UserRepeatLogin(id)
             | WebSocketFlags::LinkedUser(id)
             | WebSocketFlags::UnlinkedUser(id)
+            | WebSocketFlags::AddRole(id)
+            | WebSocketFlags::UpdateRole(id)
+            | WebSocketFlags::RemoveRole(id)
             | WebSocketFlags::AddOrderCategory(id)
             | WebSocketFlags::RemoveOrderCategory(id)
+            | WebSocketFlags::RestoreOrderCategory(id)
             | WebSocketFlags::UpdateOrderCategory(id)
             | WebSocketFlags::AddOrderPayment(id)
             | WebSocketFlags::RemoveOrderPayment(id)
+            | WebSocketFlags::RestoreOrderPayment(id)
             | WebSocketFlags::UserConnected(id)
             | WebSocketFlags::UserDisconnected(id) => Some(id),
 
             WebSocketFlags::RecalcOrders
+            | WebSocketFlags::UpdateCurrencies
+            | WebSocketFlags::ImportExchangeRates
             | WebSocketFlags::ReadyAccess
             | WebSocketFlags::Ping
             | WebSocketFlags::ClearAreas
@@ -57,7 +91,8 @@ impl From<WebSocketFlags> for WebSocketFlagsJson {
             | WebSocketFlags::ClearSKUCategories
             | WebSocketFlags::ClearSKUs
             | WebSocketFlags::ClearWarehouses
-            | WebSocketFlags::ClearOrderCategories => None,
+            | WebSocketFlags::ClearOrderCategories
+            | WebSocketFlags::Purge => None,
         };
         Self { flag: value.to_string(), id }
     }
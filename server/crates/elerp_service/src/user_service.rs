@@ -4,7 +4,17 @@ use axum::{
     routing::{delete, get, post},
     Json, Router,
 };
-use elerp_common::{model::{ListSlice, Pagination}, user_system::model::{user_configure::UserConfigure, user_info::{GetUsersQuery, UserInfo, UserType}}};
+use elerp_common::{
+    audit_log_module::model::audit_log::{AuditAction, AuditEntity},
+    model::{action_type::ActionType, ListSlice, Pagination},
+    user_system::model::{
+        role::Role,
+        user_configure::UserConfigure,
+        user_info::{GetUsersQuery, UserInfo, UserType},
+        user_permission::Permission,
+        user_session::UserSession,
+    },
+};
 use serde_qs::axum::QsQuery as Query;
 use sqlx::SqliteConnection;
 use utoipa::OpenApi;
@@ -12,7 +22,7 @@ use utoipa::OpenApi;
 use crate::custom_error::{AppError, CustomErrorCode};
 
 use super::{
-    model::authenticated_user::{AuthenticatedUser, GetTokenQuery},
+    model::authenticated_user::{AuthenticatedUser, GetTokenQuery, RefreshTokenBody},
     AppState,
 };
 
@@ -27,6 +37,14 @@ type Result<T> = core::result::Result<T, AppError>;
         get_users,
         update_user,
         get_user_token,
+        refresh_user_token,
+        get_user_sessions,
+        remove_user_session,
+        add_role,
+        remove_role,
+        get_role,
+        get_roles,
+        update_role,
     ),
     tags(
         (name = "UserSystem", description = "User System API")
@@ -37,6 +55,10 @@ type Result<T> = core::result::Result<T, AppError>;
             GetUsersQuery,
             GetTokenQuery,
             AuthenticatedUser,
+            RefreshTokenBody,
+            UserSession,
+            Role,
+            Permission,
         )
     )
 )]
@@ -51,10 +73,18 @@ pub fn get_services() -> Router<AppState> {
         )
         .route("/users_token", get(get_user_token))
         .route("/users_token/:id", delete(remove_user_token))
+        .route("/users_token_refresh", post(refresh_user_token))
+        .route("/users/:id/sessions", get(get_user_sessions))
+        .route("/users/:id/sessions/:session_id", delete(remove_user_session))
         .route(
             "/users_configure/:id",
             get(get_user_configure).put(update_user_configure),
         )
+        .route("/roles", post(add_role).get(get_roles))
+        .route(
+            "/roles/:id",
+            delete(remove_role).get(get_role).put(update_role),
+        )
         .route("/me", get(get_me_by_token))
 }
 
@@ -71,6 +101,12 @@ async fn check_user(
         )
         .into_err();
     }
+    for role_id in v.roles.iter().flatten() {
+        if !s.us.is_exists_role(*role_id, &mut *tx).await? {
+            return AppError::custom(CustomErrorCode::RoleNotFound, "Role is not exists.")
+                .into_err();
+        }
+    }
     Ok(())
 }
 
@@ -105,6 +141,10 @@ async fn add_user(
         .into_err();
     }
     let v = s.us.add_user(body, tx.as_mut()).await?;
+    s.erp
+        .audit_log
+        .add(authenticated.user.id, AuditEntity::User, v.id, AuditAction::Add, None, Some(&v), tx.as_mut())
+        .await?;
     tx.commit().await?;
     Ok(Json(v))
 }
@@ -131,9 +171,14 @@ async fn remove_user(
             .into_err();
     }
     let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.us.get_user(id, ActionType::Admin, tx.as_mut()).await?;
     let r = s.us.remove_user(id, tx.as_mut()).await?;
-    tx.commit().await?;
     if r {
+        s.erp
+            .audit_log
+            .add(authenticated.user.id, AuditEntity::User, id, AuditAction::Remove, before.as_ref(), None, tx.as_mut())
+            .await?;
+        tx.commit().await?;
         Ok(StatusCode::OK)
     } else {
         AppError::custom(CustomErrorCode::UserNotFound, "User is not exists.").into_err()
@@ -227,8 +272,13 @@ async fn update_user(
         )
         .into_err();
     }
+    let before = s.us.get_user(id, ActionType::Admin, tx.as_mut()).await?;
     let r = s.us.update_user(id, body, tx.as_mut()).await?;
     if let Some(v) = r {
+        s.erp
+            .audit_log
+            .add(authenticated.user.id, AuditEntity::User, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut())
+            .await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
@@ -255,15 +305,16 @@ async fn get_user_token(
     if authenticated.is_some() {
         return AppError::custom(CustomErrorCode::SameObject, "You have login already.").into_err();
     }
-    let mut tx = s.ps.begin_tx(false).await?;
-    if let Some(user) =
+    let mut tx = s.ps.begin_tx(true).await?;
+    if let Some(mut user) =
         s.us.get_user_by_username(&query.username, tx.as_mut())
             .await?
     {
-        if user.password == query.password {
+        if s.us.verify_password(&user, &query.password, tx.as_mut()).await? {
+            user.password = String::with_capacity(0);
             let token = s.us.get_token(&user, tx.as_mut()).await?;
             tx.commit().await?;
-            Ok(Json(AuthenticatedUser { user, token }))
+            Ok(Json(AuthenticatedUser::with_token(user, token)))
         } else {
             AppError::custom(CustomErrorCode::WrongPassword, "Password is wrong.").into_err()
         }
@@ -272,6 +323,90 @@ async fn get_user_token(
     }
 }
 
+/// refresh user token
+#[utoipa::path(
+    post,
+    path = "/users_token_refresh",
+    responses(
+        (status = 200, description = "refresh user token successfully", body = AuthenticatedUser)
+    ),
+)]
+async fn refresh_user_token(
+    State(s): State<AppState>,
+    Json(body): Json<RefreshTokenBody>,
+) -> Result<Json<AuthenticatedUser>> {
+    let mut tx = s.ps.begin_tx(true).await?;
+    if let Some(token) = s.us.refresh_token(&body.refresh_token, tx.as_mut()).await? {
+        if let Some(user) =
+            s.us.token_to_user(&token.token, ActionType::System, tx.as_mut())
+                .await?
+        {
+            tx.commit().await?;
+            return Ok(Json(AuthenticatedUser::with_token(user, token)));
+        }
+        return AppError::custom(CustomErrorCode::UserNotFound, "The user not found!").into_err();
+    }
+    AppError::custom(
+        CustomErrorCode::NoPermission,
+        "Refresh token is invalid or expired, please login again.",
+    )
+    .into_err()
+}
+
+/// get active sessions of user
+#[utoipa::path(
+    get,
+    path = "/users/{id}/sessions",
+    responses(
+        (status = 200, description = "get user sessions successfully", body = Vec<UserSession>)
+    ),
+    params(
+        ("id" = i64, Path, description = "user id")
+    )
+)]
+async fn get_user_sessions(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Vec<UserSession>>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    if !s.us.is_exists(id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::UserNotFound, "User is not exists.").into_err();
+    }
+    let sessions = s.us.get_sessions(id, tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(sessions))
+}
+
+/// revoke the session of user
+#[utoipa::path(
+    delete,
+    path = "/users/{id}/sessions/{session_id}",
+    responses(
+        (status = 200, description = "revoke user session successfully")
+    ),
+    params(
+        ("id" = i64, Path, description = "user id"),
+        ("session_id" = i64, Path, description = "session id")
+    )
+)]
+async fn remove_user_session(
+    State(s): State<AppState>,
+    Path((id, session_id)): Path<(i64, i64)>,
+    authenticated: AuthenticatedUser,
+) -> Result<StatusCode> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    let r = s.us.remove_session(id, session_id, tx.as_mut()).await?;
+    tx.commit().await?;
+    if r {
+        Ok(StatusCode::OK)
+    } else {
+        AppError::custom(CustomErrorCode::UserNotFound, "Session is not exists.").into_err()
+    }
+}
+
 /// get me by token
 #[utoipa::path(
     get,
@@ -317,6 +452,7 @@ async fn remove_user_token(
     }
     let mut tx = s.ps.begin_tx(true).await?;
     s.us.remove_token(id, tx.as_mut()).await?;
+    tx.commit().await?;
     Ok(StatusCode::OK)
 }
 
@@ -378,11 +514,177 @@ async fn update_user_configure(
         .into_err();
     }
     let mut tx = s.ps.begin_tx(true).await?;
+    let before = s.us.get_configure(id, tx.as_mut()).await?;
     let r = s.us.update_configure(id, body, tx.as_mut()).await?;
     if let Some(v) = r {
+        s.erp
+            .audit_log
+            .add(authenticated.user.id, AuditEntity::UserConfigure, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut())
+            .await?;
         tx.commit().await?;
         Ok(Json(v))
     } else {
         AppError::custom(CustomErrorCode::UserNotFound, "User is not exists.").into_err()
     }
 }
+
+async fn check_role(
+    s: AppState,
+    v: &Role,
+    prev: Option<i64>,
+    tx: &mut SqliteConnection,
+) -> Result<()> {
+    if v.name.is_empty() {
+        return AppError::custom(CustomErrorCode::CheckFailed, "Role name can't be empty.")
+            .into_err();
+    }
+    if s.us.is_exists_role_name(&v.name, prev, &mut *tx).await? {
+        return AppError::custom(
+            CustomErrorCode::SameObject,
+            "Already contains the role's name.",
+        )
+        .into_err();
+    }
+    Ok(())
+}
+
+/// add role
+#[utoipa::path(
+    post,
+    path = "/roles",
+    responses(
+        (status = 200, description = "add role successfully", body = Role)
+    ),
+)]
+async fn add_role(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Json(body): Json<Role>,
+) -> Result<Json<Role>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_role(s.clone(), &body, None, tx.as_mut()).await?;
+    let v = s.us.add_role(body, tx.as_mut()).await?;
+    s.erp
+        .audit_log
+        .add(authenticated.user.id, AuditEntity::Role, v.id, AuditAction::Add, None, Some(&v), tx.as_mut())
+        .await?;
+    tx.commit().await?;
+    Ok(Json(v))
+}
+
+/// remove role
+#[utoipa::path(
+    delete,
+    path = "/roles/{id}",
+    responses(
+        (status = 200, description = "remove role successfully")
+    ),
+    params(
+        ("id" = i64, Path, description = "role id")
+    )
+)]
+async fn remove_role(
+    State(s): State<AppState>,
+    authenticated: AuthenticatedUser,
+    Path(id): Path<i64>,
+) -> Result<StatusCode> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    if !s.us.is_exists_role(id, tx.as_mut()).await? {
+        return AppError::custom(CustomErrorCode::RoleNotFound, "Role is not exists.").into_err();
+    }
+    let before = s.us.get_role(id, tx.as_mut()).await?;
+    s.us.remove_role(id, tx.as_mut()).await?;
+    s.erp
+        .audit_log
+        .add(authenticated.user.id, AuditEntity::Role, id, AuditAction::Remove, before.as_ref(), None, tx.as_mut())
+        .await?;
+    tx.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// get role by id
+#[utoipa::path(
+    get,
+    path = "/roles/{id}",
+    responses(
+        (status = 200, description = "get role successfully", body = Role)
+    ),
+    params(
+        ("id" = i64, Path, description = "role id")
+    )
+)]
+async fn get_role(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<Role>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let r = s.us.get_role(id, tx.as_mut()).await?;
+    tx.commit().await?;
+    if let Some(v) = r {
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::RoleNotFound, "Role is not exists.").into_err()
+    }
+}
+
+/// get roles
+#[utoipa::path(
+    get,
+    path = "/roles",
+    responses(
+        (status = 200, description = "get roles successfully", body = ListSlice<Role>)
+    ),
+    params(
+        Pagination,
+    )
+)]
+async fn get_roles(
+    State(s): State<AppState>,
+    Query(pagination): Query<Pagination>,
+    authenticated: AuthenticatedUser,
+) -> Result<Json<ListSlice<Role>>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(false).await?;
+    let items = s.us.get_roles(&pagination.correct(), tx.as_mut()).await?;
+    let count = s.us.get_roles_count(tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(Json(ListSlice { items, count }))
+}
+
+/// update role by id
+#[utoipa::path(
+    put,
+    path = "/roles/{id}",
+    responses(
+        (status = 200, description = "update role successfully", body = Role)
+    ),
+    params(
+        ("id" = i64, Path, description = "role id")
+    )
+)]
+async fn update_role(
+    State(s): State<AppState>,
+    Path(id): Path<i64>,
+    authenticated: AuthenticatedUser,
+    Json(body): Json<Role>,
+) -> Result<Json<Role>> {
+    authenticated.fail_if_not_admin()?;
+    let mut tx = s.ps.begin_tx(true).await?;
+    check_role(s.clone(), &body, Some(id), tx.as_mut()).await?;
+    let before = s.us.get_role(id, tx.as_mut()).await?;
+    let r = s.us.update_role(id, body, tx.as_mut()).await?;
+    if let Some(v) = r {
+        s.erp
+            .audit_log
+            .add(authenticated.user.id, AuditEntity::Role, id, AuditAction::Update, before.as_ref(), Some(&v), tx.as_mut())
+            .await?;
+        tx.commit().await?;
+        Ok(Json(v))
+    } else {
+        AppError::custom(CustomErrorCode::RoleNotFound, "Role is not exists.").into_err()
+    }
+}
@@ -46,7 +46,7 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
     let username = &auth.user.username;
     let success = 'status: {
         if let Ok(mut tx) = s.ps.begin_tx(true).await {
-            if let Ok(success) = s.us.try_connect_socket(&auth.user, tx.as_mut()).await {
+            if let Ok(success) = s.us.try_connect_socket(&auth.user, &auth.token, tx.as_mut()).await {
                 if success {
                     if let Err(err) = tx.commit().await {
                         error!("Transaction commit failed, will break web socket: {err}");
@@ -55,7 +55,7 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
                     }
                 } else {
                     //can't connect to socket because socket count more than 1.
-                    warn!("User '{username}' not allowed to connect socket because socket count more than maximum! Will clear user tokens!");
+                    warn!("User '{username}' not allowed to connect socket because socket count of the session more than maximum! Will clear the session!");
                     let flag = WebSocketFlags::UserRepeatLogin(auth.user.id);
 
                     // Notice other logged in device and current trying connect device.
@@ -66,8 +66,8 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
                     }
                     // Notice end.
 
-                    if let Ok(_) = s.us.remove_token(auth.user.id, tx.as_mut()).await
-                    //remove user token, let user login again.
+                    if s.us.remove_session_by_token(&auth.token, tx.as_mut()).await.is_ok()
+                    //remove the session token, let user login again.
                     {
                         if let Err(err) = tx.commit().await {
                             error!("Transaction commit failed, will break web socket: {err}");
@@ -86,6 +86,7 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
 
         let name = username.clone();
         let user = auth.user.clone();
+        let token = auth.token.clone();
 
         if sender
             .send(Message::Text(serde_json::to_string(&WebSocketFlagsJson::from(WebSocketFlags::ReadyAccess)).unwrap()))
@@ -98,11 +99,7 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
         }
 
         let mut recv_task = tokio::spawn(async move {
-            if let Some(msg) = receiver.next().await {
-                if msg.is_err() {
-                    return;
-                }
-            }
+            receiver.next().await;
         });
 
         let s: AppState = s.clone();
@@ -121,11 +118,7 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
                         }
                     }
                     WebSocketFlags::Ping => print_info = false,
-                    WebSocketFlags::UserConnected(id) | WebSocketFlags::UserDisconnected(id) => {
-                        if user.id != id && user.user_type != UserType::Admin {
-                            continue;
-                        }
-                    }
+                    WebSocketFlags::UserConnected(id) | WebSocketFlags::UserDisconnected(id) if user.id != id && user.user_type != UserType::Admin => continue,
                     _ => (),
                 }
 
@@ -158,7 +151,7 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
             }
         }
         if let Ok(mut tx) = s.ps.begin_tx(true).await {
-            if let Ok(_) = s.us.disconnect_socket(&user, tx.as_mut()).await {
+            if s.us.disconnect_socket(&user, &token, tx.as_mut()).await.is_ok() {
                 if let Err(err) = tx.commit().await {
                     error!("Transaction commit failed, will break web socket: {err}");
                     return;
@@ -166,6 +159,5 @@ async fn handle_socket(s: AppState, auth: AuthenticatedUser, mut socket: WebSock
             }
         }
         info!("User '{username}' disconnected socket...");
-        return;
     }
 }
@@ -4,7 +4,9 @@ use elerp_common::guest_order_module::model::guest_order::GetGuestOrdersQuery;
 use elerp_common::guest_order_module::model::guest_order::GuestOrder;
 use elerp_common::guest_order_module::model::guest_order::GuestOrderConfirm;
 use elerp_common::guest_order_module::model::guest_order::GuestOrderStatus;
+use elerp_common::export::{ExportValue, ExportWriter, NameCache, TempPath, EXPORT_PAGE_SIZE};
 use elerp_common::model::action_type::ActionType;
+use elerp_common::model::export::ExportFormat;
 use elerp_common::model::Pagination;
 use elerp_common::model::WebSocketFlags;
 use elerp_common::order_module;
@@ -25,6 +27,9 @@ pub struct GuestOrderModule {
 }
 
 impl GuestOrderModule {
+    /// The pending guest order is expired after the seconds.
+    pub const EXPIRY_SECONDS: i64 = 28800;
+
     pub async fn new(ps: PublicSystem) -> Self {
         let mut tx = ps.begin_tx(true).await.unwrap();
         sqlx::query(
@@ -140,11 +145,11 @@ impl GuestOrderModule {
         .bind(sub_token)
         .bind(order.created_by_user_id)
             .bind(order.warehouse_id)
-            .bind(&order.currency)
+            .bind(order.currency)
             .bind(order.person_related_id)
             .bind(order.person_in_charge_id)
             .bind(&order.description)
-            .bind(&order.order_type)
+            .bind(order.order_type)
             .bind(order.is_record)
             .bind(order.non_payment)
             .bind(GuestOrderStatus::Pending)
@@ -181,13 +186,13 @@ impl GuestOrderModule {
 
             let order: Order = guest_order.into();
 
+            let now = self.ps.get_timestamp_seconds() as i64;
             let mut result = GuestOrderConfirm {
-                check_result: order_module::check(&order, false, &mut *tx).await?,
+                check_result: order_module::check(&order, false, now, &mut *tx).await?,
                 order: None,
             };
             if result.check_result.items_not_available.is_empty() {
-                let order = order_module::add(order, &mut *tx).await?;
-                let now = self.ps.get_timestamp_seconds() as i64;
+                let order = order_module::add(order, self.ps.get_config().valuation.method, self.ps.get_config().currency.base, now, &mut *tx).await?;
                 sqlx::query("UPDATE guest_orders SET order_id=?, guest_order_status=?, confirmed_date=? WHERE id=?")
                     .bind(order.id)
                     .bind(GuestOrderStatus::Confirmed)
@@ -214,7 +219,7 @@ impl GuestOrderModule {
         let order_id = row.get("order_id");
         match guest_order_status {
             GuestOrderStatus::Pending => {
-                if now > date + 28800 {
+                if now > date + Self::EXPIRY_SECONDS {
                     sqlx::query("UPDATE guest_orders SET guest_order_status=? WHERE id=?")
                         .bind(GuestOrderStatus::Expired)
                         .bind(id)
@@ -278,7 +283,7 @@ impl GuestOrderModule {
 
     pub async fn get_order_id(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<i64>> {
         let r = sqlx::query("SELECT order_id FROM guest_orders WHERE id = ?").bind(id).fetch(&mut *tx).try_next().await?;
-        Ok(if let Some(row) = r { Some(row.get("order_id")) } else { None })
+        Ok(r.map(|row| row.get("order_id")))
     }
 
     fn get_permission_inner(&self, action: ActionType) -> String {
@@ -339,6 +344,37 @@ impl GuestOrderModule {
         Ok(arr)
     }
 
+    /// Export the guest orders of the query.
+    pub async fn get_export(&self, query: &GetGuestOrdersQuery, format: ExportFormat, action: ActionType, tx: &mut SqliteConnection) -> Result<TempPath> {
+        let mut writer = ExportWriter::new(format, self.ps.get_data_path(), "guest_orders", self.ps.get_timestamp_seconds(), &["ID", "Date", "Order Type", "Status", "Order Category", "Warehouse", "Person Related", "Person In Charge", "Currency", "Order ID", "Confirmed Date", "Description"])?;
+        let mut names = NameCache::new();
+        let mut pagination = Pagination::new(0, EXPORT_PAGE_SIZE);
+        loop {
+            let list = self.get_multiple(&pagination, query, action, tx).await?;
+            for v in list.iter() {
+                writer.write_row(vec![
+                    v.id.into(),
+                    ExportValue::Date(v.date),
+                    v.order_type.as_ref().into(),
+                    v.guest_order_status.as_ref().into(),
+                    names.get("order_categories", v.order_category_id, tx).await?.into(),
+                    names.get("warehouses", v.warehouse_id, tx).await?.into(),
+                    names.get("persons", v.person_related_id, tx).await?.into(),
+                    names.get("persons", v.person_in_charge_id, tx).await?.into(),
+                    v.currency.to_string().into(),
+                    v.order_id.into(),
+                    if v.confirmed_date > 0 { ExportValue::Date(v.confirmed_date) } else { "".into() },
+                    v.description.as_str().into(),
+                ])?;
+            }
+            if (list.len() as i64) < pagination.limit() {
+                break;
+            }
+            pagination.next();
+        }
+        writer.finish()
+    }
+
     pub async fn get_multiple_ids(&self, pagination: &Pagination, query: &GetGuestOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
         let qw = query.get_where_condition();
 
@@ -1,19 +1,40 @@
-use std::path::PathBuf;
+use std::{collections::hash_map::Entry, path::PathBuf, sync::Arc};
 
 use ahash::{HashMap, HashMapExt};
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use elerp_common::{
-    inventory_module::model::inventory::{GetInventoryQuery, InventoryProduct},
-    model::{action_type::ActionType, Pagination},
+    currency_module::get_rate_sql,
+    inventory_module::{
+        self,
+        model::{
+            inventory::{GetInventoryLotsQuery, GetInventoryQuery, InventoryLot, InventoryProduct, TemplateInventory},
+            reorder_point::{GetReorderPointsQuery, InventoryAlert, InventoryAlertType, ReorderPoint},
+            reservation::{GetReservationsQuery, Reservation},
+        },
+    },
+    model::{action_type::ActionType, Pagination, WebSocketFlags},
+    sql,
 };
 use public_system::PublicSystem;
 use sqlx::{FromRow, Row, SqliteConnection};
-use tokio::{fs, io::AsyncWriteExt};
+use tokio::{
+    fs,
+    io::AsyncWriteExt,
+    sync::{
+        broadcast::error::{RecvError, TryRecvError},
+        RwLock,
+    },
+    task::JoinHandle,
+};
+use tracing::error;
 
 #[derive(Debug, Clone)]
 pub struct InventoryModule {
     ps: PublicSystem,
+    /// Alert type of the (warehouse_id, sku_id) at the last evaluation.
+    alerts: Arc<RwLock<HashMap<(i64, i64), InventoryAlertType>>>,
+    handle: Arc<Option<JoinHandle<()>>>,
 }
 
 impl InventoryModule {
@@ -42,18 +63,174 @@ impl InventoryModule {
         .execute(&mut *tx)
         .await
         .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS inventory_lots(
+                warehouse_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                lot_number TEXT NOT NULL,
+                expiry_date INT NULL,
+                quantity INT NOT NULL
+            )",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS inventory_lots_warehouses_and_skus
+        ON inventory_lots(warehouse_id, sku_id);
+        CREATE INDEX IF NOT EXISTS inventory_lots_expiry_dates
+        ON inventory_lots(expiry_date);",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS inventory_cost_layers(
+                warehouse_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                order_id INT NOT NULL,
+                date INT NOT NULL,
+                quantity INT NOT NULL,
+                unit_cost REAL NOT NULL
+            )",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS cost_of_goods_sold(
+                order_id INT NOT NULL,
+                warehouse_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                quantity INT NOT NULL,
+                amount REAL NOT NULL
+            )",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS inventory_cost_layers_warehouses_and_skus
+        ON inventory_cost_layers(warehouse_id, sku_id);
+        CREATE INDEX IF NOT EXISTS cost_of_goods_sold_order_ids
+        ON cost_of_goods_sold(order_id);
+        CREATE INDEX IF NOT EXISTS cost_of_goods_sold_warehouses_and_skus
+        ON cost_of_goods_sold(warehouse_id, sku_id);",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS reorder_points(
+                sku_id INT NOT NULL,
+                warehouse_id INT NOT NULL,
+                min_quantity INT NOT NULL,
+                reorder_quantity INT NOT NULL,
+                max_quantity INT NULL
+            )",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS reorder_points_skus_and_warehouses
+        ON reorder_points(sku_id, warehouse_id);",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS inventory_reservations(
+                id INTEGER PRIMARY KEY,
+                created_by_user_id INT NOT NULL,
+                date INT NOT NULL,
+                warehouse_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                quantity INT NOT NULL,
+                guest_order_id INT NOT NULL DEFAULT 0,
+                quotation_id INT NOT NULL DEFAULT 0,
+                expires_at INT NULL,
+                description TEXT NOT NULL
+            )",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS inventory_reservations_warehouses_and_skus
+        ON inventory_reservations(warehouse_id, sku_id);
+        CREATE INDEX IF NOT EXISTS inventory_reservations_guest_order_ids
+        ON inventory_reservations(guest_order_id);
+        CREATE INDEX IF NOT EXISTS inventory_reservations_quotation_ids
+        ON inventory_reservations(quotation_id);
+        CREATE INDEX IF NOT EXISTS inventory_reservations_expires_at
+        ON inventory_reservations(expires_at);",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
 
         Ok(())
     }
 
     pub async fn new(ps: PublicSystem) -> Self {
         let mut tx = ps.get_conn().begin().await.unwrap();
-        let s = Self { ps };
+        let mut s = Self {
+            ps: ps.clone(),
+            alerts: Arc::new(RwLock::new(HashMap::new())),
+            handle: Arc::new(None),
+        };
         s.create_table(tx.as_mut()).await.unwrap();
+        *s.alerts.write().await = s.evaluate_alerts(tx.as_mut()).await.unwrap();
         tx.commit().await.unwrap();
+
+        let t2 = s.clone();
+        let handle = tokio::spawn(async move {
+            let mut rx = ps.notication_subscribe().await;
+            loop {
+                match rx.recv().await {
+                    Ok(flag) if !Self::is_inventory_changed(&flag) => continue,
+                    Err(RecvError::Closed) => break,
+                    _ => (),
+                }
+                // The flags are sent before the commit, evaluate once for all queued flags.
+                while let Ok(_) | Err(TryRecvError::Lagged(_)) = rx.try_recv() {}
+                if let Err(err) = t2.refresh_alerts_after_commit().await {
+                    error!("Can't evaluate the inventory alerts. {}", err);
+                }
+            }
+        });
+        s.handle = Arc::new(Some(handle));
         s
     }
 
+    fn is_inventory_changed(flag: &WebSocketFlags) -> bool {
+        matches!(
+            flag,
+            WebSocketFlags::AddOrder(_)
+                | WebSocketFlags::UpdateOrder(_)
+                | WebSocketFlags::RemoveOrder(_)
+                | WebSocketFlags::RestoreOrder(_)
+                | WebSocketFlags::ConfirmGuestOrder(_)
+                | WebSocketFlags::RecalcOrders
+                | WebSocketFlags::ClearOrders
+                | WebSocketFlags::RemoveSKU(_)
+                | WebSocketFlags::RestoreSKU(_)
+                | WebSocketFlags::ClearSKUs
+                | WebSocketFlags::RemoveWarehouse(_)
+                | WebSocketFlags::RestoreWarehouse(_)
+                | WebSocketFlags::ClearWarehouses
+                | WebSocketFlags::Purge
+        )
+    }
+
+    async fn refresh_alerts_after_commit(&self) -> Result<()> {
+        // Waiting for the write lock means the transaction sent the flag is finished.
+        let mut tx = self.ps.begin_tx(true).await?;
+        self.refresh_alerts(tx.as_mut()).await?;
+        tx.commit().await
+    }
+
     pub async fn get(&self, warehouse_id: i64, sku_id: i64, tx: &mut SqliteConnection) -> Result<Option<InventoryProduct>> {
         Ok(sqlx::query_as::<_, InventoryProduct>("SELECT * FROM inventory WHERE warehouse_id=? AND sku_id=? LIMIT 1")
             .bind(warehouse_id)
@@ -71,11 +248,31 @@ impl InventoryModule {
         }
     }
 
-    const SELECT: &'static str = "SELECT
+    /// The reservations not passed the expiry date, the expired are removed by the writes.
+    fn reservations_table(&self) -> String {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        format!("(SELECT * FROM inventory_reservations WHERE expires_at IS NULL OR expires_at>{now}) AS inventory_reservations")
+    }
+
+    fn select(&self) -> String {
+        let reservations = self.reservations_table();
+        // The sales are in the currency of the orders, the cost of goods sold is in the base currency.
+        let rate = get_rate_sql(self.ps.get_config().currency.base, "o.currency", "o.date");
+        format!(
+            "SELECT
     inventory.warehouse_id,
     inventory.sku_id,
     inventory.sku_category_id,
     inventory.quantity,
+    COALESCE(reservations.reserved, 0) AS reserved,
+    inventory.quantity - COALESCE(reservations.reserved, 0) AS available,
+    COALESCE((SELECT SUM(l.quantity * l.unit_cost) FROM inventory_cost_layers l WHERE l.warehouse_id=inventory.warehouse_id AND l.sku_id=inventory.sku_id), 0.0) AS value,
+    COALESCE((SELECT SUM(oi.amount * {rate}) FROM order_items oi INNER JOIN orders o ON o.id=oi.order_id
+        WHERE o.warehouse_id=inventory.warehouse_id AND oi.sku_id=inventory.sku_id AND o.order_type='StockOut' AND o.is_record=False AND o.deleted_at IS NULL), 0.0)
+    - COALESCE((SELECT SUM(c.amount) FROM cost_of_goods_sold c WHERE c.warehouse_id=inventory.warehouse_id AND c.sku_id=inventory.sku_id), 0.0) AS gross_margin,
+    sku_list.unit AS unit,
+    CAST(inventory.quantity AS REAL) AS unit_quantity,
+    sku_list.template_id AS template_id,
 
     warehouses.name AS warehouse_name,
     sku_list.name AS sku_name,
@@ -84,10 +281,14 @@ impl InventoryModule {
     FROM inventory
     INNER JOIN warehouses ON inventory.warehouse_id=warehouses.id
     INNER JOIN sku_list ON inventory.sku_id=sku_list.id
-    INNER JOIN sku_categories ON inventory.sku_category_id=sku_categories.id";
+    INNER JOIN sku_categories ON inventory.sku_category_id=sku_categories.id
+    LEFT JOIN (SELECT warehouse_id, sku_id, SUM(quantity) AS reserved FROM {reservations} GROUP BY warehouse_id, sku_id) AS reservations
+    ON reservations.warehouse_id=inventory.warehouse_id AND reservations.sku_id=inventory.sku_id"
+        )
+    }
 
     pub async fn list(&self, pagination: &Pagination, query: &GetInventoryQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<InventoryProduct>> {
-        let select = Self::SELECT;
+        let select = self.select();
         let qw = query.get_where_condition();
         let ob = query.get_order_condition();
         let inner = self.get_permission_inner(action);
@@ -100,13 +301,66 @@ impl InventoryModule {
         for row in rows {
             arr.push(InventoryProduct::from_row(&row).unwrap())
         }
-
+        self.convert_units(&mut arr, query.unit.as_deref(), tx).await?;
         Ok(arr)
     }
 
+    /// Stock of the query summed by the product templates of the variants, the skus not the variants are excluded.
+    pub async fn list_templates(&self, query: &GetInventoryQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<TemplateInventory>> {
+        let select = self.select();
+        let qw = query.get_where_condition();
+        let inner = self.get_permission_inner(action);
+        Ok(sqlx::query_as::<_, TemplateInventory>(&format!(
+            "SELECT
+            warehouse_id,
+            template_id,
+            COUNT(*) AS variant_count,
+            SUM(quantity) AS quantity,
+            SUM(reserved) AS reserved,
+            SUM(available) AS available,
+            SUM(value) AS value,
+            SUM(gross_margin) AS gross_margin
+            FROM ({select} {inner} {qw}) AS tbl
+            WHERE template_id<>0
+            GROUP BY warehouse_id, template_id
+            ORDER BY warehouse_id, template_id"
+        ))
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    /// Show the quantities in the unit of the skus defining it, the others are kept in the base units.
+    async fn convert_units(&self, arr: &mut [InventoryProduct], unit: Option<&str>, tx: &mut SqliteConnection) -> Result<()> {
+        let unit = match unit.map(|v| v.trim()) {
+            Some(v) if !v.is_empty() => v,
+            _ => return Ok(()),
+        };
+        let mut factors: HashMap<i64, Option<i64>> = HashMap::new();
+        for p in arr.iter_mut() {
+            let factor = match factors.get(&p.sku_id) {
+                Some(v) => *v,
+                None => {
+                    let v = sqlx::query("SELECT factor FROM sku_units WHERE sku_id=? AND name=? LIMIT 1")
+                        .bind(p.sku_id)
+                        .bind(unit)
+                        .fetch_optional(&mut *tx)
+                        .await?
+                        .map(|row| row.get("factor"));
+                    factors.insert(p.sku_id, v);
+                    v
+                }
+            };
+            if let Some(factor) = factor {
+                p.unit = unit.to_owned();
+                p.unit_quantity = p.quantity as f64 / factor as f64;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn get_excel(&self, query: &GetInventoryQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<PathBuf> {
         use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet};
-        let select = Self::SELECT;
+        let select = self.select();
         let inner = self.get_permission_inner(action);
         let qw = query.get_where_condition();
         let ob = query.get_order_condition();
@@ -115,6 +369,7 @@ impl InventoryModule {
         for row in rows {
             arr.push(InventoryProduct::from_row(&row).unwrap())
         }
+        self.convert_units(&mut arr, query.unit.as_deref(), tx).await?;
         let digits = arr.len().checked_ilog10().unwrap_or(0) + 1;
         // Create a new Excel file object.
         let mut workbook = Workbook::new();
@@ -135,50 +390,40 @@ impl InventoryModule {
         worksheet.set_column_width(2, 35)?;
         worksheet.set_column_width(3, 55)?;
         worksheet.set_column_width(4, 12)?;
+        worksheet.set_column_width(5, 12)?;
+        worksheet.set_column_width(6, 12)?;
+        worksheet.set_column_width(7, 15)?;
+        worksheet.set_column_width(8, 15)?;
+        worksheet.set_column_width(9, 12)?;
+        worksheet.set_column_width(10, 15)?;
         for n in 0..(arr.len() + 1) {
             worksheet.set_row_height(n as u32, 22.25)?;
         }
-        worksheet.write_row_with_format(0, 0, ["No", "Warehouse", "SKU Category", "SKU", "Quantity"], &header_format)?;
+        worksheet.write_row_with_format(0, 0, ["No", "Warehouse", "SKU Category", "SKU", "On Hand", "Reserved", "Available", "Value", "Gross Margin", "Unit", "On Hand In Unit"], &header_format)?;
         let mut warehouses: HashMap<i64, String> = HashMap::new();
         let mut skus: HashMap<i64, String> = HashMap::new();
         let mut sku_categories: HashMap<i64, String> = HashMap::new();
         for (i, p) in arr.iter().enumerate() {
-            let warehouse = if warehouses.contains_key(&p.warehouse_id) {
-                warehouses.get(&p.warehouse_id).unwrap()
-            } else {
-                let v = sqlx::query("SELECT name FROM warehouses WHERE id = ? LIMIT 1")
-                    .bind(p.warehouse_id)
-                    .fetch_one(&mut *tx)
-                    .await?
-                    .get("name");
-                warehouses.insert(p.warehouse_id, v);
-                warehouses.get(&p.warehouse_id).unwrap()
+            let warehouse: &String = match warehouses.entry(p.warehouse_id) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(sqlx::query("SELECT name FROM warehouses WHERE id = ? LIMIT 1").bind(p.warehouse_id).fetch_one(&mut *tx).await?.get("name")),
             };
-
-            let sku_category = if sku_categories.contains_key(&p.sku_category_id) {
-                sku_categories.get(&p.sku_category_id).unwrap()
-            } else {
-                let v = sqlx::query("SELECT name FROM sku_categories WHERE id = ? LIMIT 1")
-                    .bind(p.sku_category_id)
-                    .fetch_one(&mut *tx)
-                    .await?
-                    .get("name");
-                sku_categories.insert(p.sku_category_id, v);
-                sku_categories.get(&p.sku_category_id).unwrap()
+            let sku_category: &String = match sku_categories.entry(p.sku_category_id) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(sqlx::query("SELECT name FROM sku_categories WHERE id = ? LIMIT 1").bind(p.sku_category_id).fetch_one(&mut *tx).await?.get("name")),
             };
-
-            let sku = if skus.contains_key(&p.sku_id) {
-                skus.get(&p.sku_id).unwrap()
-            } else {
-                let v = sqlx::query("SELECT name FROM sku_list WHERE id = ? LIMIT 1").bind(p.sku_id).fetch_one(&mut *tx).await?.get("name");
-                skus.insert(p.sku_id, v);
-                skus.get(&p.sku_id).unwrap()
+            let sku: &String = match skus.entry(p.sku_id) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(sqlx::query("SELECT name FROM sku_list WHERE id = ? LIMIT 1").bind(p.sku_id).fetch_one(&mut *tx).await?.get("name")),
             };
 
             let row = (i + 1) as u32;
             worksheet.write_with_format(row, 0, row, &data_format)?;
             worksheet.write_row_with_format(row, 1, [warehouse, sku_category, sku], &data_format)?;
-            worksheet.write_with_format(row, 4, p.quantity, &data_format)?;
+            worksheet.write_row_with_format(row, 4, [p.quantity, p.reserved, p.available], &data_format)?;
+            worksheet.write_row_with_format(row, 7, [p.value, p.gross_margin], &data_format)?;
+            worksheet.write_with_format(row, 9, &p.unit, &data_format)?;
+            worksheet.write_with_format(row, 10, p.unit_quantity, &data_format)?;
         }
         let excels = self.ps.get_data_path().join("excels").join("inventory");
         if !excels.is_dir() {
@@ -202,11 +447,279 @@ impl InventoryModule {
         Ok(())
     }
 
+    const SELECT_LOTS: &'static str = "SELECT
+    inventory_lots.warehouse_id,
+    inventory_lots.sku_id,
+    inventory_lots.lot_number,
+    inventory_lots.expiry_date,
+    inventory_lots.quantity
+    FROM inventory_lots
+    INNER JOIN warehouses ON inventory_lots.warehouse_id=warehouses.id
+    INNER JOIN sku_list ON inventory_lots.sku_id=sku_list.id";
+
+    fn get_lots_permission_inner(&self, action: ActionType) -> String {
+        match action {
+            ActionType::General(id) | ActionType::GeneralAllowed(id) => {
+                format!("INNER JOIN warehouse_permission ON warehouse_permission.user_id={id} AND warehouse_permission.warehouse_id=inventory_lots.warehouse_id")
+            }
+            ActionType::Admin | ActionType::System => String::new(),
+        }
+    }
+
+    pub async fn list_lots(&self, pagination: &Pagination, query: &GetInventoryLotsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<InventoryLot>> {
+        let select = Self::SELECT_LOTS;
+        let qw = query.get_where_condition(self.ps.get_timestamp_seconds() as i64);
+        let ob = query.get_order_condition();
+        let inner = self.get_lots_permission_inner(action);
+        Ok(sqlx::query_as::<_, InventoryLot>(&format!("{select} {inner} {qw} {ob} LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn get_lots_count(&self, query: &GetInventoryLotsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<i64> {
+        let select = Self::SELECT_LOTS;
+        let qw = query.get_where_condition(self.ps.get_timestamp_seconds() as i64);
+        let inner = self.get_lots_permission_inner(action);
+        let row = sqlx::query(&format!("SELECT count(*) as count FROM ({select} {inner} {qw}) AS tbl")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
     pub async fn get_count(&self, query: &GetInventoryQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<i64> {
-        let s = Self::SELECT;
+        let s = self.select();
         let qw = query.get_where_condition();
         let inner = self.get_permission_inner(action);
         let row = sqlx::query(&format!("SELECT count(*) as count FROM ({s} {inner} {qw}) AS tbl")).fetch_one(&mut *tx).await?;
         Ok(row.get("count"))
     }
+
+    pub async fn list_reorder_points(&self, query: &GetReorderPointsQuery, tx: &mut SqliteConnection) -> Result<Vec<ReorderPoint>> {
+        let qw = query.get_where_condition();
+        Ok(sqlx::query_as::<_, ReorderPoint>(&format!("SELECT * FROM reorder_points {qw} ORDER BY sku_id, warehouse_id"))
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn get_reorder_point(&self, sku_id: i64, warehouse_id: i64, tx: &mut SqliteConnection) -> Result<Option<ReorderPoint>> {
+        Ok(sqlx::query_as::<_, ReorderPoint>("SELECT * FROM reorder_points WHERE sku_id=? AND warehouse_id=? LIMIT 1")
+            .bind(sku_id)
+            .bind(warehouse_id)
+            .fetch_optional(&mut *tx)
+            .await?)
+    }
+
+    pub async fn set_reorder_point(&self, v: &ReorderPoint, tx: &mut SqliteConnection) -> Result<ReorderPoint> {
+        sqlx::query(
+            "INSERT INTO reorder_points (sku_id, warehouse_id, min_quantity, reorder_quantity, max_quantity) VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(sku_id, warehouse_id) DO UPDATE SET min_quantity=excluded.min_quantity, reorder_quantity=excluded.reorder_quantity, max_quantity=excluded.max_quantity",
+        )
+        .bind(v.sku_id)
+        .bind(v.warehouse_id)
+        .bind(v.min_quantity)
+        .bind(v.reorder_quantity)
+        .bind(v.max_quantity)
+        .execute(&mut *tx)
+        .await?;
+        self.refresh_alerts(tx).await?;
+        Ok(v.clone())
+    }
+
+    pub async fn remove_reorder_point(&self, sku_id: i64, warehouse_id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM reorder_points WHERE sku_id=? AND warehouse_id=?")
+            .bind(sku_id)
+            .bind(warehouse_id)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() > 0 {
+            self.refresh_alerts(tx).await?;
+        }
+        Ok(r.rows_affected() > 0)
+    }
+
+    /// Quantity on hand subtract the reserved, the reservations of the guest order are excluded if it is not 0.
+    pub async fn get_available(&self, warehouse_id: i64, sku_id: i64, guest_order_id: i64, tx: &mut SqliteConnection) -> Result<i64> {
+        let quantity = self.get(warehouse_id, sku_id, tx).await?.map(|p| p.quantity).unwrap_or(0);
+        Ok(quantity - inventory_module::get_reserved(warehouse_id, sku_id, guest_order_id, 0, self.ps.get_timestamp_seconds() as i64, tx).await?)
+    }
+
+    /// Remove the reservations passed the expiry date, they are not read as reserved anyway.
+    pub async fn release_expired_reservations(&self, tx: &mut SqliteConnection) -> Result<()> {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        let rows = sqlx::query("SELECT id FROM inventory_reservations WHERE expires_at<=?").bind(now).fetch_all(&mut *tx).await?;
+        for row in rows {
+            self.remove_reservation(row.get("id"), true, tx).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_reservation(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Reservation>> {
+        Ok(sqlx::query_as::<_, Reservation>("SELECT * FROM inventory_reservations WHERE id=? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?)
+    }
+
+    pub async fn add_reservation(&self, mut v: Reservation, tx: &mut SqliteConnection) -> Result<Reservation> {
+        let r = sqlx::query("INSERT INTO inventory_reservations (created_by_user_id, date, warehouse_id, sku_id, quantity, guest_order_id, quotation_id, expires_at, description) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(v.created_by_user_id)
+            .bind(v.date)
+            .bind(v.warehouse_id)
+            .bind(v.sku_id)
+            .bind(v.quantity)
+            .bind(v.guest_order_id)
+            .bind(v.quotation_id)
+            .bind(v.expires_at)
+            .bind(&v.description)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't insert the reservation to inventory_reservations!");
+        }
+        v.id = sql::try_set_standard_id(r.last_insert_rowid(), "inventory_reservations", tx).await?;
+        self.ps.notice(WebSocketFlags::AddReservation(v.id)).await?;
+        Ok(v)
+    }
+
+    pub async fn remove_reservation(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM inventory_reservations WHERE id=?").bind(id).execute(&mut *tx).await?;
+        if r.rows_affected() == 1 && notice {
+            self.ps.notice(WebSocketFlags::RemoveReservation(id)).await?;
+        }
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Remove the reservations held by the guest order or the quotation, the ids of them are returned.
+    pub async fn release_reservations(&self, guest_order_id: i64, quotation_id: i64, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let rows = sqlx::query("SELECT id FROM inventory_reservations WHERE (guest_order_id<>0 AND guest_order_id=?) OR (quotation_id<>0 AND quotation_id=?)")
+            .bind(guest_order_id)
+            .bind(quotation_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut ids = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id = row.get("id");
+            self.remove_reservation(id, true, tx).await?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    fn get_reservations_permission_inner(&self, action: ActionType) -> String {
+        match action {
+            ActionType::General(id) | ActionType::GeneralAllowed(id) => {
+                format!("INNER JOIN warehouse_permission ON warehouse_permission.user_id={id} AND warehouse_permission.warehouse_id=inventory_reservations.warehouse_id")
+            }
+            ActionType::Admin | ActionType::System => String::new(),
+        }
+    }
+
+    pub async fn list_reservations(&self, pagination: &Pagination, query: &GetReservationsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<Reservation>> {
+        let reservations = self.reservations_table();
+        let inner = self.get_reservations_permission_inner(action);
+        let qw = query.get_where_condition();
+        Ok(sqlx::query_as::<_, Reservation>(&format!("SELECT inventory_reservations.* FROM {reservations} {inner} {qw} ORDER BY inventory_reservations.id LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn get_reservations_count(&self, query: &GetReservationsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<i64> {
+        let reservations = self.reservations_table();
+        let inner = self.get_reservations_permission_inner(action);
+        let qw = query.get_where_condition();
+        let row = sqlx::query(&format!("SELECT count(*) as count FROM {reservations} {inner} {qw}")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
+    /// The reorder point of the warehouse takes precedence over the one of all warehouses,
+    /// the point of all warehouses only applies to the warehouses have the sku in the inventory.
+    const SELECT_THRESHOLDS: &'static str = "SELECT
+    r.warehouse_id,
+    r.sku_id,
+    COALESCE(inventory.quantity, 0) AS quantity,
+    r.min_quantity,
+    r.reorder_quantity,
+    r.max_quantity
+    FROM reorder_points r
+    INNER JOIN warehouses ON warehouses.id=r.warehouse_id
+    INNER JOIN sku_list ON sku_list.id=r.sku_id
+    LEFT JOIN inventory ON inventory.warehouse_id=r.warehouse_id AND inventory.sku_id=r.sku_id
+    WHERE r.warehouse_id<>0 AND warehouses.deleted_at IS NULL AND sku_list.deleted_at IS NULL
+    UNION ALL
+    SELECT
+    inventory.warehouse_id,
+    r.sku_id,
+    inventory.quantity,
+    r.min_quantity,
+    r.reorder_quantity,
+    r.max_quantity
+    FROM reorder_points r
+    INNER JOIN inventory ON inventory.sku_id=r.sku_id
+    INNER JOIN warehouses ON warehouses.id=inventory.warehouse_id
+    INNER JOIN sku_list ON sku_list.id=r.sku_id
+    WHERE r.warehouse_id=0 AND warehouses.deleted_at IS NULL AND sku_list.deleted_at IS NULL
+    AND NOT EXISTS (SELECT 1 FROM reorder_points p WHERE p.sku_id=r.sku_id AND p.warehouse_id=inventory.warehouse_id)";
+
+    pub async fn list_alerts(&self, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<InventoryAlert>> {
+        let select = Self::SELECT_THRESHOLDS;
+        let inner = match action {
+            ActionType::General(id) | ActionType::GeneralAllowed(id) => {
+                format!("INNER JOIN warehouse_permission ON warehouse_permission.user_id={id} AND warehouse_permission.warehouse_id=t.warehouse_id")
+            }
+            ActionType::Admin | ActionType::System => String::new(),
+        };
+        let rows = sqlx::query(&format!(
+            "SELECT t.* FROM ({select}) AS t {inner}
+            WHERE t.quantity<t.min_quantity OR (t.max_quantity IS NOT NULL AND t.quantity>t.max_quantity)
+            ORDER BY t.warehouse_id, t.sku_id"
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut arr = Vec::with_capacity(rows.len());
+        for row in rows {
+            let quantity: i64 = row.get("quantity");
+            let min_quantity: i64 = row.get("min_quantity");
+            arr.push(InventoryAlert {
+                warehouse_id: row.get("warehouse_id"),
+                sku_id: row.get("sku_id"),
+                quantity,
+                alert_type: if quantity < min_quantity {
+                    InventoryAlertType::LowStock
+                } else {
+                    InventoryAlertType::OverStock
+                },
+                min_quantity,
+                reorder_quantity: row.get("reorder_quantity"),
+                max_quantity: row.get("max_quantity"),
+            });
+        }
+        Ok(arr)
+    }
+
+    async fn evaluate_alerts(&self, tx: &mut SqliteConnection) -> Result<HashMap<(i64, i64), InventoryAlertType>> {
+        let alerts = self.list_alerts(ActionType::System, tx).await?;
+        Ok(alerts.into_iter().map(|v| ((v.warehouse_id, v.sku_id), v.alert_type)).collect())
+    }
+
+    /// Evaluate the reorder points, notice the skus entered, changed or left the alerts.
+    pub async fn refresh_alerts(&self, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let current = self.evaluate_alerts(tx).await?;
+        let mut alerts = self.alerts.write().await;
+        let mut sku_ids: Vec<i64> = current
+            .iter()
+            .filter(|(key, alert_type)| alerts.get(key) != Some(alert_type))
+            .chain(alerts.iter().filter(|(key, _)| !current.contains_key(key)))
+            .map(|((_, sku_id), _)| *sku_id)
+            .collect();
+        sku_ids.sort_unstable();
+        sku_ids.dedup();
+        *alerts = current;
+        drop(alerts);
+        for sku_id in sku_ids.iter() {
+            self.ps.notice(WebSocketFlags::InventoryAlert(*sku_id)).await?;
+        }
+        Ok(sku_ids)
+    }
 }
@@ -2,7 +2,7 @@ use anyhow::bail;
 use elerp_common::{
     model::{Pagination, WebSocketFlags},
     order_category_module::model::order_category::{GetOrderCategoryQuery, OrderCategory},
-    sql::{self, get_row_from_table, is_exists_in_table, remove_row_from_table, row_is_duplicate_col_in_table, rows_to_objects},
+    sql::{self, get_active_row_from_table, is_active_in_table, purge_rows_from_table, restore_row_from_table, row_is_duplicate_col_in_table, rows_to_objects, soft_remove_row_from_table},
 };
 use futures::TryStreamExt;
 use sqlx::{Row, SqliteConnection};
@@ -25,7 +25,8 @@ impl OrderCategoryModule {
                 name TEXT NOT NULL,
                 description TEXT NOT NULL,
                 color TEXT NULL,
-                text_color TEXT NULL
+                text_color TEXT NULL,
+                deleted_at INT NULL
             )",
         )
         .execute(tx.as_mut())
@@ -44,11 +45,11 @@ impl OrderCategoryModule {
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("order_categories", "id", id, tx).await
+        is_active_in_table("order_categories", id, tx).await
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM order_categories;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM order_categories WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.order_categories)
     }
 
@@ -73,15 +74,28 @@ impl OrderCategoryModule {
     }
 
     pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = remove_row_from_table(id, "order_categories", tx).await?;
+        let r = soft_remove_row_from_table(id, "order_categories", self.ps.get_timestamp_seconds() as i64, tx).await?;
         if notice {
             self.ps.notice(WebSocketFlags::RemoveOrderCategory(id)).await?;
         }
         Ok(r)
     }
 
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<OrderCategory>> {
+        if !restore_row_from_table(id, "order_categories", tx).await? {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::RestoreOrderCategory(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Physically delete the order categories removed before the date, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        purge_rows_from_table("order_categories", deleted_before, tx).await
+    }
+
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<OrderCategory>> {
-        get_row_from_table("order_categories", "id", id, tx).await
+        get_active_row_from_table("order_categories", id, tx).await
     }
 
     pub async fn get_multiple(&self, pagination: &Pagination, query: &GetOrderCategoryQuery, tx: &mut SqliteConnection) -> Result<Vec<OrderCategory>> {
@@ -126,7 +140,7 @@ impl OrderCategoryModule {
     }
 
     pub async fn update(&self, id: i64, mut v: OrderCategory, tx: &mut SqliteConnection) -> Result<Option<OrderCategory>> {
-        let r = sqlx::query("UPDATE order_categories SET name=?, description=?, color=?, text_color=? WHERE id=?")
+        let r = sqlx::query("UPDATE order_categories SET name=?, description=?, color=?, text_color=? WHERE id=? AND deleted_at IS NULL")
             .bind(&v.name)
             .bind(&v.description)
             .bind(&v.color)
@@ -144,6 +158,8 @@ impl OrderCategoryModule {
     }
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT id FROM orders WHERE order_status_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+        Ok(sqlx::query("SELECT id FROM orders WHERE order_category_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM purchase_orders WHERE order_category_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM quotations WHERE order_category_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
     }
 }
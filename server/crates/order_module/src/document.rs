@@ -0,0 +1,324 @@
+use anyhow::Result;
+use elerp_common::{
+    config::{Company, DocumentTemplate},
+    order_module::model::{
+        document::DocumentKind,
+        order::{Order, OrderItem, OrderType},
+    },
+};
+use printpdf::{BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+const FONT_SIZE: f32 = 9.0;
+const LINE_HEIGHT: f32 = 5.0;
+
+/// Address block of the warehouse or the person.
+pub struct DocumentParty {
+    pub label: &'static str,
+    pub lines: Vec<String>,
+}
+
+pub struct DocumentItem {
+    pub sku_name: String,
+    pub item: OrderItem,
+}
+
+/// Everything printed on the document, it is queried before rendering because the pdf is not `Send`.
+pub struct DocumentData<'a> {
+    pub kind: DocumentKind,
+    pub template: &'a DocumentTemplate,
+    pub company: &'a Company,
+    pub order: &'a Order,
+    pub from: DocumentParty,
+    pub to: DocumentParty,
+    pub items: Vec<DocumentItem>,
+    /// The user can view the prices.
+    pub show_price: bool,
+    /// Files of the configured fonts, the builtin fonts are used if none.
+    pub font: Option<&'a [u8]>,
+    pub bold_font: Option<&'a [u8]>,
+}
+
+struct PdfWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+}
+
+impl PdfWriter {
+    fn new(title: &str, font: Option<&[u8]>, bold_font: Option<&[u8]>) -> Result<Self> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        let layer = doc.get_page(page).get_layer(layer);
+        let (font, bold) = match font {
+            Some(font) => {
+                let regular = doc.add_external_font(font)?;
+                let bold = match bold_font {
+                    Some(bold) => doc.add_external_font(bold)?,
+                    None => regular.clone(),
+                };
+                (regular, bold)
+            }
+            None => (doc.add_builtin_font(BuiltinFont::Helvetica)?, doc.add_builtin_font(BuiltinFont::HelveticaBold)?),
+        };
+        Ok(Self {
+            doc,
+            layer,
+            font,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    fn text(&self, text: &str, size: f32, x: f32, bold: bool) {
+        let font = if bold { &self.bold } else { &self.font };
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), font);
+    }
+
+    /// Move down, continue on the new page if the space is not enough.
+    fn next_line(&mut self, height: f32) {
+        self.y -= height;
+        if self.y < MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN - height;
+        }
+    }
+
+    fn rule(&self) {
+        let y = self.y - 1.5;
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_line(Line {
+            points: vec![(Point::new(Mm(MARGIN), Mm(y)), false), (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false)],
+            is_closed: false,
+        });
+    }
+
+    fn save(self) -> Result<Vec<u8>> {
+        Ok(self.doc.save_to_bytes()?)
+    }
+}
+
+/// Width of the char in the average widths, the chinese, japanese and korean chars are about twice as wide.
+fn char_width(c: char) -> usize {
+    if c.len_utf8() >= 3 {
+        2
+    } else {
+        1
+    }
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Cut the text to the width, the fonts are not measured so the average width is used.
+fn fit(text: &str, width: f32) -> String {
+    let max = (width / (FONT_SIZE * 0.18)) as usize;
+    if text_width(text) > max {
+        let mut s = String::new();
+        let mut used = 0;
+        for c in text.chars() {
+            used += char_width(c);
+            if used > max.saturating_sub(2) {
+                break;
+            }
+            s.push(c);
+        }
+        s.push_str("..");
+        s
+    } else {
+        text.to_owned()
+    }
+}
+
+fn wrap(text: &str, width: f32) -> Vec<String> {
+    let max = ((width / (FONT_SIZE * 0.18)) as usize).max(1);
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split(' ') {
+        if !line.is_empty() && text_width(&line) + text_width(word) + 1 > max {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0).map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()
+}
+
+fn format_amount(v: f64) -> String {
+    format!("{v:.2}")
+}
+
+/// Quantity in the unit the item is entered in, with the name of the unit.
+fn format_quantity(item: &OrderItem) -> String {
+    let quantity = if item.quantity % item.unit_factor.max(1) == 0 { item.get_unit_quantity().to_string() } else { format!("{:.2}", item.get_unit_quantity()) };
+    match item.unit.as_deref() {
+        Some(unit) => format!("{quantity} {unit}"),
+        None => quantity,
+    }
+}
+
+pub fn render(data: &DocumentData) -> Result<Vec<u8>> {
+    let template = data.template;
+    let order = data.order;
+    let show_prices = data.show_price && template.show_prices.unwrap_or(data.kind == DocumentKind::Invoice);
+    let show_payment_status = show_prices && template.show_payment_status.unwrap_or(data.kind == DocumentKind::Invoice);
+    let show_lots = template.show_lots.unwrap_or(data.kind == DocumentKind::PackingSlip);
+    let title = template.title.as_deref().unwrap_or(data.kind.default_title());
+    let mut w = PdfWriter::new(&format!("{title} #{}", order.id), data.font, data.bold_font)?;
+
+    // Company on the left, document on the right.
+    let company = data.company;
+    let mut company_lines = vec![company.address.clone(), company.phone.clone(), company.email.clone()];
+    if !company.tax_id.is_empty() {
+        company_lines.push(format!("Tax ID: {}", company.tax_id));
+    }
+    let mut document_lines = vec![format!("No: {}", order.id), format!("Date: {}", format_date(order.date))];
+    if show_prices {
+        document_lines.push(format!("Currency: {}", order.currency));
+    }
+    w.text(&company.name, 16.0, MARGIN, true);
+    w.text(title, 16.0, 130.0, true);
+    w.next_line(8.0);
+    let company_lines: Vec<String> = company_lines.into_iter().filter(|v| !v.is_empty()).collect();
+    for i in 0..company_lines.len().max(document_lines.len()) {
+        if let Some(v) = company_lines.get(i) {
+            w.text(&fit(v, 110.0), FONT_SIZE, MARGIN, false);
+        }
+        if let Some(v) = document_lines.get(i) {
+            w.text(v, FONT_SIZE, 130.0, false);
+        }
+        w.next_line(LINE_HEIGHT);
+    }
+    w.next_line(LINE_HEIGHT);
+
+    // Addresses of the warehouse and the person.
+    w.text(data.from.label, FONT_SIZE, MARGIN, true);
+    w.text(data.to.label, FONT_SIZE, 110.0, true);
+    w.next_line(LINE_HEIGHT);
+    for i in 0..data.from.lines.len().max(data.to.lines.len()) {
+        if let Some(v) = data.from.lines.get(i) {
+            w.text(&fit(v, 90.0), FONT_SIZE, MARGIN, false);
+        }
+        if let Some(v) = data.to.lines.get(i) {
+            w.text(&fit(v, 85.0), FONT_SIZE, 110.0, false);
+        }
+        w.next_line(LINE_HEIGHT);
+    }
+    if !order.description.is_empty() {
+        w.next_line(LINE_HEIGHT);
+        for line in wrap(&order.description, PAGE_WIDTH - MARGIN * 2.0) {
+            w.text(&line, FONT_SIZE, MARGIN, false);
+            w.next_line(LINE_HEIGHT);
+        }
+    }
+    w.next_line(LINE_HEIGHT);
+
+    // Items table, the sku column takes the width left.
+    let mut columns = vec![("No", 8.0)];
+    let mut fixed = 8.0 + 22.0;
+    if show_lots {
+        fixed += 35.0;
+    }
+    if show_prices {
+        fixed += 20.0 + 18.0 + 18.0 + 22.0;
+    }
+    let sku_width = PAGE_WIDTH - MARGIN * 2.0 - fixed;
+    columns.push(("SKU", sku_width));
+    if show_lots {
+        columns.push(("Lot", 35.0));
+    }
+    columns.push(("Qty", 22.0));
+    if show_prices {
+        columns.extend([("Price", 20.0), ("Discount", 18.0), ("Tax", 18.0), ("Amount", 22.0)]);
+    }
+    let write_row = |w: &PdfWriter, cells: &[String], bold: bool| {
+        let mut x = MARGIN;
+        for ((_, width), cell) in columns.iter().zip(cells) {
+            w.text(&fit(cell, *width), FONT_SIZE, x, bold);
+            x += width;
+        }
+    };
+    write_row(&w, &columns.iter().map(|(name, _)| name.to_string()).collect::<Vec<_>>(), true);
+    w.rule();
+    w.next_line(LINE_HEIGHT + 1.0);
+    for (i, v) in data.items.iter().enumerate() {
+        let item = &v.item;
+        let mut cells = vec![(i + 1).to_string(), v.sku_name.clone()];
+        if show_lots {
+            let lot = match (&item.lot_number, item.expiry_date) {
+                (Some(lot), Some(expiry)) => format!("{lot} ({})", format_date(expiry)),
+                (Some(lot), None) => lot.clone(),
+                _ => String::new(),
+            };
+            cells.push(lot);
+        }
+        cells.push(format_quantity(item));
+        if show_prices {
+            cells.push(format_amount(item.price));
+            cells.push(format_amount(item.discount_amount));
+            cells.push(format_amount(item.tax_amount));
+            cells.push(format_amount(item.get_amount()));
+        }
+        write_row(&w, &cells, false);
+        w.next_line(LINE_HEIGHT);
+        if show_lots && !item.serial_numbers.is_empty() {
+            for line in wrap(&format!("S/N: {}", item.serial_numbers.join(", ")), sku_width + 35.0) {
+                w.text(&line, FONT_SIZE, MARGIN + 8.0, false);
+                w.next_line(LINE_HEIGHT);
+            }
+        }
+    }
+    w.rule();
+    w.next_line(LINE_HEIGHT + 1.0);
+
+    if show_prices {
+        let mut totals = vec![];
+        if order.discount_amount > 0.0 {
+            totals.push(("Discount", format_amount(order.discount_amount)));
+        }
+        totals.push(("Net Amount", format_amount(order.net_amount)));
+        totals.push(("Tax", format_amount(order.tax_amount)));
+        totals.push(("Total", format!("{} {}", format_amount(order.total_amount), order.currency)));
+        if show_payment_status {
+            totals.push(("Settled", format_amount(order.total_amount_settled)));
+            totals.push(("Payment Status", order.order_payment_status.to_string()));
+        }
+        for (name, value) in totals {
+            w.text(name, FONT_SIZE, 130.0, true);
+            w.text(&value, FONT_SIZE, 160.0, false);
+            w.next_line(LINE_HEIGHT);
+        }
+        w.next_line(LINE_HEIGHT);
+    }
+
+    if !template.footer.is_empty() {
+        for line in template.footer.lines().flat_map(|v| wrap(v, PAGE_WIDTH - MARGIN * 2.0)) {
+            w.text(&line, FONT_SIZE, MARGIN, false);
+            w.next_line(LINE_HEIGHT);
+        }
+    }
+    w.save()
+}
+
+/// The parties printed on the document, the transfer is delivered to its destination warehouse.
+pub fn to_label(order_type: OrderType) -> &'static str {
+    match order_type {
+        OrderType::StockIn => "Supplier",
+        OrderType::Transfer => "Destination",
+        _ => "Customer",
+    }
+}
@@ -1,24 +1,42 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 use anyhow::Result;
+use elerp_common::currency_module::get_rate_sql;
+use elerp_common::export::{ExportValue, ExportWriter, NameCache, TempPath, EXPORT_PAGE_SIZE};
+use elerp_common::import::{IdCache, ImportRow, ImportRowResult};
 use elerp_common::inventory_module;
+use elerp_common::inventory_module::Lots;
 use elerp_common::model::action_type::ActionType;
+use elerp_common::model::export::ExportQuery;
 use elerp_common::model::Pagination;
 use elerp_common::model::WebSocketFlags;
 use elerp_common::order_module;
+use elerp_common::order_module::model::document::DocumentKind;
+use elerp_common::order_module::model::order::Discount;
 use elerp_common::order_module::model::order::GetOrdersQuery;
 use elerp_common::order_module::model::order::Order;
 use elerp_common::order_module::model::order::OrderCurrency;
 use elerp_common::order_module::model::order::OrderItem;
 use elerp_common::order_module::model::order::OrderPaymentStatus;
 use elerp_common::order_module::model::order::OrderType;
+use elerp_common::order_module::model::order_revision::OrderRevision;
+use elerp_common::order_module::model::serial_number::{GetSerialNumberQuery, SerialNumberHistory};
 use elerp_common::set_to_string;
-use elerp_common::sql::{is_exists_in_table, remove_row_from_table, rows_to_objects};
+use elerp_common::tax_module;
+use elerp_common::sql::{is_active_in_table, purge_rows_from_table, restore_row_from_table, rows_to_objects, soft_remove_row_from_table};
 use elerp_common::user_system::model::user_info::UserInfo;
 use elerp_common::user_system::model::user_info::UserType;
 use futures::TryStreamExt;
-use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
+use sqlx::{sqlite::SqliteRow, types::Json, FromRow, Row, SqliteConnection};
 
 use public_system::PublicSystem;
+use tokio::{fs, io::AsyncWriteExt};
+
+use self::document::{DocumentData, DocumentItem, DocumentParty};
+
+mod document;
 
 #[derive(Debug, Clone)]
 pub struct OrderModule {
@@ -48,7 +66,15 @@ impl OrderModule {
                 order_type TEXT NOT NULL,
                 is_record BOOLEAN NOT NULL,
                 non_payment BOOLEAN NOT NULL,
-                order_category_id INT NOT NULL
+                order_category_id INT NOT NULL,
+                deleted_at INT NULL,
+                to_warehouse_id INT NOT NULL DEFAULT 0,
+                in_transit BOOLEAN NOT NULL DEFAULT False,
+                tax_inclusive BOOLEAN NOT NULL DEFAULT False,
+                net_amount REAL NOT NULL DEFAULT 0,
+                tax_amount REAL NOT NULL DEFAULT 0,
+                discount TEXT NOT NULL DEFAULT 'null',
+                discount_amount REAL NOT NULL DEFAULT 0
             )",
         )
         .execute(tx.as_mut())
@@ -81,7 +107,16 @@ impl OrderModule {
                 quantity INT NOT NULL,
                 price REAL NOT NULL,
                 amount REAL NOT NULL,
-                exchanged BOOLEAN NOT NULL
+                exchanged BOOLEAN NOT NULL,
+                lot_number TEXT NULL,
+                expiry_date INT NULL,
+                serial_numbers TEXT NOT NULL DEFAULT '[]',
+                tax_rate REAL NOT NULL DEFAULT 0,
+                tax_amount REAL NOT NULL DEFAULT 0,
+                discount TEXT NOT NULL DEFAULT 'null',
+                discount_amount REAL NOT NULL DEFAULT 0,
+                unit TEXT NULL,
+                unit_factor INT NOT NULL DEFAULT 1
             )",
         )
         .execute(tx.as_mut())
@@ -101,6 +136,58 @@ impl OrderModule {
         .await
         .unwrap();
 
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS order_revisions(
+                id INTEGER PRIMARY KEY,
+                order_id INT NOT NULL,
+                revision INT NOT NULL,
+                user_id INT NOT NULL,
+                revision_date INT NOT NULL,
+                date INT NOT NULL,
+                warehouse_id INT NOT NULL,
+                person_related_id INT NOT NULL,
+                person_in_charge_id INT NOT NULL,
+                order_category_id INT NOT NULL,
+                currency TEXT NOT NULL,
+                order_type TEXT NOT NULL,
+                description TEXT NOT NULL,
+                total_amount REAL NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS order_revision_items(
+                revision_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                quantity INT NOT NULL,
+                price REAL NOT NULL,
+                exchanged BOOLEAN NOT NULL,
+                lot_number TEXT NULL,
+                expiry_date INT NULL,
+                serial_numbers TEXT NOT NULL DEFAULT '[]',
+                tax_rate REAL NOT NULL DEFAULT 0,
+                tax_amount REAL NOT NULL DEFAULT 0,
+                discount TEXT NOT NULL DEFAULT 'null',
+                discount_amount REAL NOT NULL DEFAULT 0,
+                unit TEXT NULL,
+                unit_factor INT NOT NULL DEFAULT 1
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS order_revisions_order_ids
+        ON order_revisions(order_id);
+        CREATE INDEX IF NOT EXISTS order_revision_items_revision_ids
+        ON order_revision_items(revision_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
         let s = Self { ps: ps.clone() };
 
         tx.commit().await.unwrap();
@@ -117,7 +204,7 @@ impl OrderModule {
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("orders", "id", id, tx).await
+        is_active_in_table("orders", id, tx).await
     }
 
     pub async fn is_from_guest_order(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
@@ -130,21 +217,10 @@ impl OrderModule {
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM orders;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM orders WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.orders)
     }
 
-    fn calc_total_amount(&self, items: &Vec<OrderItem>) -> f64 {
-        let mut total: f64 = 0.0;
-        for item in items.iter() {
-            if item.exchanged {
-                continue;
-            }
-            total += (item.quantity as f64) * item.price;
-        }
-        total
-    }
-
     pub fn preprocess(&self, order: &mut Order, user: &UserInfo, initial: bool, person_in_charge_id: i64) {
         if let Some(items) = order.items.clone() {
             order.items = Some(
@@ -152,7 +228,7 @@ impl OrderModule {
                     .into_iter()
                     .filter(|item| {
                         let pass_quantity = match order.order_type {
-                            OrderType::StockIn | OrderType::StockOut | OrderType::Return => item.quantity > 0,
+                            OrderType::StockIn | OrderType::StockOut | OrderType::Return | OrderType::Transfer => item.quantity > 0,
                             _ => true,
                         };
                         let pass_exchange = if order.order_type != OrderType::Exchange { !item.exchanged } else { true };
@@ -163,7 +239,12 @@ impl OrderModule {
                     .collect(),
             );
         };
-        order.total_amount = if let Some(items) = order.items.as_ref() { self.calc_total_amount(items) } else { 0.0 };
+        order.calc_discounts();
+        // The items without the tax rate are calculated again by the defaults when the order is added.
+        (order.net_amount, order.tax_amount, order.total_amount) = match order.items.as_mut() {
+            Some(items) => tax_module::calc_amounts(items, order.tax_inclusive),
+            None => (0.0, 0.0, 0.0),
+        };
 
         let now = self.ps.get_timestamp_seconds() as i64;
         order.updated_by_user_id = user.id;
@@ -175,6 +256,10 @@ impl OrderModule {
         order.person_in_charge_id = person_in_charge_id;
         order.from_guest_order_id = 0;
         order.total_amount_settled = 0.0;
+        if order.order_type != OrderType::Transfer {
+            order.to_warehouse_id = 0;
+            order.in_transit = false;
+        }
 
         order.order_payment_status = if !order.non_payment && order.total_amount > 0.0 {
             OrderPaymentStatus::Unsettled
@@ -194,14 +279,17 @@ impl OrderModule {
                 .is_some())
     }
 
-    pub async fn add(&self, order: Order, tx: &mut SqliteConnection) -> Result<Order> {
-        let order = order_module::add(order, tx).await?;
-        self.ps.notice(WebSocketFlags::AddOrder(order.id)).await?;
+    pub async fn add(&self, order: Order, notice: bool, tx: &mut SqliteConnection) -> Result<Order> {
+        let config = self.ps.get_config();
+        let order = order_module::add(order, config.valuation.method, config.currency.base, self.ps.get_timestamp_seconds() as i64, tx).await?;
+        if notice {
+            self.ps.notice(WebSocketFlags::AddOrder(order.id)).await?;
+        }
         Ok(order)
     }
 
     async fn exists_order_type(&self, ot: OrderType, order_start: &Order, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT id FROM orders WHERE order_type=? AND date>=? AND id<>? LIMIT 1")
+        Ok(sqlx::query("SELECT id FROM orders WHERE order_type=? AND date>=? AND id<>? AND deleted_at IS NULL LIMIT 1")
             .bind(ot)
             .bind(order_start.date)
             .bind(order_start.id)
@@ -211,11 +299,25 @@ impl OrderModule {
             .is_some())
     }
 
-    pub async fn recall(&self, order: Order, action: ActionType, tx: &mut SqliteConnection) -> Result<bool> {
+    /// Whether there is any calibration in the warehouse at or after the order, the quantities are overridden by it.
+    async fn exists_calibration_in_warehouse(&self, warehouse_id: i64, order_start: &Order, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM orders WHERE order_type IN (?, ?) AND warehouse_id=? AND date>=? AND id<>? AND deleted_at IS NULL LIMIT 1")
+            .bind(OrderType::Calibration)
+            .bind(OrderType::CalibrationStrict)
+            .bind(warehouse_id)
+            .bind(order_start.date)
+            .bind(order_start.id)
+            .fetch(&mut *tx)
+            .try_next()
+            .await?
+            .is_some())
+    }
+
+    pub async fn recall(&self, order: &Order, action: ActionType, tx: &mut SqliteConnection) -> Result<bool> {
         if order.is_record {
             return Ok(true);
         }
-        let exists_any_calibration = self.exists_order_type(OrderType::Calibration, &order, tx).await? || self.exists_order_type(OrderType::CalibrationStrict, &order, tx).await?;
+        let exists_any_calibration = self.exists_order_type(OrderType::Calibration, order, tx).await? || self.exists_order_type(OrderType::CalibrationStrict, order, tx).await?;
         if !exists_any_calibration {
             let warehouse_id = order.warehouse_id;
             let mut items = self.get_order_items(order.id, &Pagination::max(), tx).await?;
@@ -226,6 +328,12 @@ impl OrderModule {
                 OrderType::StockOut => {
                     inventory_module::change(warehouse_id, &items, OrderType::StockIn, tx).await?;
                 }
+                OrderType::Transfer => {
+                    inventory_module::change(warehouse_id, &items, OrderType::StockIn, tx).await?;
+                    if !order.in_transit {
+                        inventory_module::change(order.to_warehouse_id, &items, OrderType::StockOut, tx).await?;
+                    }
+                }
                 OrderType::Exchange => {
                     for item in items.iter_mut() {
                         item.exchanged = !item.exchanged;
@@ -238,10 +346,10 @@ impl OrderModule {
                         skus.insert(item.sku_id);
                     }
 
-                    self.recalc_all(Some(HashSet::from_iter([order.warehouse_id])), Some(skus), Some(&order), action, tx).await?;
+                    self.recalc_all(Some(HashSet::from_iter([order.warehouse_id])), Some(skus), Some(order), action, tx).await?;
                 }
                 OrderType::CalibrationStrict => {
-                    self.recalc_all(Some(HashSet::from_iter([order.warehouse_id])), None, Some(&order), action, tx).await?;
+                    self.recalc_all(Some(HashSet::from_iter([order.warehouse_id])), None, Some(order), action, tx).await?;
                 }
                 OrderType::Verification | OrderType::VerificationStrict => (),
             }
@@ -255,44 +363,51 @@ impl OrderModule {
                 let skus = set_to_string(&skus, ",");
                 match &warehouse_ids {
                     Some(ids) => {
-                        let ids = set_to_string(&ids, ",");
+                        let ids = set_to_string(ids, ",");
                         sqlx::query(&format!("UPDATE inventory SET quantity=0 WHERE warehouse_id IN ({ids}) AND sku_id IN ({skus})"))
                             .execute(&mut *tx)
                             .await?;
+                        sqlx::query(&format!("DELETE FROM inventory_lots WHERE warehouse_id IN ({ids}) AND sku_id IN ({skus})"))
+                            .execute(&mut *tx)
+                            .await?;
                     }
                     None => {
                         sqlx::query("UPDATE inventory SET quantity=0").execute(&mut *tx).await?;
+                        sqlx::query("DELETE FROM inventory_lots").execute(&mut *tx).await?;
                     }
                 }
             }
             None => match &warehouse_ids {
                 Some(ids) => {
-                    let ids = set_to_string(&ids, ",");
+                    let ids = set_to_string(ids, ",");
                     sqlx::query(&format!("UPDATE inventory SET quantity=0 WHERE warehouse_id IN ({ids})")).execute(&mut *tx).await?;
+                    sqlx::query(&format!("DELETE FROM inventory_lots WHERE warehouse_id IN ({ids})")).execute(&mut *tx).await?;
                 }
                 None => {
                     sqlx::query("UPDATE inventory SET quantity=0").execute(&mut *tx).await?;
+                    sqlx::query("DELETE FROM inventory_lots").execute(&mut *tx).await?;
                 }
             },
         }
 
-        let warehouse_count = if warehouse_ids.is_none() {
-            sqlx::query("SELECT COUNT(1) AS count FROM warehouses")
+        let warehouse_count = match warehouse_ids.as_ref() {
+            Some(ids) => ids.len() as i64,
+            None => sqlx::query("SELECT COUNT(1) AS count FROM warehouses")
                 .fetch_optional(&mut *tx)
                 .await?
                 .map(|row| row.get("count"))
-                .unwrap_or(0)
-        } else {
-            warehouse_ids.as_ref().unwrap().len() as i64
+                .unwrap_or(0),
         };
 
         let mut q = GetOrdersQuery::empty();
         q.sorters = Some(vec!["date".to_owned()]);
-        q.warehouse_ids = warehouse_ids;
+        q.warehouse_ids = warehouse_ids.clone();
+        let in_scope = |warehouse_id: i64| warehouse_ids.as_ref().map(|ids| ids.contains(&warehouse_id)).unwrap_or(true);
 
         let order_total = self.get_count(&q, action, tx).await?;
 
         let mut temp = HashMap::<i64, HashMap<i64, i64>>::with_capacity(warehouse_count as _);
+        let mut lots_temp = HashMap::<(i64, i64), Lots>::with_capacity(warehouse_count as _);
         let mut p = Pagination::new(-1, 100); // start from -1 because p.next() will return the next offset.
         while p.offset() < order_total {
             let mut orders = self.get_multiple(p.next(), &q, ActionType::System, tx).await?;
@@ -306,11 +421,33 @@ impl OrderModule {
                     }
                 }
                 let items = self.get_order_items(order.id, &Pagination::max(), tx).await?;
+                if order.order_type == OrderType::CalibrationStrict {
+                    lots_temp.retain(|(warehouse_id, _), _| *warehouse_id != order.warehouse_id);
+                }
+                // Only the received side of the transfer order is in the destination warehouse.
+                if order.order_type == OrderType::Transfer && !order.in_transit && in_scope(order.to_warehouse_id) {
+                    for item in &items {
+                        let it = temp.entry(order.to_warehouse_id).or_insert(HashMap::with_capacity(items.len()));
+                        let qty = it.entry(item.sku_id).or_insert(0);
+                        *qty = inventory_module::calc_quantity_by_order_type(*qty, item, OrderType::StockIn);
+
+                        let lots = lots_temp.entry((order.to_warehouse_id, item.sku_id)).or_default();
+                        inventory_module::calc_lots_by_order_type(lots, item, OrderType::StockIn, false);
+                    }
+                }
+                if !in_scope(order.warehouse_id) {
+                    continue;
+                }
+                let mut calibrated = HashSet::with_capacity(items.len());
                 for item in &items {
                     let it = temp.entry(order.warehouse_id).or_insert(HashMap::with_capacity(items.len()));
                     let qty = it.entry(item.sku_id).or_insert(0);
 
                     *qty = inventory_module::calc_quantity_by_order_type(*qty, item, order.order_type);
+
+                    let lots = lots_temp.entry((order.warehouse_id, item.sku_id)).or_default();
+                    let reset = !item.exchanged && calibrated.insert(item.sku_id);
+                    inventory_module::calc_lots_by_order_type(lots, item, order.order_type, reset);
                 }
             }
         }
@@ -322,29 +459,138 @@ impl OrderModule {
                     quantity,
                     price: 0.0,
                     exchanged: false,
+                    lot_number: None,
+                    expiry_date: None,
+                    serial_numbers: vec![],
+                    tax_rate: None,
+                    tax_amount: 0.0,
+                    discount: None,
+                    discount_amount: 0.0,
+                    sku_code: None,
+                    unit: None,
+                    unit_factor: 1,
                 })
                 .collect();
             inventory_module::change(warehouse_id, &calibration_items, OrderType::Calibration, &mut *tx).await?;
         }
+        for ((warehouse_id, sku_id), lots) in lots_temp.iter() {
+            inventory_module::set_lots(*warehouse_id, *sku_id, lots, tx).await?;
+        }
+        // The recalculation of the warehouses is for the calibration changed, the caller revalues the order.
+        if warehouse_ids.is_none() {
+            let config = self.ps.get_config();
+            inventory_module::valuation::rebuild(config.valuation.method, config.currency.base, tx).await?;
+        }
         self.ps.notice(WebSocketFlags::RecalcOrders).await?;
         Ok(())
     }
 
     pub async fn remove(&self, id: i64, recall: bool, notice: bool, action: ActionType, tx: &mut SqliteConnection) -> Result<bool> {
         if let Some(order) = self.get(id, tx).await? {
-            let recalled = if recall { self.recall(order, action, tx).await? } else { true };
+            let revalue = recall && !order.is_record;
+            let items = if revalue { self.get_order_items(id, &Pagination::max(), tx).await? } else { vec![] };
+            let recalled = if recall { self.recall(&order, action, tx).await? } else { true };
             if recalled {
-                remove_row_from_table(id, "orders", tx).await?;
-                let r = sqlx::query("DELETE FROM order_items WHERE order_id=?").bind(id).execute(&mut *tx).await?;
+                let r = soft_remove_row_from_table(id, "orders", self.ps.get_timestamp_seconds() as i64, tx).await?;
+                if revalue {
+                    self.revalue(&order, &items, order.date, tx).await?;
+                }
                 if notice {
                     self.ps.notice(WebSocketFlags::RemoveOrder(id)).await?;
                 }
-                return Ok(r.rows_affected() > 0);
+                return Ok(r);
             }
         }
         Ok(false)
     }
 
+    /// Rebuild the cost of the inventory changed by the order since the date, needed when the history of orders changed.
+    async fn revalue(&self, order: &Order, items: &[OrderItem], date: i64, tx: &mut SqliteConnection) -> Result<()> {
+        let config = self.ps.get_config();
+        inventory_module::valuation::rebuild_order(order, items, date, config.valuation.method, config.currency.base, tx).await
+    }
+
+    /// Restore the removed order and put its effect back to the inventory.
+    pub async fn restore(&self, id: i64, action: ActionType, tx: &mut SqliteConnection) -> Result<Option<Order>> {
+        if !restore_row_from_table(id, "orders", tx).await? {
+            return Ok(None);
+        }
+        let mut order = self.get(id, tx).await?.unwrap();
+        let items = self.get_order_items(id, &Pagination::max(), tx).await?;
+        if !order.is_record {
+            let exists_any_calibration = self.exists_order_type(OrderType::Calibration, &order, tx).await? || self.exists_order_type(OrderType::CalibrationStrict, &order, tx).await?;
+            if !exists_any_calibration {
+                match &order.order_type {
+                    OrderType::StockIn | OrderType::StockOut | OrderType::Return | OrderType::Exchange => {
+                        inventory_module::change(order.warehouse_id, &items, order.order_type, tx).await?;
+                    }
+                    OrderType::Transfer => {
+                        inventory_module::change(order.warehouse_id, &items, order.order_type, tx).await?;
+                        if !order.in_transit {
+                            inventory_module::change(order.to_warehouse_id, &items, OrderType::StockIn, tx).await?;
+                        }
+                    }
+                    OrderType::Calibration => {
+                        let skus = items.iter().map(|item| item.sku_id).collect();
+                        self.recalc_all(Some(HashSet::from_iter([order.warehouse_id])), Some(skus), None, action, tx).await?;
+                    }
+                    OrderType::CalibrationStrict => {
+                        self.recalc_all(Some(HashSet::from_iter([order.warehouse_id])), None, None, action, tx).await?;
+                    }
+                    OrderType::Verification | OrderType::VerificationStrict => (),
+                }
+            }
+        }
+        if !order.is_record {
+            self.revalue(&order, &items, order.date, tx).await?;
+        }
+        order.items = Some(items);
+        self.ps.notice(WebSocketFlags::RestoreOrder(id)).await?;
+        Ok(Some(order))
+    }
+
+    /// Put the items of the transfer order in transit into the destination warehouse, none if the order is not in transit.
+    pub async fn receive(&self, id: i64, user_id: i64, tx: &mut SqliteConnection) -> Result<Option<Order>> {
+        let mut order = match self.get(id, tx).await? {
+            Some(v) if v.order_type == OrderType::Transfer && v.in_transit => v,
+            _ => return Ok(None),
+        };
+        let items = self.get_order_items(id, &Pagination::max(), tx).await?;
+        // The received side is at the date of the transfer as the recalculation does, only the calibrations of the destination override it.
+        if !order.is_record && !self.exists_calibration_in_warehouse(order.to_warehouse_id, &order, tx).await? {
+            inventory_module::change(order.to_warehouse_id, &items, OrderType::StockIn, tx).await?;
+        }
+        order.updated_by_user_id = user_id;
+        order.last_updated_date = self.ps.get_timestamp_seconds() as i64;
+        order.in_transit = false;
+        sqlx::query("UPDATE orders SET in_transit=False, updated_by_user_id=?, last_updated_date=? WHERE id=?")
+            .bind(order.updated_by_user_id)
+            .bind(order.last_updated_date)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if !order.is_record {
+            self.revalue(&order, &items, order.date, tx).await?;
+        }
+        order.items = Some(items);
+        self.ps.notice(WebSocketFlags::UpdateOrder(id)).await?;
+        Ok(Some(order))
+    }
+
+    /// Physically delete the orders removed before the date with their items, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let ids = purge_rows_from_table("orders", deleted_before, tx).await?;
+        for id in ids.iter() {
+            sqlx::query("DELETE FROM order_items WHERE order_id=?").bind(id).execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM order_revision_items WHERE revision_id IN (SELECT id FROM order_revisions WHERE order_id=?)")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM order_revisions WHERE order_id=?").bind(id).execute(&mut *tx).await?;
+        }
+        Ok(ids)
+    }
+
     fn row_to_order(&self, row: SqliteRow) -> Order {
         let id = row.get("id");
         Order {
@@ -366,13 +612,130 @@ impl OrderModule {
             order_category_id: row.get("order_category_id"),
             is_record: row.get("is_record"),
             non_payment: row.get("non_payment"),
+            to_warehouse_id: row.get("to_warehouse_id"),
+            in_transit: row.get("in_transit"),
+            tax_inclusive: row.get("tax_inclusive"),
+            net_amount: row.get("net_amount"),
+            tax_amount: row.get("tax_amount"),
+            discount: row.try_get::<Json<Option<Discount>>, _>("discount").map(|v| v.0).unwrap_or_default(),
+            discount_amount: row.get("discount_amount"),
             items: None,
         }
     }
 
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Order>> {
-        let r = sqlx::query("SELECT * FROM orders WHERE id = ?").bind(id).fetch(&mut *tx).try_next().await?;
-        Ok(if let Some(row) = r { Some(self.row_to_order(row)) } else { None })
+        let r = sqlx::query("SELECT * FROM orders WHERE id = ? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?;
+        Ok(r.map(|row| self.row_to_order(row)))
+    }
+
+    pub async fn get_removed(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Order>> {
+        let r = sqlx::query("SELECT * FROM orders WHERE id = ? AND deleted_at IS NOT NULL").bind(id).fetch(&mut *tx).try_next().await?;
+        Ok(r.map(|row| self.row_to_order(row)))
+    }
+
+    /// Get all revisions of the order with the changes from the previous one.
+    pub async fn get_revisions(&self, order_id: i64, tx: &mut SqliteConnection) -> Result<Vec<OrderRevision>> {
+        let rows = sqlx::query("SELECT * FROM order_revisions WHERE order_id=? ORDER BY revision").bind(order_id).fetch_all(&mut *tx).await?;
+        let mut arr: Vec<OrderRevision> = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id = row.get("id");
+            let items = rows_to_objects(sqlx::query("SELECT * FROM order_revision_items WHERE revision_id=?").bind(id).fetch_all(&mut *tx).await?)?;
+            let mut revision = OrderRevision {
+                id,
+                order_id,
+                revision: row.get("revision"),
+                user_id: row.get("user_id"),
+                revision_date: row.get("revision_date"),
+                date: row.get("date"),
+                warehouse_id: row.get("warehouse_id"),
+                person_related_id: row.get("person_related_id"),
+                person_in_charge_id: row.get("person_in_charge_id"),
+                order_category_id: row.get("order_category_id"),
+                currency: row.try_get("currency").unwrap_or(OrderCurrency::Unknown),
+                order_type: row.get("order_type"),
+                description: row.get("description"),
+                total_amount: row.get("total_amount"),
+                items,
+                diff: None,
+            };
+            if let Some(prev) = arr.last() {
+                revision.diff = Some(revision.changes_from(prev));
+            }
+            arr.push(revision);
+        }
+        Ok(arr)
+    }
+
+    /// Order document in pdf file for the kind, none if the order is not exists.
+    pub async fn get_document(&self, id: i64, kind: DocumentKind, show_price: bool, tx: &mut SqliteConnection) -> Result<Option<PathBuf>> {
+        let order = match self.get(id, tx).await? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let rows = sqlx::query("SELECT order_items.*, sku_list.name AS sku_name FROM order_items INNER JOIN sku_list ON order_items.sku_id=sku_list.id WHERE order_items.order_id=?")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut items = Vec::with_capacity(rows.len());
+        for row in rows {
+            items.push(DocumentItem {
+                sku_name: row.get("sku_name"),
+                item: OrderItem::from_row(&row)?,
+            });
+        }
+        let warehouse_lines = |row: SqliteRow| vec![row.get("name"), row.get("address")];
+        let from = DocumentParty {
+            label: "Warehouse",
+            lines: sqlx::query("SELECT name, address FROM warehouses WHERE id=?").bind(order.warehouse_id).fetch_optional(&mut *tx).await?.map(warehouse_lines).unwrap_or_default(),
+        };
+        let to = if order.order_type == OrderType::Transfer {
+            sqlx::query("SELECT name, address FROM warehouses WHERE id=?").bind(order.to_warehouse_id).fetch_optional(&mut *tx).await?.map(warehouse_lines)
+        } else {
+            sqlx::query("SELECT name, address, contact, email FROM persons WHERE id=?")
+                .bind(order.person_related_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .map(|row| vec![row.get("name"), row.get("address"), row.get("contact"), row.get("email")])
+        };
+        let to = DocumentParty {
+            label: document::to_label(order.order_type),
+            lines: to.unwrap_or_default().into_iter().filter(|v: &String| !v.is_empty()).collect(),
+        };
+        let config = &self.ps.get_config().document;
+        let template = match kind {
+            DocumentKind::Invoice => &config.invoice,
+            DocumentKind::DeliveryNote => &config.delivery_note,
+            DocumentKind::PackingSlip => &config.packing_slip,
+        };
+        let font = match &config.font {
+            Some(path) => Some(fs::read(path).await?),
+            None => None,
+        };
+        let bold_font = match &config.bold_font {
+            Some(path) => Some(fs::read(path).await?),
+            None => None,
+        };
+        let buffer = document::render(&DocumentData {
+            kind,
+            template,
+            company: &config.company,
+            order: &order,
+            from,
+            to,
+            items,
+            show_price,
+            font: font.as_deref(),
+            bold_font: bold_font.as_deref(),
+        })?;
+
+        let documents = self.ps.get_data_path().join("documents").join("orders");
+        if !documents.is_dir() {
+            fs::create_dir_all(&documents).await?;
+        }
+        let path = documents.join(format!("order-{}-{}-{}.pdf", order.id, kind.default_title().replace(' ', "-").to_lowercase(), self.ps.get_timestamp_seconds()));
+        let mut file = fs::File::create(&path).await?;
+        file.write_all(&buffer).await?;
+        Ok(Some(path))
     }
 
     pub async fn get_order_items(&self, order_id: i64, pagination: &Pagination, tx: &mut SqliteConnection) -> Result<Vec<OrderItem>> {
@@ -413,10 +776,17 @@ impl OrderModule {
     orders.order_type,
     orders.is_record,
     orders.non_payment,
+    orders.to_warehouse_id,
+    orders.in_transit,
     orders.order_category_id,
     orders.total_amount,
     orders.total_amount_settled,
     orders.order_payment_status,
+    orders.tax_inclusive,
+    orders.net_amount,
+    orders.tax_amount,
+    orders.discount,
+    orders.discount_amount,
     persons_related.name AS person_related_name,
     COALESCE(persons_in_charge.name, 'Empty') AS person_in_charge_name,
     warehouses.name AS warehouse_name,
@@ -451,6 +821,205 @@ impl OrderModule {
         Ok(arr)
     }
 
+    /// Export the orders of the query, one row per order item if `with_items`, the amounts are not exported if not `show_price`.
+    /// The columns are named as the order import, the export with items can be imported again.
+    pub async fn get_export(&self, query: &GetOrdersQuery, export: &ExportQuery, show_price: bool, action: ActionType, tx: &mut SqliteConnection) -> Result<TempPath> {
+        let with_items = export.with_items.unwrap_or(false);
+        let base = self.ps.get_config().currency.base;
+        let base_headers = [format!("Total Amount ({base})"), format!("Total Amount Settled ({base})")];
+        let mut headers = vec!["Order", "Date", "Order Type", "Order Category", "Warehouse", "To Warehouse", "Person", "Person In Charge", "Currency", "Tax Inclusive", "Non Payment"];
+        if show_price {
+            headers.extend(["Net Amount", "Discount Amount", "Tax Amount", "Total Amount", "Total Amount Settled", &base_headers[0], &base_headers[1], "Payment Status"]);
+        }
+        headers.push("Description");
+        if with_items {
+            headers.extend(["SKU", "Quantity", "Unit", "Lot Number", "Exchanged"]);
+            if show_price {
+                headers.extend(["Price", "Item Discount Amount", "Item Tax Amount", "Item Amount"]);
+            }
+        }
+        let mut writer = ExportWriter::new(export.get_format(), self.ps.get_data_path(), "orders", self.ps.get_timestamp_seconds(), &headers)?;
+        let mut names = NameCache::new();
+        let mut pagination = Pagination::new(0, EXPORT_PAGE_SIZE);
+        loop {
+            let orders = self.get_multiple(&pagination, query, action, tx).await?;
+            let mut items: HashMap<i64, Vec<OrderItem>> = HashMap::new();
+            let mut rates: HashMap<i64, Option<f64>> = HashMap::new();
+            let ids = orders.iter().map(|order| order.id.to_string()).collect::<Vec<_>>().join(",");
+            if show_price && !orders.is_empty() {
+                let rate = get_rate_sql(base, "orders.currency", "orders.date");
+                for row in sqlx::query(&format!("SELECT id, {rate} AS rate FROM orders WHERE id IN ({ids})")).fetch_all(&mut *tx).await? {
+                    rates.insert(row.get("id"), row.get("rate"));
+                }
+            }
+            if with_items && !orders.is_empty() {
+                for row in sqlx::query(&format!("SELECT * FROM order_items WHERE order_id IN ({ids})")).fetch_all(&mut *tx).await? {
+                    items.entry(row.get("order_id")).or_default().push(OrderItem::from_row(&row)?);
+                }
+            }
+            for order in orders.iter() {
+                let mut values: Vec<ExportValue> = vec![
+                    order.id.into(),
+                    ExportValue::Date(order.date),
+                    order.order_type.as_ref().into(),
+                    names.get("order_categories", order.order_category_id, tx).await?.into(),
+                    names.get("warehouses", order.warehouse_id, tx).await?.into(),
+                    names.get("warehouses", order.to_warehouse_id, tx).await?.into(),
+                    names.get("persons", order.person_related_id, tx).await?.into(),
+                    names.get("persons", order.person_in_charge_id, tx).await?.into(),
+                    order.currency.to_string().into(),
+                    order.tax_inclusive.into(),
+                    order.non_payment.into(),
+                ];
+                if show_price {
+                    values.extend([order.net_amount, order.discount_amount, order.tax_amount, order.total_amount, order.total_amount_settled].map(ExportValue::from));
+                    // Empty if the currency has no rate.
+                    let rate = rates.get(&order.id).copied().flatten();
+                    values.extend([order.total_amount, order.total_amount_settled].map(|amount| rate.map_or("".into(), |rate| (amount * rate).into())));
+                    values.push(order.order_payment_status.as_ref().into());
+                }
+                values.push(order.description.as_str().into());
+                let order_items = items.remove(&order.id).unwrap_or_default();
+                if order_items.is_empty() {
+                    writer.write_row(values)?;
+                    continue;
+                }
+                for item in order_items {
+                    let mut row: Vec<ExportValue> = values.iter().map(ExportValue::clone).collect();
+                    row.push(names.get("sku_list", item.sku_id, tx).await?.into());
+                    // The quantity in the unit the item is entered in, it can be imported again.
+                    row.push(item.get_unit_quantity().into());
+                    row.push(item.unit.clone().unwrap_or_default().into());
+                    row.push(item.lot_number.clone().unwrap_or_default().into());
+                    row.push(item.exchanged.into());
+                    if show_price {
+                        row.extend([item.price, item.discount_amount, item.tax_amount, item.get_amount()].map(ExportValue::from));
+                    }
+                    writer.write_row(row)?;
+                }
+            }
+            if (orders.len() as i64) < pagination.limit() {
+                break;
+            }
+            pagination.next();
+        }
+        writer.finish()
+    }
+
+    /// The order of the import row, the currency is the base currency if it is empty.
+    pub async fn parse_import_order(&self, row: &ImportRow<'_>, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<Order> {
+        let order_type = row.get_required("Order Type")?;
+        let order_type = OrderType::from_str(order_type).map_err(|_| format!("Order Type '{order_type}' is invalid."))?;
+        let currency = match row.get("Currency") {
+            "" => self.ps.get_config().currency.base,
+            v => OrderCurrency::new(v).filter(|v| *v != OrderCurrency::Unknown).ok_or(format!("Currency '{v}' is invalid."))?,
+        };
+        row.get_required("Person")?;
+        row.get_required("Warehouse")?;
+        row.get_required("Order Category")?;
+        if order_type == OrderType::Transfer {
+            row.get_required("To Warehouse")?;
+        }
+        Ok(Order {
+            id: 0,
+            created_by_user_id: 0,
+            updated_by_user_id: 0,
+            date: 0,
+            last_updated_date: 0,
+            person_in_charge_id: 0,
+            order_category_id: row.get_id("Order Category", "order_categories", ids, tx).await?,
+            from_guest_order_id: 0,
+            currency,
+            items: None,
+            total_amount: 0.0,
+            total_amount_settled: 0.0,
+            order_payment_status: OrderPaymentStatus::None,
+            warehouse_id: row.get_id("Warehouse", "warehouses", ids, tx).await?,
+            person_related_id: row.get_id("Person", "persons", ids, tx).await?,
+            description: row.get("Description").to_owned(),
+            order_type,
+            is_record: false,
+            non_payment: row.get_bool("Non Payment")?,
+            to_warehouse_id: row.get_id("To Warehouse", "warehouses", ids, tx).await?,
+            in_transit: false,
+            tax_inclusive: row.get_bool("Tax Inclusive")?,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+        })
+    }
+
+    /// The order item of the import row, the price is filled by the price lists if it is empty.
+    /// The sku name is only unique in its category, the `SKU Category` is required if the name is used by several categories.
+    pub async fn parse_import_item(&self, row: &ImportRow<'_>, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<OrderItem> {
+        let sku_name = row.get_required("SKU")?;
+        let sku_category_id = row.get_id("SKU Category", "sku_categories", ids, tx).await?;
+        let rows = if sku_category_id > 0 {
+            sqlx::query("SELECT id FROM sku_list WHERE name=? AND sku_category_id=? AND deleted_at IS NULL").bind(sku_name).bind(sku_category_id).fetch_all(&mut *tx).await?
+        } else {
+            sqlx::query("SELECT id FROM sku_list WHERE name=? AND deleted_at IS NULL").bind(sku_name).fetch_all(&mut *tx).await?
+        };
+        let sku_id: i64 = match rows.as_slice() {
+            [row] => row.get("id"),
+            // The sku is also found by its code or barcode.
+            [] => match sqlx::query(
+                "SELECT sku_barcodes.sku_id AS id FROM sku_barcodes INNER JOIN sku_list ON sku_list.id=sku_barcodes.sku_id WHERE sku_barcodes.barcode=? AND sku_list.deleted_at IS NULL
+                UNION SELECT id FROM sku_list WHERE code=? AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(sku_name)
+            .bind(sku_name)
+            .fetch_optional(&mut *tx)
+            .await?
+            {
+                Some(row) => row.get("id"),
+                None => return Err(format!("SKU '{sku_name}' is not found.").into()),
+            },
+            _ => return Err(format!("SKU '{sku_name}' is in several categories, the SKU Category is required.").into()),
+        };
+        let quantity = match row.get_i64("Quantity")? {
+            Some(v) if v >= 0 => v,
+            Some(_) => return Err("Quantity can't be negative.".to_owned().into()),
+            None => return Err("Quantity is required.".to_owned().into()),
+        };
+        let serial_numbers = match row.get("Serial Numbers") {
+            "" => vec![],
+            v => v.split(',').map(|v| v.trim().to_owned()).filter(|v| !v.is_empty()).collect(),
+        };
+        let lot_number = Some(row.get("Lot Number").to_owned()).filter(|v| !v.is_empty());
+        let unit = Some(row.get("Unit").to_owned()).filter(|v| !v.is_empty());
+        Ok(OrderItem {
+            sku_id,
+            quantity,
+            price: row.get_f64("Price")?.unwrap_or(f64::NAN),
+            exchanged: row.get_bool("Exchanged")?,
+            lot_number,
+            expiry_date: row.get_date("Expiry Date")?,
+            serial_numbers,
+            tax_rate: row.get_f64("Tax Rate")?,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+            sku_code: None,
+            // The quantity in the unit is converted as adding the order.
+            unit_factor: if unit.is_some() { 0 } else { 1 },
+            unit,
+        })
+    }
+
+    /// The warehouse id and the calibration item of the opening stock row, the price is the unit cost.
+    pub async fn parse_opening_stock_row(&self, row: &ImportRow<'_>, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<(i64, OrderItem)> {
+        row.get_required("Warehouse")?;
+        let warehouse_id = row.get_id("Warehouse", "warehouses", ids, tx).await?;
+        let mut item = self.parse_import_item(row, ids, tx).await?;
+        if !item.is_price_set() {
+            item.price = 0.0;
+        }
+        item.exchanged = false;
+        item.tax_rate = None;
+        Ok((warehouse_id, item))
+    }
+
     pub async fn get_multiple_ids(&self, pagination: &Pagination, query: &GetOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
         let qw = query.get_where_condition();
 
@@ -479,20 +1048,37 @@ impl OrderModule {
     }
 
     pub async fn is_check_pass(&self, order: &Order, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(order_module::check(order, true, tx).await?.items_not_available.is_empty())
+        Ok(order_module::check(order, true, self.ps.get_timestamp_seconds() as i64, tx).await?.items_not_available.is_empty())
+    }
+
+    pub async fn is_serial_numbers_matched(&self, items: &[OrderItem], order_type: OrderType, tx: &mut SqliteConnection) -> Result<bool> {
+        order_module::is_serial_numbers_matched(items, order_type, tx).await
+    }
+
+    pub async fn get_serial_number_histories(&self, query: &GetSerialNumberQuery, tx: &mut SqliteConnection) -> Result<Vec<SerialNumberHistory>> {
+        order_module::get_serial_number_histories(query.sku_id, &query.serial_number, tx).await
     }
 
     pub async fn update(&self, id: i64, mut v: Order, action: ActionType, tx: &mut SqliteConnection) -> Result<Option<Order>> {
+        let before = self.get(id, tx).await?;
+        // The revisions are recorded for the edits of the users only, not the settlement updates by the system.
+        let user_edit = !matches!(action, ActionType::System);
+        // Orders added before the revisions are recorded need the original state as the first revision.
+        if user_edit && sqlx::query("SELECT id FROM order_revisions WHERE order_id=? LIMIT 1").bind(id).fetch(&mut *tx).try_next().await?.is_none() {
+            if let Some(order) = before.as_ref() {
+                order_module::add_revision(id, order.updated_by_user_id, order.last_updated_date, tx).await?;
+            }
+        }
         let r =
             match action {
                 ActionType::GeneralAllowed(_) | ActionType::General(_) => {
-                    sqlx::query("UPDATE orders SET updated_by_user_id=?, last_updated_date=?, person_related_id=?, person_in_charge_id=?, description=?, currency=?, order_category_id=? WHERE id=?")
+                    sqlx::query("UPDATE orders SET updated_by_user_id=?, last_updated_date=?, person_related_id=?, person_in_charge_id=?, description=?, currency=?, order_category_id=? WHERE id=? AND deleted_at IS NULL")
                         .bind(v.updated_by_user_id)
                         .bind(v.last_updated_date)
                         .bind(v.person_related_id)
                         .bind(v.person_in_charge_id)
                         .bind(&v.description)
-                        .bind(&v.currency)
+                        .bind(v.currency)
                         .bind(v.order_category_id)
                         .bind(id)
                         .execute(&mut *tx)
@@ -500,7 +1086,7 @@ impl OrderModule {
                 }
                 ActionType::Admin => {
                     sqlx::query(
-                        "UPDATE orders SET updated_by_user_id=?, last_updated_date=?, date=?, person_related_id=?, person_in_charge_id=?, description=?, currency=?, order_category_id=? WHERE id=?",
+                        "UPDATE orders SET updated_by_user_id=?, last_updated_date=?, date=?, person_related_id=?, person_in_charge_id=?, description=?, currency=?, order_category_id=? WHERE id=? AND deleted_at IS NULL",
                     )
                     .bind(v.updated_by_user_id)
                     .bind(v.last_updated_date)
@@ -508,20 +1094,20 @@ impl OrderModule {
                     .bind(v.person_related_id)
                     .bind(v.person_in_charge_id)
                     .bind(&v.description)
-                    .bind(&v.currency)
+                    .bind(v.currency)
                     .bind(v.order_category_id)
                     .bind(id)
                     .execute(&mut *tx)
                     .await?
                 }
                 ActionType::System => sqlx::query(
-                    "UPDATE orders SET date=?, person_related_id=?, person_in_charge_id=?, description=?, currency=?, order_category_id=?, total_amount_settled=?, order_payment_status=? WHERE id=?",
+                    "UPDATE orders SET date=?, person_related_id=?, person_in_charge_id=?, description=?, currency=?, order_category_id=?, total_amount_settled=?, order_payment_status=? WHERE id=? AND deleted_at IS NULL",
                 )
                 .bind(v.date)
                 .bind(v.person_related_id)
                 .bind(v.person_in_charge_id)
                 .bind(&v.description)
-                .bind(&v.currency)
+                .bind(v.currency)
                 .bind(v.order_category_id)
                 .bind(v.total_amount_settled)
                 .bind(v.order_payment_status)
@@ -531,6 +1117,16 @@ impl OrderModule {
             };
         Ok(if r.rows_affected() == 1 {
             v.id = id;
+            if user_edit {
+                order_module::add_revision(id, v.updated_by_user_id, self.ps.get_timestamp_seconds() as i64, tx).await?;
+            }
+            // The costs of the stock in are converted at the date and by the currency of the order.
+            if let (Some(before), Some(after)) = (before.filter(|order| !order.is_record), self.get(id, tx).await?) {
+                if before.date != after.date || before.currency != after.currency {
+                    let items = self.get_order_items(id, &Pagination::max(), tx).await?;
+                    self.revalue(&after, &items, before.date.min(after.date), tx).await?;
+                }
+            }
             self.ps.notice(WebSocketFlags::UpdateOrder(v.id)).await?;
             Some(v)
         } else {
@@ -539,6 +1135,6 @@ impl OrderModule {
     }
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT id FROM order_payments WHERE order_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+        Ok(sqlx::query("SELECT id FROM order_payments WHERE order_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some())
     }
 }
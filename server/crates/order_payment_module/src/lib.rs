@@ -1,14 +1,18 @@
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use ahash::{HashMap, HashMapExt};
+use elerp_common::currency_module::get_rate_sql;
+use elerp_common::export::{ExportValue, ExportWriter, NameCache, TempPath, EXPORT_PAGE_SIZE};
 use elerp_common::model::action_type::ActionType;
+use elerp_common::model::export::ExportFormat;
 use elerp_common::model::Pagination;
 use elerp_common::model::WebSocketFlags;
 use elerp_common::order_module::model::order::OrderPaymentStatus;
 use elerp_common::order_payment_module::model::order_payment::GetOrderPaymentsQuery;
 use elerp_common::order_payment_module::model::order_payment::OrderPayment;
 use elerp_common::sql;
-use elerp_common::sql::{get_row_from_table, is_exists_in_table, remove_row_from_table, rows_to_objects};
+use elerp_common::sql::{get_active_row_from_table, is_active_in_table, purge_rows_from_table, restore_row_from_table, rows_to_objects, soft_remove_row_from_table};
 use elerp_common::user_system::model::user_info::{UserInfo, UserType};
 use futures::TryStreamExt;
 use public_system::PublicSystem;
@@ -32,7 +36,8 @@ impl OrderPaymentModule {
                 total_amount REAL NOT NULL,
                 creation_date INT NOT NULL,
                 actual_date INT NOT NULL,
-                remark TEXT NOT NULL
+                remark TEXT NOT NULL,
+                deleted_at INT NULL
             );",
         )
         .execute(tx.as_mut())
@@ -54,11 +59,11 @@ impl OrderPaymentModule {
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("order_payments", "id", id, tx).await
+        is_active_in_table("order_payments", id, tx).await
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM order_payments;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM order_payments WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.order_payments)
     }
 
@@ -136,7 +141,7 @@ impl OrderPaymentModule {
 
     pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
         if let Some(op) = self.get(id, tx).await? {
-            let r = remove_row_from_table(id, "order_payments", tx).await?;
+            let r = soft_remove_row_from_table(id, "order_payments", self.ps.get_timestamp_seconds() as i64, tx).await?;
             if r {
                 sqlx::query(
                     "UPDATE orders SET total_amount_settled = total_amount_settled - ?, 
@@ -168,8 +173,49 @@ impl OrderPaymentModule {
         }
     }
 
+    /// Restore the removed order payment and settle the amount to the order again.
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<OrderPayment>> {
+        if !restore_row_from_table(id, "order_payments", tx).await? {
+            return Ok(None);
+        }
+        let op = self.get(id, tx).await?.unwrap();
+        if let Some(order_row) = sqlx::query("SELECT total_amount, total_amount_settled FROM orders WHERE id=? AND deleted_at IS NULL LIMIT 1")
+            .bind(op.order_id)
+            .fetch_optional(&mut *tx)
+            .await?
+        {
+            let total_amount: f64 = order_row.get("total_amount");
+            let total_amount_settled: f64 = order_row.get::<f64, _>("total_amount_settled") + op.total_amount;
+            let order_payment_status = match total_amount_settled {
+                v if v >= total_amount => OrderPaymentStatus::Settled,
+                v if v > 0.0 => OrderPaymentStatus::PartialSettled,
+                _ => OrderPaymentStatus::Unsettled,
+            };
+            sqlx::query("UPDATE orders SET total_amount_settled = ?, order_payment_status = ? WHERE id = ?")
+                .bind(total_amount_settled)
+                .bind(order_payment_status)
+                .bind(op.order_id)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| "update order's amount settled failed!")?;
+        } else {
+            bail!("Order is not found!");
+        }
+        self.ps.notice(WebSocketFlags::RestoreOrderPayment(id)).await?;
+        Ok(Some(op))
+    }
+
+    /// Physically delete the order payments removed before the date, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        purge_rows_from_table("order_payments", deleted_before, tx).await
+    }
+
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<OrderPayment>> {
-        get_row_from_table("order_payments", "id", id, tx).await
+        get_active_row_from_table("order_payments", id, tx).await
+    }
+
+    pub async fn get_removed(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<OrderPayment>> {
+        Ok(sqlx::query_as("SELECT * FROM order_payments WHERE id = ? AND deleted_at IS NOT NULL").bind(id).fetch_optional(&mut *tx).await?)
     }
 
     fn get_permission_inner(&self, action: ActionType) -> String {
@@ -210,6 +256,51 @@ impl OrderPaymentModule {
         rows_to_objects(rows)
     }
 
+    /// Export the order payments of the query.
+    pub async fn get_export(&self, query: &GetOrderPaymentsQuery, format: ExportFormat, action: ActionType, tx: &mut SqliteConnection) -> Result<TempPath> {
+        let base = self.ps.get_config().currency.base;
+        let base_header = format!("Total Amount ({base})");
+        let headers = ["ID", "Order ID", "Warehouse", "Person In Charge", "Creation Date", "Actual Date", "Currency", "Total Amount", &base_header, "Remark"];
+        let mut writer = ExportWriter::new(format, self.ps.get_data_path(), "order_payments", self.ps.get_timestamp_seconds(), &headers)?;
+        let mut names = NameCache::new();
+        let mut pagination = Pagination::new(0, EXPORT_PAGE_SIZE);
+        // The payments are converted at the actual date.
+        let rate = get_rate_sql(base, "orders.currency", "order_payments.actual_date");
+        loop {
+            let list = self.get_multiple(&pagination, query, action, tx).await?;
+            let mut currencies: HashMap<i64, (String, Option<f64>)> = HashMap::new();
+            if !list.is_empty() {
+                let ids = list.iter().map(|v| v.id.to_string()).collect::<Vec<_>>().join(",");
+                let rows = sqlx::query(&format!("SELECT order_payments.id, orders.currency, {rate} AS rate FROM order_payments INNER JOIN orders ON orders.id=order_payments.order_id WHERE order_payments.id IN ({ids})"))
+                    .fetch_all(&mut *tx)
+                    .await?;
+                for row in rows {
+                    currencies.insert(row.get("id"), (row.get("currency"), row.get("rate")));
+                }
+            }
+            for v in list.iter() {
+                let (currency, rate) = currencies.remove(&v.id).unwrap_or_default();
+                writer.write_row(vec![
+                    v.id.into(),
+                    v.order_id.into(),
+                    names.get("warehouses", v.warehouse_id, tx).await?.into(),
+                    names.get("persons", v.person_in_charge_id, tx).await?.into(),
+                    ExportValue::Date(v.creation_date),
+                    ExportValue::Date(v.actual_date),
+                    currency.into(),
+                    v.total_amount.into(),
+                    rate.map_or("".into(), |rate| (v.total_amount * rate).into()),
+                    v.remark.as_str().into(),
+                ])?;
+            }
+            if (list.len() as i64) < pagination.limit() {
+                break;
+            }
+            pagination.next();
+        }
+        writer.finish()
+    }
+
     pub async fn get_multiple_ids(&self, pagination: &Pagination, query: &GetOrderPaymentsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
         let qw = query.get_where_condition();
         let inner = self.get_permission_inner(action);
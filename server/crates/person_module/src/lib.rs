@@ -1,9 +1,12 @@
 use anyhow::{bail, Result};
 use elerp_common::sql::{
-    self, get_row_from_table, is_exists_in_table, remove_row_from_table, row_is_duplicate_col_in_table, rows_to_objects
+    self, get_active_row_from_table, is_active_in_table, purge_rows_from_table, restore_row_from_table, row_is_duplicate_col_in_table, rows_to_objects,
+    soft_remove_row_from_table,
 };
 use elerp_common::{
-    model::{action_type::ActionType, Pagination, WebSocketFlags},
+    export::{ExportWriter, NameCache, TempPath, EXPORT_PAGE_SIZE},
+    import::{IdCache, ImportRow, ImportRowResult},
+    model::{action_type::ActionType, export::ExportFormat, Pagination, WebSocketFlags},
     person_module::model::person::{GetPersonsQuery, Person},
 };
 use futures::TryStreamExt;
@@ -29,7 +32,9 @@ impl PersonModule {
                 contact TEXT NOT NULL,
                 email TEXT NOT NULL,
                 color TEXT NULL,
-                text_color TEXT NULL
+                text_color TEXT NULL,
+                price_list_id INT NOT NULL DEFAULT 0,
+                deleted_at INT NULL
             )",
         )
         .execute(conn)
@@ -51,11 +56,11 @@ impl PersonModule {
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("persons", "id", id, tx).await
+        is_active_in_table("persons", id, tx).await
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM persons;")
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM persons WHERE deleted_at IS NULL;")
             .fetch_one(&mut *tx)
             .await?
             .get("count");
@@ -71,9 +76,27 @@ impl PersonModule {
         row_is_duplicate_col_in_table(name, prev, "persons", "name", tx).await
     }
 
-    pub async fn add(&self, mut person: Person, tx: &mut SqliteConnection) -> Result<Person> {
+    /// The person of the import row, the columns are the same as the export.
+    pub async fn parse_import_row(&self, row: &ImportRow<'_>, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<Person> {
+        row.get_required("Area")?;
+        Ok(Person {
+            id: 0,
+            name: row.get_required("Name")?.to_owned(),
+            description: row.get("Description").to_owned(),
+            address: row.get("Address").to_owned(),
+            area_id: row.get_id("Area", "areas", ids, tx).await?,
+            person_in_charge_id: row.get_id("Person In Charge", "persons", ids, tx).await?,
+            contact: row.get("Contact").to_owned(),
+            email: row.get("Email").to_owned(),
+            color: None,
+            text_color: None,
+            price_list_id: row.get_id("Price List", "price_lists", ids, tx).await?,
+        })
+    }
+
+    pub async fn add(&self, mut person: Person, notice: bool, tx: &mut SqliteConnection) -> Result<Person> {
         let r = sqlx::query(
-            "INSERT INTO persons (name, description, address, area_id, person_in_charge_id, contact, email, color, text_color) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO persons (name, description, address, area_id, person_in_charge_id, contact, email, color, text_color, price_list_id) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&person.name)
         .bind(&person.description)
@@ -84,6 +107,7 @@ impl PersonModule {
         .bind(&person.email)
         .bind(&person.color)
         .bind(&person.text_color)
+        .bind(person.price_list_id)
         .execute(&mut *tx)
         .await?;
         if r.rows_affected() != 1 {
@@ -92,25 +116,40 @@ impl PersonModule {
 
         person.id = sql::try_set_standard_id(r.last_insert_rowid(), "persons", tx)
             .await?;
-        self.ps.notice(WebSocketFlags::AddPerson(person.id)).await?;
+        if notice {
+            self.ps.notice(WebSocketFlags::AddPerson(person.id)).await?;
+        }
         Ok(person)
     }
 
     pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = remove_row_from_table(id, "persons", tx).await?;
+        let r = soft_remove_row_from_table(id, "persons", self.ps.get_timestamp_seconds() as i64, tx).await?;
         if notice {
             self.ps.notice(WebSocketFlags::RemovePerson(id)).await?;
         }
         Ok(r)
     }
 
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Person>> {
+        if !restore_row_from_table(id, "persons", tx).await? {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::RestorePerson(id)).await?;
+        self.get(id, ActionType::System, tx).await
+    }
+
+    /// Physically delete the persons removed before the date, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        purge_rows_from_table("persons", deleted_before, tx).await
+    }
+
     pub async fn get(
         &self,
         id: i64,
         action: ActionType,
         tx: &mut SqliteConnection,
     ) -> Result<Option<Person>> {
-        get_row_from_table("persons", "id", id, tx)
+        get_active_row_from_table("persons", id, tx)
             .await
             .map(|opt: Option<Person>| {
                 opt.map(|mut v| {
@@ -120,9 +159,7 @@ impl PersonModule {
                             v.contact = String::new();
                             v.email = String::new();
                         }
-                        ActionType::Admin | ActionType::System | ActionType::GeneralAllowed(_) => {
-                            ()
-                        }
+                        ActionType::Admin | ActionType::System | ActionType::GeneralAllowed(_) => (),
                     }
                     v
                 })
@@ -151,6 +188,7 @@ impl PersonModule {
         persons.email,
         persons.color,
         persons.text_color,
+        persons.price_list_id,
         persons2.name AS person_in_charge_name,
         areas.id AS area_name
         FROM persons
@@ -177,6 +215,34 @@ impl PersonModule {
         })
     }
 
+    /// Export the persons of the query, the contacts are hidden as the list.
+    pub async fn get_export(&self, query: &GetPersonsQuery, format: ExportFormat, action: ActionType, tx: &mut SqliteConnection) -> Result<TempPath> {
+        let mut writer = ExportWriter::new(format, self.ps.get_data_path(), "persons", self.ps.get_timestamp_seconds(), &["ID", "Name", "Area", "Person In Charge", "Address", "Contact", "Email", "Price List", "Description"])?;
+        let mut names = NameCache::new();
+        let mut pagination = Pagination::new(0, EXPORT_PAGE_SIZE);
+        loop {
+            let list = self.get_multiple(&pagination, query, action, tx).await?;
+            for v in list.iter() {
+                writer.write_row(vec![
+                    v.id.into(),
+                    v.name.as_str().into(),
+                    names.get("areas", v.area_id, tx).await?.into(),
+                    names.get("persons", v.person_in_charge_id, tx).await?.into(),
+                    v.address.as_str().into(),
+                    v.contact.as_str().into(),
+                    v.email.as_str().into(),
+                    names.get("price_lists", v.price_list_id, tx).await?.into(),
+                    v.description.as_str().into(),
+                ])?;
+            }
+            if (list.len() as i64) < pagination.limit() {
+                break;
+            }
+            pagination.next();
+        }
+        writer.finish()
+    }
+
     pub async fn get_multiple_ids(
         &self,
         pagination: &Pagination,
@@ -215,8 +281,8 @@ impl PersonModule {
         mut v: Person,
         tx: &mut SqliteConnection,
     ) -> Result<Option<Person>> {
-        let r = sqlx::query("UPDATE persons SET name=?, description=?, address=?, area_id=?, person_in_charge_id=?, contact=?, email=?, color=?, text_color=? WHERE id=?")
-        .bind(&v.name).bind(&v.description).bind(&v.address).bind(v.area_id).bind(v.person_in_charge_id).bind(&v.contact).bind(&v.email).bind(&v.color).bind(&v.text_color).bind(id).execute(&mut *tx).await?;
+        let r = sqlx::query("UPDATE persons SET name=?, description=?, address=?, area_id=?, person_in_charge_id=?, contact=?, email=?, color=?, text_color=?, price_list_id=? WHERE id=? AND deleted_at IS NULL")
+        .bind(&v.name).bind(&v.description).bind(&v.address).bind(v.area_id).bind(v.person_in_charge_id).bind(&v.contact).bind(&v.email).bind(&v.color).bind(&v.text_color).bind(v.price_list_id).bind(id).execute(&mut *tx).await?;
         Ok(if r.rows_affected() == 1 {
             v.id = id;
             self.ps.notice(WebSocketFlags::UpdatePerson(id)).await?;
@@ -228,25 +294,37 @@ impl PersonModule {
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
         Ok(
-            sqlx::query("SELECT id FROM persons WHERE person_in_charge_id=?")
+            sqlx::query("SELECT id FROM persons WHERE person_in_charge_id=? AND deleted_at IS NULL")
                 .bind(id)
                 .fetch(&mut *tx)
                 .try_next()
                 .await?
                 .is_some()
-                || sqlx::query("SELECT id FROM warehouses WHERE person_in_charge_id=?")
+                || sqlx::query("SELECT id FROM warehouses WHERE person_in_charge_id=? AND deleted_at IS NULL")
+                    .bind(id)
+                    .fetch(&mut *tx)
+                    .try_next()
+                    .await?
+                    .is_some()
+                || sqlx::query("SELECT id FROM order_payments WHERE person_in_charge_id=? AND deleted_at IS NULL")
+                    .bind(id)
+                    .fetch(&mut *tx)
+                    .try_next()
+                    .await?
+                    .is_some()
+                || sqlx::query("SELECT id FROM orders WHERE person_related_id=? AND deleted_at IS NULL")
                     .bind(id)
                     .fetch(&mut *tx)
                     .try_next()
                     .await?
                     .is_some()
-                || sqlx::query("SELECT id FROM order_payments WHERE person_in_charge_id=?")
+                || sqlx::query("SELECT id FROM purchase_orders WHERE supplier_id=?")
                     .bind(id)
                     .fetch(&mut *tx)
                     .try_next()
                     .await?
                     .is_some()
-                || sqlx::query("SELECT id FROM orders WHERE person_related_id=?")
+                || sqlx::query("SELECT id FROM quotations WHERE person_related_id=?")
                     .bind(id)
                     .fetch(&mut *tx)
                     .try_next()
@@ -0,0 +1,214 @@
+use anyhow::{bail, Result};
+use elerp_common::{
+    model::{Pagination, WebSocketFlags},
+    order_module::model::order::{OrderCurrency, OrderItem},
+    price_list_module::{
+        self,
+        model::price_list::{AppliedPrice, GetPriceListItemsQuery, GetPriceQuery, PriceList, PriceListItem},
+    },
+    sql::{self, rows_to_objects},
+};
+use futures::TryStreamExt;
+use public_system::PublicSystem;
+use sqlx::{Row, SqliteConnection};
+
+#[derive(Debug, Clone)]
+pub struct PriceListModule {
+    ps: PublicSystem,
+}
+
+impl PriceListModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.begin_tx(true).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS price_lists(
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                is_default BOOLEAN NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS price_list_items(
+                id INTEGER PRIMARY KEY,
+                price_list_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                currency TEXT NOT NULL,
+                price REAL NOT NULL,
+                valid_from INT NULL,
+                valid_to INT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS price_list_items_price_list_ids
+        ON price_list_items(price_list_id);
+        CREATE INDEX IF NOT EXISTS price_list_items_skus
+        ON price_list_items(sku_id, currency);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        tx.commit().await.unwrap();
+        Self { ps }
+    }
+
+    pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM price_lists WHERE id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+    }
+
+    /// Only one price list is the default, the others are not the default after it is set.
+    async fn clear_default(&self, id: i64, tx: &mut SqliteConnection) -> Result<()> {
+        sqlx::query("UPDATE price_lists SET is_default=false WHERE id<>?").bind(id).execute(&mut *tx).await?;
+        Ok(())
+    }
+
+    pub async fn add(&self, mut v: PriceList, tx: &mut SqliteConnection) -> Result<PriceList> {
+        let r = sqlx::query("INSERT INTO price_lists (name, description, is_default) VALUES(?, ?, ?)")
+            .bind(&v.name)
+            .bind(&v.description)
+            .bind(v.is_default)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't add price list");
+        }
+        v.id = sql::try_set_standard_id(r.last_insert_rowid(), "price_lists", tx).await?;
+        if v.is_default {
+            self.clear_default(v.id, tx).await?;
+        }
+        self.ps.notice(WebSocketFlags::AddPriceList(v.id)).await?;
+        Ok(v)
+    }
+
+    pub async fn update(&self, id: i64, mut v: PriceList, tx: &mut SqliteConnection) -> Result<Option<PriceList>> {
+        let r = sqlx::query("UPDATE price_lists SET name=?, description=?, is_default=? WHERE id=?")
+            .bind(&v.name)
+            .bind(&v.description)
+            .bind(v.is_default)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(if r.rows_affected() == 1 {
+            v.id = id;
+            if v.is_default {
+                self.clear_default(id, tx).await?;
+            }
+            self.ps.notice(WebSocketFlags::UpdatePriceList(id)).await?;
+            Some(v)
+        } else {
+            None
+        })
+    }
+
+    /// Remove the price list with its items.
+    pub async fn remove(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM price_lists WHERE id=?").bind(id).execute(&mut *tx).await?;
+        if r.rows_affected() == 1 {
+            sqlx::query("DELETE FROM price_list_items WHERE price_list_id=?").bind(id).execute(&mut *tx).await?;
+            self.ps.notice(WebSocketFlags::RemovePriceList(id)).await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<PriceList>> {
+        Ok(sqlx::query_as("SELECT id, name, description, is_default FROM price_lists WHERE id=?").bind(id).fetch_optional(&mut *tx).await?)
+    }
+
+    pub async fn list(&self, tx: &mut SqliteConnection) -> Result<Vec<PriceList>> {
+        let rows = sqlx::query("SELECT id, name, description, is_default FROM price_lists ORDER BY name ASC").fetch_all(&mut *tx).await?;
+        rows_to_objects(rows)
+    }
+
+    pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM persons WHERE price_list_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM areas WHERE price_list_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+    }
+
+    pub async fn get_item(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<PriceListItem>> {
+        Ok(sqlx::query_as("SELECT * FROM price_list_items WHERE id=?").bind(id).fetch_optional(&mut *tx).await?)
+    }
+
+    pub async fn add_item(&self, mut v: PriceListItem, tx: &mut SqliteConnection) -> Result<PriceListItem> {
+        let r = sqlx::query("INSERT INTO price_list_items (price_list_id, sku_id, currency, price, valid_from, valid_to) VALUES(?, ?, ?, ?, ?, ?)")
+            .bind(v.price_list_id)
+            .bind(v.sku_id)
+            .bind(v.currency)
+            .bind(v.price)
+            .bind(v.valid_from)
+            .bind(v.valid_to)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't add price list item");
+        }
+        v.id = sql::try_set_standard_id(r.last_insert_rowid(), "price_list_items", tx).await?;
+        self.ps.notice(WebSocketFlags::UpdatePriceListItems(v.price_list_id)).await?;
+        Ok(v)
+    }
+
+    pub async fn update_item(&self, id: i64, mut v: PriceListItem, tx: &mut SqliteConnection) -> Result<Option<PriceListItem>> {
+        let r = sqlx::query("UPDATE price_list_items SET price_list_id=?, sku_id=?, currency=?, price=?, valid_from=?, valid_to=? WHERE id=?")
+            .bind(v.price_list_id)
+            .bind(v.sku_id)
+            .bind(v.currency)
+            .bind(v.price)
+            .bind(v.valid_from)
+            .bind(v.valid_to)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(if r.rows_affected() == 1 {
+            v.id = id;
+            self.ps.notice(WebSocketFlags::UpdatePriceListItems(v.price_list_id)).await?;
+            Some(v)
+        } else {
+            None
+        })
+    }
+
+    pub async fn remove_item(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let row = sqlx::query("DELETE FROM price_list_items WHERE id=? RETURNING price_list_id").bind(id).fetch_optional(&mut *tx).await?;
+        Ok(if let Some(row) = row {
+            self.ps.notice(WebSocketFlags::UpdatePriceListItems(row.get("price_list_id"))).await?;
+            true
+        } else {
+            false
+        })
+    }
+
+    pub async fn get_items(&self, pagination: &Pagination, query: &GetPriceListItemsQuery, tx: &mut SqliteConnection) -> Result<Vec<PriceListItem>> {
+        let qw = query.get_where_condition();
+        let rows = sqlx::query(&format!("SELECT * FROM price_list_items {qw} ORDER BY sku_id ASC, currency ASC, valid_from ASC LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?;
+        rows_to_objects(rows)
+    }
+
+    pub async fn get_items_count(&self, query: &GetPriceListItemsQuery, tx: &mut SqliteConnection) -> Result<i64> {
+        let qw = query.get_where_condition();
+        let row = sqlx::query(&format!("SELECT COUNT(*) AS count FROM price_list_items {qw}")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
+    pub async fn get_price(&self, query: &GetPriceQuery, tx: &mut SqliteConnection) -> Result<Option<AppliedPrice>> {
+        let date = query.date.unwrap_or_else(|| self.ps.get_timestamp_seconds() as i64);
+        price_list_module::get_price(query.person_id, query.sku_id, query.currency, date, tx).await
+    }
+
+    /// Fill the prices omitted at the date, now if the date is 0, return the skus without any price.
+    pub async fn fill_prices(&self, person_id: i64, currency: OrderCurrency, date: i64, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let date = if date > 0 { date } else { self.ps.get_timestamp_seconds() as i64 };
+        price_list_module::fill_prices(person_id, currency, date, items, tx).await
+    }
+}
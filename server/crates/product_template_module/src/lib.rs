@@ -0,0 +1,185 @@
+use anyhow::{bail, Result};
+use elerp_common::{
+    model::{Pagination, WebSocketFlags},
+    product_template_module::model::product_template::{GetProductTemplatesQuery, ProductTemplate, TemplateAttribute},
+    sql::{self, rows_to_objects},
+};
+use futures::TryStreamExt;
+use public_system::PublicSystem;
+use sqlx::{Row, SqliteConnection};
+
+#[derive(Debug, Clone)]
+pub struct ProductTemplateModule {
+    ps: PublicSystem,
+}
+
+impl ProductTemplateModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.begin_tx(true).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS product_templates(
+                id INTEGER PRIMARY KEY,
+                sku_category_id INT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS product_template_attributes(
+                id INTEGER PRIMARY KEY,
+                template_id INT NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS product_templates_names
+        ON product_templates(name);
+        CREATE INDEX IF NOT EXISTS product_template_attributes_templates
+        ON product_template_attributes(template_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        tx.commit().await.unwrap();
+        Self { ps }
+    }
+
+    pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM product_templates WHERE id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+    }
+
+    pub async fn is_exists_name(&self, name: &str, prev: Option<i64>, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM product_templates WHERE name=? AND id<>? LIMIT 1")
+            .bind(name)
+            .bind(prev.unwrap_or_default())
+            .fetch(&mut *tx)
+            .try_next()
+            .await?
+            .is_some())
+    }
+
+    /// The values of the attributes are kept in the order of them.
+    async fn set_attributes(&self, v: &ProductTemplate, tx: &mut SqliteConnection) -> Result<()> {
+        sqlx::query("DELETE FROM product_template_attributes WHERE template_id=?").bind(v.id).execute(&mut *tx).await?;
+        for attribute in v.attributes.iter() {
+            for value in attribute.values.iter() {
+                sqlx::query("INSERT INTO product_template_attributes (template_id, name, value) VALUES(?, ?, ?)")
+                    .bind(v.id)
+                    .bind(&attribute.name)
+                    .bind(value)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn fill_attributes(&self, list: &mut [ProductTemplate], tx: &mut SqliteConnection) -> Result<()> {
+        if list.is_empty() {
+            return Ok(());
+        }
+        let ids = list.iter().map(|v| v.id.to_string()).collect::<Vec<_>>().join(",");
+        let rows = sqlx::query(&format!("SELECT template_id, name, value FROM product_template_attributes WHERE template_id IN ({ids}) ORDER BY id"))
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in rows {
+            let template_id: i64 = row.get("template_id");
+            let name: String = row.get("name");
+            if let Some(v) = list.iter_mut().find(|v| v.id == template_id) {
+                match v.attributes.iter_mut().find(|attribute| attribute.name == name) {
+                    Some(attribute) => attribute.values.push(row.get("value")),
+                    None => v.attributes.push(TemplateAttribute {
+                        name,
+                        values: vec![row.get("value")],
+                    }),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn add(&self, mut v: ProductTemplate, tx: &mut SqliteConnection) -> Result<ProductTemplate> {
+        let r = sqlx::query("INSERT INTO product_templates (sku_category_id, name, description) VALUES(?, ?, ?)")
+            .bind(v.sku_category_id)
+            .bind(&v.name)
+            .bind(&v.description)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't add product template");
+        }
+        v.id = sql::try_set_standard_id(r.last_insert_rowid(), "product_templates", tx).await?;
+        self.set_attributes(&v, tx).await?;
+        self.ps.notice(WebSocketFlags::AddProductTemplate(v.id)).await?;
+        Ok(v)
+    }
+
+    pub async fn update(&self, id: i64, mut v: ProductTemplate, tx: &mut SqliteConnection) -> Result<Option<ProductTemplate>> {
+        let r = sqlx::query("UPDATE product_templates SET sku_category_id=?, name=?, description=? WHERE id=?")
+            .bind(v.sku_category_id)
+            .bind(&v.name)
+            .bind(&v.description)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(if r.rows_affected() == 1 {
+            v.id = id;
+            self.set_attributes(&v, tx).await?;
+            self.ps.notice(WebSocketFlags::UpdateProductTemplate(id)).await?;
+            Some(v)
+        } else {
+            None
+        })
+    }
+
+    /// Remove the product template with its attributes.
+    pub async fn remove(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM product_templates WHERE id=?").bind(id).execute(&mut *tx).await?;
+        if r.rows_affected() == 1 {
+            sqlx::query("DELETE FROM product_template_attributes WHERE template_id=?").bind(id).execute(&mut *tx).await?;
+            self.ps.notice(WebSocketFlags::RemoveProductTemplate(id)).await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<ProductTemplate>> {
+        let mut r: Option<ProductTemplate> = sqlx::query_as("SELECT id, sku_category_id, name, description FROM product_templates WHERE id=?").bind(id).fetch_optional(&mut *tx).await?;
+        if let Some(v) = r.as_mut() {
+            self.fill_attributes(std::slice::from_mut(v), tx).await?;
+        }
+        Ok(r)
+    }
+
+    pub async fn get_multiple(&self, pagination: &Pagination, query: &GetProductTemplatesQuery, tx: &mut SqliteConnection) -> Result<Vec<ProductTemplate>> {
+        let qw = query.get_where_condition();
+        let rows = sqlx::query(&format!("SELECT id, sku_category_id, name, description FROM product_templates {qw} ORDER BY name ASC LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut list: Vec<ProductTemplate> = rows_to_objects(rows)?;
+        self.fill_attributes(&mut list, tx).await?;
+        Ok(list)
+    }
+
+    pub async fn get_count(&self, query: &GetProductTemplatesQuery, tx: &mut SqliteConnection) -> Result<i64> {
+        let qw = query.get_where_condition();
+        let row = sqlx::query(&format!("SELECT COUNT(*) AS count FROM product_templates {qw}")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
+    /// The active variants depend on the template.
+    pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM sku_list WHERE template_id=? AND deleted_at IS NULL LIMIT 1").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+    }
+}
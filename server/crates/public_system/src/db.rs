@@ -160,6 +160,287 @@ pub async fn update(pool: Pool<Sqlite>) -> bool {
         }
     }
 
+    for table in ["areas", "persons", "warehouses", "sku_categories", "sku_list", "order_categories", "orders", "order_payments"] {
+        if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name=?")
+            .bind(table)
+            .fetch_optional(tx.as_mut())
+            .await
+            .unwrap()
+            .is_some()
+        {
+            let q = sqlx::query(&format!(
+                "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='deleted_at'), 0) AS deleted_at;
+        "
+            ))
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+
+            if !q.get::<bool, _>("deleted_at") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD deleted_at INT NULL;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+        }
+    }
+
+    if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name='sku_list'")
+        .fetch_optional(tx.as_mut())
+        .await
+        .unwrap()
+        .is_some()
+    {
+        let q = sqlx::query(
+            "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('sku_list') WHERE name='serialized'), 0) AS serialized,
+    IFNULL((SELECT 1 FROM pragma_table_info('sku_list') WHERE name='code'), 0) AS code,
+    IFNULL((SELECT 1 FROM pragma_table_info('sku_list') WHERE name='unit'), 0) AS unit,
+    IFNULL((SELECT 1 FROM pragma_table_info('sku_list') WHERE name='template_id'), 0) AS template_id;
+        ",
+        )
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+
+        if !q.get::<bool, _>("serialized") {
+            sqlx::query("ALTER TABLE sku_list ADD serialized BOOLEAN NOT NULL DEFAULT False;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+        if !q.get::<bool, _>("code") {
+            sqlx::query("ALTER TABLE sku_list ADD code TEXT NOT NULL DEFAULT '';").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+        if !q.get::<bool, _>("unit") {
+            sqlx::query("ALTER TABLE sku_list ADD unit TEXT NOT NULL DEFAULT '';").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+        if !q.get::<bool, _>("template_id") {
+            sqlx::query("ALTER TABLE sku_list ADD template_id INT NOT NULL DEFAULT 0;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+    }
+
+    if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name='orders'")
+        .fetch_optional(tx.as_mut())
+        .await
+        .unwrap()
+        .is_some()
+    {
+        let q = sqlx::query(
+            "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='to_warehouse_id'), 0) AS to_warehouse_id,
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='in_transit'), 0) AS in_transit;
+        ",
+        )
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+
+        if !q.get::<bool, _>("to_warehouse_id") {
+            sqlx::query("ALTER TABLE orders ADD to_warehouse_id INT NOT NULL DEFAULT 0;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+
+        if !q.get::<bool, _>("in_transit") {
+            sqlx::query("ALTER TABLE orders ADD in_transit BOOLEAN NOT NULL DEFAULT False;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+    }
+
+    for table in ["order_items", "order_revision_items"] {
+        if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name=?")
+            .bind(table)
+            .fetch_optional(tx.as_mut())
+            .await
+            .unwrap()
+            .is_some()
+        {
+            let q = sqlx::query(&format!(
+                "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='lot_number'), 0) AS lot_number,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='expiry_date'), 0) AS expiry_date,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='serial_numbers'), 0) AS serial_numbers;
+        "
+            ))
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+
+            if !q.get::<bool, _>("lot_number") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD lot_number TEXT NULL;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+
+            if !q.get::<bool, _>("expiry_date") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD expiry_date INT NULL;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+
+            if !q.get::<bool, _>("serial_numbers") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD serial_numbers TEXT NOT NULL DEFAULT '[]';"))
+                    .execute(tx.as_mut())
+                    .await
+                    .unwrap();
+                updated += 1;
+            }
+        }
+    }
+
+    if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name='orders'")
+        .fetch_optional(tx.as_mut())
+        .await
+        .unwrap()
+        .is_some()
+    {
+        let q = sqlx::query(
+            "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='tax_inclusive'), 0) AS tax_inclusive,
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='net_amount'), 0) AS net_amount,
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='tax_amount'), 0) AS tax_amount,
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='discount'), 0) AS discount,
+    IFNULL((SELECT 1 FROM pragma_table_info('orders') WHERE name='discount_amount'), 0) AS discount_amount;
+        ",
+        )
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+
+        if !q.get::<bool, _>("tax_inclusive") {
+            sqlx::query("ALTER TABLE orders ADD tax_inclusive BOOLEAN NOT NULL DEFAULT False;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+
+        if !q.get::<bool, _>("net_amount") {
+            sqlx::query("ALTER TABLE orders ADD net_amount REAL NOT NULL DEFAULT 0;").execute(tx.as_mut()).await.unwrap();
+            // The orders before the taxes are tax free.
+            sqlx::query("UPDATE orders SET net_amount=total_amount;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+
+        if !q.get::<bool, _>("tax_amount") {
+            sqlx::query("ALTER TABLE orders ADD tax_amount REAL NOT NULL DEFAULT 0;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+
+        if !q.get::<bool, _>("discount") {
+            sqlx::query("ALTER TABLE orders ADD discount TEXT NOT NULL DEFAULT 'null';").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+
+        if !q.get::<bool, _>("discount_amount") {
+            sqlx::query("ALTER TABLE orders ADD discount_amount REAL NOT NULL DEFAULT 0;").execute(tx.as_mut()).await.unwrap();
+            updated += 1;
+        }
+    }
+
+    for table in ["order_items", "order_revision_items"] {
+        if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name=?")
+            .bind(table)
+            .fetch_optional(tx.as_mut())
+            .await
+            .unwrap()
+            .is_some()
+        {
+            let q = sqlx::query(&format!(
+                "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='tax_rate'), 0) AS tax_rate,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='tax_amount'), 0) AS tax_amount,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='discount'), 0) AS discount,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='discount_amount'), 0) AS discount_amount,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='unit'), 0) AS unit,
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='unit_factor'), 0) AS unit_factor;
+        "
+            ))
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+
+            if !q.get::<bool, _>("tax_rate") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD tax_rate REAL NOT NULL DEFAULT 0;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+
+            if !q.get::<bool, _>("tax_amount") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD tax_amount REAL NOT NULL DEFAULT 0;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+
+            if !q.get::<bool, _>("discount") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD discount TEXT NOT NULL DEFAULT 'null';")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+
+            if !q.get::<bool, _>("discount_amount") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD discount_amount REAL NOT NULL DEFAULT 0;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+            if !q.get::<bool, _>("unit") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD unit TEXT NULL;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+            if !q.get::<bool, _>("unit_factor") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD unit_factor INT NOT NULL DEFAULT 1;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+        }
+    }
+
+    for table in ["sku_list", "sku_categories"] {
+        if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name=?")
+            .bind(table)
+            .fetch_optional(tx.as_mut())
+            .await
+            .unwrap()
+            .is_some()
+        {
+            let q = sqlx::query(&format!(
+                "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='tax_rate_id'), 0) AS tax_rate_id;
+        "
+            ))
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+
+            if !q.get::<bool, _>("tax_rate_id") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD tax_rate_id INT NOT NULL DEFAULT 0;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+        }
+    }
+
+    for table in ["persons", "areas"] {
+        if sqlx::query("SELECT 1 FROM sqlite_schema WHERE type='table' AND name=?")
+            .bind(table)
+            .fetch_optional(tx.as_mut())
+            .await
+            .unwrap()
+            .is_some()
+        {
+            let q = sqlx::query(&format!(
+                "
+        SELECT
+    IFNULL((SELECT 1 FROM pragma_table_info('{table}') WHERE name='price_list_id'), 0) AS price_list_id;
+        "
+            ))
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+
+            if !q.get::<bool, _>("price_list_id") {
+                sqlx::query(&format!("ALTER TABLE {table} ADD price_list_id INT NOT NULL DEFAULT 0;")).execute(tx.as_mut()).await.unwrap();
+                updated += 1;
+            }
+        }
+    }
+
     tx.commit().await.unwrap();
     updated > 0
 }
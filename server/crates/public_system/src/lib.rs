@@ -162,7 +162,7 @@ impl PublicSystem {
     }
 
     pub async fn get_backup_count(&self, tx: &mut SqliteConnection) -> Result<i64> {
-        let row = sqlx::query(&format!("SELECT count(*) AS count FROM backup_records")).fetch_one(&mut *tx).await?;
+        let row = sqlx::query("SELECT count(*) AS count FROM backup_records").fetch_one(&mut *tx).await?;
         Ok(row.get("count"))
     }
 
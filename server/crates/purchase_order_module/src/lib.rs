@@ -0,0 +1,361 @@
+use anyhow::{bail, Result};
+use elerp_common::{
+    model::{action_type::ActionType, Pagination, WebSocketFlags},
+    order_module::model::order::{Order, OrderCurrency},
+    purchase_order_module::model::purchase_order::{GetPurchaseOrdersQuery, PurchaseOrder, PurchaseOrderItem, PurchaseOrderStatus},
+    sql::{self, is_exists_in_table, remove_row_from_table},
+    user_system::model::user_info::{UserInfo, UserType},
+};
+use futures::TryStreamExt;
+use public_system::PublicSystem;
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
+
+#[derive(Debug, Clone)]
+pub struct PurchaseOrderModule {
+    ps: PublicSystem,
+}
+
+impl PurchaseOrderModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.begin_tx(true).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS purchase_orders(
+                id INTEGER PRIMARY KEY,
+                created_by_user_id INT NOT NULL,
+                updated_by_user_id INT NOT NULL,
+                date INT NOT NULL,
+                last_updated_date INT NOT NULL,
+                person_in_charge_id INT NOT NULL,
+                supplier_id INT NOT NULL,
+                warehouse_id INT NOT NULL,
+                order_category_id INT NOT NULL,
+                currency TEXT NOT NULL,
+                description TEXT NOT NULL,
+                status TEXT NOT NULL,
+                total_amount REAL NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS purchase_order_items(
+                purchase_order_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                quantity INT NOT NULL,
+                price REAL NOT NULL,
+                received_quantity INT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS purchase_order_receipts(
+                purchase_order_id INT NOT NULL,
+                order_id INT NOT NULL,
+                date INT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS purchase_orders_suppliers
+        ON purchase_orders(supplier_id);
+        CREATE INDEX IF NOT EXISTS purchase_orders_warehouses
+        ON purchase_orders(warehouse_id);
+        CREATE INDEX IF NOT EXISTS purchase_orders_order_category_ids
+        ON purchase_orders(order_category_id);
+        CREATE INDEX IF NOT EXISTS purchase_orders_status
+        ON purchase_orders(status);
+        CREATE INDEX IF NOT EXISTS purchase_order_items_purchase_order_ids
+        ON purchase_order_items(purchase_order_id);
+        CREATE INDEX IF NOT EXISTS purchase_order_receipts_purchase_order_ids
+        ON purchase_order_receipts(purchase_order_id);
+        CREATE INDEX IF NOT EXISTS purchase_order_receipts_order_ids
+        ON purchase_order_receipts(order_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        let s = Self { ps: ps.clone() };
+
+        tx.commit().await.unwrap();
+        s
+    }
+
+    pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        is_exists_in_table("purchase_orders", "id", id, tx).await
+    }
+
+    /// The order is created by receiving the purchase order.
+    pub async fn is_receipt_order(&self, order_id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        is_exists_in_table("purchase_order_receipts", "order_id", order_id, tx).await
+    }
+
+    pub async fn can_access(&self, id: i64, user: &UserInfo, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(user.user_type == UserType::Admin
+            || sqlx::query("SELECT id FROM purchase_orders WHERE id=? AND created_by_user_id=? LIMIT 1")
+                .bind(id)
+                .bind(user.id)
+                .fetch(&mut *tx)
+                .try_next()
+                .await?
+                .is_some())
+    }
+
+    pub fn preprocess(&self, order: &mut PurchaseOrder, user: &UserInfo, initial: bool, person_in_charge_id: i64) {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        order.updated_by_user_id = user.id;
+        if initial {
+            order.date = now;
+            order.created_by_user_id = user.id;
+        }
+        order.last_updated_date = now;
+        order.person_in_charge_id = person_in_charge_id;
+        order.status = PurchaseOrderStatus::Draft;
+        order.order_ids = vec![];
+        if let Some(items) = order.items.as_mut() {
+            items.iter_mut().for_each(|item| item.received_quantity = 0);
+        }
+        order.total_amount = order.items.iter().flatten().map(|item| item.quantity as f64 * item.price).sum();
+    }
+
+    async fn add_items(&self, id: i64, items: &[PurchaseOrderItem], tx: &mut SqliteConnection) -> Result<()> {
+        for item in items {
+            sqlx::query("INSERT INTO purchase_order_items (purchase_order_id, sku_id, quantity, price, received_quantity) VALUES (?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(item.sku_id)
+                .bind(item.quantity)
+                .bind(item.price)
+                .bind(item.received_quantity)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn add(&self, mut order: PurchaseOrder, tx: &mut SqliteConnection) -> Result<PurchaseOrder> {
+        let r = sqlx::query("INSERT INTO purchase_orders (created_by_user_id, updated_by_user_id, date, last_updated_date, person_in_charge_id, supplier_id, warehouse_id, order_category_id, currency, description, status, total_amount) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(order.created_by_user_id)
+            .bind(order.updated_by_user_id)
+            .bind(order.date)
+            .bind(order.last_updated_date)
+            .bind(order.person_in_charge_id)
+            .bind(order.supplier_id)
+            .bind(order.warehouse_id)
+            .bind(order.order_category_id)
+            .bind(order.currency)
+            .bind(&order.description)
+            .bind(order.status)
+            .bind(order.total_amount)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't insert the order to purchase_orders!");
+        }
+        order.id = sql::try_set_standard_id(r.last_insert_rowid(), "purchase_orders", tx).await?;
+        if let Some(items) = order.items.as_ref() {
+            self.add_items(order.id, items, tx).await?;
+        }
+
+        self.ps.notice(WebSocketFlags::AddPurchaseOrder(order.id)).await?;
+        Ok(order)
+    }
+
+    /// Update the draft purchase order, the items are replaced if it is not none.
+    pub async fn update(&self, id: i64, v: PurchaseOrder, tx: &mut SqliteConnection) -> Result<Option<PurchaseOrder>> {
+        let r = sqlx::query("UPDATE purchase_orders SET updated_by_user_id=?, last_updated_date=?, person_in_charge_id=?, supplier_id=?, warehouse_id=?, order_category_id=?, currency=?, description=? WHERE id=?")
+            .bind(v.updated_by_user_id)
+            .bind(v.last_updated_date)
+            .bind(v.person_in_charge_id)
+            .bind(v.supplier_id)
+            .bind(v.warehouse_id)
+            .bind(v.order_category_id)
+            .bind(v.currency)
+            .bind(&v.description)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            return Ok(None);
+        }
+        if let Some(items) = v.items.as_ref() {
+            sqlx::query("DELETE FROM purchase_order_items WHERE purchase_order_id=?").bind(id).execute(&mut *tx).await?;
+            self.add_items(id, items, tx).await?;
+            sqlx::query("UPDATE purchase_orders SET total_amount=? WHERE id=?").bind(v.total_amount).bind(id).execute(&mut *tx).await?;
+        }
+        self.ps.notice(WebSocketFlags::UpdatePurchaseOrder(id)).await?;
+        self.get(id, tx).await
+    }
+
+    pub async fn set_status(&self, id: i64, status: PurchaseOrderStatus, user_id: i64, tx: &mut SqliteConnection) -> Result<Option<PurchaseOrder>> {
+        let r = sqlx::query("UPDATE purchase_orders SET status=?, updated_by_user_id=?, last_updated_date=? WHERE id=?")
+            .bind(status)
+            .bind(user_id)
+            .bind(self.ps.get_timestamp_seconds() as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::UpdatePurchaseOrder(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Count the items of the stock in order as received, the status is changed by the outstanding quantities.
+    /// The quantity is received by the lines of the sku in order, so the lines of the same sku are not counted twice.
+    pub async fn receive(&self, id: i64, order: &Order, user_id: i64, tx: &mut SqliteConnection) -> Result<Option<PurchaseOrder>> {
+        if !self.is_exists(id, tx).await? {
+            return Ok(None);
+        }
+        for item in order.items.iter().flatten() {
+            let lines = sqlx::query("SELECT rowid, quantity, received_quantity FROM purchase_order_items WHERE purchase_order_id=? AND sku_id=? ORDER BY rowid")
+                .bind(id)
+                .bind(item.sku_id)
+                .fetch_all(&mut *tx)
+                .await?;
+            let mut rest = item.quantity;
+            for (i, line) in lines.iter().enumerate() {
+                // The last line takes the rest.
+                let quantity = if i + 1 == lines.len() {
+                    rest
+                } else {
+                    rest.min((line.get::<i64, _>("quantity") - line.get::<i64, _>("received_quantity")).max(0))
+                };
+                if quantity == 0 {
+                    continue;
+                }
+                sqlx::query("UPDATE purchase_order_items SET received_quantity=received_quantity+? WHERE rowid=?")
+                    .bind(quantity)
+                    .bind(line.get::<i64, _>("rowid"))
+                    .execute(&mut *tx)
+                    .await?;
+                rest -= quantity;
+            }
+        }
+        sqlx::query("INSERT INTO purchase_order_receipts (purchase_order_id, order_id, date) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(order.id)
+            .bind(order.date)
+            .execute(&mut *tx)
+            .await?;
+        let outstanding: i64 = sqlx::query("SELECT COUNT(*) AS count FROM purchase_order_items WHERE purchase_order_id=? AND received_quantity<quantity")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("count");
+        let status = if outstanding > 0 { PurchaseOrderStatus::PartiallyReceived } else { PurchaseOrderStatus::Received };
+        self.set_status(id, status, user_id, tx).await
+    }
+
+    pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+        if remove_row_from_table(id, "purchase_orders", tx).await? {
+            sqlx::query("DELETE FROM purchase_order_items WHERE purchase_order_id=?").bind(id).execute(&mut *tx).await?;
+            if notice {
+                self.ps.notice(WebSocketFlags::RemovePurchaseOrder(id)).await?;
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn row_to_order(&self, row: SqliteRow) -> PurchaseOrder {
+        PurchaseOrder {
+            id: row.get("id"),
+            created_by_user_id: row.get("created_by_user_id"),
+            updated_by_user_id: row.get("updated_by_user_id"),
+            date: row.get("date"),
+            last_updated_date: row.get("last_updated_date"),
+            person_in_charge_id: row.get("person_in_charge_id"),
+            supplier_id: row.get("supplier_id"),
+            warehouse_id: row.get("warehouse_id"),
+            order_category_id: row.get("order_category_id"),
+            currency: row.try_get("currency").unwrap_or(OrderCurrency::Unknown),
+            description: row.get("description"),
+            status: row.get("status"),
+            total_amount: row.get("total_amount"),
+            items: None,
+            order_ids: vec![],
+        }
+    }
+
+    pub async fn get_items(&self, id: i64, tx: &mut SqliteConnection) -> Result<Vec<PurchaseOrderItem>> {
+        Ok(sqlx::query_as::<_, PurchaseOrderItem>("SELECT sku_id, quantity, price, received_quantity FROM purchase_order_items WHERE purchase_order_id=? ORDER BY rowid")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn get_order_ids(&self, id: i64, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let rows = sqlx::query("SELECT order_id FROM purchase_order_receipts WHERE purchase_order_id=? ORDER BY rowid").bind(id).fetch_all(&mut *tx).await?;
+        Ok(rows.into_iter().map(|row| row.get("order_id")).collect())
+    }
+
+    pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<PurchaseOrder>> {
+        let row = sqlx::query("SELECT * FROM purchase_orders WHERE id=? LIMIT 1").bind(id).fetch_optional(&mut *tx).await?;
+        Ok(match row {
+            Some(row) => {
+                let mut order = self.row_to_order(row);
+                order.items = Some(self.get_items(id, tx).await?);
+                order.order_ids = self.get_order_ids(id, tx).await?;
+                Some(order)
+            }
+            None => None,
+        })
+    }
+
+    fn get_permission_inner(&self, action: ActionType) -> String {
+        match action {
+            ActionType::General(id) | ActionType::GeneralAllowed(id) => {
+                format!(
+                    "INNER JOIN warehouse_permission
+                ON warehouse_permission.user_id={id} AND warehouse_permission.warehouse_id=purchase_orders.warehouse_id"
+                )
+            }
+            ActionType::Admin | ActionType::System => String::new(),
+        }
+    }
+
+    const SELECT_MULTIPLE: &'static str = "
+    SELECT
+    purchase_orders.*,
+    suppliers.name AS supplier_name,
+    warehouses.name AS warehouse_name
+    FROM purchase_orders
+    INNER JOIN persons AS suppliers ON purchase_orders.supplier_id=suppliers.id
+    INNER JOIN warehouses ON purchase_orders.warehouse_id=warehouses.id";
+
+    pub async fn get_multiple(&self, pagination: &Pagination, query: &GetPurchaseOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<PurchaseOrder>> {
+        let s = Self::SELECT_MULTIPLE;
+        let qw = query.get_where_condition();
+        let ob = query.get_order_condition();
+        let inner = self.get_permission_inner(action);
+
+        let rows = sqlx::query(&format!("{s} {inner} {qw} {ob} LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut arr = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut order = self.row_to_order(row);
+            order.order_ids = self.get_order_ids(order.id, tx).await?;
+            arr.push(order);
+        }
+
+        Ok(arr)
+    }
+
+    pub async fn get_count(&self, query: &GetPurchaseOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<i64> {
+        let s = Self::SELECT_MULTIPLE;
+        let qw = query.get_where_condition();
+        let inner = self.get_permission_inner(action);
+        let row = sqlx::query(&format!("SELECT count(*) as count FROM ({s} {inner} {qw}) AS tbl")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+}
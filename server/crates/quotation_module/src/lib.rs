@@ -0,0 +1,403 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use elerp_common::{
+    currency_module::get_rate_sql,
+    model::{action_type::ActionType, Pagination, WebSocketFlags},
+    order_module::model::order::OrderCurrency,
+    quotation_module::model::quotation::{GetQuotationsQuery, Quotation, QuotationItem, QuotationStatus},
+    sql::{self, is_exists_in_table, remove_row_from_table},
+    tax_module,
+    user_system::model::user_info::{UserInfo, UserType},
+};
+use futures::TryStreamExt;
+use public_system::PublicSystem;
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
+use tokio::{fs, io::AsyncWriteExt};
+
+#[derive(Debug, Clone)]
+pub struct QuotationModule {
+    ps: PublicSystem,
+}
+
+impl QuotationModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.begin_tx(true).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS quotations(
+                id INTEGER PRIMARY KEY,
+                created_by_user_id INT NOT NULL,
+                updated_by_user_id INT NOT NULL,
+                date INT NOT NULL,
+                last_updated_date INT NOT NULL,
+                person_in_charge_id INT NOT NULL,
+                person_related_id INT NOT NULL,
+                warehouse_id INT NOT NULL,
+                order_category_id INT NOT NULL,
+                currency TEXT NOT NULL,
+                description TEXT NOT NULL,
+                valid_until INT NOT NULL,
+                status TEXT NOT NULL,
+                revision INT NOT NULL,
+                order_id INT NOT NULL DEFAULT 0,
+                total_amount REAL NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS quotation_items(
+                quotation_id INT NOT NULL,
+                sku_id INT NOT NULL,
+                quantity INT NOT NULL,
+                price REAL NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS quotations_person_related_ids
+        ON quotations(person_related_id);
+        CREATE INDEX IF NOT EXISTS quotations_warehouses
+        ON quotations(warehouse_id);
+        CREATE INDEX IF NOT EXISTS quotations_status
+        ON quotations(status);
+        CREATE INDEX IF NOT EXISTS quotations_valid_until
+        ON quotations(valid_until);
+        CREATE INDEX IF NOT EXISTS quotation_items_quotation_ids
+        ON quotation_items(quotation_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        let s = Self { ps: ps.clone() };
+
+        tx.commit().await.unwrap();
+        s
+    }
+
+    pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        is_exists_in_table("quotations", "id", id, tx).await
+    }
+
+    pub async fn can_access(&self, id: i64, user: &UserInfo, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(user.user_type == UserType::Admin
+            || sqlx::query("SELECT id FROM quotations WHERE id=? AND created_by_user_id=? LIMIT 1")
+                .bind(id)
+                .bind(user.id)
+                .fetch(&mut *tx)
+                .try_next()
+                .await?
+                .is_some())
+    }
+
+    pub fn preprocess(&self, quotation: &mut Quotation, user: &UserInfo, initial: bool, person_in_charge_id: i64) {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        quotation.updated_by_user_id = user.id;
+        if initial {
+            quotation.date = now;
+            quotation.created_by_user_id = user.id;
+            quotation.revision = 0;
+        }
+        quotation.last_updated_date = now;
+        quotation.person_in_charge_id = person_in_charge_id;
+        quotation.status = QuotationStatus::Draft;
+        quotation.order_id = 0;
+    }
+
+    /// Total amount of the order converted from the quotation, the discounts and the default tax rates are applied as the order.
+    async fn calc_total_amount(quotation: &Quotation, tx: &mut SqliteConnection) -> Result<f64> {
+        let mut order = quotation.to_order();
+        order.calc_discounts();
+        tax_module::apply(&mut order, tx).await?;
+        Ok(order.total_amount)
+    }
+
+    /// The quotations table with the status of the ones passed the valid date read as expired, the rows are not changed.
+    fn table(&self) -> String {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        format!(
+            "(SELECT id, created_by_user_id, updated_by_user_id, date, last_updated_date, person_in_charge_id, person_related_id, warehouse_id, order_category_id, currency, description, valid_until,
+            CASE WHEN status IN ('Draft', 'Sent') AND valid_until<{now} THEN 'Expired' ELSE status END AS status,
+            revision, order_id, total_amount
+            FROM quotations) AS quotations"
+        )
+    }
+
+    async fn add_items(&self, id: i64, items: &[QuotationItem], tx: &mut SqliteConnection) -> Result<()> {
+        for item in items {
+            sqlx::query("INSERT INTO quotation_items (quotation_id, sku_id, quantity, price) VALUES (?, ?, ?, ?)")
+                .bind(id)
+                .bind(item.sku_id)
+                .bind(item.quantity)
+                .bind(item.price)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn add(&self, mut quotation: Quotation, tx: &mut SqliteConnection) -> Result<Quotation> {
+        quotation.total_amount = Self::calc_total_amount(&quotation, tx).await?;
+        let r = sqlx::query("INSERT INTO quotations (created_by_user_id, updated_by_user_id, date, last_updated_date, person_in_charge_id, person_related_id, warehouse_id, order_category_id, currency, description, valid_until, status, revision, total_amount) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(quotation.created_by_user_id)
+            .bind(quotation.updated_by_user_id)
+            .bind(quotation.date)
+            .bind(quotation.last_updated_date)
+            .bind(quotation.person_in_charge_id)
+            .bind(quotation.person_related_id)
+            .bind(quotation.warehouse_id)
+            .bind(quotation.order_category_id)
+            .bind(quotation.currency)
+            .bind(&quotation.description)
+            .bind(quotation.valid_until)
+            .bind(quotation.status)
+            .bind(quotation.revision)
+            .bind(quotation.total_amount)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't insert the quotation to quotations!");
+        }
+        quotation.id = sql::try_set_standard_id(r.last_insert_rowid(), "quotations", tx).await?;
+        if let Some(items) = quotation.items.as_ref() {
+            self.add_items(quotation.id, items, tx).await?;
+        }
+
+        self.ps.notice(WebSocketFlags::AddQuotation(quotation.id)).await?;
+        Ok(quotation)
+    }
+
+    /// Revise the quotation, it is changed to draft and the revision is increased.
+    pub async fn update(&self, id: i64, v: Quotation, tx: &mut SqliteConnection) -> Result<Option<Quotation>> {
+        let r = sqlx::query("UPDATE quotations SET updated_by_user_id=?, last_updated_date=?, person_in_charge_id=?, person_related_id=?, warehouse_id=?, order_category_id=?, currency=?, description=?, valid_until=?, status=?, revision=revision+1 WHERE id=?")
+            .bind(v.updated_by_user_id)
+            .bind(v.last_updated_date)
+            .bind(v.person_in_charge_id)
+            .bind(v.person_related_id)
+            .bind(v.warehouse_id)
+            .bind(v.order_category_id)
+            .bind(v.currency)
+            .bind(&v.description)
+            .bind(v.valid_until)
+            .bind(v.status)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            return Ok(None);
+        }
+        if let Some(items) = v.items.as_ref() {
+            sqlx::query("DELETE FROM quotation_items WHERE quotation_id=?").bind(id).execute(&mut *tx).await?;
+            self.add_items(id, items, tx).await?;
+            let total_amount = Self::calc_total_amount(&v, tx).await?;
+            sqlx::query("UPDATE quotations SET total_amount=? WHERE id=?").bind(total_amount).bind(id).execute(&mut *tx).await?;
+        }
+        self.ps.notice(WebSocketFlags::UpdateQuotation(id)).await?;
+        self.get(id, tx).await
+    }
+
+    pub async fn set_status(&self, id: i64, status: QuotationStatus, user_id: i64, tx: &mut SqliteConnection) -> Result<Option<Quotation>> {
+        let r = sqlx::query("UPDATE quotations SET status=?, updated_by_user_id=?, last_updated_date=? WHERE id=?")
+            .bind(status)
+            .bind(user_id)
+            .bind(self.ps.get_timestamp_seconds() as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::UpdateQuotation(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Mark the quotation is converted to the order.
+    pub async fn convert(&self, id: i64, order_id: i64, user_id: i64, tx: &mut SqliteConnection) -> Result<Option<Quotation>> {
+        sqlx::query("UPDATE quotations SET order_id=? WHERE id=?").bind(order_id).bind(id).execute(&mut *tx).await?;
+        self.set_status(id, QuotationStatus::Converted, user_id, tx).await
+    }
+
+    pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
+        if remove_row_from_table(id, "quotations", tx).await? {
+            sqlx::query("DELETE FROM quotation_items WHERE quotation_id=?").bind(id).execute(&mut *tx).await?;
+            if notice {
+                self.ps.notice(WebSocketFlags::RemoveQuotation(id)).await?;
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn row_to_quotation(&self, row: SqliteRow) -> Quotation {
+        Quotation {
+            id: row.get("id"),
+            created_by_user_id: row.get("created_by_user_id"),
+            updated_by_user_id: row.get("updated_by_user_id"),
+            date: row.get("date"),
+            last_updated_date: row.get("last_updated_date"),
+            person_in_charge_id: row.get("person_in_charge_id"),
+            person_related_id: row.get("person_related_id"),
+            warehouse_id: row.get("warehouse_id"),
+            order_category_id: row.get("order_category_id"),
+            currency: row.try_get("currency").unwrap_or(OrderCurrency::Unknown),
+            description: row.get("description"),
+            valid_until: row.get("valid_until"),
+            status: row.get("status"),
+            revision: row.get("revision"),
+            order_id: row.get("order_id"),
+            total_amount: row.get("total_amount"),
+            items: None,
+        }
+    }
+
+    pub async fn get_items(&self, id: i64, tx: &mut SqliteConnection) -> Result<Vec<QuotationItem>> {
+        Ok(sqlx::query_as::<_, QuotationItem>("SELECT sku_id, quantity, price FROM quotation_items WHERE quotation_id=? ORDER BY rowid")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Quotation>> {
+        let table = self.table();
+        let row = sqlx::query(&format!("SELECT * FROM {table} WHERE id=? LIMIT 1")).bind(id).fetch_optional(&mut *tx).await?;
+        Ok(match row {
+            Some(row) => {
+                let mut quotation = self.row_to_quotation(row);
+                quotation.items = Some(self.get_items(id, tx).await?);
+                Some(quotation)
+            }
+            None => None,
+        })
+    }
+
+    fn get_permission_inner(&self, action: ActionType) -> String {
+        match action {
+            ActionType::General(id) | ActionType::GeneralAllowed(id) => {
+                format!(
+                    "INNER JOIN warehouse_permission
+                ON warehouse_permission.user_id={id} AND warehouse_permission.warehouse_id=quotations.warehouse_id"
+                )
+            }
+            ActionType::Admin | ActionType::System => String::new(),
+        }
+    }
+
+    fn select_multiple(&self) -> String {
+        let table = self.table();
+        format!(
+            "
+    SELECT
+    quotations.*,
+    persons_related.name AS person_related_name,
+    warehouses.name AS warehouse_name
+    FROM {table}
+    INNER JOIN persons AS persons_related ON quotations.person_related_id=persons_related.id
+    INNER JOIN warehouses ON quotations.warehouse_id=warehouses.id"
+        )
+    }
+
+    pub async fn get_multiple(&self, pagination: &Pagination, query: &GetQuotationsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<Quotation>> {
+        let s = self.select_multiple();
+        let qw = query.get_where_condition();
+        let ob = query.get_order_condition();
+        let inner = self.get_permission_inner(action);
+
+        let rows = sqlx::query(&format!("{s} {inner} {qw} {ob} LIMIT ? OFFSET ?"))
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?;
+        Ok(rows.into_iter().map(|row| self.row_to_quotation(row)).collect())
+    }
+
+    pub async fn get_count(&self, query: &GetQuotationsQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<i64> {
+        let s = self.select_multiple();
+        let qw = query.get_where_condition();
+        let inner = self.get_permission_inner(action);
+        let row = sqlx::query(&format!("SELECT count(*) as count FROM ({s} {inner} {qw}) AS tbl")).fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
+    /// Quotation document in excel file to send to the customer, none if the quotation is not exists.
+    pub async fn get_document(&self, id: i64, show_price: bool, tx: &mut SqliteConnection) -> Result<Option<PathBuf>> {
+        use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet};
+        let quotation = match self.get(id, tx).await? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let customer: String = sqlx::query("SELECT name FROM persons WHERE id=? LIMIT 1")
+            .bind(quotation.person_related_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.get("name"))
+            .unwrap_or_default();
+        let valid_until = chrono::DateTime::from_timestamp(quotation.valid_until, 0).map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+
+        let mut workbook = Workbook::new();
+        let title_format = Format::new().set_bold().set_font_size(16);
+        let header_format = Format::new()
+            .set_background_color(Color::Theme(4, 0))
+            .set_font_color(Color::Theme(0, 0))
+            .set_border(FormatBorder::Thin)
+            .set_bold()
+            .set_align(FormatAlign::Center)
+            .set_align(FormatAlign::VerticalCenter);
+        let data_format = Format::new().set_border(FormatBorder::Thin).set_align(FormatAlign::Center).set_align(FormatAlign::VerticalCenter);
+
+        let worksheet: &mut Worksheet = workbook.add_worksheet();
+        worksheet.set_column_width(0, 6)?;
+        worksheet.set_column_width(1, 45)?;
+        worksheet.set_column_width(2, 12)?;
+        worksheet.set_column_width(3, 12)?;
+        worksheet.set_column_width(4, 15)?;
+        worksheet.write_with_format(0, 0, format!("Quotation #{} (Revision {})", quotation.id, quotation.revision), &title_format)?;
+        worksheet.write_row(1, 0, ["Customer", &customer])?;
+        worksheet.write_row(2, 0, ["Valid Until", &valid_until])?;
+        worksheet.write_row(3, 0, ["Currency", quotation.currency.as_ref()])?;
+        worksheet.write_row(4, 0, ["Description", &quotation.description])?;
+        worksheet.write_row_with_format(6, 0, ["No", "SKU", "Quantity", "Price", "Amount"], &header_format)?;
+        let items = quotation.items.unwrap_or_default();
+        for (i, item) in items.iter().enumerate() {
+            let sku: String = sqlx::query("SELECT name FROM sku_list WHERE id=? LIMIT 1").bind(item.sku_id).fetch_one(&mut *tx).await?.get("name");
+            let row = (i + 7) as u32;
+            worksheet.write_with_format(row, 0, (i + 1) as u32, &data_format)?;
+            worksheet.write_with_format(row, 1, &sku, &data_format)?;
+            worksheet.write_with_format(row, 2, item.quantity, &data_format)?;
+            if show_price {
+                worksheet.write_row_with_format(row, 3, [item.price, item.quantity as f64 * item.price], &data_format)?;
+            }
+        }
+        if show_price {
+            let row = (items.len() + 7) as u32;
+            worksheet.write_with_format(row, 3, "Total", &header_format)?;
+            worksheet.write_with_format(row, 4, quotation.total_amount, &data_format)?;
+            let base = self.ps.get_config().currency.base;
+            if quotation.currency != base {
+                let rate = get_rate_sql(base, "quotations.currency", "quotations.date");
+                let rate: Option<f64> = sqlx::query(&format!("SELECT {rate} AS rate FROM quotations WHERE id=?")).bind(id).fetch_one(&mut *tx).await?.get("rate");
+                if let Some(rate) = rate {
+                    worksheet.write_with_format(row + 1, 3, format!("Total ({base})"), &header_format)?;
+                    worksheet.write_with_format(row + 1, 4, quotation.total_amount * rate, &data_format)?;
+                }
+            }
+        }
+
+        let excels = self.ps.get_data_path().join("excels").join("quotations");
+        if !excels.is_dir() {
+            fs::create_dir_all(&excels).await?;
+        }
+        let path = excels.join(format!("quotation-{}-{}.xlsx", quotation.id, self.ps.get_timestamp_seconds()));
+        if path.is_file() {
+            fs::remove_file(&path).await?;
+        }
+        let mut file = fs::File::create(&path).await?;
+        let buffer = workbook.save_to_buffer()?;
+        file.write_all(&buffer).await?;
+        Ok(Some(path))
+    }
+}
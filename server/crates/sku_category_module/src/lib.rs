@@ -1,5 +1,5 @@
 use anyhow::bail;
-use elerp_common::sql::{get_row_from_table, is_exists_in_table, remove_row_from_table, row_is_duplicate_col_in_table, rows_to_objects};
+use elerp_common::sql::{get_active_row_from_table, is_active_in_table, purge_rows_from_table, restore_row_from_table, row_is_duplicate_col_in_table, rows_to_objects, soft_remove_row_from_table};
 use elerp_common::{
     model::{Pagination, WebSocketFlags},
     sku_category_module::model::sku_category::{GetSKUCategoriesQuery, SKUCategory},
@@ -26,7 +26,9 @@ impl SKUCategoryModule {
                 name TEXT NOT NULL,
                 description TEXT NOT NULL,
                 color TEXT NULL,
-                text_color TEXT NULL
+                text_color TEXT NULL,
+                tax_rate_id INT NOT NULL DEFAULT 0,
+                deleted_at INT NULL
             )",
         )
         .execute(conn)
@@ -44,11 +46,11 @@ impl SKUCategoryModule {
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("sku_categories", "id", id, tx).await
+        is_active_in_table("sku_categories", id, tx).await
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM sku_categories;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM sku_categories WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.sku_categories)
     }
 
@@ -57,11 +59,12 @@ impl SKUCategoryModule {
     }
 
     pub async fn add(&self, mut v: SKUCategory, tx: &mut SqliteConnection) -> Result<SKUCategory> {
-        let r = sqlx::query("INSERT INTO sku_categories (name, description, color, text_color) VALUES(?, ?, ?, ?)")
+        let r = sqlx::query("INSERT INTO sku_categories (name, description, color, text_color, tax_rate_id) VALUES(?, ?, ?, ?, ?)")
             .bind(&v.name)
             .bind(&v.description)
             .bind(&v.color)
             .bind(&v.text_color)
+            .bind(v.tax_rate_id)
             .execute(&mut *tx)
             .await?;
         if r.rows_affected() != 1 {
@@ -73,15 +76,28 @@ impl SKUCategoryModule {
     }
 
     pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = remove_row_from_table(id, "sku_categories", tx).await?;
+        let r = soft_remove_row_from_table(id, "sku_categories", self.ps.get_timestamp_seconds() as i64, tx).await?;
         if notice {
             self.ps.notice(WebSocketFlags::RemoveSKUCategory(id)).await?;
         }
         Ok(r)
     }
 
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<SKUCategory>> {
+        if !restore_row_from_table(id, "sku_categories", tx).await? {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::RestoreSKUCategory(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Physically delete the sku categories removed before the date, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        purge_rows_from_table("sku_categories", deleted_before, tx).await
+    }
+
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<SKUCategory>> {
-        get_row_from_table("sku_categories", "id", id, tx).await
+        get_active_row_from_table("sku_categories", id, tx).await
     }
 
     pub async fn get_multiple(&self, pagination: &Pagination, query: &GetSKUCategoriesQuery, tx: &mut SqliteConnection) -> Result<Vec<SKUCategory>> {
@@ -93,7 +109,8 @@ impl SKUCategoryModule {
             sku_categories.name,
             sku_categories.description,
             sku_categories.color,
-            sku_categories.text_color
+            sku_categories.text_color,
+            sku_categories.tax_rate_id
             FROM sku_categories
             {qw} {ob} LIMIT ? OFFSET ?"
         ))
@@ -126,11 +143,12 @@ impl SKUCategoryModule {
     }
 
     pub async fn update(&self, id: i64, mut v: SKUCategory, tx: &mut SqliteConnection) -> Result<Option<SKUCategory>> {
-        let r = sqlx::query("UPDATE sku_categories SET name=?, description=?, color=?, text_color=? WHERE id=?")
+        let r = sqlx::query("UPDATE sku_categories SET name=?, description=?, color=?, text_color=?, tax_rate_id=? WHERE id=? AND deleted_at IS NULL")
             .bind(&v.name)
             .bind(&v.description)
             .bind(&v.color)
             .bind(&v.text_color)
+            .bind(v.tax_rate_id)
             .bind(id)
             .execute(&mut *tx)
             .await?;
@@ -144,6 +162,6 @@ impl SKUCategoryModule {
     }
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT id FROM sku_list WHERE sku_category_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+        Ok(sqlx::query("SELECT id FROM sku_list WHERE sku_category_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some())
     }
 }
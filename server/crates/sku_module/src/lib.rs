@@ -1,9 +1,17 @@
+use std::collections::{hash_map::Entry, BTreeMap};
+
 use anyhow::bail;
-use elerp_common::sql::{self, get_row_from_table, is_exists_in_table, remove_row_from_table, rows_to_objects};
+use elerp_common::sql::{
+    self, get_active_row_from_table, is_active_in_table, purge_rows_from_table, restore_row_from_table, rows_to_objects, soft_remove_row_from_table,
+};
 use elerp_common::{
-    model::{Pagination, WebSocketFlags},
-    sku_module::model::sku::{GetSKUsQuery, SKU},
+    export::{ExportWriter, NameCache, TempPath, EXPORT_PAGE_SIZE},
+    import::{IdCache, ImportRow, ImportRowResult},
+    model::{export::ExportFormat, Pagination, WebSocketFlags},
+    order_module::model::order::OrderItem,
+    sku_module::model::sku::{GetSKUsQuery, SKUUnit, SKU},
 };
+use ahash::{HashMap, HashMapExt};
 use futures::TryStreamExt;
 use sqlx::{Row, SqliteConnection};
 
@@ -26,7 +34,45 @@ impl SKUModule {
                 sku_category_id INT NOT NULL,
                 description TEXT NOT NULL,
                 color TEXT NULL,
-                text_color TEXT NULL
+                text_color TEXT NULL,
+                serialized BOOLEAN NOT NULL DEFAULT False,
+                tax_rate_id INT NOT NULL DEFAULT 0,
+                code TEXT NOT NULL DEFAULT '',
+                unit TEXT NOT NULL DEFAULT '',
+                template_id INT NOT NULL DEFAULT 0,
+                deleted_at INT NULL
+            )",
+        )
+        .execute(conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS sku_barcodes(
+                id INTEGER PRIMARY KEY,
+                sku_id INT NOT NULL,
+                barcode TEXT NOT NULL
+            )",
+        )
+        .execute(conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS sku_units(
+                id INTEGER PRIMARY KEY,
+                sku_id INT NOT NULL,
+                name TEXT NOT NULL,
+                factor INT NOT NULL
+            )",
+        )
+        .execute(conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS sku_attributes(
+                id INTEGER PRIMARY KEY,
+                sku_id INT NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL
             )",
         )
         .execute(conn)
@@ -36,7 +82,21 @@ impl SKUModule {
             "CREATE INDEX IF NOT EXISTS sku_list_names
     ON sku_list(name);
     CREATE INDEX IF NOT EXISTS sku_list_categories
-    ON sku_list(sku_category_id);",
+    ON sku_list(sku_category_id);
+    CREATE INDEX IF NOT EXISTS sku_list_codes
+    ON sku_list(code);
+    CREATE INDEX IF NOT EXISTS sku_list_templates
+    ON sku_list(template_id);
+    CREATE INDEX IF NOT EXISTS sku_barcodes_barcodes
+    ON sku_barcodes(barcode);
+    CREATE INDEX IF NOT EXISTS sku_barcodes_skus
+    ON sku_barcodes(sku_id);
+    CREATE INDEX IF NOT EXISTS sku_units_skus
+    ON sku_units(sku_id);
+    CREATE INDEX IF NOT EXISTS sku_attributes_skus
+    ON sku_attributes(sku_id);
+    CREATE INDEX IF NOT EXISTS sku_attributes_values
+    ON sku_attributes(name, value);",
         )
         .execute(conn)
         .await
@@ -45,13 +105,12 @@ impl SKUModule {
         Self { ps }
     }
 
-    #[allow(dead_code)]
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("sku_list", "id", id, tx).await
+        is_active_in_table("sku_list", id, tx).await
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM sku_list;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM sku_list WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.skus)
     }
 
@@ -66,33 +125,210 @@ impl SKUModule {
         Ok(r.try_next().await?.is_some())
     }
 
-    pub async fn add(&self, mut v: SKU, tx: &mut SqliteConnection) -> Result<SKU> {
-        let r = sqlx::query("INSERT INTO sku_list (name, description, sku_category_id, color, text_color) VALUES(?, ?, ?, ?, ?)")
+    pub async fn is_exists_code(&self, code: &str, prev: Option<i64>, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM sku_list WHERE code=? AND id<>? AND deleted_at IS NULL LIMIT 1")
+            .bind(code)
+            .bind(prev.unwrap_or_default())
+            .fetch(&mut *tx)
+            .try_next()
+            .await?
+            .is_some())
+    }
+
+    /// The first barcode used by another active sku.
+    pub async fn get_exists_barcode<'a>(&self, barcodes: &'a [String], prev: Option<i64>, tx: &mut SqliteConnection) -> Result<Option<&'a str>> {
+        for barcode in barcodes {
+            if sqlx::query("SELECT sku_barcodes.sku_id FROM sku_barcodes INNER JOIN sku_list ON sku_list.id=sku_barcodes.sku_id WHERE sku_barcodes.barcode=? AND sku_barcodes.sku_id<>? AND sku_list.deleted_at IS NULL LIMIT 1")
+                .bind(barcode)
+                .bind(prev.unwrap_or_default())
+                .fetch(&mut *tx)
+                .try_next()
+                .await?
+                .is_some()
+            {
+                return Ok(Some(barcode));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Id of the active sku with the barcode, or the sku code if no barcode is matched.
+    pub async fn get_id_by_code(&self, code: &str, tx: &mut SqliteConnection) -> Result<Option<i64>> {
+        if code.is_empty() {
+            return Ok(None);
+        }
+        let id = sqlx::query("SELECT sku_barcodes.sku_id FROM sku_barcodes INNER JOIN sku_list ON sku_list.id=sku_barcodes.sku_id WHERE sku_barcodes.barcode=? AND sku_list.deleted_at IS NULL LIMIT 1")
+            .bind(code)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if let Some(row) = id {
+            return Ok(Some(row.get("sku_id")));
+        }
+        Ok(sqlx::query("SELECT id FROM sku_list WHERE code=? AND deleted_at IS NULL LIMIT 1")
+            .bind(code)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.get("id")))
+    }
+
+    async fn set_children(&self, v: &SKU, tx: &mut SqliteConnection) -> Result<()> {
+        sqlx::query("DELETE FROM sku_barcodes WHERE sku_id=?").bind(v.id).execute(&mut *tx).await?;
+        for barcode in v.barcodes.iter() {
+            sqlx::query("INSERT INTO sku_barcodes (sku_id, barcode) VALUES(?, ?)").bind(v.id).bind(barcode).execute(&mut *tx).await?;
+        }
+        sqlx::query("DELETE FROM sku_units WHERE sku_id=?").bind(v.id).execute(&mut *tx).await?;
+        for unit in v.units.iter() {
+            sqlx::query("INSERT INTO sku_units (sku_id, name, factor) VALUES(?, ?, ?)").bind(v.id).bind(&unit.name).bind(unit.factor).execute(&mut *tx).await?;
+        }
+        sqlx::query("DELETE FROM sku_attributes WHERE sku_id=?").bind(v.id).execute(&mut *tx).await?;
+        for (name, value) in v.attributes.iter() {
+            sqlx::query("INSERT INTO sku_attributes (sku_id, name, value) VALUES(?, ?, ?)").bind(v.id).bind(name).bind(value).execute(&mut *tx).await?;
+        }
+        Ok(())
+    }
+
+    async fn fill_children(&self, list: &mut [SKU], tx: &mut SqliteConnection) -> Result<()> {
+        if list.is_empty() {
+            return Ok(());
+        }
+        let ids = list.iter().map(|v| v.id.to_string()).collect::<Vec<_>>().join(",");
+        let rows = sqlx::query(&format!("SELECT sku_id, barcode FROM sku_barcodes WHERE sku_id IN ({ids}) ORDER BY id")).fetch_all(&mut *tx).await?;
+        for row in rows {
+            let sku_id: i64 = row.get("sku_id");
+            if let Some(v) = list.iter_mut().find(|v| v.id == sku_id) {
+                v.barcodes.push(row.get("barcode"));
+            }
+        }
+        let rows = sqlx::query(&format!("SELECT sku_id, name, factor FROM sku_units WHERE sku_id IN ({ids}) ORDER BY id")).fetch_all(&mut *tx).await?;
+        for row in rows {
+            let sku_id: i64 = row.get("sku_id");
+            if let Some(v) = list.iter_mut().find(|v| v.id == sku_id) {
+                v.units.push(SKUUnit {
+                    name: row.get("name"),
+                    factor: row.get("factor"),
+                });
+            }
+        }
+        let rows = sqlx::query(&format!("SELECT sku_id, name, value FROM sku_attributes WHERE sku_id IN ({ids})")).fetch_all(&mut *tx).await?;
+        for row in rows {
+            let sku_id: i64 = row.get("sku_id");
+            if let Some(v) = list.iter_mut().find(|v| v.id == sku_id) {
+                v.attributes.insert(row.get("name"), row.get("value"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the quantities of the items entered in the units of the skus to the base units,
+    /// the prices are kept as the prices of the units. The factors are always resolved by the skus,
+    /// the items with the factor are in the base units already and their factor must be the one of the sku.
+    /// Return the sku and the unit not found or not matched.
+    pub async fn fill_units(&self, items: &mut [OrderItem], tx: &mut SqliteConnection) -> Result<Option<(i64, String)>> {
+        let mut skus: HashMap<i64, Option<SKU>> = HashMap::new();
+        for item in items.iter_mut() {
+            let unit = item.unit.as_deref().unwrap_or_default();
+            let factor = if unit.is_empty() {
+                Some(1)
+            } else {
+                if let Entry::Vacant(e) = skus.entry(item.sku_id) {
+                    e.insert(self.get(item.sku_id, tx).await?);
+                }
+                skus[&item.sku_id].as_ref().and_then(|sku| sku.get_unit_factor(unit))
+            };
+            match factor {
+                Some(factor) if item.unit_factor == 0 => {
+                    item.quantity *= factor;
+                    item.unit_factor = factor;
+                }
+                Some(factor) if item.unit_factor == factor => (),
+                _ => return Ok(Some((item.sku_id, unit.to_owned()))),
+            }
+            if unit.is_empty() {
+                item.unit = None;
+            }
+        }
+        Ok(None)
+    }
+
+    /// The sku of the import row, the columns are the same as the export.
+    pub async fn parse_import_row(&self, row: &ImportRow<'_>, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<SKU> {
+        row.get_required("SKU Category")?;
+        Ok(SKU {
+            id: 0,
+            sku_category_id: row.get_id("SKU Category", "sku_categories", ids, tx).await?,
+            name: row.get_required("Name")?.to_owned(),
+            description: row.get("Description").to_owned(),
+            color: None,
+            text_color: None,
+            serialized: row.get_bool("Serialized")?,
+            tax_rate_id: row.get_id("Tax Rate", "tax_rates", ids, tx).await?,
+            code: row.get("Code").to_owned(),
+            unit: row.get("Unit").to_owned(),
+            barcodes: row.get("Barcodes").split(',').map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.to_owned()).collect(),
+            units: parse_units(row.get("Units")).ok_or_else(|| "Units must be the list of the unit=factor.".to_owned())?,
+            template_id: row.get_id("Template", "product_templates", ids, tx).await?,
+            attributes: parse_attributes(row.get("Attributes")).ok_or_else(|| "Attributes must be the list of the name=value.".to_owned())?,
+        })
+    }
+
+    pub async fn add(&self, mut v: SKU, notice: bool, tx: &mut SqliteConnection) -> Result<SKU> {
+        let r = sqlx::query("INSERT INTO sku_list (name, description, sku_category_id, color, text_color, serialized, tax_rate_id, code, unit, template_id) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
             .bind(&v.name)
             .bind(&v.description)
             .bind(v.sku_category_id)
             .bind(&v.color)
             .bind(&v.text_color)
+            .bind(v.serialized)
+            .bind(v.tax_rate_id)
+            .bind(&v.code)
+            .bind(&v.unit)
+            .bind(v.template_id)
             .execute(&mut *tx)
             .await?;
         if r.rows_affected() != 1 {
             bail!("Can't add sku");
         }
         v.id = sql::try_set_standard_id(r.last_insert_rowid(), "sku_list", tx).await?;
-        self.ps.notice(WebSocketFlags::AddSKU(v.id)).await?;
+        self.set_children(&v, tx).await?;
+        if notice {
+            self.ps.notice(WebSocketFlags::AddSKU(v.id)).await?;
+        }
         Ok(v)
     }
 
     pub async fn remove(&self, id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = remove_row_from_table(id, "sku_list", tx).await?;
+        let r = soft_remove_row_from_table(id, "sku_list", self.ps.get_timestamp_seconds() as i64, tx).await?;
         if notice {
             self.ps.notice(WebSocketFlags::RemoveSKU(id)).await?;
         }
         Ok(r)
     }
 
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<SKU>> {
+        if !restore_row_from_table(id, "sku_list", tx).await? {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::RestoreSKU(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Physically delete the skus removed before the date, return the ids of them.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let ids = purge_rows_from_table("sku_list", deleted_before, tx).await?;
+        for id in ids.iter() {
+            sqlx::query("DELETE FROM sku_barcodes WHERE sku_id=?").bind(id).execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM sku_units WHERE sku_id=?").bind(id).execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM sku_attributes WHERE sku_id=?").bind(id).execute(&mut *tx).await?;
+        }
+        Ok(ids)
+    }
+
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<SKU>> {
-        get_row_from_table("sku_list", "id", id, tx).await
+        let mut r: Option<SKU> = get_active_row_from_table("sku_list", id, tx).await?;
+        if let Some(v) = r.as_mut() {
+            self.fill_children(std::slice::from_mut(v), tx).await?;
+        }
+        Ok(r)
     }
 
     pub async fn get_multiple(&self, pagination: &Pagination, query: &GetSKUsQuery, tx: &mut SqliteConnection) -> Result<Vec<SKU>> {
@@ -106,6 +342,11 @@ impl SKUModule {
             sku_list.description,
             sku_list.color,
             sku_list.text_color,
+            sku_list.serialized,
+            sku_list.tax_rate_id,
+            sku_list.code,
+            sku_list.unit,
+            sku_list.template_id,
             sku_categories.name AS sku_category_name
             FROM sku_list
             INNER JOIN sku_categories ON sku_list.sku_category_id=sku_categories.id
@@ -115,7 +356,40 @@ impl SKUModule {
         .bind(pagination.offset())
         .fetch_all(&mut *tx)
         .await?;
-        rows_to_objects(rows)
+        let mut list: Vec<SKU> = rows_to_objects(rows)?;
+        self.fill_children(&mut list, tx).await?;
+        Ok(list)
+    }
+
+    /// Export the skus of the query.
+    pub async fn get_export(&self, query: &GetSKUsQuery, format: ExportFormat, tx: &mut SqliteConnection) -> Result<TempPath> {
+        let mut writer = ExportWriter::new(format, self.ps.get_data_path(), "skus", self.ps.get_timestamp_seconds(), &["ID", "Name", "SKU Category", "Code", "Unit", "Units", "Barcodes", "Template", "Attributes", "Serialized", "Tax Rate", "Description"])?;
+        let mut names = NameCache::new();
+        let mut pagination = Pagination::new(0, EXPORT_PAGE_SIZE);
+        loop {
+            let list = self.get_multiple(&pagination, query, tx).await?;
+            for v in list.iter() {
+                writer.write_row(vec![
+                    v.id.into(),
+                    v.name.as_str().into(),
+                    names.get("sku_categories", v.sku_category_id, tx).await?.into(),
+                    v.code.as_str().into(),
+                    v.unit.as_str().into(),
+                    v.units.iter().map(|v| format!("{}={}", v.name, v.factor)).collect::<Vec<_>>().join(",").into(),
+                    v.barcodes.join(",").into(),
+                    names.get("product_templates", v.template_id, tx).await?.into(),
+                    v.attributes.iter().map(|(name, value)| format!("{name}={value}")).collect::<Vec<_>>().join(",").into(),
+                    v.serialized.into(),
+                    names.get("tax_rates", v.tax_rate_id, tx).await?.into(),
+                    v.description.as_str().into(),
+                ])?;
+            }
+            if (list.len() as i64) < pagination.limit() {
+                break;
+            }
+            pagination.next();
+        }
+        writer.finish()
     }
 
     pub async fn get_multiple_ids(&self, pagination: &Pagination, query: &GetSKUsQuery, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
@@ -140,17 +414,23 @@ impl SKUModule {
     }
 
     pub async fn update(&self, id: i64, mut v: SKU, tx: &mut SqliteConnection) -> Result<Option<SKU>> {
-        let r = sqlx::query("UPDATE sku_list SET name=?, description=?, sku_category_id=?, color=?, text_color=? WHERE id=?")
+        let r = sqlx::query("UPDATE sku_list SET name=?, description=?, sku_category_id=?, color=?, text_color=?, serialized=?, tax_rate_id=?, code=?, unit=?, template_id=? WHERE id=? AND deleted_at IS NULL")
             .bind(&v.name)
             .bind(&v.description)
             .bind(v.sku_category_id)
             .bind(&v.color)
             .bind(&v.text_color)
+            .bind(v.serialized)
+            .bind(v.tax_rate_id)
+            .bind(&v.code)
+            .bind(&v.unit)
+            .bind(v.template_id)
             .bind(id)
             .execute(&mut *tx)
             .await?;
         Ok(if r.rows_affected() == 1 {
             v.id = id;
+            self.set_children(&v, tx).await?;
             self.ps.notice(WebSocketFlags::UpdateSKU(v.id)).await?;
             Some(v)
         } else {
@@ -159,11 +439,40 @@ impl SKUModule {
     }
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT sku_id FROM order_items WHERE sku_id=? LIMIT 1")
+        Ok(sqlx::query("SELECT sku_id FROM order_items INNER JOIN orders ON orders.id=order_items.order_id WHERE sku_id=? AND orders.deleted_at IS NULL LIMIT 1")
             .bind(id)
             .fetch(&mut *tx)
             .try_next()
             .await?
-            .is_some())
+            .is_some()
+            || sqlx::query("SELECT sku_id FROM purchase_order_items WHERE sku_id=? LIMIT 1").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT sku_id FROM quotation_items WHERE sku_id=? LIMIT 1").bind(id).fetch(&mut *tx).try_next().await?.is_some())
     }
 }
+
+/// The units of `carton=24,box=6` in the imports and exports, none if any of them is invalid.
+fn parse_units(v: &str) -> Option<Vec<SKUUnit>> {
+    v.split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let (name, factor) = v.split_once('=')?;
+            Some(SKUUnit {
+                name: name.trim().to_owned(),
+                factor: factor.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// The attributes of `colour=red,size=M` in the imports and exports, none if any of them is invalid.
+fn parse_attributes(v: &str) -> Option<BTreeMap<String, String>> {
+    v.split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let (name, value) = v.split_once('=')?;
+            Some((name.trim().to_owned(), value.trim().to_owned()))
+        })
+        .collect()
+}
@@ -1,11 +1,12 @@
 use ahash::{HashMap, HashMapExt};
 use anyhow::Result;
 use elerp_common::{
+    currency_module::get_rate_sql,
     model::{action_type::ActionType, WebSocketFlags},
     order_module::model::order::{GetOrdersQuery, OrderCurrency, OrderType},
     set_to_string,
     sql::in_or_not,
-    statistical_module::model::statistical_data::{GetStatisticalDataQuery, PopularSKU, SalesAmountWithCurrency, StatisticalData, StatisticalOrderCountData, StatisticalOrderData},
+    statistical_module::model::statistical_data::{GetStatisticalDataQuery, GetTaxSummaryQuery, PopularSKU, SalesAmountWithCurrency, StatisticalData, StatisticalOrderCountData, StatisticalOrderData, TaxSummary},
 };
 use futures::TryStreamExt;
 use public_system::PublicSystem;
@@ -13,11 +14,14 @@ use sqlx::{Row, SqliteConnection};
 use std::{borrow::Cow, sync::Arc};
 use tokio::{sync::RwLock, task::JoinHandle};
 
+/// The statistical data last read for the action and the query.
+type LastData = HashMap<(ActionType, Cow<'static, String>), StatisticalData>;
+
 #[derive(Debug, Clone)]
 pub struct StatisticalModule {
     #[allow(dead_code)]
     ps: PublicSystem,
-    last_data: Arc<RwLock<HashMap<(ActionType, Cow<'static, String>), StatisticalData>>>,
+    last_data: Arc<RwLock<LastData>>,
     handle: Arc<Option<JoinHandle<()>>>,
 }
 
@@ -37,27 +41,40 @@ impl StatisticalModule {
                 match flag {
                     WebSocketFlags::AddArea(_) => map.values_mut().for_each(|data| data.area_count += 1),
                     WebSocketFlags::RemoveArea(_) => map.values_mut().for_each(|data| data.area_count -= 1),
+                    WebSocketFlags::RestoreArea(_) => map.values_mut().for_each(|data| data.area_count += 1),
                     WebSocketFlags::AddPerson(_) => map.values_mut().for_each(|data| data.person_count += 1),
                     WebSocketFlags::RemovePerson(_) => map.values_mut().for_each(|data| data.person_count -= 1),
+                    WebSocketFlags::RestorePerson(_) => map.values_mut().for_each(|data| data.person_count += 1),
                     WebSocketFlags::AddSKUCategory(_) => map.values_mut().for_each(|data| data.sku_category_count += 1),
                     WebSocketFlags::RemoveSKUCategory(_) => map.values_mut().for_each(|data| data.sku_category_count -= 1),
+                    WebSocketFlags::RestoreSKUCategory(_) => map.values_mut().for_each(|data| data.sku_category_count += 1),
                     WebSocketFlags::AddSKU(_) => map.values_mut().for_each(|data| data.sku_count += 1),
                     WebSocketFlags::RemoveSKU(_) => map.values_mut().for_each(|data| data.sku_count -= 1),
+                    WebSocketFlags::RestoreSKU(_) => map.values_mut().for_each(|data| data.sku_count += 1),
 
                     WebSocketFlags::AddWarehouse(_)
                     | WebSocketFlags::RemoveWarehouse(_)
+                    | WebSocketFlags::RestoreWarehouse(_)
                     | WebSocketFlags::AddOrder(_)
                     | WebSocketFlags::RemoveOrder(_)
+                    | WebSocketFlags::RestoreOrder(_)
                     | WebSocketFlags::AddGuestOrder(_)
                     | WebSocketFlags::RemoveGuestOrder(_)
                     | WebSocketFlags::ConfirmGuestOrder(_)
                     | WebSocketFlags::RecalcOrders
                     | WebSocketFlags::AddOrderCategory(_)
                     | WebSocketFlags::RemoveOrderCategory(_)
+                    | WebSocketFlags::RestoreOrderCategory(_)
                     | WebSocketFlags::AddOrderPayment(_)
                     | WebSocketFlags::RemoveOrderPayment(_)
+                    | WebSocketFlags::RestoreOrderPayment(_)
+                    | WebSocketFlags::UpdateCurrencies
+                    | WebSocketFlags::AddExchangeRate(_)
+                    | WebSocketFlags::RemoveExchangeRate(_)
+                    | WebSocketFlags::ImportExchangeRates
                     | WebSocketFlags::LinkedWarehouse(_)
-                    | WebSocketFlags::UnlinkedWarehouse(_) => {
+                    | WebSocketFlags::UnlinkedWarehouse(_)
+                    | WebSocketFlags::Purge => {
                         map.clear();
                     }
 
@@ -90,8 +107,10 @@ impl StatisticalModule {
 
         order_query.order_type = Some(OrderType::StockOut);
         let total_amount = self.get_total_amount(&order_query, action, tx).await?;
+        let (total_amount_in_base_currency, unconverted_count) = self.get_total_amount_in_base_currency(&order_query, action, tx).await?;
         let max = self.ps.get_config().limit.statistics;
-        let most_popular_skus = self.read_popular_skus(max as usize, order_query, action, tx).await?;
+        let most_popular_skus = self.read_popular_skus(max as usize, order_query.clone(), false, action, tx).await?;
+        let most_popular_templates = self.read_popular_skus(max as usize, order_query, true, action, tx).await?;
 
         let data = StatisticalData {
             area_count,
@@ -99,26 +118,33 @@ impl StatisticalModule {
             warehouse_count,
             sku_category_count,
             sku_count,
-            order: StatisticalOrderData { total_count, total_amount },
+            order: StatisticalOrderData {
+                total_count,
+                total_amount,
+                total_amount_in_base_currency,
+                unconverted_count,
+            },
             order_category_count,
             most_popular_skus,
+            most_popular_templates,
         };
         self.last_data.write().await.insert((action, Cow::Owned(order_query_str)), data.clone());
 
         Ok(data)
     }
 
-    async fn read_popular_skus(&self, max: usize, mut query: GetOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<PopularSKU>> {
+    /// The skus sold most, or the product templates of them if by the templates.
+    async fn read_popular_skus(&self, max: usize, mut query: GetOrdersQuery, by_template: bool, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<PopularSKU>> {
         let reverse = query.reverse.as_ref();
         let mut oi_conditions = vec!["orders.id=oi.order_id".to_owned()];
         if let Some(v) = &query.items {
             let in_not = in_or_not(reverse, "items");
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             oi_conditions.push(format!("oi.sku_id {in_not} ({v})"));
         }
         if let Some(v) = &query.item_categories {
             let in_not = in_or_not(reverse, "item_categories");
-            let v = set_to_string(&v, ",");
+            let v = set_to_string(v, ",");
             oi_conditions.push(format!("oi.sku_category_id {in_not} ({v})"));
         }
         let oi_q = oi_conditions.join(" AND ");
@@ -126,20 +152,27 @@ impl StatisticalModule {
         query.item_categories.take();
         let qw = query.get_where_condition();
         let inner = self.get_order_inner(action);
+        let (id_col, template_inner) = if by_template {
+            ("sku_list.template_id", "INNER JOIN sku_list ON sku_list.id=oi.sku_id AND sku_list.template_id<>0")
+        } else {
+            ("oi.sku_id", "")
+        };
         let mut arr = Vec::with_capacity(100);
         let rows = sqlx::query(&format!(
             "SELECT
             orders.warehouse_id,
-            oi.sku_id AS id,
+            {id_col} AS id,
             orders.currency AS currency,
             COUNT(DISTINCT orders.id) AS order_count,
             SUM(oi.quantity) AS total_out,
-            SUM(oi.amount) / SUM(oi.quantity) AS average_price
+            SUM(oi.amount) / SUM(oi.quantity) AS average_price,
+            SUM(oi.quantity * oi.price / oi.unit_factor) / SUM(oi.quantity) AS average_list_price
             FROM orders
             {inner}
             INNER JOIN order_items oi ON {oi_q}
+            {template_inner}
             {qw}
-            GROUP BY oi.sku_id, orders.currency
+            GROUP BY {id_col}, orders.currency
             ORDER BY total_out DESC, average_price DESC
             LIMIT {max};"
         ))
@@ -152,6 +185,7 @@ impl StatisticalModule {
                 currency: row.get("currency"),
                 order_count: row.get("order_count"),
                 average_price: row.get("average_price"),
+                average_list_price: row.get("average_list_price"),
                 total_out: row.get("total_out"),
             })
         }
@@ -159,7 +193,7 @@ impl StatisticalModule {
     }
 
     pub async fn get(&self, query: &GetStatisticalDataQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<StatisticalData> {
-        let data = self.read(&query, action, tx).await?;
+        let data = self.read(query, action, tx).await?;
         Ok(data)
     }
 
@@ -176,6 +210,7 @@ impl StatisticalModule {
             calibration_strict_count: 0,
             verification_count: 0,
             verification_strict_count: 0,
+            transfer_count: 0,
         };
         if let Ok(rows) = sqlx::query(&format!(
             "SELECT COUNT(*) as count, order_type
@@ -199,6 +234,7 @@ impl StatisticalModule {
                     OrderType::CalibrationStrict => &mut data.calibration_strict_count,
                     OrderType::Verification => &mut data.verification_count,
                     OrderType::VerificationStrict => &mut data.verification_strict_count,
+                    OrderType::Transfer => &mut data.transfer_count,
                 };
                 *ref_count = count;
             }
@@ -210,18 +246,24 @@ impl StatisticalModule {
             + data.calibration_count
             + data.calibration_strict_count
             + data.verification_count
-            + data.verification_strict_count;
+            + data.verification_strict_count
+            + data.transfer_count;
         Ok(data)
     }
 
     pub async fn get_total_amount(&self, query: &GetOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<SalesAmountWithCurrency>> {
         let qw = query.get_where_condition();
         let inner = self.get_order_inner(action);
+        // The cost of goods sold is in the base currency, it is converted back to the currency of the order.
+        let rate = get_rate_sql(self.ps.get_config().currency.base, "orders.currency", "orders.date");
         if let Ok(rows) = sqlx::query(&format!(
             "SELECT orders.warehouse_id, orders.currency, SUM(total_amount) AS any,
             SUM(CASE WHEN order_payment_status='Unsettled' THEN total_amount ELSE 0.0 END) AS unsettled,
             SUM(CASE WHEN order_payment_status='Settled' THEN total_amount_settled ELSE 0.0 END) AS settled,
-            SUM(CASE WHEN order_payment_status='PartialSettled' THEN total_amount_settled ELSE 0.0 END) AS partial_settled
+            SUM(CASE WHEN order_payment_status='PartialSettled' THEN total_amount_settled ELSE 0.0 END) AS partial_settled,
+            SUM(tax_amount) AS tax,
+            SUM(CASE WHEN is_record THEN 0.0 ELSE net_amount END) AS not_record,
+            COALESCE(SUM((SELECT SUM(amount) FROM cost_of_goods_sold WHERE cost_of_goods_sold.order_id=orders.id) / COALESCE({rate}, 1.0)), 0.0) AS cost_of_goods_sold
             FROM orders
             {inner}
             {qw} 
@@ -237,6 +279,9 @@ impl StatisticalModule {
                     settled: row.get("settled"),
                     unsettled: row.get("unsettled"),
                     partial_settled: row.get("partial_settled"),
+                    tax: row.get("tax"),
+                    cost_of_goods_sold: row.get("cost_of_goods_sold"),
+                    gross_margin: row.get::<f64, _>("not_record") - row.get::<f64, _>("cost_of_goods_sold"),
                     currency: row.try_get("currency").unwrap_or(OrderCurrency::Unknown),
                 })
                 .collect();
@@ -247,6 +292,74 @@ impl StatisticalModule {
         }
     }
 
+    /// Sum the amounts of all the currencies in the base currency, return it with the count of the orders without a rate.
+    pub async fn get_total_amount_in_base_currency(&self, query: &GetOrdersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<(SalesAmountWithCurrency, i64)> {
+        let qw = query.get_where_condition();
+        let inner = self.get_order_inner(action);
+        let base = self.ps.get_config().currency.base;
+        let rate = get_rate_sql(base, "orders.currency", "orders.date");
+        let row = sqlx::query(&format!(
+            "SELECT COALESCE(SUM(total_amount * rate), 0.0) AS any,
+            COALESCE(SUM(CASE WHEN order_payment_status='Unsettled' THEN total_amount * rate ELSE 0.0 END), 0.0) AS unsettled,
+            COALESCE(SUM(CASE WHEN order_payment_status='Settled' THEN total_amount_settled * rate ELSE 0.0 END), 0.0) AS settled,
+            COALESCE(SUM(CASE WHEN order_payment_status='PartialSettled' THEN total_amount_settled * rate ELSE 0.0 END), 0.0) AS partial_settled,
+            COALESCE(SUM(tax_amount * rate), 0.0) AS tax,
+            COALESCE(SUM(CASE WHEN is_record THEN 0.0 ELSE net_amount * rate END), 0.0) AS not_record,
+            COALESCE(SUM((SELECT SUM(amount) FROM cost_of_goods_sold WHERE cost_of_goods_sold.order_id=orders.id)), 0.0) AS cost_of_goods_sold,
+            COUNT(*) - COUNT(rate) AS unconverted_count
+            FROM (SELECT orders.*, {rate} AS rate
+            FROM orders
+            {inner}
+            {qw}) AS orders"
+        ))
+        .fetch_one(&mut *tx)
+        .await?;
+        let amount = SalesAmountWithCurrency {
+            any: row.get("any"),
+            settled: row.get("settled"),
+            unsettled: row.get("unsettled"),
+            partial_settled: row.get("partial_settled"),
+            tax: row.get("tax"),
+            cost_of_goods_sold: row.get("cost_of_goods_sold"),
+            gross_margin: row.get::<f64, _>("not_record") - row.get::<f64, _>("cost_of_goods_sold"),
+            currency: base,
+        };
+        Ok((amount, row.get("unconverted_count")))
+    }
+
+    pub async fn get_tax_summary(&self, query: &GetTaxSummaryQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<TaxSummary>> {
+        let qw = query.get_order_query().get_where_condition();
+        let inner = self.get_order_inner(action);
+        let format = query.period.unwrap_or_default().get_format();
+        let rows = sqlx::query(&format!(
+            "SELECT strftime('{format}', orders.date, 'unixepoch') AS period,
+            orders.currency, orders.order_type, oi.tax_rate,
+            SUM(CASE WHEN orders.tax_inclusive THEN oi.amount - oi.tax_amount ELSE oi.amount END) AS net_amount,
+            SUM(oi.tax_amount) AS tax_amount,
+            SUM(CASE WHEN orders.tax_inclusive THEN oi.amount ELSE oi.amount + oi.tax_amount END) AS gross_amount
+            FROM orders
+            {inner}
+            INNER JOIN order_items oi ON orders.id=oi.order_id AND NOT oi.exchanged
+            {qw}
+            GROUP BY period, orders.currency, orders.order_type, oi.tax_rate
+            ORDER BY period ASC, orders.currency ASC, orders.order_type ASC, oi.tax_rate ASC"
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| TaxSummary {
+                period: row.get("period"),
+                currency: row.try_get("currency").unwrap_or(OrderCurrency::Unknown),
+                order_type: row.get("order_type"),
+                tax_rate: row.get("tax_rate"),
+                net_amount: row.get("net_amount"),
+                tax_amount: row.get("tax_amount"),
+                gross_amount: row.get("gross_amount"),
+            })
+            .collect())
+    }
+
     fn get_order_inner(&self, action: ActionType) -> Cow<'static, str> {
         match action {
             ActionType::General(id) | ActionType::GeneralAllowed(id) => {
@@ -257,7 +370,7 @@ impl StatisticalModule {
     }
 
     async fn get_count(&self, table: &str, tx: &mut SqliteConnection) -> Result<i64> {
-        Ok(sqlx::query(&format!("SELECT COUNT(*) as count FROM {table}"))
+        Ok(sqlx::query(&format!("SELECT COUNT(*) as count FROM {table} WHERE deleted_at IS NULL"))
             .fetch(&mut *tx)
             .try_next()
             .await?
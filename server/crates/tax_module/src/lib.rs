@@ -0,0 +1,95 @@
+use anyhow::{bail, Result};
+use elerp_common::{
+    model::WebSocketFlags,
+    sql::{self, rows_to_objects},
+    tax_module::model::tax_rate::TaxRate,
+};
+use futures::TryStreamExt;
+use public_system::PublicSystem;
+use sqlx::SqliteConnection;
+
+#[derive(Debug, Clone)]
+pub struct TaxModule {
+    ps: PublicSystem,
+}
+
+impl TaxModule {
+    pub async fn new(ps: PublicSystem) -> Self {
+        let mut tx = ps.begin_tx(true).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS tax_rates(
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                rate REAL NOT NULL,
+                description TEXT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        tx.commit().await.unwrap();
+        Self { ps }
+    }
+
+    pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM tax_rates WHERE id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+    }
+
+    pub async fn add(&self, mut v: TaxRate, tx: &mut SqliteConnection) -> Result<TaxRate> {
+        let r = sqlx::query("INSERT INTO tax_rates (name, rate, description) VALUES(?, ?, ?)")
+            .bind(&v.name)
+            .bind(v.rate)
+            .bind(&v.description)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't add tax rate");
+        }
+        v.id = sql::try_set_standard_id(r.last_insert_rowid(), "tax_rates", tx).await?;
+        self.ps.notice(WebSocketFlags::AddTaxRate(v.id)).await?;
+        Ok(v)
+    }
+
+    /// The orders added keep the rates of their items, only the new orders use the updated rate.
+    pub async fn update(&self, id: i64, mut v: TaxRate, tx: &mut SqliteConnection) -> Result<Option<TaxRate>> {
+        let r = sqlx::query("UPDATE tax_rates SET name=?, rate=?, description=? WHERE id=?")
+            .bind(&v.name)
+            .bind(v.rate)
+            .bind(&v.description)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(if r.rows_affected() == 1 {
+            v.id = id;
+            self.ps.notice(WebSocketFlags::UpdateTaxRate(id)).await?;
+            Some(v)
+        } else {
+            None
+        })
+    }
+
+    pub async fn remove(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM tax_rates WHERE id=?").bind(id).execute(&mut *tx).await?;
+        if r.rows_affected() == 1 {
+            self.ps.notice(WebSocketFlags::RemoveTaxRate(id)).await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<TaxRate>> {
+        Ok(sqlx::query_as("SELECT id, name, rate, description FROM tax_rates WHERE id=?").bind(id).fetch_optional(&mut *tx).await?)
+    }
+
+    pub async fn list(&self, tx: &mut SqliteConnection) -> Result<Vec<TaxRate>> {
+        let rows = sqlx::query("SELECT id, name, rate, description FROM tax_rates ORDER BY name ASC").fetch_all(&mut *tx).await?;
+        rows_to_objects(rows)
+    }
+
+    pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT id FROM sku_list WHERE tax_rate_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM sku_categories WHERE tax_rate_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+    }
+}
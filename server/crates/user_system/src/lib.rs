@@ -1,20 +1,28 @@
 use std::{sync::Arc, time::Duration};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
 use elerp_common::sql::{is_exists_in_table, remove_row_from_table, row_is_duplicate_col_in_table, rows_to_objects};
 use elerp_common::{
     model::{action_type::ActionType, Pagination, WebSocketFlags},
     user_system::model::{
+        role::Role,
         user_configure::{UserConfigure, UserConfigureDefaults},
         user_info::{GetUsersQuery, UserInfo, UserType},
-        user_permission::{ADD_ORDER, MANAGE_AREA, MANAGE_PERSON, MANAGE_SKU, MANAGE_SKU_CATEGORY, MANAGE_WAREHOUSE, UPDATE_REMOVE_ORDER},
+        user_permission::Permission,
+        user_session::{UserSession, UserToken},
     },
 };
 use futures::TryStreamExt;
 use public_system::PublicSystem;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
 use sqlx::{Row, SqliteConnection};
 use tokio::task::JoinHandle;
-use tracing::{error, info, warn};
+use tracing::{error, info};
 
 #[derive(Debug, Clone)]
 pub struct UserSystem {
@@ -26,6 +34,7 @@ pub struct UserSystem {
 impl UserSystem {
     pub async fn new(ps: PublicSystem) -> Self {
         let mut tx = ps.get_conn().begin().await.unwrap();
+        let migrate_roles = !ps.exists_table("roles", tx.as_mut()).await;
         let _r = sqlx::query(
             "CREATE TABLE IF NOT EXISTS users(
                 id INTEGER PRIMARY KEY,
@@ -55,6 +64,45 @@ impl UserSystem {
         .await
         .unwrap();
 
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS roles(
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL
+        )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS role_permissions(
+                role_id INT NOT NULL,
+                permission TEXT NOT NULL
+        )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS user_roles(
+                user_id INT NOT NULL,
+                role_id INT NOT NULL
+        )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        sqlx::query("CREATE INDEX IF NOT EXISTS role_permissions_role_id ON role_permissions(role_id)")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        sqlx::query("CREATE INDEX IF NOT EXISTS user_roles_user_id ON user_roles(user_id)")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+
         sqlx::query("DROP TABLE IF EXISTS tokens").execute(tx.as_mut()).await.unwrap();
 
         sqlx::query(
@@ -73,14 +121,22 @@ impl UserSystem {
             "CREATE TABLE IF NOT EXISTS tokens(
                 id INTEGER PRIMARY KEY,
                 created_at INT NOT NULL,
+                expires_at INT NOT NULL,
+                refresh_expires_at INT NOT NULL,
                 user_id INT NOT NULL,
                 token TEXT NOT NULL,
+                refresh_token TEXT NOT NULL,
                 socket_count INT NOT NULL
         )",
         )
         .execute(tx.as_mut())
         .await
         .unwrap();
+        sqlx::query("CREATE INDEX IF NOT EXISTS tokens_token ON tokens(token)").execute(tx.as_mut()).await.unwrap();
+        sqlx::query("CREATE INDEX IF NOT EXISTS tokens_refresh_token ON tokens(refresh_token)")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
 
         let have_admin = sqlx::query("SELECT id FROM users WHERE user_type=?")
             .bind(UserType::Admin)
@@ -106,7 +162,8 @@ impl UserSystem {
                     username: username.clone(),
                     password: password.clone(),
                     user_type: UserType::Admin,
-                    permission: MANAGE_AREA + MANAGE_PERSON + MANAGE_SKU + MANAGE_SKU_CATEGORY + MANAGE_WAREHOUSE + ADD_ORDER + UPDATE_REMOVE_ORDER,
+                    roles: None,
+                    permissions: vec![],
                     is_connected: false,
                 },
                 tx.as_mut(),
@@ -115,6 +172,9 @@ impl UserSystem {
             .unwrap();
             info!("Admin created, username: {username}, password: {password}");
         }
+        if migrate_roles {
+            this.migrate_roles(tx.as_mut()).await.unwrap();
+        }
         tx.commit().await.unwrap();
 
         let t2 = this.clone();
@@ -123,11 +183,13 @@ impl UserSystem {
                 if let Ok(mut tx) = t2.ps.begin_tx(true).await {
                     if let Err(err) = t2.clear_sub_token(None, true, tx.as_mut()).await {
                         error!("Can't clear sub tokens. {}", err);
+                    } else if let Err(err) = t2.clear_expired_token(tx.as_mut()).await {
+                        error!("Can't clear expired tokens. {}", err);
                     } else {
                         if let Err(err) = tx.commit().await {
                             error!("Can't commit the transaction for clear sub tokens. {}", err);
                         }
-                        info!("Clear sub tokens finish.");
+                        info!("Clear sub tokens and expired tokens finish.");
                     }
                 } else {
                     error!("Can't get transaction to clear sub tokens.");
@@ -192,8 +254,7 @@ impl UserSystem {
 
     pub async fn get_sub_token(&self, user: &UserInfo, tx: &mut SqliteConnection) -> Result<String> {
         let now = self.ps.get_timestamp_seconds();
-        let to_calc = user.id as usize + now as usize;
-        let token = self.ps.calculate_hash(&to_calc, true).await.to_string();
+        let token = generate_token();
         let r = sqlx::query("INSERT INTO sub_tokens (created_at, user_id, token) VALUES (?, ?, ?)")
             .bind(now as i64)
             .bind(user.id)
@@ -221,36 +282,117 @@ impl UserSystem {
         }
     }
 
-    pub async fn get_token(&self, user: &UserInfo, tx: &mut SqliteConnection) -> Result<String> {
-        let now: u64 = self.ps.get_timestamp_seconds();
-
-        if self.remove_token(user.id, &mut *tx).await? {
-            warn!("User '{}' logged in, will refresh token...", &user.username);
-            self.ps.notice(WebSocketFlags::UserRepeatLogin(user.id)).await?;
+    pub async fn get_token(&self, user: &UserInfo, tx: &mut SqliteConnection) -> Result<UserToken> {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        let config = &self.ps.get_config().token;
+        let token = generate_token();
+        let refresh_token = generate_token();
+        let expires_at = now + config.ttl;
+        let refresh_expires_at = now + config.refresh_ttl;
+        let r = sqlx::query(
+            "INSERT INTO tokens (created_at, expires_at, refresh_expires_at, user_id, token, refresh_token, socket_count) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(now)
+        .bind(expires_at)
+        .bind(refresh_expires_at)
+        .bind(user.id)
+        .bind(hash_token(&token))
+        .bind(hash_token(&refresh_token))
+        .bind(0)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() == 1 {
+            Ok(UserToken {
+                session_id: r.last_insert_rowid(),
+                token,
+                expires_at,
+                refresh_token,
+                refresh_expires_at,
+            })
+        } else {
+            bail!("Can't insert the token to tokens!")
         }
+    }
 
-        let token = self.ps.calculate_hash(&user, true).await.to_string();
-        let r = sqlx::query("INSERT INTO tokens (created_at, user_id, token, socket_count) VALUES (?, ?, ?, ?)")
-            .bind(now as i64)
-            .bind(user.id)
-            .bind(&token)
-            .bind(0)
+    /// Get new token and refresh token with refresh token. The session is keep, but old tokens will inactive.
+    pub async fn refresh_token(&self, refresh_token: &str, tx: &mut SqliteConnection) -> Result<Option<UserToken>> {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        let session_id: i64 = match sqlx::query("SELECT id FROM tokens WHERE refresh_token=? AND refresh_expires_at > ? LIMIT 1")
+            .bind(hash_token(refresh_token))
+            .bind(now)
+            .fetch_optional(&mut *tx)
+            .await?
+        {
+            Some(row) => row.get("id"),
+            None => return Ok(None),
+        };
+
+        let config = &self.ps.get_config().token;
+        let token = generate_token();
+        let refresh_token = generate_token();
+        let expires_at = now + config.ttl;
+        let refresh_expires_at = now + config.refresh_ttl;
+        let r = sqlx::query("UPDATE tokens SET expires_at=?, refresh_expires_at=?, token=?, refresh_token=? WHERE id=?")
+            .bind(expires_at)
+            .bind(refresh_expires_at)
+            .bind(hash_token(&token))
+            .bind(hash_token(&refresh_token))
+            .bind(session_id)
             .execute(&mut *tx)
             .await?;
-        if r.rows_affected() == 1 {
-            Ok(token)
+        Ok(if r.rows_affected() == 1 {
+            Some(UserToken {
+                session_id,
+                token,
+                expires_at,
+                refresh_token,
+                refresh_expires_at,
+            })
         } else {
-            bail!("Can't insert the token to tokens!")
-        }
+            None
+        })
     }
 
-    pub async fn try_connect_socket(&self, user: &UserInfo, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = sqlx::query("SELECT socket_count FROM tokens WHERE user_id=?").bind(user.id).fetch(&mut *tx).try_next().await?;
+    pub async fn get_sessions(&self, user_id: i64, tx: &mut SqliteConnection) -> Result<Vec<UserSession>> {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        Ok(sqlx::query_as(
+            "SELECT id, user_id, created_at, expires_at, refresh_expires_at, socket_count > 0 AS is_connected FROM tokens
+            WHERE user_id=? AND refresh_expires_at > ? ORDER BY id",
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    pub async fn remove_session(&self, user_id: i64, session_id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM tokens WHERE id=? AND user_id=?").bind(session_id).bind(user_id).execute(&mut *tx).await?;
+        Ok(r.rows_affected() > 0)
+    }
+
+    pub async fn clear_expired_token(&self, tx: &mut SqliteConnection) -> Result<()> {
+        let now = self.ps.get_timestamp_seconds() as i64;
+        sqlx::query("DELETE FROM tokens WHERE refresh_expires_at < ?").bind(now).execute(&mut *tx).await?;
+        Ok(())
+    }
+
+    /// The socket is counted by the session of the token, each session can connect one socket.
+    pub async fn try_connect_socket(&self, user: &UserInfo, token: &str, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("SELECT socket_count FROM tokens WHERE token=? AND user_id=?")
+            .bind(hash_token(token))
+            .bind(user.id)
+            .fetch(&mut *tx)
+            .try_next()
+            .await?;
 
         if let Some(row) = r {
             let socket_count: i64 = row.get("socket_count");
             if socket_count < 1 {
-                let r = sqlx::query("UPDATE tokens SET socket_count=socket_count+1 WHERE user_id=?").bind(user.id).execute(&mut *tx).await?;
+                let r = sqlx::query("UPDATE tokens SET socket_count=socket_count+1 WHERE token=? AND user_id=?")
+                    .bind(hash_token(token))
+                    .bind(user.id)
+                    .execute(&mut *tx)
+                    .await?;
                 let success = r.rows_affected() > 0;
                 if success {
                     self.ps.notice(WebSocketFlags::UserConnected(user.id)).await?;
@@ -261,8 +403,9 @@ impl UserSystem {
         Ok(false)
     }
 
-    pub async fn disconnect_socket(&self, user: &UserInfo, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = sqlx::query("UPDATE tokens SET socket_count=socket_count-1 WHERE user_id=? AND socket_count > 0")
+    pub async fn disconnect_socket(&self, user: &UserInfo, token: &str, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("UPDATE tokens SET socket_count=socket_count-1 WHERE token=? AND user_id=? AND socket_count > 0")
+            .bind(hash_token(token))
             .bind(user.id)
             .execute(&mut *tx)
             .await?;
@@ -272,7 +415,13 @@ impl UserSystem {
     }
 
     pub async fn token_to_user(&self, token: &str, action: ActionType, tx: &mut SqliteConnection) -> Result<Option<UserInfo>> {
-        let r = sqlx::query("SELECT user_id FROM tokens WHERE token=?").bind(token).fetch(&mut *tx).try_next().await?;
+        let now = self.ps.get_timestamp_seconds() as i64;
+        let r = sqlx::query("SELECT user_id FROM tokens WHERE token=? AND expires_at > ?")
+            .bind(hash_token(token))
+            .bind(now)
+            .fetch(&mut *tx)
+            .try_next()
+            .await?;
         Ok(if let Some(row) = r {
             let user_id = row.get("user_id");
             self.get_user(user_id, action, &mut *tx).await?
@@ -281,9 +430,10 @@ impl UserSystem {
         })
     }
 
-    pub async fn is_socket_connected(&self, user_id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT user_id FROM tokens WHERE user_id=? AND socket_count > 0")
-            .bind(user_id)
+    /// Whether the session of the token connected the socket.
+    pub async fn is_socket_connected(&self, token: &str, tx: &mut SqliteConnection) -> Result<bool> {
+        Ok(sqlx::query("SELECT user_id FROM tokens WHERE token=? AND socket_count > 0")
+            .bind(hash_token(token))
             .fetch_one(&mut *tx)
             .await
             .is_ok())
@@ -294,6 +444,12 @@ impl UserSystem {
         Ok(r.rows_affected() > 0)
     }
 
+    /// Remove the session of the token only, the other sessions of the user are kept.
+    pub async fn remove_session_by_token(&self, token: &str, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM tokens WHERE token=?").bind(hash_token(token)).execute(&mut *tx).await?;
+        Ok(r.rows_affected() > 0)
+    }
+
     pub async fn remove_sub_token(&self, token: &str, tx: &mut SqliteConnection) -> Result<bool> {
         let r = sqlx::query("DELETE FROM sub_tokens WHERE token=?").bind(token).execute(&mut *tx).await?;
         Ok(r.rows_affected() > 0)
@@ -308,18 +464,31 @@ impl UserSystem {
     }
 
     pub async fn add_user(&self, mut user: UserInfo, tx: &mut SqliteConnection) -> Result<UserInfo> {
+        if user.password.is_empty() {
+            bail!("Password can't be empty");
+        }
+        let password_hash = hash_password(&user.password)?;
         let r = sqlx::query("INSERT INTO users (username, password, alias, user_type, permission) VALUES(?, ?, ?, ?, ?)")
             .bind(&user.username)
-            .bind(&user.password)
+            .bind(&password_hash)
             .bind(&user.alias)
-            .bind(&user.user_type)
-            .bind(user.permission)
+            .bind(user.user_type)
+            .bind(0)
             .execute(&mut *tx)
             .await?;
         if r.rows_affected() != 1 {
             bail!("Can't add user");
         }
         user.id = r.last_insert_rowid() as i64;
+        user.password = String::with_capacity(0);
+        let mut roles = user.roles.take().unwrap_or_default();
+        // The general users can view all data by default as before the roles.
+        if user.user_type == UserType::General && roles.is_empty() {
+            roles.push(self.get_viewer_role_id(&mut *tx).await?);
+        }
+        self.set_user_roles(user.id, &roles, &mut *tx).await?;
+        user.roles = Some(roles);
+        user.permissions = self.get_user_permissions(user.id, &mut *tx).await?;
         self.add_configure(
             UserConfigure {
                 user_id: user.id,
@@ -336,6 +505,7 @@ impl UserSystem {
     pub async fn remove_user(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
         let r = remove_row_from_table(id, "users", &mut *tx).await?;
         self.remove_configure(id, &mut *tx).await?;
+        self.set_user_roles(id, &[], &mut *tx).await?;
         self.ps.notice(WebSocketFlags::RemoveUser(id)).await?;
         Ok(r)
     }
@@ -351,7 +521,8 @@ impl UserSystem {
                         username: String::with_capacity(0),
                         password: String::with_capacity(0),
                         user_type: row.get("user_type"),
-                        permission: 0,
+                        roles: None,
+                        permissions: vec![],
                         is_connected: false,
                     })
                 }
@@ -359,8 +530,7 @@ impl UserSystem {
             ActionType::Admin | ActionType::System => {
                 if let Some(row) = sqlx::query(
                     "SELECT users.*, 
-                CASE WHEN tokens.user_id IS NULL OR tokens.socket_count < 1 THEN 0 ELSE 1 END AS is_connected FROM users 
-                LEFT JOIN tokens ON users.id = tokens.user_id WHERE users.id = ? LIMIT 1",
+                EXISTS(SELECT 1 FROM tokens WHERE tokens.user_id = users.id AND tokens.socket_count > 0) AS is_connected FROM users WHERE users.id = ? LIMIT 1",
                 )
                 .bind(id)
                 .fetch_optional(&mut *tx)
@@ -370,9 +540,10 @@ impl UserSystem {
                         id,
                         alias: row.get("alias"),
                         username: row.get("username"),
-                        password: row.get("password"),
+                        password: String::with_capacity(0),
                         user_type: row.get("user_type"),
-                        permission: row.get("permission"),
+                        roles: Some(self.get_user_roles(id, &mut *tx).await?),
+                        permissions: self.get_user_permissions(id, &mut *tx).await?,
                         is_connected: row.get("is_connected"),
                     })
                 }
@@ -382,40 +553,66 @@ impl UserSystem {
     }
 
     pub async fn get_user_by_username(&self, username: &str, tx: &mut SqliteConnection) -> Result<Option<UserInfo>> {
-        Ok(sqlx::query_as(
+        let user: Option<UserInfo> = sqlx::query_as(
             "SELECT users.*, 
-        CASE WHEN tokens.user_id IS NULL OR tokens.socket_count < 1 THEN 0 ELSE 1 END AS is_connected FROM users 
-        LEFT JOIN tokens ON users.id = tokens.user_id WHERE users.username = ? LIMIT 1",
+        EXISTS(SELECT 1 FROM tokens WHERE tokens.user_id = users.id AND tokens.socket_count > 0) AS is_connected FROM users WHERE users.username = ? LIMIT 1",
         )
         .bind(username)
         .fetch_optional(&mut *tx)
-        .await?)
+        .await?;
+        Ok(match user {
+            Some(mut user) => {
+                user.roles = Some(self.get_user_roles(user.id, &mut *tx).await?);
+                user.permissions = self.get_user_permissions(user.id, &mut *tx).await?;
+                Some(user)
+            }
+            None => None,
+        })
+    }
+
+    /// Verify the password of user. The user must be get from `get_user_by_username` because it contain the stored password.
+    /// Password stored in plaintext (created by old version) will upgrade to hash after verified.
+    pub async fn verify_password(&self, user: &UserInfo, password: &str, tx: &mut SqliteConnection) -> Result<bool> {
+        // The password can't be parsed as hash with the output is the plaintext.
+        if let Some(parsed) = PasswordHash::new(&user.password).ok().filter(|v| v.hash.is_some()) {
+            return Ok(Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok());
+        }
+        if user.password.is_empty() || user.password != password {
+            return Ok(false);
+        }
+        sqlx::query("UPDATE users SET password=? WHERE id=?")
+            .bind(hash_password(password)?)
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+        info!("Password of user '{}' upgraded to hash.", &user.username);
+        Ok(true)
     }
 
     pub async fn get_users(&self, pagination: &Pagination, query: &GetUsersQuery, action: ActionType, tx: &mut SqliteConnection) -> Result<Vec<UserInfo>> {
         let qw = query.get_where_condition();
         let rows = sqlx::query(&format!(
             "SELECT users.*, 
-        CASE WHEN tokens.user_id IS NULL OR tokens.socket_count < 1 THEN 0 ELSE 1 END AS is_connected FROM users 
-        LEFT JOIN tokens ON users.id = tokens.user_id {qw} LIMIT ? OFFSET ?"
+        EXISTS(SELECT 1 FROM tokens WHERE tokens.user_id = users.id AND tokens.socket_count > 0) AS is_connected FROM users {qw} LIMIT ? OFFSET ?"
         ))
         .bind(pagination.limit())
         .bind(pagination.offset())
         .fetch_all(&mut *tx)
         .await?;
-        rows_to_objects::<UserInfo>(rows).map(|mut items| {
-            for item in items.iter_mut() {
-                match action {
-                    ActionType::General(_) | ActionType::GeneralAllowed(_) => {
-                        item.username = String::with_capacity(0);
-                        item.password = String::with_capacity(0);
-                        item.permission = 0;
-                    }
-                    ActionType::Admin | ActionType::System => (),
+        let mut items = rows_to_objects::<UserInfo>(rows)?;
+        for item in items.iter_mut() {
+            item.password = String::with_capacity(0);
+            match action {
+                ActionType::General(_) | ActionType::GeneralAllowed(_) => {
+                    item.username = String::with_capacity(0);
+                }
+                ActionType::Admin | ActionType::System => {
+                    item.roles = Some(self.get_user_roles(item.id, &mut *tx).await?);
+                    item.permissions = self.get_user_permissions(item.id, &mut *tx).await?;
                 }
             }
-            items
-        })
+        }
+        Ok(items)
     }
 
     pub async fn get_users_count(&self, query: &GetUsersQuery, tx: &mut SqliteConnection) -> Result<i64> {
@@ -424,18 +621,35 @@ impl UserSystem {
         Ok(row.get("count"))
     }
 
+    /// Update the user. Empty password mean keep the current password.
     pub async fn update_user(&self, id: i64, mut v: UserInfo, tx: &mut SqliteConnection) -> Result<Option<UserInfo>> {
-        let r = sqlx::query("UPDATE users SET username=?, password=?, alias=?, user_type=?, permission=? WHERE id=?")
-            .bind(&v.username)
-            .bind(&v.password)
-            .bind(&v.alias)
-            .bind(&v.user_type)
-            .bind(v.permission)
-            .bind(id)
-            .execute(&mut *tx)
-            .await?;
+        let r = if v.password.is_empty() {
+            sqlx::query("UPDATE users SET username=?, alias=?, user_type=? WHERE id=?")
+                .bind(&v.username)
+                .bind(&v.alias)
+                .bind(v.user_type)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+        } else {
+            sqlx::query("UPDATE users SET username=?, password=?, alias=?, user_type=? WHERE id=?")
+                .bind(&v.username)
+                .bind(hash_password(&v.password)?)
+                .bind(&v.alias)
+                .bind(v.user_type)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+        };
         Ok(if r.rows_affected() == 1 {
             v.id = id;
+            v.password = String::with_capacity(0);
+            // The client not sending the roles is not going to change them.
+            if let Some(roles) = v.roles.as_ref() {
+                self.set_user_roles(id, roles, &mut *tx).await?;
+            }
+            v.roles = Some(self.get_user_roles(id, &mut *tx).await?);
+            v.permissions = self.get_user_permissions(id, &mut *tx).await?;
             self.ps.notice(WebSocketFlags::UpdateUser(id)).await?;
             Some(v)
         } else {
@@ -443,15 +657,236 @@ impl UserSystem {
         })
     }
 
+    /// Create the roles equivalent to the legacy permission bitmask and assign them to the users.
+    /// Before roles, every user can view all data, so they will get the viewer role too.
+    async fn migrate_roles(&self, tx: &mut SqliteConnection) -> Result<()> {
+        let viewer = self.add_role(Self::viewer_role(), &mut *tx).await?;
+        let mut legacy_roles = Vec::with_capacity(10);
+        for (bit, permission, name) in Permission::legacy() {
+            let role = self
+                .add_role(
+                    Role {
+                        id: 0,
+                        name: name.to_owned(),
+                        description: String::new(),
+                        permissions: vec![permission],
+                    },
+                    &mut *tx,
+                )
+                .await?;
+            legacy_roles.push((bit, role.id));
+        }
+        self.add_role(
+            Role {
+                id: 0,
+                name: "Order viewer without price".to_owned(),
+                description: "View orders but not prices.".to_owned(),
+                permissions: vec![
+                    Permission::ViewOrder,
+                    Permission::ViewArea,
+                    Permission::ViewPerson,
+                    Permission::ViewWarehouse,
+                    Permission::ViewSKU,
+                    Permission::ViewSKUCategory,
+                    Permission::ViewOrderCategory,
+                ],
+            },
+            &mut *tx,
+        )
+        .await?;
+        self.add_role(
+            Role {
+                id: 0,
+                name: "Inventory viewer".to_owned(),
+                description: "View inventory only.".to_owned(),
+                permissions: vec![Permission::ViewInventory, Permission::ViewWarehouse, Permission::ViewSKU, Permission::ViewSKUCategory],
+            },
+            &mut *tx,
+        )
+        .await?;
+
+        let rows = sqlx::query("SELECT id, permission FROM users WHERE user_type=?")
+            .bind(UserType::General)
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in rows {
+            let user_id: i64 = row.get("id");
+            let permission: i64 = row.get("permission");
+            let mut roles = vec![viewer.id];
+            for (bit, role_id) in legacy_roles.iter() {
+                if permission & bit == *bit {
+                    roles.push(*role_id);
+                }
+            }
+            self.set_user_roles(user_id, &roles, &mut *tx).await?;
+        }
+        info!("Migrated the permission of users to roles.");
+        Ok(())
+    }
+
+    fn viewer_role() -> Role {
+        Role {
+            id: 0,
+            name: "Viewer".to_owned(),
+            description: "View all data.".to_owned(),
+            permissions: Permission::views(),
+        }
+    }
+
+    /// Id of the viewer role, it is added again if removed.
+    async fn get_viewer_role_id(&self, tx: &mut SqliteConnection) -> Result<i64> {
+        let name = Self::viewer_role().name;
+        match sqlx::query_scalar("SELECT id FROM roles WHERE name=? ORDER BY id LIMIT 1").bind(&name).fetch_optional(&mut *tx).await? {
+            Some(id) => Ok(id),
+            None => Ok(self.add_role(Self::viewer_role(), &mut *tx).await?.id),
+        }
+    }
+
+    pub async fn get_user_roles(&self, user_id: i64, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        Ok(sqlx::query_scalar("SELECT role_id FROM user_roles WHERE user_id=? ORDER BY role_id")
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn set_user_roles(&self, user_id: i64, roles: &[i64], tx: &mut SqliteConnection) -> Result<()> {
+        sqlx::query("DELETE FROM user_roles WHERE user_id=?").bind(user_id).execute(&mut *tx).await?;
+        for role_id in roles {
+            sqlx::query("INSERT INTO user_roles (user_id, role_id) VALUES(?, ?)")
+                .bind(user_id)
+                .bind(role_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Get the permissions granted by all roles of the user.
+    pub async fn get_user_permissions(&self, user_id: i64, tx: &mut SqliteConnection) -> Result<Vec<Permission>> {
+        let mut permissions: Vec<Permission> = sqlx::query_scalar(
+            "SELECT DISTINCT role_permissions.permission FROM user_roles
+            INNER JOIN role_permissions ON user_roles.role_id = role_permissions.role_id
+            WHERE user_roles.user_id=?",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        permissions.sort();
+        Ok(permissions)
+    }
+
+    pub async fn is_exists_role(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        is_exists_in_table("roles", "id", id, tx).await
+    }
+
+    pub async fn is_exists_role_name(&self, name: &str, prev: Option<i64>, tx: &mut SqliteConnection) -> Result<bool> {
+        row_is_duplicate_col_in_table(name, prev, "roles", "name", &mut *tx).await
+    }
+
+    async fn set_role_permissions(&self, role_id: i64, permissions: &[Permission], tx: &mut SqliteConnection) -> Result<()> {
+        sqlx::query("DELETE FROM role_permissions WHERE role_id=?").bind(role_id).execute(&mut *tx).await?;
+        for permission in permissions {
+            sqlx::query("INSERT INTO role_permissions (role_id, permission) VALUES(?, ?)")
+                .bind(role_id)
+                .bind(permission)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn add_role(&self, mut role: Role, tx: &mut SqliteConnection) -> Result<Role> {
+        let r = sqlx::query("INSERT INTO roles (name, description) VALUES(?, ?)")
+            .bind(&role.name)
+            .bind(&role.description)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() != 1 {
+            bail!("Can't add role");
+        }
+        role.id = r.last_insert_rowid();
+        role.permissions.sort();
+        role.permissions.dedup();
+        self.set_role_permissions(role.id, &role.permissions, &mut *tx).await?;
+        self.ps.notice(WebSocketFlags::AddRole(role.id)).await?;
+        Ok(role)
+    }
+
+    pub async fn update_role(&self, id: i64, mut role: Role, tx: &mut SqliteConnection) -> Result<Option<Role>> {
+        let r = sqlx::query("UPDATE roles SET name=?, description=? WHERE id=?")
+            .bind(&role.name)
+            .bind(&role.description)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(if r.rows_affected() == 1 {
+            role.id = id;
+            role.permissions.sort();
+            role.permissions.dedup();
+            self.set_role_permissions(id, &role.permissions, &mut *tx).await?;
+            self.ps.notice(WebSocketFlags::UpdateRole(id)).await?;
+            Some(role)
+        } else {
+            None
+        })
+    }
+
+    pub async fn remove_role(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
+        let r = remove_row_from_table(id, "roles", &mut *tx).await?;
+        sqlx::query("DELETE FROM role_permissions WHERE role_id=?").bind(id).execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM user_roles WHERE role_id=?").bind(id).execute(&mut *tx).await?;
+        self.ps.notice(WebSocketFlags::RemoveRole(id)).await?;
+        Ok(r)
+    }
+
+    pub async fn get_role(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Role>> {
+        Ok(match sqlx::query("SELECT name, description FROM roles WHERE id=?").bind(id).fetch_optional(&mut *tx).await? {
+            Some(row) => {
+                let mut permissions: Vec<Permission> = sqlx::query_scalar("SELECT permission FROM role_permissions WHERE role_id=?")
+                    .bind(id)
+                    .fetch_all(&mut *tx)
+                    .await?;
+                permissions.sort();
+                Some(Role {
+                    id,
+                    name: row.get("name"),
+                    description: row.get("description"),
+                    permissions,
+                })
+            }
+            None => None,
+        })
+    }
+
+    pub async fn get_roles(&self, pagination: &Pagination, tx: &mut SqliteConnection) -> Result<Vec<Role>> {
+        let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM roles ORDER BY id LIMIT ? OFFSET ?")
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut roles = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(role) = self.get_role(id, &mut *tx).await? {
+                roles.push(role);
+            }
+        }
+        Ok(roles)
+    }
+
+    pub async fn get_roles_count(&self, tx: &mut SqliteConnection) -> Result<i64> {
+        let row = sqlx::query("SELECT count(*) AS count FROM roles").fetch_one(&mut *tx).await?;
+        Ok(row.get("count"))
+    }
+
     pub async fn add_configure(&self, config: UserConfigure, tx: &mut SqliteConnection) -> Result<UserConfigure> {
         let r = sqlx::query("INSERT INTO configures (user_id, language, d_order_type, d_order_category_id, d_warehouse_id, d_person_related_id, d_order_currency) VALUES(?, ?, ?, ?, ?, ?, ?)")
             .bind(config.user_id)
             .bind(&config.language)
-            .bind(&config.defaults.order_type)
+            .bind(config.defaults.order_type)
             .bind(config.defaults.order_category_id)
             .bind(config.defaults.warehouse_id)
             .bind(config.defaults.person_related_id)
-            .bind(&config.defaults.order_currency)
+            .bind(config.defaults.order_currency)
             .execute(&mut *tx)
             .await?;
         if r.rows_affected() != 1 {
@@ -463,31 +898,27 @@ impl UserSystem {
 
     pub async fn get_configure(&self, user_id: i64, tx: &mut SqliteConnection) -> Result<Option<UserConfigure>> {
         let mut r = sqlx::query("SELECT * FROM configures WHERE user_id=?").bind(user_id).fetch(&mut *tx);
-        Ok(if let Some(row) = r.try_next().await? {
-            Some(UserConfigure {
-                user_id,
-                language: row.get("language"),
-                defaults: UserConfigureDefaults {
-                    order_type: row.get("d_order_type"),
-                    order_category_id: row.get("d_order_category_id"),
-                    warehouse_id: row.get("d_warehouse_id"),
-                    person_related_id: row.get("d_person_related_id"),
-                    order_currency: row.get("d_order_currency"),
-                },
-            })
-        } else {
-            None
-        })
+        Ok(r.try_next().await?.map(|row| UserConfigure {
+            user_id,
+            language: row.get("language"),
+            defaults: UserConfigureDefaults {
+                order_type: row.get("d_order_type"),
+                order_category_id: row.get("d_order_category_id"),
+                warehouse_id: row.get("d_warehouse_id"),
+                person_related_id: row.get("d_person_related_id"),
+                order_currency: row.get("d_order_currency"),
+            },
+        }))
     }
 
     pub async fn update_configure(&self, user_id: i64, config: UserConfigure, tx: &mut SqliteConnection) -> Result<Option<UserConfigure>> {
         let r = sqlx::query("UPDATE configures SET language=?, d_order_type=?, d_order_category_id=?, d_warehouse_id=?, d_person_related_id=?, d_order_currency=? WHERE user_id=?")
             .bind(&config.language)
-            .bind(&config.defaults.order_type)
+            .bind(config.defaults.order_type)
             .bind(config.defaults.order_category_id)
             .bind(config.defaults.warehouse_id)
             .bind(config.defaults.person_related_id)
-            .bind(&config.defaults.order_currency)
+            .bind(config.defaults.order_currency)
             .bind(user_id)
             .execute(&mut *tx)
             .await?;
@@ -499,3 +930,26 @@ impl UserSystem {
         Ok(r.rows_affected() == 1)
     }
 }
+
+/// Generate unguessable token from the OS random generator.
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| anyhow!("Can't hash the password: {err}"))
+}
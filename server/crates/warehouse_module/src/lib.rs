@@ -1,10 +1,13 @@
 use anyhow::bail;
 use anyhow::Result;
+use elerp_common::import::{IdCache, ImportRow, ImportRowResult};
 use elerp_common::model::action_type::ActionType;
 use elerp_common::model::Pagination;
 use elerp_common::model::WebSocketFlags;
 use elerp_common::sql;
-use elerp_common::sql::{get_row_from_table, is_exists_in_table, remove_row_from_table, row_is_duplicate_col_in_table, rows_to_objects};
+use elerp_common::sql::{
+    get_active_row_from_table, is_active_in_table, purge_rows_from_table, restore_row_from_table, row_is_duplicate_col_in_table, rows_to_objects, soft_remove_row_from_table,
+};
 use elerp_common::user_system::model::user_info::UserInfo;
 use elerp_common::user_system::model::user_info::UserType;
 use elerp_common::warehouse_module::model::fn_argument::UserInfoID;
@@ -32,7 +35,8 @@ impl WarehouseModule {
                 area_id INT NOT NULL,
                 address TEXT NOT NULL,
                 color TEXT NULL,
-                text_color TEXT NULL
+                text_color TEXT NULL,
+                deleted_at INT NULL
             )",
         )
         .execute(conn)
@@ -75,7 +79,7 @@ impl WarehouseModule {
     }
 
     pub async fn is_limit_reached(&self, tx: &mut SqliteConnection) -> Result<bool> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM warehouses;").fetch_one(&mut *tx).await?.get("count");
+        let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM warehouses WHERE deleted_at IS NULL;").fetch_one(&mut *tx).await?.get("count");
         Ok(count >= self.ps.get_config().limit.warehouses)
     }
 
@@ -83,7 +87,23 @@ impl WarehouseModule {
         row_is_duplicate_col_in_table(name, prev, "warehouses", "name", tx).await
     }
 
-    pub async fn add(&self, mut warehouse: Warehouse, tx: &mut SqliteConnection) -> Result<Warehouse> {
+    /// The warehouse of the import row.
+    pub async fn parse_import_row(&self, row: &ImportRow<'_>, ids: &mut IdCache, tx: &mut SqliteConnection) -> ImportRowResult<Warehouse> {
+        row.get_required("Area")?;
+        row.get_required("Person In Charge")?;
+        Ok(Warehouse {
+            id: 0,
+            name: row.get_required("Name")?.to_owned(),
+            description: row.get("Description").to_owned(),
+            person_in_charge_id: row.get_id("Person In Charge", "persons", ids, tx).await?,
+            area_id: row.get_id("Area", "areas", ids, tx).await?,
+            address: row.get("Address").to_owned(),
+            color: None,
+            text_color: None,
+        })
+    }
+
+    pub async fn add(&self, mut warehouse: Warehouse, notice: bool, tx: &mut SqliteConnection) -> Result<Warehouse> {
         let r = sqlx::query("INSERT INTO warehouses (name, description, address, person_in_charge_id, area_id, color, text_color) VALUES(?, ?, ?, ?, ?, ?, ?)")
             .bind(&warehouse.name)
             .bind(&warehouse.description)
@@ -98,24 +118,44 @@ impl WarehouseModule {
             bail!("Can't add warehouse");
         }
         warehouse.id = sql::try_set_standard_id(r.last_insert_rowid(), "warehouses", tx).await?;
-        self.ps.notice(WebSocketFlags::AddWarehouse(warehouse.id)).await?;
+        if notice {
+            self.ps.notice(WebSocketFlags::AddWarehouse(warehouse.id)).await?;
+        }
         Ok(warehouse)
     }
 
     pub async fn remove(&self, warehouse_id: i64, notice: bool, tx: &mut SqliteConnection) -> Result<bool> {
-        let r = remove_row_from_table(warehouse_id, "warehouses", tx).await?;
+        let r = soft_remove_row_from_table(warehouse_id, "warehouses", self.ps.get_timestamp_seconds() as i64, tx).await?;
         if notice {
             self.ps.notice(WebSocketFlags::RemoveWarehouse(warehouse_id)).await?;
         }
         Ok(r)
     }
 
+    pub async fn restore(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Warehouse>> {
+        if !restore_row_from_table(id, "warehouses", tx).await? {
+            return Ok(None);
+        }
+        self.ps.notice(WebSocketFlags::RestoreWarehouse(id)).await?;
+        self.get(id, tx).await
+    }
+
+    /// Physically delete the warehouses removed before the date, return the ids of them.
+    /// The links of the users to the warehouses are deleted too.
+    pub async fn purge(&self, deleted_before: Option<i64>, tx: &mut SqliteConnection) -> Result<Vec<i64>> {
+        let ids = purge_rows_from_table("warehouses", deleted_before, tx).await?;
+        for id in ids.iter() {
+            sqlx::query("DELETE FROM warehouse_permission WHERE warehouse_id=?").bind(id).execute(&mut *tx).await?;
+        }
+        Ok(ids)
+    }
+
     pub async fn get(&self, id: i64, tx: &mut SqliteConnection) -> Result<Option<Warehouse>> {
-        get_row_from_table("warehouses", "id", id, tx).await
+        get_active_row_from_table("warehouses", id, tx).await
     }
 
     pub async fn is_exists(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        is_exists_in_table("warehouses", "id", id, tx).await
+        is_active_in_table("warehouses", id, tx).await
     }
 
     fn get_permission_inner(&self, action: ActionType) -> String {
@@ -190,7 +230,7 @@ impl WarehouseModule {
     }
 
     pub async fn update(&self, id: i64, mut v: Warehouse, tx: &mut SqliteConnection) -> Result<Option<Warehouse>> {
-        let r = sqlx::query("UPDATE warehouses SET name=?, description=?, person_in_charge_id=?, area_id=?, address=?, color=?, text_color=? WHERE id=?")
+        let r = sqlx::query("UPDATE warehouses SET name=?, description=?, person_in_charge_id=?, area_id=?, address=?, color=?, text_color=? WHERE id=? AND deleted_at IS NULL")
             .bind(&v.name)
             .bind(&v.description)
             .bind(v.person_in_charge_id)
@@ -211,7 +251,9 @@ impl WarehouseModule {
     }
 
     pub async fn is_depend_by_another(&self, id: i64, tx: &mut SqliteConnection) -> Result<bool> {
-        Ok(sqlx::query("SELECT id FROM orders WHERE warehouse_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
+        Ok(sqlx::query("SELECT id FROM orders WHERE warehouse_id=? AND deleted_at IS NULL").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM purchase_orders WHERE warehouse_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some()
+            || sqlx::query("SELECT id FROM quotations WHERE warehouse_id=?").bind(id).fetch(&mut *tx).try_next().await?.is_some())
     }
 
     pub async fn link(&self, warehouse_id: i64, user_id: i64, tx: &mut SqliteConnection) -> Result<bool> {
@@ -290,13 +332,12 @@ impl WarehouseModule {
     }
 
     pub async fn get_linked_users(&self, warehouse_id: i64, pagination: &Pagination, tx: &mut SqliteConnection) -> Result<Vec<UserInfo>> {
-        let rows = sqlx::query(&format!(
+        let rows = sqlx::query(
             "SELECT users.*, 
-            CASE WHEN tokens.user_id IS NULL OR tokens.socket_count < 1 THEN 0 ELSE 1 END AS is_connected FROM users 
-            LEFT JOIN tokens ON users.id = tokens.user_id 
+            EXISTS(SELECT 1 FROM tokens WHERE tokens.user_id = users.id AND tokens.socket_count > 0) AS is_connected FROM users 
             INNER JOIN warehouse_permission
-            ON warehouse_permission.warehouse_id = ? AND warehouse_permission.user_id = users.id LIMIT ? OFFSET ?"
-        ))
+            ON warehouse_permission.warehouse_id = ? AND warehouse_permission.user_id = users.id LIMIT ? OFFSET ?",
+        )
         .bind(warehouse_id)
         .bind(pagination.limit())
         .bind(pagination.offset())
@@ -307,12 +348,12 @@ impl WarehouseModule {
 
     #[allow(dead_code)]
     pub async fn get_linked_warehouses(&self, user_id: i64, pagination: &Pagination, tx: &mut SqliteConnection) -> Result<Vec<Warehouse>> {
-        let rows = sqlx::query(&format!(
+        let rows = sqlx::query(
             "SELECT warehouses.*
             FROM warehouses
             INNER JOIN warehouse_permission
-            ON warehouse_permission.user_id = ? AND warehouse_permission.warehouse_id = warehouses.id LIMIT ? OFFSET ?"
-        ))
+            ON warehouse_permission.user_id = ? AND warehouse_permission.warehouse_id = warehouses.id LIMIT ? OFFSET ?",
+        )
         .bind(user_id)
         .bind(pagination.limit())
         .bind(pagination.offset())
@@ -322,7 +363,7 @@ impl WarehouseModule {
     }
 
     pub async fn get_linked_users_count(&self, warehouse_id: i64, tx: &mut SqliteConnection) -> Result<i64> {
-        let row = sqlx::query(&format!("SELECT count(*) as count FROM warehouse_permission WHERE warehouse_id=?"))
+        let row = sqlx::query("SELECT count(*) as count FROM warehouse_permission WHERE warehouse_id=?")
             .bind(warehouse_id)
             .fetch_one(&mut *tx)
             .await?;
@@ -7,7 +7,7 @@ async fn test_module() {
     let c = common::init_ctx().await;
 
     let mut tx = c.ps.begin_tx(false).await.unwrap();
-    assert_eq!(c.area.is_limit_reached(tx.as_mut()).await.unwrap(), false);
+    assert!(!c.area.is_limit_reached(tx.as_mut()).await.unwrap());
     tx.commit().await.unwrap();
 
     let max = c.ps.get_config().limit.areas;
@@ -24,6 +24,7 @@ async fn test_module() {
                     description: "testing area".to_owned(),
                     color: None,
                     text_color: None,
+                    price_list_id: 0,
                 },
                 tx.as_mut(),
             )
@@ -36,11 +37,54 @@ async fn test_module() {
     let mut tx = c.ps.begin_tx(false).await.unwrap();
     let count = c.area.get_count(&GetAreasQuery::default(), tx.as_mut()).await.unwrap();
     assert_eq!(count, max);
-    assert_eq!(c.area.is_limit_reached(tx.as_mut()).await.unwrap(), true);
-    assert_eq!(c.area.is_exists(last_id, tx.as_mut()).await.unwrap(), true);
-    assert_eq!(c.area.is_exists_name(&last_name, None, tx.as_mut()).await.unwrap(), true);
-    assert_eq!(c.area.is_exists_name(&last_name, Some(last_id), tx.as_mut()).await.unwrap(), false);
+    assert!(c.area.is_limit_reached(tx.as_mut()).await.unwrap());
+    assert!(c.area.is_exists(last_id, tx.as_mut()).await.unwrap());
+    assert!(c.area.is_exists_name(&last_name, None, tx.as_mut()).await.unwrap());
+    assert!(!c.area.is_exists_name(&last_name, Some(last_id), tx.as_mut()).await.unwrap());
 
     let row = c.area.get(last_id, tx.as_mut()).await.unwrap();
     assert!(row.is_some())
 }
+
+#[tokio::test]
+async fn test_remove_and_restore() {
+    let c = common::init_ctx().await;
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let area = c
+        .area
+        .add(
+            Area {
+                id: 0,
+                name: "Test area".to_owned(),
+                description: "testing area".to_owned(),
+                color: None,
+                text_color: None,
+                price_list_id: 0,
+            },
+            tx.as_mut(),
+        )
+        .await
+        .unwrap();
+    assert!(c.area.remove(area.id, false, tx.as_mut()).await.unwrap());
+    assert!(!c.area.remove(area.id, false, tx.as_mut()).await.unwrap());
+    assert!(!c.area.is_exists(area.id, tx.as_mut()).await.unwrap());
+    assert!(c.area.get(area.id, tx.as_mut()).await.unwrap().is_none());
+    assert_eq!(c.area.get_count(&GetAreasQuery::default(), tx.as_mut()).await.unwrap(), 0);
+    let deleted = GetAreasQuery {
+        deleted: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(c.area.get_count(&deleted, tx.as_mut()).await.unwrap(), 1);
+
+    let restored = c.area.restore(area.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(restored.name, area.name);
+    assert!(c.area.is_exists(area.id, tx.as_mut()).await.unwrap());
+    assert!(c.area.restore(area.id, tx.as_mut()).await.unwrap().is_none());
+
+    c.area.remove(area.id, false, tx.as_mut()).await.unwrap();
+    assert_eq!(c.area.purge(None, tx.as_mut()).await.unwrap(), vec![area.id]);
+    assert!(c.area.restore(area.id, tx.as_mut()).await.unwrap().is_none());
+    assert_eq!(c.area.get_count(&deleted, tx.as_mut()).await.unwrap(), 0);
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,57 @@
+pub mod common;
+
+use elerp_common::{
+    area_module::model::area::Area,
+    audit_log_module::model::audit_log::{AuditAction, AuditEntity, GetAuditLogsQuery},
+    model::Pagination,
+};
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let before = c.area.get(p.area1.id, tx.as_mut()).await.unwrap().unwrap();
+    let mut after = c.area.get(p.area1.id, tx.as_mut()).await.unwrap().unwrap();
+    after.name = "Renamed area".to_owned();
+    let after = c.area.update(p.area1.id, after, tx.as_mut()).await.unwrap().unwrap();
+    c.audit_log
+        .add(p.user1.id, AuditEntity::Area, p.area1.id, AuditAction::Update, Some(&before), Some(&after), tx.as_mut())
+        .await
+        .unwrap();
+    c.audit_log
+        .add(p.user2.id, AuditEntity::Area, p.area2.id, AuditAction::Remove, Some(&p.area2), None, tx.as_mut())
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = c.ps.begin_tx(false).await.unwrap();
+    let mut query = GetAuditLogsQuery {
+        user_id: None,
+        entity: Some(AuditEntity::Area),
+        entity_id: None,
+        action: None,
+        date_start: None,
+        date_end: None,
+        sorters: None,
+    };
+    assert_eq!(c.audit_log.get_count(&query, tx.as_mut()).await.unwrap(), 2);
+    let logs = c.audit_log.get_multiple(&Pagination::max(), &query, tx.as_mut()).await.unwrap();
+    assert_eq!(logs[0].entity_id, p.area2.id, "Latest log is expected first.");
+
+    query.action = Some(AuditAction::Remove);
+    let logs = c.audit_log.get_multiple(&Pagination::max(), &query, tx.as_mut()).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].user_id, p.user2.id);
+    assert!(logs[0].after.is_none());
+    let removed: Area = serde_json::from_str(logs[0].before.as_ref().unwrap()).unwrap();
+    assert_eq!(removed.name, p.area2.name);
+
+    query.action = None;
+    query.user_id = Some(p.user1.id);
+    let logs = c.audit_log.get_multiple(&Pagination::max(), &query, tx.as_mut()).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    let updated: Area = serde_json::from_str(logs[0].after.as_ref().unwrap()).unwrap();
+    assert_eq!(updated.name, "Renamed area");
+}
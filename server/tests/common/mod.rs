@@ -1,6 +1,12 @@
+// Each test uses only some of the modules and the prelude.
+#![allow(dead_code)]
+
 use area_module::AreaModule;
+use audit_log_module::AuditLogModule;
+use currency_module::CurrencyModule;
 use elerp_common::{
     area_module::model::area::Area,
+    config::AppConfig,
     get_test_config,
     order_category_module::model::order_category::OrderCategory,
     person_module::model::person::Person,
@@ -15,10 +21,15 @@ use order_category_module::OrderCategoryModule;
 use order_module::OrderModule;
 use order_payment_module::OrderPaymentModule;
 use person_module::PersonModule;
+use price_list_module::PriceListModule;
+use product_template_module::ProductTemplateModule;
+use purchase_order_module::PurchaseOrderModule;
 use public_system::PublicSystem;
+use quotation_module::QuotationModule;
 use sku_category_module::SKUCategoryModule;
 use sku_module::SKUModule;
 use statistical_module::StatisticalModule;
+use tax_module::TaxModule;
 use user_system::UserSystem;
 use warehouse_module::WarehouseModule;
 
@@ -34,8 +45,15 @@ pub struct TestContext {
     pub order_category: OrderCategoryModule,
     pub order_payment: OrderPaymentModule,
     pub guest_order: GuestOrderModule,
+    pub purchase_order: PurchaseOrderModule,
+    pub quotation: QuotationModule,
+    pub currency: CurrencyModule,
+    pub tax: TaxModule,
+    pub price_list: PriceListModule,
+    pub product_template: ProductTemplateModule,
     pub inventory: InventoryModule,
     pub statistical: StatisticalModule,
+    pub audit_log: AuditLogModule,
 }
 
 pub struct TestPrelude {
@@ -56,7 +74,10 @@ pub struct TestPrelude {
 }
 
 pub async fn init_ctx() -> TestContext {
-    let config = get_test_config();
+    init_ctx_with_config(get_test_config()).await
+}
+
+pub async fn init_ctx_with_config(config: AppConfig) -> TestContext {
     let ps = PublicSystem::new(config.clone()).await;
 
     TestContext {
@@ -70,8 +91,15 @@ pub async fn init_ctx() -> TestContext {
         order_category: OrderCategoryModule::new(ps.clone()).await,
         order_payment: OrderPaymentModule::new(ps.clone()).await,
         guest_order: GuestOrderModule::new(ps.clone()).await,
+        purchase_order: PurchaseOrderModule::new(ps.clone()).await,
+        quotation: QuotationModule::new(ps.clone()).await,
+        currency: CurrencyModule::new(ps.clone()).await,
+        tax: TaxModule::new(ps.clone()).await,
+        price_list: PriceListModule::new(ps.clone()).await,
+        product_template: ProductTemplateModule::new(ps.clone()).await,
         inventory: InventoryModule::new(ps.clone()).await,
         statistical: StatisticalModule::new(ps.clone()).await,
+        audit_log: AuditLogModule::new(ps.clone()).await,
         ps,
     }
 }
@@ -88,6 +116,7 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 description: "".to_owned(),
                 color: None,
                 text_color: None,
+                price_list_id: 0,
             },
             tx.as_mut(),
         )
@@ -102,6 +131,7 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 description: "".to_owned(),
                 color: None,
                 text_color: None,
+                price_list_id: 0,
             },
             tx.as_mut(),
         )
@@ -121,7 +151,9 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 email: "".to_owned(),
                 color: None,
                 text_color: None,
+                price_list_id: 0,
             },
+            true,
             tx.as_mut(),
         )
         .await
@@ -140,7 +172,9 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 email: "".to_owned(),
                 color: None,
                 text_color: None,
+                price_list_id: 0,
             },
+            true,
             tx.as_mut(),
         )
         .await
@@ -158,6 +192,7 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 color: None,
                 text_color: None,
             },
+            true,
             tx.as_mut(),
         )
         .await
@@ -175,6 +210,7 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 color: None,
                 text_color: None,
             },
+            true,
             tx.as_mut(),
         )
         .await
@@ -188,6 +224,7 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 description: "".to_owned(),
                 color: None,
                 text_color: None,
+                tax_rate_id: 0,
             },
             tx.as_mut(),
         )
@@ -202,6 +239,7 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 description: "".to_owned(),
                 color: None,
                 text_color: None,
+                tax_rate_id: 0,
             },
             tx.as_mut(),
         )
@@ -217,7 +255,16 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 description: "".to_owned(),
                 color: None,
                 text_color: None,
+                serialized: false,
+                tax_rate_id: 0,
+                code: "".to_owned(),
+                unit: "".to_owned(),
+                barcodes: vec![],
+                units: vec![],
+                template_id: 0,
+                attributes: Default::default(),
             },
+            true,
             tx.as_mut(),
         )
         .await
@@ -232,7 +279,16 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                 description: "".to_owned(),
                 color: None,
                 text_color: None,
+                serialized: false,
+                tax_rate_id: 0,
+                code: "".to_owned(),
+                unit: "".to_owned(),
+                barcodes: vec![],
+                units: vec![],
+                template_id: 0,
+                attributes: Default::default(),
             },
+            true,
             tx.as_mut(),
         )
         .await
@@ -276,7 +332,8 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                     username: "user1".to_owned(),
                     password: "tester".to_owned(),
                     user_type: UserType::General,
-                    permission: 0,
+                    roles: None,
+                    permissions: vec![],
                     is_connected: false,
                 },
                 tx.as_mut(),
@@ -292,7 +349,8 @@ pub async fn prelude(ctx: &TestContext) -> TestPrelude {
                     username: "user2".to_owned(),
                     password: "tester".to_owned(),
                     user_type: UserType::General,
-                    permission: 0,
+                    roles: None,
+                    permissions: vec![],
                     is_connected: false,
                 },
                 tx.as_mut(),
@@ -0,0 +1,149 @@
+mod common;
+
+use currency_module::CurrencyModule;
+use elerp_common::{
+    currency_module::model::currency::{Currency, ExchangeRate, GetExchangeRatesQuery},
+    inventory_module::model::inventory::GetInventoryQuery,
+    model::{action_type::ActionType, Pagination},
+    order_module::model::order::{GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+};
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let eur = OrderCurrency::new("eur").unwrap();
+    assert_eq!(eur.as_ref(), "EUR");
+    assert!(OrderCurrency::new("EURO").is_none());
+
+    // The currencies of the old enum are seeded.
+    assert_eq!(c.currency.list(tx.as_mut()).await.unwrap().len(), 8);
+    assert!(!c.currency.is_exists(eur, tx.as_mut()).await.unwrap());
+    assert!(c.currency.is_exists(OrderCurrency::Unknown, tx.as_mut()).await.unwrap());
+    let currency = Currency {
+        code: eur,
+        name: "Euro".to_owned(),
+        symbol: "€".to_owned(),
+    };
+    c.currency.set(currency, tx.as_mut()).await.unwrap();
+    assert!(c.currency.is_exists(eur, tx.as_mut()).await.unwrap());
+
+    // The latest rate on or before the date is used, the earliest one for the date before all the rates.
+    assert_eq!(c.currency.get_rate(OrderCurrency::USD, 100, tx.as_mut()).await.unwrap(), Some(1.0));
+    assert_eq!(c.currency.get_rate(eur, 100, tx.as_mut()).await.unwrap(), None);
+    let rate = ExchangeRate {
+        id: 0,
+        currency: eur,
+        date: 1000,
+        rate: 1.5,
+    };
+    let rate = c.currency.set_rate(rate, true, tx.as_mut()).await.unwrap();
+    let rates = CurrencyModule::parse_rates_csv("currency,date,rate\nEUR,2000,1.2\n\nEUR,1970-01-01,1.1\n").unwrap();
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[1].date, 0);
+    assert!(CurrencyModule::parse_rates_csv("EUR,2000,-1").is_err());
+    assert!(CurrencyModule::parse_rates_csv("EUR,2000").is_err());
+    c.currency.import_rates(rates, tx.as_mut()).await.unwrap();
+    let q = GetExchangeRatesQuery {
+        currency: Some(eur),
+        ..Default::default()
+    };
+    assert_eq!(c.currency.get_rates_count(&q, tx.as_mut()).await.unwrap(), 3);
+    assert_eq!(c.currency.get_rates(&Pagination::max(), &q, tx.as_mut()).await.unwrap()[0].rate, 1.2);
+    assert_eq!(c.currency.get_rate(eur, 1500, tx.as_mut()).await.unwrap(), Some(1.5));
+    assert_eq!(c.currency.get_rate(eur, 2500, tx.as_mut()).await.unwrap(), Some(1.2));
+    assert!(c.currency.remove_rate(rate.id, tx.as_mut()).await.unwrap());
+    assert_eq!(c.currency.get_rate(eur, 1500, tx.as_mut()).await.unwrap(), Some(1.1));
+
+    // The amounts of all the currencies are converted to the base currency.
+    let new_order = |currency: OrderCurrency, order_type: OrderType, is_record: bool| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency,
+        items: Some(vec![OrderItem {
+            sku_id: p.sku1.id,
+            quantity: 10,
+            price: 10.0,
+            exchanged: false,
+            lot_number: None,
+            expiry_date: None,
+            serial_numbers: vec![],
+            tax_rate: None,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+            sku_code: None,
+            unit: None,
+            unit_factor: 1,
+        }]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing currency".to_owned(),
+        order_type,
+        is_record,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    for currency in [OrderCurrency::USD, eur, OrderCurrency::CNY] {
+        let mut order = new_order(currency, OrderType::StockOut, true);
+        c.order.preprocess(&mut order, &p.user1, true, p.person2.id);
+        c.order.add(order, true, tx.as_mut()).await.unwrap();
+    }
+    assert!(c.currency.is_depend_by_another(eur, tx.as_mut()).await.unwrap());
+    let q = GetOrdersQuery::empty();
+    assert_eq!(c.statistical.get_total_amount(&q, ActionType::Admin, tx.as_mut()).await.unwrap().len(), 3);
+    let (amount, unconverted_count) = c.statistical.get_total_amount_in_base_currency(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(amount.currency, OrderCurrency::USD);
+    assert_eq!(amount.any, 100.0 + 120.0);
+    assert_eq!(unconverted_count, 1);
+
+    // The cost of the stock in is converted to the base currency.
+    let mut order = new_order(eur, OrderType::StockIn, false);
+    c.order.preprocess(&mut order, &p.user1, true, p.person2.id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let value: f64 = sqlx::query_scalar("SELECT SUM(quantity * unit_cost) FROM inventory_cost_layers WHERE warehouse_id=?")
+        .bind(p.warehouse1.id)
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+    assert_eq!(value, 120.0);
+    // The cost of goods sold is in the base currency, the total in the currency of the order is converted back.
+    let mut order = new_order(eur, OrderType::StockOut, false);
+    c.order.preprocess(&mut order, &p.user1, true, p.person2.id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let (amount, _) = c.statistical.get_total_amount_in_base_currency(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(amount.cost_of_goods_sold, 120.0);
+    let amounts = c.statistical.get_total_amount(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(amounts.iter().find(|v| v.currency == eur).unwrap().cost_of_goods_sold, 100.0);
+    // The sales are converted to the base currency before the cost is subtracted.
+    let q = GetInventoryQuery {
+        warehouse_ids: Some([p.warehouse1.id].into_iter().collect()),
+        sku_ids: Some([p.sku1.id].into_iter().collect()),
+        sku_category_ids: None,
+        template_ids: None,
+        attributes: None,
+        quantity_start: None,
+        quantity_end: None,
+        unit: None,
+        sorters: None,
+    };
+    let products = c.inventory.list(&Pagination::max(), &q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(products[0].gross_margin, 120.0 - 120.0);
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use elerp_common::{
+    model::{action_type::ActionType, Pagination},
+    order_module::model::order::{Discount, DiscountType, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    order_payment_module::model::order_payment::OrderPayment,
+    statistical_module::model::statistical_data::GetStatisticalDataQuery,
+};
+
+fn item(sku_id: i64, quantity: i64, price: f64, lot_number: Option<&str>, discount: Option<Discount>) -> OrderItem {
+    OrderItem {
+        sku_id,
+        quantity,
+        price,
+        exchanged: false,
+        lot_number: lot_number.map(|v| v.to_owned()),
+        expiry_date: None,
+        serial_numbers: vec![],
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    }
+}
+
+fn discount(discount_type: DiscountType, value: f64) -> Option<Discount> {
+    Some(Discount {
+        discount_type,
+        value,
+        reason: Some("PROMO".to_owned()),
+    })
+}
+
+#[test]
+fn test_calc_discounts() {
+    assert!(discount(DiscountType::Percentage, 100.0).unwrap().is_valid());
+    assert!(!discount(DiscountType::Percentage, 120.0).unwrap().is_valid());
+    assert!(!discount(DiscountType::Fixed, -1.0).unwrap().is_valid());
+    assert!(!discount(DiscountType::Fixed, f64::NAN).unwrap().is_valid());
+    assert_eq!(discount(DiscountType::Fixed, 50.0).unwrap().calc(20.0), 20.0);
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let new_order = |order_type: OrderType, items: Vec<OrderItem>, discount: Option<Discount>| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing discount".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount,
+        discount_amount: 0.0,
+    };
+
+    let mut order = new_order(OrderType::StockIn, vec![item(p.sku1.id, 5, 4.0, Some("L1"), None), item(p.sku1.id, 5, 4.0, Some("L2"), None), item(p.sku2.id, 5, 4.0, None, None)], None);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    // The order discount is shared by the items after their own discounts.
+    let items = vec![item(p.sku1.id, 8, 10.0, None, discount(DiscountType::Fixed, 8.0)), item(p.sku2.id, 2, 10.0, None, discount(DiscountType::Percentage, 10.0))];
+    let mut order = new_order(OrderType::StockOut, items, discount(DiscountType::Fixed, 9.0));
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    assert_eq!(order.items.as_ref().unwrap().iter().map(|item| item.discount_amount).collect::<Vec<_>>(), vec![15.2, 3.8]);
+    assert_eq!((order.discount_amount, order.total_amount), (19.0, 81.0));
+
+    // The discount of the item is shared by the lots it is split to.
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!((order.discount_amount, order.net_amount, order.total_amount), (19.0, 81.0, 81.0));
+    let items = c.order.get_order_items(order.id, &Pagination::max(), tx.as_mut()).await.unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items.iter().map(|item| item.quantity).collect::<Vec<_>>(), vec![5, 3, 2]);
+    assert_eq!(items.iter().map(|item| item.discount_amount).collect::<Vec<_>>(), vec![9.5, 5.7, 3.8]);
+    assert_eq!(items.iter().map(|item| item.price).collect::<Vec<_>>(), vec![10.0, 10.0, 10.0]);
+    assert_eq!(items[0].discount, discount(DiscountType::Fixed, 8.0));
+    let got = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(got.discount, discount(DiscountType::Fixed, 9.0));
+    assert_eq!((got.discount_amount, got.total_amount), (19.0, 81.0));
+
+    // The payments settle the discounted amount.
+    let mut payment = OrderPayment {
+        id: 0,
+        created_by_user_id: 0,
+        order_id: order.id,
+        warehouse_id: 0,
+        person_in_charge_id: 0,
+        creation_date: 0,
+        actual_date: 0,
+        total_amount: 81.0,
+        remark: "".to_owned(),
+    };
+    c.order_payment.preprocess(&mut payment, &p.user1);
+    c.order_payment.add(payment, tx.as_mut()).await.unwrap();
+    let got = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(got.order_payment_status, OrderPaymentStatus::Settled);
+
+    let q = GetStatisticalDataQuery {
+        date_start: None,
+        date_end: None,
+        order_category_id: None,
+        warehouse_ids: None,
+        items: None,
+        item_categories: None,
+        person_related_id: None,
+        person_in_charge_id: None,
+        currency: None,
+        created_by_user_id: None,
+        reverse: None,
+    };
+    let data = c.statistical.get(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    let sku1 = data.most_popular_skus.iter().find(|v| v.id == p.sku1.id).unwrap();
+    assert_eq!((sku1.average_price, sku1.average_list_price), (8.1, 10.0));
+    assert_eq!(data.order.total_amount[0].any, 81.0);
+    assert_eq!(data.order.total_amount[0].gross_margin, 81.0 - 40.0);
+    tx.commit().await.unwrap();
+}
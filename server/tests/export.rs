@@ -0,0 +1,215 @@
+mod common;
+
+use elerp_common::{
+    guest_order_module::model::guest_order::GetGuestOrdersQuery,
+    import::{IdCache, ImportTable},
+    model::{
+        action_type::ActionType,
+        export::{ExportFormat, ExportQuery},
+    },
+    order_module::model::order::{GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    order_payment_module::model::order_payment::{GetOrderPaymentsQuery, OrderPayment},
+    person_module::model::person::GetPersonsQuery,
+    sku_module::model::sku::GetSKUsQuery,
+};
+
+async fn read_csv(path: impl AsRef<std::path::Path>) -> Vec<Vec<String>> {
+    let buf = tokio::fs::read_to_string(path).await.unwrap();
+    buf.lines().map(|line| line.split(',').map(|v| v.to_owned()).collect()).collect()
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let item = |sku_id: i64, quantity: i64| OrderItem {
+        sku_id,
+        quantity,
+        price: 2.0,
+        exchanged: false,
+        lot_number: None,
+        expiry_date: None,
+        serial_numbers: vec![],
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    };
+    let mut order = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![item(p.sku1.id, 10), item(p.sku2.id, 5)]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing export".to_owned(),
+        order_type: OrderType::StockIn,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    // One row per order, the names are resolved.
+    let export = ExportQuery {
+        format: Some(ExportFormat::Csv),
+        with_items: None,
+    };
+    let path = c.order.get_export(&GetOrdersQuery::empty(), &export, true, ActionType::Admin, tx.as_mut()).await.unwrap();
+    let rows = read_csv(path).await;
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][..5], ["Order", "Date", "Order Type", "Order Category", "Warehouse"]);
+    assert_eq!(rows[1][4..7], [p.warehouse1.name.clone(), "".to_owned(), p.person1.name.clone()]);
+    assert_eq!(rows[1][14], "30");
+    assert_eq!((rows[0][16].as_str(), rows[1][16].as_str()), ("Total Amount (USD)", "30"));
+
+    // One row per order item, the amounts are hidden without the price permission.
+    let export = ExportQuery {
+        format: Some(ExportFormat::Csv),
+        with_items: Some(true),
+    };
+    let path = c.order.get_export(&GetOrdersQuery::empty(), &export, false, ActionType::Admin, tx.as_mut()).await.unwrap();
+    let rows = read_csv(path).await;
+    assert_eq!(rows.len(), 3);
+    assert!(!rows[0].contains(&"Total Amount".to_owned()));
+    assert_eq!(rows[1][12..14], [p.sku1.name.clone(), "10".to_owned()]);
+    assert_eq!(rows[2][12..14], [p.sku2.name.clone(), "5".to_owned()]);
+
+    // The permission join of the warehouses is honoured.
+    let path = c.order.get_export(&GetOrdersQuery::empty(), &export, true, ActionType::General(p.user2.id), tx.as_mut()).await.unwrap();
+    assert_eq!(read_csv(path).await.len(), 1);
+
+    let path = c.order.get_export(&GetOrdersQuery::empty(), &ExportQuery::default(), true, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert!(tokio::fs::read(&path).await.unwrap().starts_with(b"PK"));
+    // The files exported at the same time don't collide, each one is removed when its path is dropped.
+    let another = c.order.get_export(&GetOrdersQuery::empty(), &ExportQuery::default(), true, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_ne!(path.to_path_buf(), another.to_path_buf());
+    let file = path.to_path_buf();
+    drop(path);
+    assert!(!file.exists());
+    assert!(another.is_file());
+
+    // The transfer exported with its items is imported again.
+    let mut transfer = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![item(p.sku1.id, 2)]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "".to_owned(),
+        order_type: OrderType::Transfer,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: p.warehouse2.id,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    c.order.preprocess(&mut transfer, &p.user1, true, p.person1.person_in_charge_id);
+    let transfer = c.order.add(transfer, true, tx.as_mut()).await.unwrap();
+    let query = GetOrdersQuery {
+        id: Some(transfer.id),
+        ..GetOrdersQuery::empty()
+    };
+    let export = ExportQuery {
+        format: Some(ExportFormat::Xlsx),
+        with_items: Some(true),
+    };
+    let path = c.order.get_export(&query, &export, true, ActionType::Admin, tx.as_mut()).await.unwrap();
+    let table = ImportTable::read(ExportFormat::Xlsx, &tokio::fs::read(&path).await.unwrap()).unwrap();
+    let rows: Vec<_> = table.rows().collect();
+    let mut ids = IdCache::new();
+    let imported = c.order.parse_import_order(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((imported.order_type, imported.warehouse_id, imported.to_warehouse_id), (OrderType::Transfer, p.warehouse1.id, p.warehouse2.id));
+    assert_eq!((imported.person_related_id, imported.order_category_id, imported.currency), (p.person1.id, p.order_category1.id, OrderCurrency::USD));
+    let imported = c.order.parse_import_item(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((imported.sku_id, imported.quantity, imported.price), (p.sku1.id, 2, 2.0));
+
+    let mut payment = OrderPayment {
+        id: 0,
+        created_by_user_id: 0,
+        order_id: order.id,
+        warehouse_id: 0,
+        person_in_charge_id: 0,
+        creation_date: 0,
+        actual_date: 0,
+        total_amount: 12.0,
+        remark: "Cash".to_owned(),
+    };
+    c.order_payment.preprocess(&mut payment, &p.user1);
+    c.order_payment.add(payment, tx.as_mut()).await.unwrap();
+    let q = GetOrderPaymentsQuery {
+        id: None,
+        order_id: Some(order.id),
+        warehouse_ids: None,
+        created_by_user_id: None,
+        person_in_charge_id: None,
+        creation_date_start: None,
+        creation_date_end: None,
+        actual_date_start: None,
+        actual_date_end: None,
+        deleted: None,
+        sorters: None,
+    };
+    let rows = read_csv(c.order_payment.get_export(&q, ExportFormat::Csv, ActionType::Admin, tx.as_mut()).await.unwrap()).await;
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][6..9], ["Currency", "Total Amount", "Total Amount (USD)"]);
+    assert_eq!((rows[1][2].as_str(), rows[1][7].as_str(), rows[1][8].as_str(), rows[1][9].as_str()), (p.warehouse1.name.as_str(), "12", "12", "Cash"));
+
+    let rows = read_csv(c.person.get_export(&GetPersonsQuery::default(), ExportFormat::Csv, ActionType::Admin, tx.as_mut()).await.unwrap()).await;
+    assert_eq!(rows.len(), 3);
+    let person2 = rows.iter().find(|row| row[1] == p.person2.name).unwrap();
+    assert_eq!((person2[2].as_str(), person2[3].as_str()), (p.area2.name.as_str(), p.person1.name.as_str()));
+
+    let q = GetSKUsQuery {
+        id: None,
+        sku_category_id: None,
+        name: None,
+        code: None,
+        template_id: None,
+        attributes: None,
+        deleted: None,
+        sorters: None,
+    };
+    let rows = read_csv(c.sku.get_export(&q, ExportFormat::Csv, tx.as_mut()).await.unwrap()).await;
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1][2], p.sku_category1.name);
+
+    let rows = read_csv(c.guest_order.get_export(&GetGuestOrdersQuery::default(), ExportFormat::Csv, ActionType::Admin, tx.as_mut()).await.unwrap()).await;
+    assert_eq!(rows.len(), 1);
+    tx.commit().await.unwrap();
+}
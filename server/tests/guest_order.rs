@@ -1,9 +1,17 @@
 mod common;
 
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
 use elerp_common::{
     guest_order_module::model::guest_order::{GuestOrder, GuestOrderStatus},
+    model::Pagination,
     order_module::model::order::{OrderCurrency, OrderItem, OrderType},
+    price_list_module::model::price_list::{PriceList, PriceListItem},
 };
+use elerp_service::{erp::ERP, erp_service, AppState};
+use tower::ServiceExt;
 
 #[tokio::test]
 async fn test_order_preprocess() {
@@ -68,6 +76,16 @@ async fn test_module() {
                 quantity: 5,
                 price: 1.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             }]),
         };
         let mut to_confirm = guest.clone();
@@ -91,7 +109,7 @@ async fn test_module() {
         assert!(result.order.is_some());
 
         to_confirm.order_type = OrderType::StockOut;
-        to_confirm.items.as_mut().map(|items| items[0].quantity = 3);
+        to_confirm.items.as_mut().unwrap()[0].quantity = 3;
         let r = c.guest_order.add("testing_changed", to_confirm.clone(), tx.as_mut()).await.unwrap();
         let result = c.guest_order.confirm(r.id, to_confirm.clone(), tx.as_mut()).await.unwrap().unwrap();
         assert!(result.check_result.items_not_available.is_empty());
@@ -99,7 +117,7 @@ async fn test_module() {
         assert!(result.order.is_some());
 
         to_confirm.is_record = true;
-        to_confirm.items.as_mut().map(|items| items[0].quantity = 3);
+        to_confirm.items.as_mut().unwrap()[0].quantity = 3;
         let r = c.guest_order.add("testing_changed", to_confirm.clone(), tx.as_mut()).await.unwrap();
         let result = c.guest_order.confirm(r.id, to_confirm.clone(), tx.as_mut()).await.unwrap().unwrap();
         assert!(result.check_result.items_not_available.is_empty());
@@ -109,3 +127,73 @@ async fn test_module() {
         tx.commit().await.unwrap();
     }
 }
+
+#[tokio::test]
+async fn test_confirm_without_prices() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let list = PriceList {
+        id: 0,
+        name: "Default".to_owned(),
+        description: "".to_owned(),
+        is_default: true,
+    };
+    let list = c.price_list.add(list, tx.as_mut()).await.unwrap();
+    let item = PriceListItem {
+        id: 0,
+        price_list_id: list.id,
+        sku_id: p.sku1.id,
+        currency: OrderCurrency::USD,
+        price: 4.0,
+        valid_from: None,
+        valid_to: None,
+    };
+    c.price_list.add_item(item, tx.as_mut()).await.unwrap();
+    let token = c.us.get_sub_token(&p.user1, tx.as_mut()).await.unwrap();
+    let guest = GuestOrder {
+        id: 0,
+        created_by_user_id: p.user1.id,
+        person_in_charge_id: p.person1.id,
+        date: 0,
+        confirmed_date: 0,
+        sub_token: "".to_owned(),
+        currency: OrderCurrency::USD,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person2.id,
+        description: "".to_owned(),
+        order_type: OrderType::StockIn,
+        is_record: false,
+        non_payment: false,
+        guest_order_status: GuestOrderStatus::Pending,
+        order_id: 0,
+        order_category_id: p.order_category1.id,
+        items: None,
+    };
+    let guest = c.guest_order.add(&token, guest, tx.as_mut()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    // The guest confirms the lines without the prices, they are filled by the price lists.
+    let state = AppState {
+        erp: ERP::new(c.ps.clone()).await,
+        us: c.us.clone(),
+        ps: c.ps.clone(),
+    };
+    let mut body = serde_json::to_value(&guest).unwrap();
+    body["items"] = serde_json::json!([{ "sku_id": p.sku1.id, "quantity": 2 }]);
+    let request = Request::put(format!("/guest_orders/{}", guest.id))
+        .header("Content-Type", "application/json")
+        .header("X-Sub-Authorization", &token)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = erp_service::get_services().with_state(state).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut tx = c.ps.begin_tx(false).await.unwrap();
+    let guest = c.guest_order.get(guest.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(guest.guest_order_status, GuestOrderStatus::Confirmed);
+    let items = c.order.get_order_items(guest.order_id, &Pagination::max(), tx.as_mut()).await.unwrap();
+    assert_eq!((items[0].quantity, items[0].price), (2, 4.0));
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use elerp_common::{
+    export::ExportWriter,
+    import::{IdCache, ImportError, ImportTable},
+    model::export::ExportFormat,
+    order_module::model::order::{Order, OrderCurrency, OrderPaymentStatus, OrderType},
+    sku_module::model::sku::SKU,
+    tax_module::model::tax_rate::TaxRate,
+};
+
+fn row_error<T>(r: Result<T, ImportError>) -> String {
+    match r {
+        Err(ImportError::Row(msg)) => msg,
+        _ => panic!("Expected the error of the row."),
+    }
+}
+
+#[test]
+fn test_table() {
+    let csv = "Name, Quantity,Serialized,Date\nA,10,yes,2024-01-02\n,,,\nB,x,maybe,2024-01-02 03:04:05\n";
+    let table = ImportTable::read(ExportFormat::Csv, csv.as_bytes()).unwrap();
+    // The empty row is skipped but the row numbers are the same as the file.
+    assert_eq!(table.len(), 2);
+    assert!(table.has_header("Quantity"));
+    assert_eq!(table.get_missing_header(&["Name", "Price"]), Some("Price"));
+    let rows: Vec<_> = table.rows().collect();
+    assert_eq!((rows[0].number(), rows[0].get("Name"), rows[0].get("Missing")), (2, "A", ""));
+    assert_eq!(rows[0].get_i64("Quantity").unwrap(), Some(10));
+    assert!(rows[0].get_bool("Serialized").unwrap());
+    assert_eq!(rows[0].get_date("Date").unwrap(), Some(1704153600));
+    assert_eq!(rows[1].number(), 4);
+    assert_eq!(row_error(rows[1].get_i64("Quantity")), "Quantity must be an integer.");
+    assert_eq!(row_error(rows[1].get_bool("Serialized")), "Serialized must be true or false.");
+    assert_eq!(rows[1].get_date("Date").unwrap(), Some(1704164645));
+    assert!(ImportTable::read(ExportFormat::Csv, b"").is_err());
+    assert!(ImportTable::read(ExportFormat::Xlsx, b"not a workbook").is_err());
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut ids = IdCache::new();
+
+    // The exported files can be imported again.
+    let mut writer = ExportWriter::new(ExportFormat::Xlsx, c.ps.get_data_path(), "skus", 0, &["Name", "SKU Category", "Serialized", "Tax Rate", "Description"]).unwrap();
+    writer.write_row(vec!["SKU 3".into(), p.sku_category1.name.as_str().into(), true.into(), "VAT".into(), "Imported".into()]).unwrap();
+    writer.write_row(vec!["SKU 4".into(), "Unknown".into(), false.into(), "".into(), "".into()]).unwrap();
+    writer.write_row(vec!["".into(), p.sku_category1.name.as_str().into(), false.into(), "".into(), "".into()]).unwrap();
+    let buf = tokio::fs::read(writer.finish().unwrap()).await.unwrap();
+    let table = ImportTable::read(ExportFormat::Xlsx, &buf).unwrap();
+    let rows: Vec<_> = table.rows().collect();
+    assert_eq!(row_error(c.sku.parse_import_row(&rows[0], &mut ids, tx.as_mut()).await), "Tax Rate 'VAT' is not found.");
+    let vat = TaxRate {
+        id: 0,
+        name: "VAT".to_owned(),
+        rate: 10.0,
+        description: "".to_owned(),
+    };
+    let vat = c.tax.add(vat, tx.as_mut()).await.unwrap();
+    let sku = c.sku.parse_import_row(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((sku.name.as_str(), sku.sku_category_id, sku.serialized, sku.tax_rate_id), ("SKU 3", p.sku_category1.id, true, vat.id));
+    assert_eq!(sku.description, "Imported");
+    assert_eq!(row_error(c.sku.parse_import_row(&rows[1], &mut ids, tx.as_mut()).await), "SKU Category 'Unknown' is not found.");
+    assert_eq!(row_error(c.sku.parse_import_row(&rows[2], &mut ids, tx.as_mut()).await), "Name is required.");
+
+    // The person in charge can be the person added by the rows before.
+    let csv = format!(
+        "Name,Area,Person In Charge,Address,Contact,Email,Price List,Description\nPerson 3,{},,Street 1,123,p3@example.com,,\nPerson 4,{},Person 3,,,,,\n",
+        p.area1.name, p.area2.name
+    );
+    let table = ImportTable::read(ExportFormat::Csv, csv.as_bytes()).unwrap();
+    let rows: Vec<_> = table.rows().collect();
+    let person3 = c.person.parse_import_row(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((person3.area_id, person3.person_in_charge_id, person3.email.as_str()), (p.area1.id, 0, "p3@example.com"));
+    assert_eq!(row_error(c.person.parse_import_row(&rows[1], &mut ids, tx.as_mut()).await), "Person In Charge 'Person 3' is not found.");
+    // The rows imported are noticed after the commit.
+    let mut rx = c.ps.notication_subscribe().await;
+    let person3 = c.person.add(person3, false, tx.as_mut()).await.unwrap();
+    assert!(rx.try_recv().is_err());
+    let person4 = c.person.parse_import_row(&rows[1], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((person4.area_id, person4.person_in_charge_id), (p.area2.id, person3.id));
+
+    let csv = format!("Name,Area,Person In Charge\nWarehouse 3,{},{}\nWarehouse 4,{},\n", p.area1.name, p.person1.name, p.area1.name);
+    let table = ImportTable::read(ExportFormat::Csv, csv.as_bytes()).unwrap();
+    let rows: Vec<_> = table.rows().collect();
+    let warehouse = c.warehouse.parse_import_row(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((warehouse.area_id, warehouse.person_in_charge_id), (p.area1.id, p.person1.id));
+    assert_eq!(row_error(c.warehouse.parse_import_row(&rows[1], &mut ids, tx.as_mut()).await), "Person In Charge is required.");
+
+    // The sku name used by several categories needs its category.
+    let sku = SKU {
+        id: 0,
+        sku_category_id: p.sku_category2.id,
+        name: p.sku1.name.clone(),
+        description: "".to_owned(),
+        color: None,
+        text_color: None,
+        serialized: false,
+        tax_rate_id: 0,
+        code: "".to_owned(),
+        unit: "".to_owned(),
+        barcodes: vec![],
+        units: vec![],
+        template_id: 0,
+        attributes: Default::default(),
+    };
+    c.sku.add(sku, true, tx.as_mut()).await.unwrap();
+    let csv = format!(
+        "Warehouse,SKU,SKU Category,Quantity,Price,Lot Number,Expiry Date\n{w},{s1},,5,,,\n{w},{s1},{c1},5,2.5,L1,2030-01-01\n{w},{s2},,-1,,,\n{w},Unknown,,1,,,\n",
+        w = p.warehouse1.name,
+        s1 = p.sku1.name,
+        c1 = p.sku_category1.name,
+        s2 = p.sku2.name,
+    );
+    let table = ImportTable::read(ExportFormat::Csv, csv.as_bytes()).unwrap();
+    let rows: Vec<_> = table.rows().collect();
+    assert_eq!(
+        row_error(c.order.parse_opening_stock_row(&rows[0], &mut ids, tx.as_mut()).await),
+        format!("SKU '{}' is in several categories, the SKU Category is required.", p.sku1.name)
+    );
+    let (warehouse_id, item) = c.order.parse_opening_stock_row(&rows[1], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((warehouse_id, item.sku_id, item.quantity, item.price), (p.warehouse1.id, p.sku1.id, 5, 2.5));
+    assert_eq!((item.lot_number.as_deref(), item.expiry_date), (Some("L1"), Some(1893456000)));
+    assert_eq!(row_error(c.order.parse_opening_stock_row(&rows[2], &mut ids, tx.as_mut()).await), "Quantity can't be negative.");
+    assert_eq!(row_error(c.order.parse_opening_stock_row(&rows[3], &mut ids, tx.as_mut()).await), "SKU 'Unknown' is not found.");
+
+    // The opening stock is added as the calibration order.
+    let mut order = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![item]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id,
+        person_related_id: p.warehouse1.person_in_charge_id,
+        description: "Opening stock".to_owned(),
+        order_type: OrderType::Calibration,
+        is_record: false,
+        non_payment: true,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    c.order.preprocess(&mut order, &p.user1, true, 0);
+    assert!(c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let quantity: i64 = sqlx::query_scalar("SELECT quantity FROM inventory WHERE warehouse_id=? AND sku_id=?")
+        .bind(p.warehouse1.id)
+        .bind(p.sku1.id)
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+    assert_eq!(quantity, 5);
+    // The price of the opening stock is the unit cost.
+    let value: f64 = sqlx::query_scalar("SELECT SUM(quantity * unit_cost) FROM inventory_cost_layers WHERE warehouse_id=? AND sku_id=?")
+        .bind(p.warehouse1.id)
+        .bind(p.sku1.id)
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+    assert_eq!(value, 12.5);
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_orders() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut ids = IdCache::new();
+
+    let csv = format!(
+        "Order,Order Type,Person,Warehouse,To Warehouse,Order Category,Currency,Description,SKU,Quantity,Price,Tax Rate\n\
+        A,StockIn,{person},{w1},,{category},gbp,Big customer,{s1},10,2.5,\n\
+        A,,,,,,,,{s2},3,,5\n\
+        B,Transfer,{person},{w1},,{category},,,{s1},1,,\n\
+        C,Sell,{person},{w1},,{category},,,{s1},1,,\n\
+        D,StockOut,{person},{w1},,{category},EURO,,{s1},1,,\n",
+        person = p.person1.name,
+        w1 = p.warehouse1.name,
+        category = p.order_category1.name,
+        s1 = p.sku1.name,
+        s2 = p.sku2.name,
+    );
+    let table = ImportTable::read(ExportFormat::Csv, csv.as_bytes()).unwrap();
+    let rows: Vec<_> = table.rows().collect();
+    let order = c.order.parse_import_order(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((order.order_type, order.currency, order.description.as_str()), (OrderType::StockIn, OrderCurrency::GBP, "Big customer"));
+    assert_eq!((order.person_related_id, order.warehouse_id, order.order_category_id), (p.person1.id, p.warehouse1.id, p.order_category1.id));
+    let item = c.order.parse_import_item(&rows[0], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((item.sku_id, item.quantity, item.price, item.tax_rate), (p.sku1.id, 10, 2.5, None));
+    // The price omitted is filled by the price lists as adding the order.
+    let item = c.order.parse_import_item(&rows[1], &mut ids, tx.as_mut()).await.unwrap();
+    assert_eq!((item.sku_id, item.is_price_set(), item.tax_rate), (p.sku2.id, false, Some(5.0)));
+
+    assert_eq!(row_error(c.order.parse_import_order(&rows[2], &mut ids, tx.as_mut()).await), "To Warehouse is required.");
+    assert_eq!(row_error(c.order.parse_import_order(&rows[3], &mut ids, tx.as_mut()).await), "Order Type 'Sell' is invalid.");
+    assert_eq!(row_error(c.order.parse_import_order(&rows[4], &mut ids, tx.as_mut()).await), "Currency 'EURO' is invalid.");
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,656 @@
+mod common;
+
+use elerp_common::{
+    config::ValuationMethod,
+    get_test_config,
+    inventory_module::model::{
+        inventory::{GetInventoryLotsQuery, GetInventoryQuery},
+        reorder_point::{InventoryAlertType, ReorderPoint},
+        reservation::{GetReservationsQuery, Reservation},
+    },
+    model::{action_type::ActionType, Pagination, WebSocketFlags},
+    order_module::{
+        self,
+        model::{
+            order::{GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+            serial_number::{GetSerialNumberQuery, SerialNumberStatus},
+        },
+    },
+    warehouse_module::model::warehouse::Warehouse,
+};
+
+fn item(sku_id: i64, quantity: i64, lot_number: Option<&str>, expiry_date: Option<i64>) -> OrderItem {
+    OrderItem {
+        sku_id,
+        quantity,
+        price: 1.0,
+        exchanged: false,
+        lot_number: lot_number.map(|v| v.to_owned()),
+        expiry_date,
+        serial_numbers: vec![],
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    }
+}
+
+fn lots_query(expiring_within_days: Option<i64>) -> GetInventoryLotsQuery {
+    GetInventoryLotsQuery {
+        warehouse_ids: None,
+        sku_ids: None,
+        lot_number: None,
+        expiring_within_days,
+        sorters: None,
+    }
+}
+
+#[tokio::test]
+async fn test_lots() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let now = c.ps.get_timestamp_seconds() as i64;
+
+    let new_order = |order_type: OrderType, items: Vec<OrderItem>| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing lots".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut order = new_order(
+        OrderType::StockIn,
+        vec![
+            item(p.sku1.id, 10, Some("B"), Some(now + 30 * 86400)),
+            item(p.sku1.id, 10, Some("A"), Some(now + 5 * 86400)),
+            item(p.sku1.id, 5, None, None),
+        ],
+    );
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 25);
+
+    let lots = c.inventory.list_lots(&Pagination::max(), &lots_query(None), ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(lots.len(), 2);
+    assert_eq!(lots[0].lot_number, "A");
+    assert_eq!(lots[0].quantity, 10);
+    let lots = c.inventory.list_lots(&Pagination::max(), &lots_query(Some(7)), ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(lots.len(), 1);
+    assert_eq!(lots[0].lot_number, "A");
+    assert_eq!(c.inventory.get_lots_count(&lots_query(Some(60)), ActionType::System, tx.as_mut()).await.unwrap(), 2);
+    let q = GetInventoryLotsQuery {
+        lot_number: Some("A' OR '1'='1".to_owned()),
+        ..lots_query(None)
+    };
+    assert_eq!(c.inventory.get_lots_count(&q, ActionType::System, tx.as_mut()).await.unwrap(), 0);
+
+    // First-expired, first-out.
+    let mut order = new_order(OrderType::StockOut, vec![item(p.sku1.id, 15, None, None)]);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let stock_out = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let items = c.order.get_order_items(stock_out.id, &Pagination::max(), tx.as_mut()).await.unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!((items[0].lot_number.as_deref(), items[0].quantity), (Some("A"), 10));
+    assert_eq!((items[1].lot_number.as_deref(), items[1].quantity), (Some("B"), 5));
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 10);
+    let lots = c.inventory.list_lots(&Pagination::max(), &lots_query(None), ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(lots.len(), 1);
+    assert_eq!((lots[0].lot_number.as_str(), lots[0].quantity), ("B", 5));
+
+    // Explicit lot override.
+    let order = new_order(OrderType::StockOut, vec![item(p.sku1.id, 8, Some("B"), None)]);
+    let r = order_module::check(&order, false, now, tx.as_mut()).await.unwrap();
+    assert_eq!(r.items_not_available.len(), 1);
+    assert_eq!(r.items_not_available[0].lot_number.as_deref(), Some("B"));
+    assert_eq!(r.items_not_available[0].actual_quantity, 5);
+    let order = new_order(OrderType::StockOut, vec![item(p.sku1.id, 5, Some("B"), None)]);
+    assert!(c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+
+    // Removing the order put the lots back.
+    c.order.remove(stock_out.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
+    let lots = c.inventory.list_lots(&Pagination::max(), &lots_query(None), ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(lots.len(), 2);
+    assert_eq!((lots[0].lot_number.as_str(), lots[0].quantity), ("A", 10));
+    assert_eq!((lots[1].lot_number.as_str(), lots[1].quantity), ("B", 10));
+
+    c.order.recalc_all(None, None, None, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 25);
+    let lots = c.inventory.list_lots(&Pagination::max(), &lots_query(None), ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(lots.len(), 2);
+    assert_eq!(lots[0].expiry_date, Some(now + 5 * 86400));
+
+    // The expired lots go out only when the item names the lot.
+    let mut order = new_order(OrderType::StockIn, vec![item(p.sku1.id, 3, Some("X"), Some(now - 86400))]);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let mut order = new_order(OrderType::StockOut, vec![item(p.sku1.id, 12, None, None)]);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let stock_out = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let items = c.order.get_order_items(stock_out.id, &Pagination::max(), tx.as_mut()).await.unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!((items[0].lot_number.as_deref(), items[0].quantity), (Some("A"), 10));
+    assert_eq!((items[1].lot_number.as_deref(), items[1].quantity), (Some("B"), 2));
+    let order = new_order(OrderType::StockOut, vec![item(p.sku1.id, 3, Some("X"), None)]);
+    assert!(c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_serial_numbers() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let new_order = |order_type: OrderType, warehouse_id: i64, serial_numbers: &[&str]| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![OrderItem {
+            serial_numbers: serial_numbers.iter().map(|v| v.to_string()).collect(),
+            ..item(p.sku1.id, serial_numbers.len() as i64, None, None)
+        }]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id,
+        person_related_id: p.person1.id,
+        description: "Testing serial numbers".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let items = |order: &Order| order.items.clone().unwrap();
+    // Serial numbers are not allowed before the sku is serialized.
+    let order = new_order(OrderType::StockIn, p.warehouse1.id, &["SN1", "SN2"]);
+    assert!(!c.order.is_serial_numbers_matched(&items(&order), order.order_type, tx.as_mut()).await.unwrap());
+    let mut sku = c.sku.get(p.sku1.id, tx.as_mut()).await.unwrap().unwrap();
+    sku.serialized = true;
+    c.sku.update(sku.id, sku, tx.as_mut()).await.unwrap();
+    assert!(c.order.is_serial_numbers_matched(&items(&order), order.order_type, tx.as_mut()).await.unwrap());
+    let duplicated = new_order(OrderType::StockIn, p.warehouse1.id, &["SN1", "SN1"]);
+    assert!(!c.order.is_serial_numbers_matched(&items(&duplicated), duplicated.order_type, tx.as_mut()).await.unwrap());
+    let mut missing = new_order(OrderType::StockIn, p.warehouse1.id, &["SN1"]);
+    missing.items.as_mut().unwrap()[0].quantity = 2;
+    assert!(!c.order.is_serial_numbers_matched(&items(&missing), missing.order_type, tx.as_mut()).await.unwrap());
+
+    let mut order = order;
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    // A serial number in stock can't be stocked in again.
+    let order = new_order(OrderType::StockIn, p.warehouse2.id, &["SN1"]);
+    let r = order_module::check(&order, false, c.ps.get_timestamp_seconds() as i64, tx.as_mut()).await.unwrap();
+    assert_eq!(r.items_not_available.len(), 1);
+    assert_eq!(r.items_not_available[0].serial_number.as_deref(), Some("SN1"));
+
+    // Only the serial numbers in stock of the warehouse can be stocked out.
+    let order = new_order(OrderType::StockOut, p.warehouse2.id, &["SN1"]);
+    assert!(!c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    let order = new_order(OrderType::StockOut, p.warehouse1.id, &["SN3"]);
+    assert!(!c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    let mut order = new_order(OrderType::StockOut, p.warehouse1.id, &["SN1"]);
+    assert!(c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let order = new_order(OrderType::StockOut, p.warehouse1.id, &["SN1"]);
+    assert!(!c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+
+    // The shipped serial number can be returned to any warehouse.
+    let order = new_order(OrderType::Return, p.warehouse2.id, &["SN2"]);
+    assert!(!c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    let mut order = new_order(OrderType::Return, p.warehouse2.id, &["SN1"]);
+    assert!(c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let returned = c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    let query = GetSerialNumberQuery {
+        serial_number: "SN1".to_owned(),
+        sku_id: None,
+    };
+    let histories = c.order.get_serial_number_histories(&query, tx.as_mut()).await.unwrap();
+    assert_eq!(histories.len(), 1);
+    assert_eq!(histories[0].status, Some(SerialNumberStatus::InStock));
+    assert_eq!(histories[0].warehouse_id, Some(p.warehouse2.id));
+    assert_eq!(histories[0].movements.iter().map(|m| m.incoming).collect::<Vec<_>>(), vec![true, false, true]);
+
+    // Removed orders are not in the history.
+    c.order.remove(returned.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
+    let histories = c.order.get_serial_number_histories(&query, tx.as_mut()).await.unwrap();
+    assert_eq!(histories[0].status, Some(SerialNumberStatus::Shipped));
+    assert_eq!(histories[0].movements.len(), 2);
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_transfer() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let (w1, w2) = (p.warehouse1.id, p.warehouse2.id);
+
+    let new_order = |order_type: OrderType, items: Vec<OrderItem>, in_transit: bool| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: w1,
+        person_related_id: p.person1.id,
+        description: "Testing transfer".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: w2,
+        in_transit,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut order = new_order(OrderType::StockIn, vec![item(p.sku1.id, 10, Some("A"), Some(4102444800))], false);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let stock_in = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(stock_in.to_warehouse_id, 0);
+
+    // Both warehouses are changed by the transfer, the lot is moved with the items.
+    let mut order = new_order(OrderType::Transfer, vec![item(p.sku1.id, 4, None, None)], false);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let transfer = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(w1, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 6);
+    assert_eq!(c.inventory.get(w2, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 4);
+    let mut q = lots_query(None);
+    q.warehouse_ids = Some([w2].into_iter().collect());
+    let lots = c.inventory.list_lots(&Pagination::max(), &q, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(lots.len(), 1);
+    assert_eq!((lots[0].lot_number.as_str(), lots[0].expiry_date, lots[0].quantity), ("A", Some(4102444800), 4));
+
+    let order = new_order(OrderType::Transfer, vec![item(p.sku1.id, 7, None, None)], false);
+    assert!(!c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+
+    // The destination is not changed until the transfer in transit is received.
+    let mut order = new_order(OrderType::Transfer, vec![item(p.sku1.id, 3, None, None)], true);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let in_transit = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(w1, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 3);
+    assert_eq!(c.inventory.get(w2, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 4);
+    assert!(c.order.receive(transfer.id, p.user1.id, tx.as_mut()).await.unwrap().is_none());
+    // The calibration of another warehouse does not stop the receipt.
+    let w3 = c
+        .warehouse
+        .add(
+            Warehouse {
+                id: 0,
+                name: "Warehouse 3".to_owned(),
+                description: "".to_owned(),
+                person_in_charge_id: p.person1.id,
+                area_id: p.area1.id,
+                address: "".to_owned(),
+                color: None,
+                text_color: None,
+            },
+            true,
+            tx.as_mut(),
+        )
+        .await
+        .unwrap();
+    let mut order = new_order(OrderType::Calibration, vec![item(p.sku1.id, 2, None, None)], false);
+    order.warehouse_id = w3.id;
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let calibration = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let received = c.order.receive(in_transit.id, p.user1.id, tx.as_mut()).await.unwrap().unwrap();
+    assert!(!received.in_transit);
+    assert_eq!(c.inventory.get(w2, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 7);
+    c.order.remove(calibration.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
+    assert!(c.order.receive(in_transit.id, p.user1.id, tx.as_mut()).await.unwrap().is_none());
+
+    // The transfer orders are listed in the destination warehouse too.
+    let mut q = GetOrdersQuery::empty();
+    q.warehouse_ids = Some([w2].into_iter().collect());
+    assert_eq!(c.order.get_count(&q, ActionType::System, tx.as_mut()).await.unwrap(), 2);
+
+    c.order.recalc_all(Some([w2].into_iter().collect()), None, None, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(w1, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 3);
+    assert_eq!(c.inventory.get(w2, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 7);
+
+    c.order.remove(transfer.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(w1, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 7);
+    assert_eq!(c.inventory.get(w2, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 3);
+
+    c.order.recalc_all(None, None, None, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(w1, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 7);
+    assert_eq!(c.inventory.get(w2, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 3);
+    tx.commit().await.unwrap();
+}
+
+fn inventory_query(warehouse_id: i64) -> GetInventoryQuery {
+    GetInventoryQuery {
+        warehouse_ids: Some([warehouse_id].into_iter().collect()),
+        sku_ids: None,
+        sku_category_ids: None,
+        template_ids: None,
+        attributes: None,
+        quantity_start: None,
+        quantity_end: None,
+        unit: None,
+        sorters: None,
+    }
+}
+
+#[tokio::test]
+async fn test_valuation() {
+    for method in [ValuationMethod::MovingAverage, ValuationMethod::FIFO] {
+        let mut config = get_test_config();
+        config.valuation.method = method;
+        let c = common::init_ctx_with_config(config).await;
+        let p = common::prelude(&c).await;
+        let (w1, w2) = (p.warehouse1.id, p.warehouse2.id);
+
+        let new_order = |order_type: OrderType, quantity: i64, price: f64| Order {
+            id: 0,
+            created_by_user_id: 0,
+            updated_by_user_id: 0,
+            date: 0,
+            last_updated_date: 0,
+            person_in_charge_id: 0,
+            order_category_id: p.order_category1.id,
+            from_guest_order_id: 0,
+            currency: OrderCurrency::USD,
+            items: Some(vec![OrderItem {
+                price,
+                ..item(p.sku1.id, quantity, None, None)
+            }]),
+            total_amount: 0.0,
+            total_amount_settled: 0.0,
+            order_payment_status: OrderPaymentStatus::None,
+            warehouse_id: w1,
+            person_related_id: p.person1.id,
+            description: "Testing valuation".to_owned(),
+            order_type,
+            is_record: false,
+            non_payment: false,
+            to_warehouse_id: w2,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+        };
+
+        let mut tx = c.ps.begin_tx(true).await.unwrap();
+        let mut ids = vec![];
+        for (order_type, quantity, price) in [(OrderType::StockIn, 10, 2.0), (OrderType::StockIn, 10, 4.0), (OrderType::StockOut, 15, 10.0), (OrderType::Transfer, 3, 0.0)] {
+            let mut order = new_order(order_type, quantity, price);
+            c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+            ids.push(c.order.add(order, true, tx.as_mut()).await.unwrap().id);
+        }
+
+        // Moving average: 15 * 3.0 sold, 2 * 3.0 left. FIFO: 10 * 2.0 + 5 * 4.0 sold, 2 * 4.0 left.
+        let (sold, w1_value, w2_value) = match method {
+            ValuationMethod::MovingAverage => (45.0, 6.0, 9.0),
+            ValuationMethod::FIFO => (40.0, 8.0, 12.0),
+        };
+        for _ in 0..2 {
+            let products = c.inventory.list(&Pagination::max(), &inventory_query(w1), ActionType::System, tx.as_mut()).await.unwrap();
+            assert_eq!(products[0].quantity, 2);
+            assert_eq!(products[0].value, w1_value);
+            assert_eq!(products[0].gross_margin, 150.0 - sold);
+            let products = c.inventory.list(&Pagination::max(), &inventory_query(w2), ActionType::System, tx.as_mut()).await.unwrap();
+            assert_eq!(products[0].value, w2_value);
+            let mut q = GetOrdersQuery::empty();
+            q.order_type = Some(OrderType::StockOut);
+            let data = c.statistical.get_total_amount(&q, ActionType::System, tx.as_mut()).await.unwrap();
+            assert_eq!(data[0].cost_of_goods_sold, sold);
+            assert_eq!(data[0].gross_margin, 150.0 - sold);
+            // Rebuilt from the orders gets the same result.
+            c.order.recalc_all(None, None, None, ActionType::System, tx.as_mut()).await.unwrap();
+        }
+
+        // The cost of goods sold is changed after the stock in is removed.
+        c.order.remove(ids[0], true, false, ActionType::System, tx.as_mut()).await.unwrap();
+        let products = c.inventory.list(&Pagination::max(), &inventory_query(w1), ActionType::System, tx.as_mut()).await.unwrap();
+        assert_eq!(products[0].quantity, -8);
+        assert_eq!(products[0].gross_margin, 150.0 - 60.0);
+        // The cost transferred from the changed warehouse is rebuilt too, the same as rebuilding all.
+        for _ in 0..2 {
+            let products = c.inventory.list(&Pagination::max(), &inventory_query(w2), ActionType::System, tx.as_mut()).await.unwrap();
+            assert_eq!(products[0].value, 12.0);
+            c.order.recalc_all(None, None, None, ActionType::System, tx.as_mut()).await.unwrap();
+        }
+        tx.commit().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_reorder_points() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let (w1, w2, sku1) = (p.warehouse1.id, p.warehouse2.id, p.sku1.id);
+    let mut rx = c.ps.notication_subscribe().await;
+
+    let new_order = |order_type: OrderType, warehouse_id: i64, quantity: i64| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![item(sku1, quantity, None, None)]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id,
+        person_related_id: p.person1.id,
+        description: "Testing reorder points".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let point = |warehouse_id: i64, min_quantity: i64, max_quantity: Option<i64>| ReorderPoint {
+        sku_id: sku1,
+        warehouse_id,
+        min_quantity,
+        reorder_quantity: 20,
+        max_quantity,
+    };
+    c.inventory.set_reorder_point(&point(0, 5, None), tx.as_mut()).await.unwrap();
+    c.inventory.set_reorder_point(&point(w2, 1, Some(3)), tx.as_mut()).await.unwrap();
+    // The point of all warehouses only applies to the warehouses have the sku.
+    let alerts = c.inventory.list_alerts(ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!((alerts[0].warehouse_id, alerts[0].quantity, alerts[0].alert_type), (w2, 0, InventoryAlertType::LowStock));
+
+    for warehouse_id in [w1, w2] {
+        let mut order = new_order(OrderType::StockIn, warehouse_id, 10);
+        c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+        c.order.add(order, true, tx.as_mut()).await.unwrap();
+    }
+    assert_eq!(c.inventory.refresh_alerts(tx.as_mut()).await.unwrap(), vec![sku1]);
+    assert!(c.inventory.refresh_alerts(tx.as_mut()).await.unwrap().is_empty());
+    let alerts = c.inventory.list_alerts(ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!((alerts[0].warehouse_id, alerts[0].alert_type), (w2, InventoryAlertType::OverStock));
+    tx.commit().await.unwrap();
+    while rx.try_recv().is_ok() {}
+
+    // The background task evaluates the alerts after the order is committed.
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut order = new_order(OrderType::StockOut, w1, 8);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    tx.commit().await.unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while !matches!(rx.recv().await.unwrap(), WebSocketFlags::InventoryAlert(id) if id == sku1) {}
+    })
+    .await
+    .unwrap();
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let alerts = c.inventory.list_alerts(ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(alerts.len(), 2);
+    assert_eq!((alerts[0].warehouse_id, alerts[0].quantity, alerts[0].alert_type), (w1, 2, InventoryAlertType::LowStock));
+    assert!(c.inventory.remove_reorder_point(sku1, w2, tx.as_mut()).await.unwrap());
+    // The point of all warehouses applies to the warehouse 2 now.
+    let alerts = c.inventory.list_alerts(ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].warehouse_id, w1);
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reservations() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let (w1, sku1) = (p.warehouse1.id, p.sku1.id);
+    let now = c.ps.get_timestamp_seconds() as i64;
+
+    let new_order = |order_type: OrderType, quantity: i64, from_guest_order_id: i64| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id,
+        currency: OrderCurrency::USD,
+        items: Some(vec![item(sku1, quantity, None, None)]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: w1,
+        person_related_id: p.person1.id,
+        description: "Testing reservations".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    let reservation = |quantity: i64, guest_order_id: i64, expires_at: Option<i64>| Reservation {
+        id: 0,
+        created_by_user_id: p.user1.id,
+        date: now,
+        warehouse_id: w1,
+        sku_id: sku1,
+        quantity,
+        guest_order_id,
+        quotation_id: 0,
+        expires_at,
+        description: "".to_owned(),
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut order = new_order(OrderType::StockIn, 10, 0);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    c.inventory.add_reservation(reservation(6, 0, None), tx.as_mut()).await.unwrap();
+    let guest_reservation = c.inventory.add_reservation(reservation(3, 99, Some(now + 3600)), tx.as_mut()).await.unwrap();
+    // The expired reservation holds nothing.
+    c.inventory.add_reservation(reservation(1, 0, Some(now - 1)), tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get_available(w1, sku1, 0, tx.as_mut()).await.unwrap(), 1);
+
+    // The reserved quantity lowers the available quantity but not the quantity on hand.
+    let q = GetInventoryQuery {
+        warehouse_ids: None,
+        sku_ids: None,
+        sku_category_ids: None,
+        template_ids: None,
+        attributes: None,
+        quantity_start: None,
+        quantity_end: None,
+        unit: None,
+        sorters: None,
+    };
+    let products = c.inventory.list(&Pagination::max(), &q, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!((products[0].quantity, products[0].reserved, products[0].available), (10, 9, 1));
+    let q = GetReservationsQuery::default();
+    assert_eq!(c.inventory.get_reservations_count(&q, ActionType::System, tx.as_mut()).await.unwrap(), 2);
+    // The expired reservation is removed by the writes, not the reads.
+    let stored = "SELECT COUNT(1) FROM inventory_reservations";
+    assert_eq!(sqlx::query_scalar::<_, i64>(stored).fetch_one(tx.as_mut()).await.unwrap(), 3);
+    c.inventory.release_expired_reservations(tx.as_mut()).await.unwrap();
+    assert_eq!(sqlx::query_scalar::<_, i64>(stored).fetch_one(tx.as_mut()).await.unwrap(), 2);
+
+    let r = order_module::check(&new_order(OrderType::StockOut, 2, 0), false, now, tx.as_mut()).await.unwrap();
+    assert_eq!(r.items_not_available.len(), 1);
+    assert_eq!(r.items_not_available[0].actual_quantity, 1);
+    // The guest order can take the quantity reserved by itself.
+    let r = order_module::check(&new_order(OrderType::StockOut, 4, 99), false, now, tx.as_mut()).await.unwrap();
+    assert!(r.items_not_available.is_empty());
+
+    assert_eq!(c.inventory.release_reservations(99, 0, tx.as_mut()).await.unwrap(), vec![guest_reservation.id]);
+    assert!(c.inventory.get_reservation(guest_reservation.id, tx.as_mut()).await.unwrap().is_none());
+    assert_eq!(c.inventory.get_available(w1, sku1, 0, tx.as_mut()).await.unwrap(), 4);
+    let mut order = new_order(OrderType::StockOut, 4, 0);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    assert!(c.order.is_check_pass(&order, tx.as_mut()).await.unwrap());
+    tx.commit().await.unwrap();
+}
@@ -1,8 +1,11 @@
 mod common;
 
 use elerp_common::{
-    model::action_type::ActionType,
-    order_module::model::order::{GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    model::{action_type::ActionType, Pagination},
+    order_module::model::{
+        order::{GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+        order_revision::OrderFieldChange,
+    },
     sql,
 };
 
@@ -27,12 +30,32 @@ async fn test_order_preprocess() {
                 quantity: 100,
                 price: 18.5,
                 exchanged: true,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 250,
                 price: 10.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -40,10 +63,17 @@ async fn test_order_preprocess() {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing order #1"),
+        description: "Testing order #1".to_owned(),
         order_type: OrderType::StockIn,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut order, &p.user1, true, p.person2.id);
     assert_eq!(order.created_by_user_id, p.user1.id);
@@ -55,7 +85,7 @@ async fn test_order_preprocess() {
     assert_eq!(order.items.as_ref().unwrap()[0].sku_id, p.sku2.id);
     assert_eq!(order.items.as_ref().unwrap()[0].quantity, 250);
     assert_eq!(order.items.as_ref().unwrap()[0].price, 10.0);
-    assert_eq!(order.items.as_ref().unwrap()[0].exchanged, false);
+    assert!(!order.items.as_ref().unwrap()[0].exchanged);
     assert_eq!(order.total_amount, 2500.0);
     assert_eq!(order.total_amount_settled, 0.0);
     assert_eq!(order.order_payment_status, OrderPaymentStatus::Unsettled);
@@ -99,12 +129,32 @@ async fn test_module() {
                 quantity: 100,
                 price: 18.5,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 250,
                 price: 10.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -112,14 +162,21 @@ async fn test_module() {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing order"),
+        description: "Testing order".to_owned(),
         order_type: OrderType::StockOut,
         is_record: true,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
 
-    let r = c.order.add(order, tx.as_mut()).await.unwrap();
+    let r = c.order.add(order, true, tx.as_mut()).await.unwrap();
     assert!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().is_none());
     assert!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().is_none());
     c.order.remove(r.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
@@ -147,12 +204,32 @@ async fn test_module() {
                     quantity: 100,
                     price: 18.5,
                     exchanged: false,
+                    lot_number: None,
+                    expiry_date: None,
+                    serial_numbers: vec![],
+                    tax_rate: None,
+                    tax_amount: 0.0,
+                    discount: None,
+                    discount_amount: 0.0,
+                    sku_code: None,
+                    unit: None,
+                    unit_factor: 1,
                 },
                 OrderItem {
                     sku_id: p.sku2.id,
                     quantity: 250,
                     price: 10.0,
                     exchanged: false,
+                    lot_number: None,
+                    expiry_date: None,
+                    serial_numbers: vec![],
+                    tax_rate: None,
+                    tax_amount: 0.0,
+                    discount: None,
+                    discount_amount: 0.0,
+                    sku_code: None,
+                    unit: None,
+                    unit_factor: 1,
                 },
             ]),
             total_amount: 0.0,
@@ -164,10 +241,17 @@ async fn test_module() {
             order_type: OrderType::StockIn,
             is_record: false,
             non_payment: false,
+            to_warehouse_id: 0,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
         };
         c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
 
-        let r = c.order.add(order, tx.as_mut()).await.unwrap();
+        let r = c.order.add(order, true, tx.as_mut()).await.unwrap();
 
         assert_eq!(r.total_amount, 4350.0);
         assert_eq!(r.total_amount_settled, 0.0);
@@ -175,7 +259,7 @@ async fn test_module() {
         assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 100 * (n + 1));
         assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 250 * (n + 1));
 
-        assert_eq!(c.order.is_limit_reached(tx.as_mut()).await.unwrap(), if n == max - 1 { true } else { false });
+        assert_eq!(c.order.is_limit_reached(tx.as_mut()).await.unwrap(), n == max - 1);
         assert!(c.order.is_exists(r.id, tx.as_mut()).await.unwrap());
         assert!(!c.order.is_exists(r.id + 1, tx.as_mut()).await.unwrap());
 
@@ -210,12 +294,32 @@ async fn test_module() {
                 quantity: 50 * max,
                 price: 1.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 125 * max,
                 price: 5.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -223,13 +327,20 @@ async fn test_module() {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing stock out..."),
+        description: "Testing stock out...".to_owned(),
         order_type: OrderType::StockOut,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut stock_out_order, &p.user1, true, p.person1.person_in_charge_id);
-    let stock_out_order = c.order.add(stock_out_order, tx.as_mut()).await.unwrap();
+    let stock_out_order = c.order.add(stock_out_order, true, tx.as_mut()).await.unwrap();
     assert_eq!(stock_out_order.order_payment_status, OrderPaymentStatus::Unsettled);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, sku1_qty / 2);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, sku2_qty / 2);
@@ -254,12 +365,32 @@ async fn test_module() {
                 quantity: 50 * max,
                 price: 0.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 125 * max,
                 price: 0.0,
                 exchanged: true,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -267,13 +398,20 @@ async fn test_module() {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing stock out..."),
+        description: "Testing stock out...".to_owned(),
         order_type: OrderType::Exchange,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut exchange_order, &p.user1, true, p.person1.person_in_charge_id);
-    let exchange_order = c.order.add(exchange_order, tx.as_mut()).await.unwrap();
+    let exchange_order = c.order.add(exchange_order, true, tx.as_mut()).await.unwrap();
     assert_eq!(exchange_order.order_payment_status, OrderPaymentStatus::None);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, sku1_qty / 2);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 125 * max + sku2_qty);
@@ -298,12 +436,32 @@ async fn test_module() {
                 quantity: 999,
                 price: 0.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 666,
                 price: 0.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -311,13 +469,20 @@ async fn test_module() {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing stock out..."),
+        description: "Testing stock out...".to_owned(),
         order_type: OrderType::Calibration,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut calibration_order, &p.user1, true, p.person1.person_in_charge_id);
-    let calibration_order = c.order.add(calibration_order, tx.as_mut()).await.unwrap();
+    let calibration_order = c.order.add(calibration_order, true, tx.as_mut()).await.unwrap();
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 999);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 666);
 
@@ -337,12 +502,32 @@ async fn test_module() {
                 quantity: 1,
                 price: 0.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 334,
                 price: 0.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -350,13 +535,20 @@ async fn test_module() {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing stock out..."),
+        description: "Testing stock out...".to_owned(),
         order_type: OrderType::StockIn,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut stock_in_order, &p.user1, true, p.person1.person_in_charge_id);
-    let stock_in_order = c.order.add(stock_in_order, tx.as_mut()).await.unwrap();
+    let stock_in_order = c.order.add(stock_in_order, true, tx.as_mut()).await.unwrap();
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 1000);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 1000);
 
@@ -383,19 +575,36 @@ async fn test_module() {
             quantity: 1314,
             price: 0.0,
             exchanged: false,
+            lot_number: None,
+            expiry_date: None,
+            serial_numbers: vec![],
+            tax_rate: None,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+            sku_code: None,
+            unit: None,
+            unit_factor: 1,
         }]),
         total_amount: 0.0,
         total_amount_settled: 0.0,
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing stock out..."),
+        description: "Testing stock out...".to_owned(),
         order_type: OrderType::CalibrationStrict,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut calibration_strict_order, &p.user1, true, p.person1.person_in_charge_id);
-    let calibration_strict_order = c.order.add(calibration_strict_order, tx.as_mut()).await.unwrap();
+    let calibration_strict_order = c.order.add(calibration_strict_order, true, tx.as_mut()).await.unwrap();
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 1314);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 0);
 
@@ -406,8 +615,9 @@ async fn test_module() {
     tx.commit().await.unwrap();
 
     let mut tx = c.ps.begin_tx(true).await.unwrap();
+    // The removed record order is soft deleted and still holds the first id.
     for n in 0..max {
-        let id = sql::get_standard_id(n + 1);
+        let id = sql::get_standard_id(n + 2);
         assert!(c.order.is_exists(id, tx.as_mut()).await.unwrap());
         c.order.remove(id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
         assert!(!c.order.is_exists(id, tx.as_mut()).await.unwrap());
@@ -439,12 +649,32 @@ async fn remove_after_calibration(strict: bool) {
                 quantity: 100,
                 price: 18.5,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 250,
                 price: 10.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -452,15 +682,22 @@ async fn remove_after_calibration(strict: bool) {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing order #1"),
+        description: "Testing order #1".to_owned(),
         order_type: OrderType::StockIn,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
 
     let mut tx = c.ps.begin_tx(true).await.unwrap();
     c.order.preprocess(&mut in_order, &p.user1, true, p.person2.id);
-    let in_order = c.order.add(in_order, tx.as_mut()).await.unwrap();
+    let in_order = c.order.add(in_order, true, tx.as_mut()).await.unwrap();
 
     let mut out_order = Order {
         id: 0,
@@ -478,12 +715,32 @@ async fn remove_after_calibration(strict: bool) {
                 quantity: 10,
                 price: 18.5,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 10,
                 price: 10.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -491,14 +748,21 @@ async fn remove_after_calibration(strict: bool) {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing order #1"),
+        description: "Testing order #1".to_owned(),
         order_type: OrderType::StockOut,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
 
     c.order.preprocess(&mut out_order, &p.user1, true, p.person2.id);
-    let out_order = c.order.add(out_order, tx.as_mut()).await.unwrap();
+    let out_order = c.order.add(out_order, true, tx.as_mut()).await.unwrap();
 
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 90);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 240);
@@ -519,12 +783,32 @@ async fn remove_after_calibration(strict: bool) {
                 quantity: 5,
                 price: 18.5,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
             OrderItem {
                 sku_id: p.sku2.id,
                 quantity: 6,
                 price: 10.0,
                 exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
             },
         ]),
         total_amount: 0.0,
@@ -532,14 +816,21 @@ async fn remove_after_calibration(strict: bool) {
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing order #1"),
+        description: "Testing order #1".to_owned(),
         order_type: if strict { OrderType::CalibrationStrict } else { OrderType::Calibration },
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
 
     c.order.preprocess(&mut calibration_order, &p.user1, true, p.person2.id);
-    let _calibration_order = c.order.add(calibration_order, tx.as_mut()).await.unwrap();
+    let _calibration_order = c.order.add(calibration_order, true, tx.as_mut()).await.unwrap();
 
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 5);
     assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 6);
@@ -564,3 +855,320 @@ async fn test_remove_after_calibration() {
 async fn test_remove_after_calibration_strict() {
     remove_after_calibration(true).await;
 }
+
+#[tokio::test]
+async fn test_remove_and_restore() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let mut in_order = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![
+            OrderItem {
+                sku_id: p.sku1.id,
+                quantity: 100,
+                price: 18.5,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            },
+            OrderItem {
+                sku_id: p.sku2.id,
+                quantity: 250,
+                price: 10.0,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            },
+        ]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing order #1".to_owned(),
+        order_type: OrderType::StockIn,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    c.order.preprocess(&mut in_order, &p.user1, true, p.person2.id);
+    c.order.add(in_order, true, tx.as_mut()).await.unwrap();
+
+    let mut out_order = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![
+            OrderItem {
+                sku_id: p.sku1.id,
+                quantity: 10,
+                price: 18.5,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            },
+            OrderItem {
+                sku_id: p.sku2.id,
+                quantity: 10,
+                price: 10.0,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            },
+        ]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing order #2".to_owned(),
+        order_type: OrderType::StockOut,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    c.order.preprocess(&mut out_order, &p.user1, true, p.person2.id);
+    let out_order = c.order.add(out_order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 90);
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 240);
+
+    assert!(c.order.remove(out_order.id, true, false, ActionType::System, tx.as_mut()).await.unwrap());
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 100);
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 250);
+    assert!(!c.order.is_exists(out_order.id, tx.as_mut()).await.unwrap());
+    assert!(c.order.get(out_order.id, tx.as_mut()).await.unwrap().is_none());
+    assert_eq!(c.order.get_count(&GetOrdersQuery::empty(), ActionType::System, tx.as_mut()).await.unwrap(), 1);
+    let mut deleted = GetOrdersQuery::empty();
+    deleted.deleted = Some(true);
+    assert_eq!(c.order.get_count(&deleted, ActionType::System, tx.as_mut()).await.unwrap(), 1);
+
+    // Recalculation must ignore the removed order.
+    c.order.recalc_all(None, None, None, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 100);
+
+    let restored = c.order.restore(out_order.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(restored.items.unwrap().len(), 2);
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 90);
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku2.id, tx.as_mut()).await.unwrap().unwrap().quantity, 240);
+    assert!(c.order.is_exists(out_order.id, tx.as_mut()).await.unwrap());
+
+    c.order.remove(out_order.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.order.purge(None, tx.as_mut()).await.unwrap(), vec![out_order.id]);
+    assert!(c.order.get_order_items(out_order.id, &Pagination::max(), tx.as_mut()).await.unwrap().is_empty());
+    assert!(c.order.restore(out_order.id, ActionType::System, tx.as_mut()).await.unwrap().is_none());
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 100);
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_revisions() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let mut order = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(vec![
+            OrderItem {
+                sku_id: p.sku1.id,
+                quantity: 100,
+                price: 18.5,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            },
+            OrderItem {
+                sku_id: p.sku2.id,
+                quantity: 250,
+                price: 10.0,
+                exchanged: false,
+                lot_number: None,
+                expiry_date: None,
+                serial_numbers: vec![],
+                tax_rate: None,
+                tax_amount: 0.0,
+                discount: None,
+                discount_amount: 0.0,
+                sku_code: None,
+                unit: None,
+                unit_factor: 1,
+            },
+        ]),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing order #1".to_owned(),
+        order_type: OrderType::StockIn,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    let revisions = c.order.get_revisions(order.id, tx.as_mut()).await.unwrap();
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].revision, 1);
+    assert_eq!(revisions[0].items.len(), 2);
+    assert!(revisions[0].diff.is_none());
+
+    let mut to_update = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    to_update.description = "Testing order #1 updated".to_owned();
+    to_update.person_related_id = p.person2.id;
+    c.order.update(order.id, to_update, ActionType::Admin, tx.as_mut()).await.unwrap().unwrap();
+
+    let revisions = c.order.get_revisions(order.id, tx.as_mut()).await.unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[1].revision, 2);
+    assert_eq!(revisions[1].description, "Testing order #1 updated");
+    let diff = revisions[1].diff.as_ref().unwrap();
+    assert_eq!(diff.fields.len(), 2);
+    assert!(diff.fields.iter().any(|f| f.field == "description" && f.before == "Testing order #1" && f.after == "Testing order #1 updated"));
+    assert!(diff.fields.iter().any(|f| f.field == "person_related_id" && f.after == p.person2.id.to_string()));
+    assert!(diff.items.is_empty());
+
+    // Quantity and price changed, removed and added items.
+    let mut next = revisions[1].clone();
+    next.items = vec![
+        OrderItem {
+            sku_id: p.sku1.id,
+            quantity: 120,
+            price: 17.0,
+            exchanged: false,
+            lot_number: None,
+            expiry_date: None,
+            serial_numbers: vec![],
+            tax_rate: Some(5.0),
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+            sku_code: None,
+            unit: None,
+            unit_factor: 1,
+        },
+        OrderItem {
+            sku_id: p.sku2.id + 1,
+            quantity: 5,
+            price: 1.0,
+            exchanged: false,
+            lot_number: None,
+            expiry_date: None,
+            serial_numbers: vec![],
+            tax_rate: None,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+            sku_code: None,
+            unit: None,
+            unit_factor: 1,
+        },
+    ];
+    let diff = next.changes_from(&revisions[1]);
+    assert!(diff.fields.is_empty());
+    assert_eq!(diff.items.len(), 3);
+    let sku1 = diff.items.iter().find(|item| item.sku_id == p.sku1.id).unwrap();
+    assert_eq!((sku1.before_quantity, sku1.after_quantity), (Some(100), Some(120)));
+    assert_eq!((sku1.before_price, sku1.after_price), (Some(18.5), Some(17.0)));
+    assert_eq!(sku1.fields, vec![OrderFieldChange { field: "tax_rate".to_owned(), before: "0".to_owned(), after: "5".to_owned() }]);
+    let sku2 = diff.items.iter().find(|item| item.sku_id == p.sku2.id).unwrap();
+    assert_eq!((sku2.before_quantity, sku2.after_quantity), (Some(250), None));
+    let sku3 = diff.items.iter().find(|item| item.sku_id == p.sku2.id + 1).unwrap();
+    assert_eq!((sku3.before_quantity, sku3.after_quantity), (None, Some(5)));
+
+    // The updates by the system are not the revisions.
+    let to_update = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    c.order.update(order.id, to_update, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(c.order.get_revisions(order.id, tx.as_mut()).await.unwrap().len(), 2);
+
+    c.order.remove(order.id, true, false, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(c.order.purge(None, tx.as_mut()).await.unwrap(), vec![order.id]);
+    assert!(c.order.get_revisions(order.id, tx.as_mut()).await.unwrap().is_empty());
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::TestPrelude;
+use elerp_common::{
+    config::{Company, DocumentTemplate},
+    get_test_config,
+    order_module::model::{
+        document::DocumentKind,
+        order::{Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    },
+};
+
+#[tokio::test]
+async fn test_module() {
+    let mut config = get_test_config();
+    config.document.company = Company {
+        name: "Elerp Trading".to_owned(),
+        address: "1 Market Street".to_owned(),
+        ..Default::default()
+    };
+    config.document.invoice = DocumentTemplate {
+        title: Some("Tax Invoice".to_owned()),
+        footer: "Payment due in 30 days.".to_owned(),
+        ..Default::default()
+    };
+    let c = common::init_ctx_with_config(config).await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let mut order = new_order(&p, OrderType::StockIn, vec![new_item(&p, 100, "L1", vec![])]);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    // Enough items to continue on the next page.
+    let items = (0..80).map(|i| new_item(&p, 1, "L1", vec![format!("SN-{i}")])).collect();
+    let mut order = new_order(&p, OrderType::StockOut, items);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    for kind in [DocumentKind::Invoice, DocumentKind::DeliveryNote, DocumentKind::PackingSlip] {
+        let path = c.order.get_document(order.id, kind, true, tx.as_mut()).await.unwrap().unwrap();
+        let buf = tokio::fs::read(&path).await.unwrap();
+        assert!(buf.starts_with(b"%PDF"));
+    }
+    let path = c.order.get_document(order.id, DocumentKind::Invoice, false, tx.as_mut()).await.unwrap().unwrap();
+    assert!(path.is_file());
+    assert!(c.order.get_document(order.id + 100, DocumentKind::Invoice, true, tx.as_mut()).await.unwrap().is_none());
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_font() {
+    let mut config = get_test_config();
+    config.document.font = Some(config.data_path.join("not-exists.ttf"));
+    let c = common::init_ctx_with_config(config).await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let mut order = new_order(&p, OrderType::StockIn, vec![new_item(&p, 1, "L1", vec![])]);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert!(c.order.get_document(order.id, DocumentKind::Invoice, true, tx.as_mut()).await.is_err(), "Font configured is expected readable.");
+}
+
+#[test]
+fn test_kind() {
+    let q: DocumentKind = serde_json::from_str(r#""delivery_note""#).unwrap();
+    assert_eq!(q, DocumentKind::DeliveryNote);
+    assert_eq!(q.default_title(), "Delivery Note");
+}
+
+fn new_item(p: &TestPrelude, quantity: i64, lot_number: &str, serial_numbers: Vec<String>) -> OrderItem {
+    OrderItem {
+        sku_id: p.sku1.id,
+        quantity,
+        price: 2.5,
+        exchanged: false,
+        lot_number: Some(lot_number.to_owned()),
+        expiry_date: None,
+        serial_numbers,
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    }
+}
+
+fn new_order(p: &TestPrelude, order_type: OrderType, items: Vec<OrderItem>) -> Order {
+    Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing document".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    }
+}
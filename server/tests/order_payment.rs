@@ -48,19 +48,36 @@ async fn test_module() {
             quantity: 1314,
             price: 1.0,
             exchanged: false,
+            lot_number: None,
+            expiry_date: None,
+            serial_numbers: vec![],
+            tax_rate: None,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
+            sku_code: None,
+            unit: None,
+            unit_factor: 1,
         }]),
         total_amount: 0.0,
         total_amount_settled: 0.0,
         order_payment_status: OrderPaymentStatus::None,
         warehouse_id: p.warehouse1.id,
         person_related_id: p.person1.id,
-        description: format!("Testing stock out..."),
+        description: "Testing stock out...".to_owned(),
         order_type: OrderType::StockOut,
         is_record: false,
         non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
     };
     c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
-    let order = c.order.add(order, tx.as_mut()).await.unwrap();
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
 
     let mut payment = OrderPayment {
         id: 0,
@@ -23,12 +23,13 @@ async fn test_module() {
                 description: "testing".to_owned(),
                 color: None,
                 text_color: None,
+                price_list_id: 0,
             },
             tx.as_mut(),
         )
         .await
         .unwrap();
-    assert_eq!(m.is_limit_reached(tx.as_mut()).await.unwrap(), false);
+    assert!(!m.is_limit_reached(tx.as_mut()).await.unwrap());
     tx.commit().await.unwrap();
 
     let max = config.limit.persons;
@@ -44,12 +45,14 @@ async fn test_module() {
                     description: "testing area".to_owned(),
                     color: None,
                     text_color: None,
+                    price_list_id: 0,
                     address: "address...".to_owned(),
                     area_id: area.id,
                     person_in_charge_id: 0,
                     contact: "0123456789".to_owned(),
                     email: "example@email.com".to_owned(),
                 },
+                true,
                 tx.as_mut(),
             )
             .await
@@ -59,23 +62,23 @@ async fn test_module() {
         last_id = r.id;
     }
     let mut tx = ps.begin_tx(false).await.unwrap();
-    let mut query = GetPersonsQuery::default();
-    query.area_id = Some(area.id);
+    let query = GetPersonsQuery {
+        area_id: Some(area.id),
+        ..Default::default()
+    };
     let count = m.get_count(&query, tx.as_mut()).await.unwrap();
     assert_eq!(count, max);
-    assert_eq!(m.is_limit_reached(tx.as_mut()).await.unwrap(), true);
-    assert_eq!(m.is_exists(last_id, tx.as_mut()).await.unwrap(), true);
-    assert_eq!(
+    assert!(m.is_limit_reached(tx.as_mut()).await.unwrap());
+    assert!(m.is_exists(last_id, tx.as_mut()).await.unwrap());
+    assert!(
         m.is_exists_name(&last_name, None, tx.as_mut())
             .await
-            .unwrap(),
-        true
+            .unwrap()
     );
-    assert_eq!(
-        m.is_exists_name(&last_name, Some(last_id), tx.as_mut())
+    assert!(
+        !m.is_exists_name(&last_name, Some(last_id), tx.as_mut())
             .await
-            .unwrap(),
-        false
+            .unwrap()
     );
 
     let row = m
@@ -0,0 +1,127 @@
+mod common;
+
+use elerp_common::{
+    area_module::model::area::Area,
+    model::Pagination,
+    order_module::model::order::{OrderCurrency, OrderItem},
+    person_module::model::person::Person,
+    price_list_module::model::price_list::{GetPriceListItemsQuery, GetPriceQuery, PriceList, PriceListItem},
+};
+
+fn item(sku_id: i64, price: f64) -> OrderItem {
+    OrderItem {
+        sku_id,
+        quantity: 1,
+        price,
+        exchanged: false,
+        lot_number: None,
+        expiry_date: None,
+        serial_numbers: vec![],
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    }
+}
+
+#[test]
+fn test_price_omitted() {
+    let v: OrderItem = serde_json::from_str(r#"{"sku_id":1,"quantity":2}"#).unwrap();
+    assert!(!v.is_price_set());
+    let v: OrderItem = serde_json::from_str(r#"{"sku_id":1,"quantity":2,"price":0.0}"#).unwrap();
+    assert!(v.is_price_set());
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let new_list = |name: &str, is_default: bool| PriceList {
+        id: 0,
+        name: name.to_owned(),
+        description: "".to_owned(),
+        is_default,
+    };
+    let new_item = |price_list_id: i64, sku_id: i64, price: f64, valid_from: Option<i64>, valid_to: Option<i64>| PriceListItem {
+        id: 0,
+        price_list_id,
+        sku_id,
+        currency: OrderCurrency::USD,
+        price,
+        valid_from,
+        valid_to,
+    };
+    let default = c.price_list.add(new_list("Default", true), tx.as_mut()).await.unwrap();
+    let area = c.price_list.add(new_list("Area", false), tx.as_mut()).await.unwrap();
+    let person = c.price_list.add(new_list("Person", false), tx.as_mut()).await.unwrap();
+
+    // Only one price list is the default.
+    let other = c.price_list.add(new_list("Other", true), tx.as_mut()).await.unwrap();
+    assert!(!c.price_list.get(default.id, tx.as_mut()).await.unwrap().unwrap().is_default);
+    c.price_list.update(default.id, new_list("Default", true), tx.as_mut()).await.unwrap().unwrap();
+    assert!(!c.price_list.get(other.id, tx.as_mut()).await.unwrap().unwrap().is_default);
+    assert!(c.price_list.remove(other.id, tx.as_mut()).await.unwrap());
+    assert_eq!(c.price_list.list(tx.as_mut()).await.unwrap().len(), 3);
+
+    let default_sku1 = c.price_list.add_item(new_item(default.id, p.sku1.id, 10.0, None, None), tx.as_mut()).await.unwrap();
+    c.price_list.add_item(new_item(default.id, p.sku2.id, 20.0, None, None), tx.as_mut()).await.unwrap();
+    let area_sku1 = c.price_list.add_item(new_item(area.id, p.sku1.id, 9.0, None, None), tx.as_mut()).await.unwrap();
+    // The promotion of the person is only valid in the period.
+    let person_sku1 = c.price_list.add_item(new_item(person.id, p.sku1.id, 8.0, Some(100), Some(200)), tx.as_mut()).await.unwrap();
+    let q = GetPriceListItemsQuery {
+        price_list_id: Some(default.id),
+        ..Default::default()
+    };
+    assert_eq!(c.price_list.get_items_count(&q, tx.as_mut()).await.unwrap(), 2);
+    assert_eq!(c.price_list.get_items(&Pagination::max(), &q, tx.as_mut()).await.unwrap().len(), 2);
+
+    let price = |sku_id: i64, date: i64| GetPriceQuery {
+        person_id: p.person1.id,
+        sku_id,
+        currency: OrderCurrency::USD,
+        date: Some(date),
+    };
+    let r = c.price_list.get_price(&price(p.sku1.id, 150), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!((r.price_list_id, r.price_list_item_id, r.price), (default.id, default_sku1.id, 10.0));
+
+    let area1 = Area { price_list_id: area.id, ..p.area1 };
+    c.area.update(area1.id, area1, tx.as_mut()).await.unwrap().unwrap();
+    let r = c.price_list.get_price(&price(p.sku1.id, 150), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!((r.price_list_id, r.price_list_item_id, r.price), (area.id, area_sku1.id, 9.0));
+
+    let person1 = Person { price_list_id: person.id, ..p.person1 };
+    let person1 = c.person.update(person1.id, person1, tx.as_mut()).await.unwrap().unwrap();
+    assert!(c.price_list.is_depend_by_another(person.id, tx.as_mut()).await.unwrap());
+    let r = c.price_list.get_price(&price(p.sku1.id, 150), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!((r.price_list_id, r.price_list_item_id, r.price), (person.id, person_sku1.id, 8.0));
+    // Out of the period, the list of the area is used.
+    let r = c.price_list.get_price(&price(p.sku1.id, 201), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(r.price, 9.0);
+    // The more specific lists without the sku fall back to the default.
+    let r = c.price_list.get_price(&price(p.sku2.id, 150), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!((r.price_list_id, r.price), (default.id, 20.0));
+    let mut q = price(p.sku2.id, 150);
+    q.currency = OrderCurrency::GBP;
+    assert!(c.price_list.get_price(&q, tx.as_mut()).await.unwrap().is_none());
+
+    // Only the prices omitted are filled.
+    let mut items = vec![item(p.sku1.id, f64::NAN), item(p.sku2.id, 5.0), item(p.sku2.id, f64::NAN)];
+    let not_found = c.price_list.fill_prices(person1.id, OrderCurrency::USD, 150, &mut items, tx.as_mut()).await.unwrap();
+    assert!(not_found.is_empty());
+    assert_eq!(items.iter().map(|item| item.price).collect::<Vec<_>>(), vec![8.0, 5.0, 20.0]);
+    let mut items = vec![item(p.sku1.id, f64::NAN)];
+    let not_found = c.price_list.fill_prices(person1.id, OrderCurrency::GBP, 150, &mut items, tx.as_mut()).await.unwrap();
+    assert_eq!(not_found, vec![p.sku1.id]);
+
+    assert!(c.price_list.remove_item(person_sku1.id, tx.as_mut()).await.unwrap());
+    let r = c.price_list.get_price(&price(p.sku1.id, 150), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(r.price, 9.0);
+    assert!(c.price_list.remove(area.id, tx.as_mut()).await.unwrap());
+    assert!(c.price_list.get_item(area_sku1.id, tx.as_mut()).await.unwrap().is_none());
+    tx.commit().await.unwrap();
+}
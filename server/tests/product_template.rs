@@ -0,0 +1,199 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use elerp_common::{
+    inventory_module::model::inventory::GetInventoryQuery,
+    model::{action_type::ActionType, Pagination},
+    order_module::model::order::{Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    product_template_module::model::product_template::{GetProductTemplatesQuery, ProductTemplate, TemplateAttribute},
+    sku_module::model::sku::{GetSKUsQuery, SKU},
+    statistical_module::model::statistical_data::GetStatisticalDataQuery,
+};
+
+fn attributes(v: &[(&str, &str)]) -> BTreeMap<String, String> {
+    v.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+fn template() -> ProductTemplate {
+    ProductTemplate {
+        id: 0,
+        sku_category_id: 0,
+        name: "T-shirt".to_owned(),
+        description: "".to_owned(),
+        attributes: vec![
+            TemplateAttribute {
+                name: "colour".to_owned(),
+                values: vec!["red".to_owned(), "blue".to_owned()],
+            },
+            TemplateAttribute {
+                name: "size".to_owned(),
+                values: vec!["M".to_owned(), "L".to_owned()],
+            },
+        ],
+    }
+}
+
+fn item(sku_id: i64, quantity: i64) -> OrderItem {
+    OrderItem {
+        sku_id,
+        quantity,
+        price: 10.0,
+        exchanged: false,
+        lot_number: None,
+        expiry_date: None,
+        serial_numbers: vec![],
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    }
+}
+
+#[test]
+fn test_variants() {
+    let t = template();
+    let variants = t.get_variants();
+    assert_eq!(variants.len(), 4);
+    assert_eq!(variants.iter().map(|v| t.get_variant_name(v)).collect::<Vec<_>>(), vec!["T-shirt red M", "T-shirt red L", "T-shirt blue M", "T-shirt blue L"]);
+    assert!(t.is_valid_variant(&attributes(&[("colour", "blue"), ("size", "L")])));
+    assert!(!t.is_valid_variant(&attributes(&[("colour", "green"), ("size", "L")])));
+    assert!(!t.is_valid_variant(&attributes(&[("colour", "blue")])));
+    assert!(!t.is_valid_variant(&attributes(&[("colour", "blue"), ("size", "L"), ("sleeve", "long")])));
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let t = c.product_template.add(ProductTemplate { sku_category_id: p.sku_category1.id, ..template() }, tx.as_mut()).await.unwrap();
+    let got = c.product_template.get(t.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(got.attributes, t.attributes);
+    assert!(c.product_template.is_exists_name("T-shirt", None, tx.as_mut()).await.unwrap());
+    assert!(!c.product_template.is_exists_name("T-shirt", Some(t.id), tx.as_mut()).await.unwrap());
+    let q = GetProductTemplatesQuery {
+        sku_category_id: Some(p.sku_category1.id),
+        name: None,
+    };
+    assert_eq!(c.product_template.get_multiple(&Pagination::max(), &q, tx.as_mut()).await.unwrap().len(), 1);
+    assert!(!c.product_template.is_depend_by_another(t.id, tx.as_mut()).await.unwrap());
+
+    let mut variants = Vec::new();
+    for attributes in t.get_variants() {
+        let sku = SKU {
+            id: 0,
+            sku_category_id: t.sku_category_id,
+            name: t.get_variant_name(&attributes),
+            description: "".to_owned(),
+            color: None,
+            text_color: None,
+            serialized: false,
+            tax_rate_id: 0,
+            code: "".to_owned(),
+            unit: "".to_owned(),
+            barcodes: vec![],
+            units: vec![],
+            template_id: t.id,
+            attributes,
+        };
+        variants.push(c.sku.add(sku, true, tx.as_mut()).await.unwrap());
+    }
+    assert!(c.product_template.is_depend_by_another(t.id, tx.as_mut()).await.unwrap());
+    assert_eq!(c.sku.get(variants[1].id, tx.as_mut()).await.unwrap().unwrap().attributes, attributes(&[("colour", "red"), ("size", "L")]));
+
+    // The skus are filtered by the template and the attribute values.
+    let q = GetSKUsQuery {
+        template_id: Some(t.id),
+        ..Default::default()
+    };
+    assert_eq!(c.sku.get_count(&q, tx.as_mut()).await.unwrap(), 4);
+    let q = GetSKUsQuery {
+        attributes: Some(attributes(&[("colour", "red")])),
+        ..Default::default()
+    };
+    let list = c.sku.get_multiple(&Pagination::max(), &q, tx.as_mut()).await.unwrap();
+    assert_eq!(list.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), vec!["T-shirt red M", "T-shirt red L"]);
+    let q = GetSKUsQuery {
+        attributes: Some(attributes(&[("colour", "blue"), ("size", "M")])),
+        ..Default::default()
+    };
+    assert_eq!(c.sku.get_multiple_ids(&Pagination::max(), &q, tx.as_mut()).await.unwrap(), vec![variants[2].id]);
+
+    let new_order = |order_type: OrderType, items: Vec<OrderItem>| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "T-shirts".to_owned(),
+        order_type,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    let mut order = new_order(OrderType::StockIn, variants.iter().map(|v| item(v.id, 10)).collect());
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let mut order = new_order(OrderType::StockOut, vec![item(variants[0].id, 3), item(variants[3].id, 2), item(p.sku1.id, 1)]);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    c.order.add(order, true, tx.as_mut()).await.unwrap();
+
+    // The stock of the variants is summed by the template, the other skus are excluded.
+    let mut q = GetInventoryQuery {
+        warehouse_ids: None,
+        sku_ids: None,
+        sku_category_ids: None,
+        template_ids: None,
+        attributes: None,
+        quantity_start: None,
+        quantity_end: None,
+        unit: None,
+        sorters: None,
+    };
+    let list = c.inventory.list_templates(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!((list[0].warehouse_id, list[0].template_id, list[0].variant_count, list[0].quantity), (p.warehouse1.id, t.id, 4, 35));
+    q.attributes = Some(attributes(&[("size", "M")]));
+    let list = c.inventory.list(&Pagination::max(), &q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(list.iter().map(|v| (v.sku_id, v.quantity)).collect::<Vec<_>>(), vec![(variants[0].id, 7), (variants[2].id, 10)]);
+    assert_eq!(c.inventory.list_templates(&q, ActionType::Admin, tx.as_mut()).await.unwrap()[0].quantity, 17);
+
+    let q = GetStatisticalDataQuery {
+        date_start: None,
+        date_end: None,
+        order_category_id: None,
+        warehouse_ids: None,
+        items: None,
+        item_categories: None,
+        person_related_id: None,
+        person_in_charge_id: None,
+        currency: None,
+        created_by_user_id: None,
+        reverse: None,
+    };
+    let data = c.statistical.get(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(data.most_popular_templates.len(), 1);
+    assert_eq!((data.most_popular_templates[0].id, data.most_popular_templates[0].total_out), (t.id, 5));
+    tx.commit().await.unwrap();
+}
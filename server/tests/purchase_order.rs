@@ -0,0 +1,170 @@
+mod common;
+
+use elerp_common::{
+    model::{action_type::ActionType, Pagination},
+    order_module::model::order::OrderCurrency,
+    purchase_order_module::model::purchase_order::{GetPurchaseOrdersQuery, PurchaseOrder, PurchaseOrderItem, PurchaseOrderReceipt, PurchaseOrderReceiptItem, PurchaseOrderStatus},
+};
+
+fn receipt(sku_id: i64, quantity: i64) -> PurchaseOrderReceipt {
+    PurchaseOrderReceipt {
+        items: vec![PurchaseOrderReceiptItem {
+            sku_id,
+            quantity,
+            lot_number: None,
+            expiry_date: None,
+            serial_numbers: vec![],
+        }],
+        description: "".to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let mut order = PurchaseOrder {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        supplier_id: p.person2.id,
+        warehouse_id: p.warehouse1.id,
+        order_category_id: p.order_category1.id,
+        currency: OrderCurrency::USD,
+        description: "Testing purchase order".to_owned(),
+        status: PurchaseOrderStatus::Closed,
+        total_amount: 0.0,
+        items: Some(vec![
+            PurchaseOrderItem {
+                sku_id: p.sku1.id,
+                quantity: 10,
+                price: 2.0,
+                received_quantity: 3,
+            },
+            PurchaseOrderItem {
+                sku_id: p.sku2.id,
+                quantity: 5,
+                price: 4.0,
+                received_quantity: 0,
+            },
+        ]),
+        order_ids: vec![],
+    };
+    c.purchase_order.preprocess(&mut order, &p.user1, true, p.person1.id);
+    assert_eq!(order.status, PurchaseOrderStatus::Draft);
+    assert_eq!(order.total_amount, 40.0);
+    assert_eq!(order.items.as_ref().unwrap()[0].received_quantity, 0);
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let order = c.purchase_order.add(order, tx.as_mut()).await.unwrap();
+    let id = order.id;
+    assert_eq!(c.purchase_order.get(id, tx.as_mut()).await.unwrap().unwrap().items.unwrap().len(), 2);
+    // The skus and the order category are depended by the purchase order.
+    assert!(c.sku.is_depend_by_another(p.sku2.id, tx.as_mut()).await.unwrap());
+    assert!(c.order_category.is_depend_by_another(p.order_category1.id, tx.as_mut()).await.unwrap());
+
+    let order = c.purchase_order.set_status(id, PurchaseOrderStatus::Approved, p.user1.id, tx.as_mut()).await.unwrap().unwrap();
+    // Only the outstanding quantities of the items in the purchase order can be received.
+    assert!(!order.is_receivable(&receipt(p.sku1.id, 11)));
+    assert!(!order.is_receivable(&receipt(p.sku1.id, 0)));
+    assert!(!order.is_receivable(&PurchaseOrderReceipt {
+        items: vec![],
+        description: "".to_owned()
+    }));
+
+    // Receive 6 of sku1, then the rest of the items.
+    let mut received = vec![];
+    for r in [receipt(p.sku1.id, 6), {
+        let mut r = receipt(p.sku1.id, 4);
+        r.items.extend(receipt(p.sku2.id, 5).items);
+        r
+    }] {
+        let order = c.purchase_order.get(id, tx.as_mut()).await.unwrap().unwrap();
+        assert!(order.is_receivable(&r));
+        let mut stock_in = order.to_receipt_order(r);
+        c.order.preprocess(&mut stock_in, &p.user1, true, order.person_in_charge_id);
+        assert_eq!(stock_in.person_related_id, p.person2.id);
+        let stock_in = c.order.add(stock_in, true, tx.as_mut()).await.unwrap();
+        assert!(!c.purchase_order.is_receipt_order(stock_in.id, tx.as_mut()).await.unwrap());
+        received.push(c.purchase_order.receive(id, &stock_in, p.user1.id, tx.as_mut()).await.unwrap().unwrap());
+        assert!(c.purchase_order.is_receipt_order(stock_in.id, tx.as_mut()).await.unwrap());
+    }
+    assert_eq!(received[0].status, PurchaseOrderStatus::PartiallyReceived);
+    assert_eq!(received[0].items.as_ref().unwrap()[0].outstanding_quantity(), 4);
+    assert_eq!(received[1].status, PurchaseOrderStatus::Received);
+    assert_eq!(received[1].order_ids.len(), 2);
+    assert!(received[1].items.as_ref().unwrap().iter().all(|item| item.outstanding_quantity() == 0));
+
+    // The stock in orders used the prices of the purchase order.
+    let inventory = c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(inventory.quantity, 10);
+    let stock_in = c.order.get(received[1].order_ids[1], tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(stock_in.total_amount, 4.0 * 2.0 + 5.0 * 4.0);
+
+    let q = GetPurchaseOrdersQuery {
+        status: Some(PurchaseOrderStatus::Received),
+        ..Default::default()
+    };
+    assert_eq!(c.purchase_order.get_count(&q, ActionType::System, tx.as_mut()).await.unwrap(), 1);
+    let orders = c.purchase_order.get_multiple(&Pagination::max(), &q, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(orders[0].order_ids, received[1].order_ids);
+
+    let order = c.purchase_order.set_status(id, PurchaseOrderStatus::Closed, p.user1.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(order.status, PurchaseOrderStatus::Closed);
+    assert!(c.purchase_order.remove(id, true, tx.as_mut()).await.unwrap());
+    assert!(c.purchase_order.get(id, tx.as_mut()).await.unwrap().is_none());
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_receive_lines() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let line = |quantity: i64| PurchaseOrderItem {
+        sku_id: p.sku1.id,
+        quantity,
+        price: 2.0,
+        received_quantity: 0,
+    };
+    let mut order = PurchaseOrder {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        supplier_id: p.person2.id,
+        warehouse_id: p.warehouse1.id,
+        order_category_id: p.order_category1.id,
+        currency: OrderCurrency::USD,
+        description: "".to_owned(),
+        status: PurchaseOrderStatus::Draft,
+        total_amount: 0.0,
+        items: Some(vec![line(5), line(5)]),
+        order_ids: vec![],
+    };
+    c.purchase_order.preprocess(&mut order, &p.user1, true, p.person1.id);
+
+    // The lines of the same sku are received in order, not counted twice.
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let id = c.purchase_order.add(order, tx.as_mut()).await.unwrap().id;
+    let mut received = vec![];
+    for r in [receipt(p.sku1.id, 7), receipt(p.sku1.id, 3)] {
+        let order = c.purchase_order.get(id, tx.as_mut()).await.unwrap().unwrap();
+        assert!(order.is_receivable(&r));
+        let mut stock_in = order.to_receipt_order(r);
+        c.order.preprocess(&mut stock_in, &p.user1, true, order.person_in_charge_id);
+        let stock_in = c.order.add(stock_in, true, tx.as_mut()).await.unwrap();
+        received.push(c.purchase_order.receive(id, &stock_in, p.user1.id, tx.as_mut()).await.unwrap().unwrap());
+    }
+    assert_eq!(received[0].status, PurchaseOrderStatus::PartiallyReceived);
+    assert_eq!(received[0].items.as_ref().unwrap().iter().map(|item| item.received_quantity).collect::<Vec<_>>(), [5, 2]);
+    assert_eq!(received[1].status, PurchaseOrderStatus::Received);
+    assert_eq!(received[1].items.as_ref().unwrap().iter().map(|item| item.received_quantity).collect::<Vec<_>>(), [5, 5]);
+    tx.commit().await.unwrap();
+}
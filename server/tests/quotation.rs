@@ -0,0 +1,149 @@
+mod common;
+
+use elerp_common::{
+    inventory_module::model::reservation::Reservation,
+    model::{action_type::ActionType, Pagination},
+    order_module::{
+        self,
+        model::order::{OrderCurrency, OrderType},
+    },
+    quotation_module::model::quotation::{GetQuotationsQuery, Quotation, QuotationItem, QuotationStatus},
+    sku_module::model::sku::SKU,
+    tax_module::model::tax_rate::TaxRate,
+};
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let now = c.ps.get_timestamp_seconds() as i64;
+
+    let mut quotation = Quotation {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        person_related_id: p.person2.id,
+        warehouse_id: p.warehouse1.id,
+        order_category_id: p.order_category1.id,
+        currency: OrderCurrency::USD,
+        description: "Testing quotation".to_owned(),
+        valid_until: now + 86400,
+        status: QuotationStatus::Converted,
+        revision: 3,
+        order_id: 9,
+        total_amount: 0.0,
+        items: Some(vec![
+            QuotationItem {
+                sku_id: p.sku1.id,
+                quantity: 10,
+                price: 2.0,
+            },
+            QuotationItem {
+                sku_id: p.sku2.id,
+                quantity: 5,
+                price: 4.0,
+            },
+        ]),
+    };
+    c.quotation.preprocess(&mut quotation, &p.user1, true, p.person1.id);
+    assert_eq!(quotation.status, QuotationStatus::Draft);
+    assert_eq!(quotation.revision, 0);
+    assert_eq!(quotation.order_id, 0);
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let quotation = c.quotation.add(quotation, tx.as_mut()).await.unwrap();
+    let id = quotation.id;
+    assert_eq!(quotation.total_amount, 40.0);
+    assert!(c.person.is_depend_by_another(p.person2.id, tx.as_mut()).await.unwrap());
+
+    // Revising the sent quotation makes it a draft again.
+    let quotation = c.quotation.set_status(id, QuotationStatus::Sent, p.user1.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(quotation.status, QuotationStatus::Sent);
+    let mut revised = quotation.clone();
+    revised.items.as_mut().unwrap()[0].price = 3.0;
+    c.quotation.preprocess(&mut revised, &p.user1, false, p.person1.id);
+    let quotation = c.quotation.update(id, revised, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(quotation.status, QuotationStatus::Draft);
+    assert_eq!(quotation.revision, 1);
+    assert_eq!(quotation.total_amount, 50.0);
+
+    // Nothing is in the warehouse, the quotation has no effect on the inventory.
+    let order = quotation.to_order();
+    assert_eq!(order.order_type, OrderType::StockOut);
+    assert!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().is_none());
+    let r = order_module::check(&order, false, now, tx.as_mut()).await.unwrap();
+    assert_eq!(r.items_not_available.len(), 2);
+
+    // Stock in the items, then the quotation can be converted.
+    let mut stock_in = quotation.to_order();
+    stock_in.order_type = OrderType::StockIn;
+    c.order.preprocess(&mut stock_in, &p.user1, true, p.person1.id);
+    c.order.add(stock_in, true, tx.as_mut()).await.unwrap();
+    let mut order = quotation.to_order();
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.id);
+    assert!(order_module::check(&order, false, now, tx.as_mut()).await.unwrap().items_not_available.is_empty());
+    // The quantity reserved by the quotation can be taken by the converted order only.
+    let reservation = Reservation {
+        id: 0,
+        created_by_user_id: p.user1.id,
+        date: now,
+        warehouse_id: p.warehouse1.id,
+        sku_id: p.sku1.id,
+        quantity: 10,
+        guest_order_id: 0,
+        quotation_id: id,
+        expires_at: None,
+        description: "".to_owned(),
+    };
+    c.inventory.add_reservation(reservation, tx.as_mut()).await.unwrap();
+    assert_eq!(order_module::check(&order, false, now, tx.as_mut()).await.unwrap().items_not_available.len(), 1);
+    assert!(order_module::check_with_quotation(&order, id, false, now, tx.as_mut()).await.unwrap().items_not_available.is_empty());
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(order.total_amount, quotation.total_amount);
+    assert_eq!(c.inventory.release_reservations(0, id, tx.as_mut()).await.unwrap().len(), 1);
+    let quotation = c.quotation.convert(id, order.id, p.user1.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(quotation.status, QuotationStatus::Converted);
+    assert_eq!(quotation.order_id, order.id);
+    assert_eq!(c.inventory.get(p.warehouse1.id, p.sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 0);
+
+    // The quotation passed the valid date is expired when it is read.
+    let mut expiring = quotation.clone();
+    c.quotation.preprocess(&mut expiring, &p.user1, true, p.person1.id);
+    expiring.valid_until = now - 1;
+    // The default tax rates are applied to the total as the converted order.
+    let rate = TaxRate {
+        id: 0,
+        name: "VAT".to_owned(),
+        rate: 10.0,
+        description: "".to_owned(),
+    };
+    let rate = c.tax.add(rate, tx.as_mut()).await.unwrap();
+    let sku1 = SKU { tax_rate_id: rate.id, ..p.sku1 };
+    c.sku.update(sku1.id, sku1, tx.as_mut()).await.unwrap().unwrap();
+    let expiring = c.quotation.add(expiring, tx.as_mut()).await.unwrap();
+    assert_eq!(expiring.status, QuotationStatus::Draft);
+    assert_eq!(expiring.total_amount, 30.0 * 1.1 + 20.0);
+    assert_eq!(c.quotation.get(expiring.id, tx.as_mut()).await.unwrap().unwrap().status, QuotationStatus::Expired);
+    // Reading doesn't write the status.
+    let status: String = sqlx::query_scalar("SELECT status FROM quotations WHERE id=?").bind(expiring.id).fetch_one(tx.as_mut()).await.unwrap();
+    assert_eq!(status, "Draft");
+    // The converted quotation is never expired.
+    assert_eq!(c.quotation.get(id, tx.as_mut()).await.unwrap().unwrap().status, QuotationStatus::Converted);
+
+    let q = GetQuotationsQuery {
+        status: Some(QuotationStatus::Expired),
+        ..Default::default()
+    };
+    assert_eq!(c.quotation.get_count(&q, ActionType::System, tx.as_mut()).await.unwrap(), 1);
+    let quotations = c.quotation.get_multiple(&Pagination::max(), &q, ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(quotations[0].id, expiring.id);
+
+    assert!(c.quotation.get_document(id, true, tx.as_mut()).await.unwrap().unwrap().is_file());
+    assert!(c.quotation.remove(expiring.id, true, tx.as_mut()).await.unwrap());
+    assert!(c.quotation.get(expiring.id, tx.as_mut()).await.unwrap().is_none());
+    assert!(c.quotation.get_document(expiring.id, true, tx.as_mut()).await.unwrap().is_none());
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use elerp_common::{
+    import::{IdCache, ImportTable},
+    model::{export::ExportFormat, Pagination},
+    order_module::model::order::OrderItem,
+    sku_module::model::sku::{is_valid_barcode, GetSKUsQuery, SKU},
+};
+
+#[test]
+fn test_barcode() {
+    assert!(is_valid_barcode("4006381333931"));
+    assert!(is_valid_barcode("036000291452"));
+    assert!(is_valid_barcode("96385074"));
+    assert!(is_valid_barcode("10012345678902"));
+    assert!(!is_valid_barcode("4006381333932"));
+    assert!(!is_valid_barcode("400638133393"));
+    assert!(!is_valid_barcode("40063813339a1"));
+    assert!(!is_valid_barcode(""));
+
+    let v: OrderItem = serde_json::from_str(r#"{"sku_code":"4006381333931","quantity":2}"#).unwrap();
+    assert_eq!((v.sku_id, v.sku_code.as_deref()), (0, Some("4006381333931")));
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let sku = SKU {
+        id: 0,
+        sku_category_id: p.sku_category1.id,
+        name: "SKU 3".to_owned(),
+        description: "".to_owned(),
+        color: None,
+        text_color: None,
+        serialized: false,
+        tax_rate_id: 0,
+        code: "A-001".to_owned(),
+        unit: "kg".to_owned(),
+        barcodes: vec!["4006381333931".to_owned(), "96385074".to_owned()],
+        units: vec![],
+        template_id: 0,
+        attributes: Default::default(),
+    };
+    let sku = c.sku.add(sku, true, tx.as_mut()).await.unwrap();
+    let v = c.sku.get(sku.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!((v.code.as_str(), v.unit.as_str()), ("A-001", "kg"));
+    assert_eq!(v.barcodes, vec!["4006381333931", "96385074"]);
+
+    let q = GetSKUsQuery {
+        id: None,
+        sku_category_id: None,
+        name: None,
+        code: Some("A-001".to_owned()),
+        template_id: None,
+        attributes: None,
+        deleted: None,
+        sorters: None,
+    };
+    let list = c.sku.get_multiple(&Pagination::max(), &q, tx.as_mut()).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].barcodes, vec!["4006381333931", "96385074"]);
+
+    // The barcodes are matched before the codes.
+    assert_eq!(c.sku.get_id_by_code("96385074", tx.as_mut()).await.unwrap(), Some(sku.id));
+    assert_eq!(c.sku.get_id_by_code("A-001", tx.as_mut()).await.unwrap(), Some(sku.id));
+    assert_eq!(c.sku.get_id_by_code("036000291452", tx.as_mut()).await.unwrap(), None);
+    assert_eq!(c.sku.get_id_by_code("", tx.as_mut()).await.unwrap(), None);
+
+    assert!(c.sku.is_exists_code("A-001", None, tx.as_mut()).await.unwrap());
+    assert!(!c.sku.is_exists_code("A-001", Some(sku.id), tx.as_mut()).await.unwrap());
+    let barcodes = vec!["036000291452".to_owned(), "96385074".to_owned()];
+    assert_eq!(c.sku.get_exists_barcode(&barcodes, Some(p.sku1.id), tx.as_mut()).await.unwrap(), Some("96385074"));
+    assert_eq!(c.sku.get_exists_barcode(&barcodes, Some(sku.id), tx.as_mut()).await.unwrap(), None);
+
+    // The skus of the imported orders are found by the codes too.
+    let table = ImportTable::read(ExportFormat::Csv, b"SKU,Quantity\n96385074,1\nA-001,2\n").unwrap();
+    for row in table.rows() {
+        let item = c.order.parse_import_item(&row, &mut IdCache::new(), tx.as_mut()).await.unwrap();
+        assert_eq!(item.sku_id, sku.id);
+    }
+
+    // The barcodes are replaced as updating.
+    let v = SKU {
+        barcodes: vec!["036000291452".to_owned()],
+        ..v
+    };
+    c.sku.update(sku.id, v, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(c.sku.get(sku.id, tx.as_mut()).await.unwrap().unwrap().barcodes, vec!["036000291452"]);
+    assert_eq!(c.sku.get_id_by_code("96385074", tx.as_mut()).await.unwrap(), None);
+
+    // The removed sku is not matched and its barcodes can be used again.
+    c.sku.remove(sku.id, false, tx.as_mut()).await.unwrap();
+    assert_eq!(c.sku.get_id_by_code("036000291452", tx.as_mut()).await.unwrap(), None);
+    assert_eq!(c.sku.get_exists_barcode(&barcodes, None, tx.as_mut()).await.unwrap(), None);
+    assert!(!c.sku.is_exists_code("A-001", None, tx.as_mut()).await.unwrap());
+    tx.commit().await.unwrap();
+}
@@ -29,12 +29,32 @@ async fn test_module() {
                     quantity: 1000,
                     price: 18.5,
                     exchanged: false,
+                    lot_number: None,
+                    expiry_date: None,
+                    serial_numbers: vec![],
+                    tax_rate: None,
+                    tax_amount: 0.0,
+                    discount: None,
+                    discount_amount: 0.0,
+                    sku_code: None,
+                    unit: None,
+                    unit_factor: 1,
                 },
                 OrderItem {
                     sku_id: p.sku2.id,
                     quantity: 2500,
                     price: 10.0,
                     exchanged: false,
+                    lot_number: None,
+                    expiry_date: None,
+                    serial_numbers: vec![],
+                    tax_rate: None,
+                    tax_amount: 0.0,
+                    discount: None,
+                    discount_amount: 0.0,
+                    sku_code: None,
+                    unit: None,
+                    unit_factor: 1,
                 },
             ]),
             total_amount: 0.0,
@@ -46,9 +66,16 @@ async fn test_module() {
             order_type: OrderType::StockOut,
             is_record: true,
             non_payment: false,
+            to_warehouse_id: 0,
+            in_transit: false,
+            tax_inclusive: false,
+            net_amount: 0.0,
+            tax_amount: 0.0,
+            discount: None,
+            discount_amount: 0.0,
         };
         c.order.preprocess(&mut order, &p.user1, true, p.person2.id);
-        c.order.add(order, tx.as_mut()).await.unwrap();
+        c.order.add(order, true, tx.as_mut()).await.unwrap();
     }
     let q = GetOrdersQuery::empty();
     let total_count = c.statistical.get_total_count(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
@@ -0,0 +1,169 @@
+mod common;
+
+use elerp_common::{
+    model::{action_type::ActionType, Pagination},
+    order_module::model::order::{GetOrdersQuery, Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    order_payment_module::model::order_payment::OrderPayment,
+    sku_module::model::sku::SKU,
+    statistical_module::model::statistical_data::{GetTaxSummaryQuery, TaxSummaryPeriod},
+    tax_module::{self, model::tax_rate::TaxRate},
+};
+
+fn item(sku_id: i64, quantity: i64, price: f64, tax_rate: Option<f64>) -> OrderItem {
+    OrderItem {
+        sku_id,
+        quantity,
+        price,
+        exchanged: false,
+        lot_number: None,
+        expiry_date: None,
+        serial_numbers: vec![],
+        tax_rate,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: None,
+        unit_factor: 1,
+    }
+}
+
+#[test]
+fn test_calc_amounts() {
+    let mut items = vec![item(1, 1, 110.0, Some(10.0)), item(2, 3, 1.0, None)];
+    assert_eq!(tax_module::calc_amounts(&mut items, false), (113.0, 11.0, 124.0));
+    assert_eq!(items[0].tax_amount, 11.0);
+    assert_eq!(tax_module::calc_amounts(&mut items, true), (103.0, 10.0, 113.0));
+    assert_eq!(items[0].tax_amount, 10.0);
+    assert_eq!(items[1].tax_amount, 0.0);
+    items[0].exchanged = true;
+    assert_eq!(tax_module::calc_amounts(&mut items, true), (3.0, 0.0, 3.0));
+    assert_eq!(items[0].tax_amount, 0.0);
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let standard = TaxRate {
+        id: 0,
+        name: "VAT".to_owned(),
+        rate: 10.0,
+        description: "".to_owned(),
+    };
+    let standard = c.tax.add(standard, tx.as_mut()).await.unwrap();
+    let reduced = TaxRate {
+        id: 0,
+        name: "Reduced VAT".to_owned(),
+        rate: 5.0,
+        description: "".to_owned(),
+    };
+    let reduced = c.tax.add(reduced, tx.as_mut()).await.unwrap();
+    assert_eq!(c.tax.list(tx.as_mut()).await.unwrap().len(), 2);
+    assert!(!c.tax.is_depend_by_another(standard.id, tx.as_mut()).await.unwrap());
+
+    // The category rate is the default of its skus, the sku rate overrides it.
+    let mut category = p.sku_category1.clone();
+    category.tax_rate_id = standard.id;
+    c.sku_category.update(category.id, category, tx.as_mut()).await.unwrap().unwrap();
+    let sku2 = SKU { tax_rate_id: reduced.id, ..p.sku2 };
+    c.sku.update(sku2.id, sku2, tx.as_mut()).await.unwrap().unwrap();
+    assert!(c.tax.is_depend_by_another(standard.id, tx.as_mut()).await.unwrap());
+    assert_eq!(tax_module::get_default_rate(p.sku1.id, tx.as_mut()).await.unwrap(), 10.0);
+    assert_eq!(tax_module::get_default_rate(p.sku2.id, tx.as_mut()).await.unwrap(), 5.0);
+
+    let new_order = |items: Vec<OrderItem>, tax_inclusive: bool| Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Testing tax".to_owned(),
+        order_type: OrderType::StockOut,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+
+    // Tax exclusive, the line rate overrides the defaults.
+    let mut order = new_order(vec![item(p.sku1.id, 10, 10.0, None), item(p.sku2.id, 2, 50.0, None), item(p.sku1.id, 1, 20.0, Some(0.0))], false);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    let items = order.items.as_ref().unwrap();
+    assert_eq!(items.iter().map(|item| item.tax_rate).collect::<Vec<_>>(), vec![Some(10.0), Some(5.0), Some(0.0)]);
+    assert_eq!(items.iter().map(|item| item.tax_amount).collect::<Vec<_>>(), vec![10.0, 5.0, 0.0]);
+    assert_eq!((order.net_amount, order.tax_amount, order.total_amount), (220.0, 15.0, 235.0));
+    let got = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!((got.net_amount, got.tax_amount, got.total_amount), (220.0, 15.0, 235.0));
+
+    // The payments settle the gross amount.
+    let mut payment = OrderPayment {
+        id: 0,
+        created_by_user_id: 0,
+        order_id: order.id,
+        warehouse_id: 0,
+        person_in_charge_id: 0,
+        creation_date: 0,
+        actual_date: 0,
+        total_amount: 220.0,
+        remark: "".to_owned(),
+    };
+    c.order_payment.preprocess(&mut payment, &p.user1);
+    c.order_payment.add(payment.clone(), tx.as_mut()).await.unwrap();
+    let got = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(got.order_payment_status, OrderPaymentStatus::PartialSettled);
+    payment.total_amount = 15.0;
+    c.order_payment.add(payment, tx.as_mut()).await.unwrap();
+    let got = c.order.get(order.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(got.total_amount_settled, 235.0);
+    assert_eq!(got.order_payment_status, OrderPaymentStatus::Settled);
+
+    // Tax inclusive, the price contains the tax.
+    let mut order = new_order(vec![item(p.sku1.id, 1, 110.0, None)], true);
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!((order.net_amount, order.tax_amount, order.total_amount), (100.0, 10.0, 110.0));
+
+    // The orders added keep the rate of their items.
+    let mut standard = standard;
+    standard.rate = 20.0;
+    c.tax.update(standard.id, standard.clone(), tx.as_mut()).await.unwrap().unwrap();
+    let items = c.order.get_order_items(order.id, &Pagination::max(), tx.as_mut()).await.unwrap();
+    assert_eq!(items[0].tax_rate, Some(10.0));
+
+    let amounts = c.statistical.get_total_amount(&GetOrdersQuery::empty(), ActionType::Admin, tx.as_mut()).await.unwrap();
+    assert_eq!(amounts.len(), 1);
+    assert_eq!((amounts[0].any, amounts[0].tax), (345.0, 25.0));
+    assert_eq!(amounts[0].gross_margin, 320.0);
+
+    let q = GetTaxSummaryQuery {
+        date_start: None,
+        date_end: None,
+        warehouse_ids: None,
+        currency: None,
+        order_type: None,
+        period: Some(TaxSummaryPeriod::Year),
+    };
+    let summary = c.statistical.get_tax_summary(&q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    let summary = summary.iter().map(|v| (v.tax_rate, v.net_amount, v.tax_amount, v.gross_amount)).collect::<Vec<_>>();
+    assert_eq!(summary, vec![(0.0, 20.0, 0.0, 20.0), (5.0, 100.0, 5.0, 105.0), (10.0, 200.0, 20.0, 220.0)]);
+    tx.commit().await.unwrap();
+}
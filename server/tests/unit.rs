@@ -0,0 +1,138 @@
+mod common;
+
+use elerp_common::{
+    inventory_module::model::inventory::GetInventoryQuery,
+    model::{action_type::ActionType, Pagination},
+    order_module::model::order::{Order, OrderCurrency, OrderItem, OrderPaymentStatus, OrderType},
+    sku_module::model::sku::{SKUUnit, SKU},
+};
+
+fn item(sku_id: i64, quantity: i64, price: f64, unit: Option<&str>) -> OrderItem {
+    OrderItem {
+        sku_id,
+        quantity,
+        price,
+        exchanged: false,
+        lot_number: None,
+        expiry_date: None,
+        serial_numbers: vec![],
+        tax_rate: None,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+        sku_code: None,
+        unit: unit.map(|v| v.to_owned()),
+        unit_factor: 0,
+    }
+}
+
+#[test]
+fn test_unit_quantity() {
+    let mut v = item(1, 48, 10.0, Some("carton"));
+    v.unit_factor = 24;
+    assert_eq!((v.get_unit_quantity(), v.get_amount()), (2.0, 20.0));
+    v.quantity = 12;
+    assert_eq!((v.get_unit_quantity(), v.get_amount()), (0.5, 5.0));
+    let v: OrderItem = serde_json::from_str(r#"{"sku_id":1,"quantity":2,"unit":"carton"}"#).unwrap();
+    assert_eq!((v.unit.as_deref(), v.unit_factor), (Some("carton"), 0));
+}
+
+#[tokio::test]
+async fn test_module() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+
+    let sku1 = SKU {
+        unit: "pcs".to_owned(),
+        units: vec![
+            SKUUnit {
+                name: "carton".to_owned(),
+                factor: 24,
+            },
+            SKUUnit {
+                name: "box".to_owned(),
+                factor: 6,
+            },
+        ],
+        ..p.sku1
+    };
+    c.sku.update(sku1.id, sku1, tx.as_mut()).await.unwrap().unwrap();
+    let sku1 = c.sku.get(p.sku1.id, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(sku1.units.len(), 2);
+    assert_eq!((sku1.get_unit_factor("carton"), sku1.get_unit_factor("pcs"), sku1.get_unit_factor("")), (Some(24), Some(1), Some(1)));
+    assert_eq!(sku1.get_unit_factor("pallet"), None);
+
+    // The quantities are converted to the base units only once.
+    let mut items = vec![item(sku1.id, 2, 100.0, Some("carton")), item(sku1.id, 5, 5.0, Some("pcs")), item(p.sku2.id, 3, 1.0, None)];
+    assert_eq!(c.sku.fill_units(&mut items, tx.as_mut()).await.unwrap(), None);
+    assert_eq!(items.iter().map(|v| (v.quantity, v.unit_factor)).collect::<Vec<_>>(), vec![(48, 24), (5, 1), (3, 1)]);
+    assert_eq!(c.sku.fill_units(&mut items, tx.as_mut()).await.unwrap(), None);
+    assert_eq!(items[0].quantity, 48);
+    let mut wrong = vec![item(p.sku2.id, 1, 1.0, Some("carton"))];
+    assert_eq!(c.sku.fill_units(&mut wrong, tx.as_mut()).await.unwrap(), Some((p.sku2.id, "carton".to_owned())));
+
+    // The factors sent are never trusted, they must be the factors of the skus.
+    let mut forged = vec![item(sku1.id, 1, 1.0, None)];
+    forged[0].unit_factor = 1000;
+    assert_eq!(c.sku.fill_units(&mut forged, tx.as_mut()).await.unwrap(), Some((sku1.id, "".to_owned())));
+    let mut forged = vec![item(sku1.id, 1, 1.0, Some("carton"))];
+    forged[0].unit_factor = 6;
+    assert_eq!(c.sku.fill_units(&mut forged, tx.as_mut()).await.unwrap(), Some((sku1.id, "carton".to_owned())));
+    assert_eq!(forged[0].quantity, 1);
+
+    // The cartons are stocked in as the pieces.
+    let mut order = Order {
+        id: 0,
+        created_by_user_id: 0,
+        updated_by_user_id: 0,
+        date: 0,
+        last_updated_date: 0,
+        person_in_charge_id: 0,
+        order_category_id: p.order_category1.id,
+        from_guest_order_id: 0,
+        currency: OrderCurrency::USD,
+        items: Some(items),
+        total_amount: 0.0,
+        total_amount_settled: 0.0,
+        order_payment_status: OrderPaymentStatus::None,
+        warehouse_id: p.warehouse1.id,
+        person_related_id: p.person1.id,
+        description: "Buy by the carton".to_owned(),
+        order_type: OrderType::StockIn,
+        is_record: false,
+        non_payment: false,
+        to_warehouse_id: 0,
+        in_transit: false,
+        tax_inclusive: false,
+        net_amount: 0.0,
+        tax_amount: 0.0,
+        discount: None,
+        discount_amount: 0.0,
+    };
+    c.order.preprocess(&mut order, &p.user1, true, p.person1.person_in_charge_id);
+    let order = c.order.add(order, true, tx.as_mut()).await.unwrap();
+    assert_eq!(order.total_amount, 228.0);
+    let items = c.order.get_order_items(order.id, &Pagination::max(), tx.as_mut()).await.unwrap();
+    assert_eq!((items[0].quantity, items[0].unit.as_deref(), items[0].unit_factor, items[0].price), (48, Some("carton"), 24, 100.0));
+    assert_eq!(c.inventory.get(p.warehouse1.id, sku1.id, tx.as_mut()).await.unwrap().unwrap().quantity, 53);
+
+    // The inventory is shown in the unit of the skus defining it.
+    let q = GetInventoryQuery {
+        warehouse_ids: Some([p.warehouse1.id].into_iter().collect()),
+        sku_ids: None,
+        sku_category_ids: None,
+        template_ids: None,
+        attributes: None,
+        quantity_start: None,
+        quantity_end: None,
+        unit: Some("box".to_owned()),
+        sorters: None,
+    };
+    let list = c.inventory.list(&Pagination::max(), &q, ActionType::Admin, tx.as_mut()).await.unwrap();
+    let v = list.iter().find(|v| v.sku_id == sku1.id).unwrap();
+    assert_eq!((v.unit.as_str(), v.unit_quantity), ("box", 53.0 / 6.0));
+    let v = list.iter().find(|v| v.sku_id == p.sku2.id).unwrap();
+    assert_eq!((v.unit.as_str(), v.unit_quantity), ("", 3.0));
+    tx.commit().await.unwrap();
+}
@@ -1,4 +1,12 @@
-use elerp_common::model::action_type::ActionType;
+use elerp_common::{
+    model::{action_type::ActionType, Pagination},
+    user_system::model::{
+        role::Role,
+        user_info::GetUsersQuery,
+        user_permission::{Permission, ADD_ORDER, MANAGE_AREA},
+    },
+};
+use user_system::UserSystem;
 
 mod common;
 
@@ -8,12 +16,13 @@ async fn test_module() {
     let p = common::prelude(&c).await;
 
     let mut tx = c.ps.begin_tx(true).await.unwrap();
-    let token = c.us.get_token(&p.user1, tx.as_mut()).await.unwrap();
+    let token = c.us.get_token(&p.user1, tx.as_mut()).await.unwrap().token;
     let user1 = c.us.token_to_user(&token, ActionType::System, tx.as_mut()).await.unwrap();
     assert!(user1.is_some());
-    let new_token = c.us.get_token(&p.user1, tx.as_mut()).await.unwrap();
+    let new_token = c.us.get_token(&p.user1, tx.as_mut()).await.unwrap().token;
     let user1 = c.us.token_to_user(&token, ActionType::System, tx.as_mut()).await.unwrap();
-    assert!(user1.is_none(), "Old token is expected inactive.");
+    assert!(user1.is_some(), "Session of other login is expected kept.");
+    assert_eq!(c.us.get_sessions(p.user1.id, tx.as_mut()).await.unwrap().len(), 2);
     let user1 = c.us.token_to_user(&new_token, ActionType::System, tx.as_mut()).await.unwrap();
     assert!(user1.is_some());
 
@@ -21,14 +30,179 @@ async fn test_module() {
     assert_eq!(user1.id, p.user1.id);
 
     assert!(!user1.is_connected, "User is expected disconnected!");
-    assert!(c.us.try_connect_socket(&user1, tx.as_mut()).await.unwrap());
+    assert!(c.us.try_connect_socket(&user1, &new_token, tx.as_mut()).await.unwrap());
 
     let user1 = c.us.get_user(user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
     assert!(user1.is_connected, "User is expected connected!");
+    // Each session connects its own socket.
+    assert!(!c.us.try_connect_socket(&user1, &new_token, tx.as_mut()).await.unwrap());
+    assert!(c.us.is_socket_connected(&new_token, tx.as_mut()).await.unwrap());
+    assert!(!c.us.is_socket_connected(&token, tx.as_mut()).await.unwrap());
+    assert!(c.us.try_connect_socket(&user1, &token, tx.as_mut()).await.unwrap());
+    assert!(c.us.disconnect_socket(&user1, &token, tx.as_mut()).await.unwrap());
+    let users = c.us.get_users(&Pagination::max(), &GetUsersQuery::emptpy(), ActionType::System, tx.as_mut()).await.unwrap();
+    assert_eq!(users.iter().filter(|v| v.id == user1.id).count(), 1, "User of several sessions is expected listed once.");
 
-    assert!(c.us.disconnect_socket(&user1, tx.as_mut()).await.unwrap());
+    assert!(c.us.disconnect_socket(&user1, &new_token, tx.as_mut()).await.unwrap());
     assert!(
         !c.us.get_user(user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap().is_connected,
         "User is expected disconnected!"
     );
 }
+
+#[tokio::test]
+async fn test_password() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let user1 = c.us.get_user_by_username(&p.user1.username, tx.as_mut()).await.unwrap().unwrap();
+    assert!(user1.password.starts_with("$argon2id"), "Password is expected stored as hash.");
+    assert!(c.us.verify_password(&user1, "tester", tx.as_mut()).await.unwrap());
+    assert!(!c.us.verify_password(&user1, "wrong password", tx.as_mut()).await.unwrap());
+
+    let user = c.us.get_user(user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert!(user.password.is_empty(), "Password hash is expected never returned.");
+    let json = serde_json::to_value(&user1).unwrap();
+    assert!(json.get("password").is_none(), "Password is expected skipped when serializing.");
+
+    // plaintext password from old version will upgrade after login
+    sqlx::query("UPDATE users SET password='tester' WHERE id=?").bind(user1.id).execute(tx.as_mut()).await.unwrap();
+    let user1 = c.us.get_user_by_username(&p.user1.username, tx.as_mut()).await.unwrap().unwrap();
+    assert!(!c.us.verify_password(&user1, "wrong password", tx.as_mut()).await.unwrap());
+    assert!(c.us.verify_password(&user1, "tester", tx.as_mut()).await.unwrap());
+    let user1 = c.us.get_user_by_username(&p.user1.username, tx.as_mut()).await.unwrap().unwrap();
+    assert!(user1.password.starts_with("$argon2id"), "Plaintext password is expected upgraded to hash.");
+    assert!(c.us.verify_password(&user1, "tester", tx.as_mut()).await.unwrap());
+
+    // plaintext password looks like the hash prefix is still plaintext.
+    sqlx::query("UPDATE users SET password='$argon2tester' WHERE id=?").bind(user1.id).execute(tx.as_mut()).await.unwrap();
+    let user1 = c.us.get_user_by_username(&p.user1.username, tx.as_mut()).await.unwrap().unwrap();
+    assert!(c.us.verify_password(&user1, "$argon2tester", tx.as_mut()).await.unwrap());
+    let user1 = c.us.get_user_by_username(&p.user1.username, tx.as_mut()).await.unwrap().unwrap();
+    assert!(user1.password.starts_with("$argon2id"), "Plaintext password is expected upgraded to hash.");
+}
+
+#[tokio::test]
+async fn test_token() {
+    let c = common::init_ctx().await;
+    let p = common::prelude(&c).await;
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let token = c.us.get_token(&p.user1, tx.as_mut()).await.unwrap();
+    assert_eq!(token.token.len(), 64);
+    assert_ne!(token.token, token.refresh_token);
+    let stored: String = sqlx::query_scalar("SELECT token FROM tokens WHERE id=?").bind(token.session_id).fetch_one(tx.as_mut()).await.unwrap();
+    assert_ne!(stored, token.token, "Token is expected stored as hash.");
+
+    // refresh token can't be used as token.
+    assert!(c.us.token_to_user(&token.refresh_token, ActionType::System, tx.as_mut()).await.unwrap().is_none());
+
+    let refreshed = c.us.refresh_token(&token.refresh_token, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(refreshed.session_id, token.session_id);
+    assert!(c.us.token_to_user(&token.token, ActionType::System, tx.as_mut()).await.unwrap().is_none(), "Old token is expected inactive.");
+    assert!(c.us.refresh_token(&token.refresh_token, tx.as_mut()).await.unwrap().is_none(), "Old refresh token is expected inactive.");
+    assert!(c.us.token_to_user(&refreshed.token, ActionType::System, tx.as_mut()).await.unwrap().is_some());
+
+    sqlx::query("UPDATE tokens SET expires_at=0 WHERE id=?").bind(token.session_id).execute(tx.as_mut()).await.unwrap();
+    assert!(c.us.token_to_user(&refreshed.token, ActionType::System, tx.as_mut()).await.unwrap().is_none(), "Expired token is expected inactive.");
+    let refreshed = c.us.refresh_token(&refreshed.refresh_token, tx.as_mut()).await.unwrap().unwrap();
+    assert!(c.us.token_to_user(&refreshed.token, ActionType::System, tx.as_mut()).await.unwrap().is_some());
+
+    let sessions = c.us.get_sessions(p.user1.id, tx.as_mut()).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, token.session_id);
+    assert!(!c.us.remove_session(p.user2.id, token.session_id, tx.as_mut()).await.unwrap(), "Session of other user is expected not removed.");
+    assert!(c.us.remove_session(p.user1.id, token.session_id, tx.as_mut()).await.unwrap());
+    assert!(c.us.get_sessions(p.user1.id, tx.as_mut()).await.unwrap().is_empty());
+    assert!(c.us.token_to_user(&refreshed.token, ActionType::System, tx.as_mut()).await.unwrap().is_none(), "Revoked token is expected inactive.");
+}
+
+#[tokio::test]
+async fn test_role() {
+    let c = common::init_ctx().await;
+    let mut p = common::prelude(&c).await;
+
+    // The general users added without roles can view all data.
+    assert_eq!(p.user2.permissions, Permission::views());
+
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let role1 = c
+        .us
+        .add_role(
+            Role {
+                id: 0,
+                name: "Order viewer".to_owned(),
+                description: String::new(),
+                permissions: vec![Permission::ViewOrder, Permission::ViewSKU, Permission::ViewOrder],
+            },
+            tx.as_mut(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(role1.permissions, vec![Permission::ViewSKU, Permission::ViewOrder], "Permissions are expected deduplicated.");
+    let role2 = c
+        .us
+        .add_role(
+            Role {
+                id: 0,
+                name: "Order creator".to_owned(),
+                description: String::new(),
+                permissions: vec![Permission::AddOrder, Permission::ViewSKU],
+            },
+            tx.as_mut(),
+        )
+        .await
+        .unwrap();
+    assert!(c.us.is_exists_role_name("Order viewer", None, tx.as_mut()).await.unwrap());
+    assert!(!c.us.is_exists_role_name("Order viewer", Some(role1.id), tx.as_mut()).await.unwrap());
+
+    p.user1.roles = Some(vec![role1.id, role2.id]);
+    let user1 = c.us.update_user(p.user1.id, p.user1.clone(), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user1.permissions, vec![Permission::AddOrder, Permission::ViewSKU, Permission::ViewOrder]);
+    let user1 = c.us.get_user(user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user1.roles, Some(vec![role1.id, role2.id]));
+    assert_eq!(user1.permissions.len(), 3);
+    // The roles are kept when the update is without roles.
+    p.user1.roles = None;
+    p.user1.alias = "Tester".to_owned();
+    let user1 = c.us.update_user(p.user1.id, p.user1.clone(), tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user1.roles, Some(vec![role1.id, role2.id]));
+    assert_eq!(user1.permissions.len(), 3);
+    let user1 = c.us.get_user(user1.id, ActionType::General(p.user2.id), tx.as_mut()).await.unwrap().unwrap();
+    assert!(user1.roles.is_none() && user1.permissions.is_empty(), "Roles are expected hidden for general user.");
+
+    let mut role2 = c.us.get_role(role2.id, tx.as_mut()).await.unwrap().unwrap();
+    role2.permissions = vec![Permission::UpdateRemoveOrder];
+    c.us.update_role(role2.id, role2.clone(), tx.as_mut()).await.unwrap().unwrap();
+    let user1 = c.us.get_user(p.user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user1.permissions, vec![Permission::UpdateRemoveOrder, Permission::ViewSKU, Permission::ViewOrder]);
+
+    assert!(c.us.remove_role(role1.id, tx.as_mut()).await.unwrap());
+    assert!(c.us.get_role(role1.id, tx.as_mut()).await.unwrap().is_none());
+    let user1 = c.us.get_user(p.user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user1.roles, Some(vec![role2.id]));
+    assert_eq!(user1.permissions, vec![Permission::UpdateRemoveOrder]);
+
+    // preset roles created when the role tables initialized.
+    assert_eq!(c.us.get_roles_count(tx.as_mut()).await.unwrap(), 14);
+    assert_eq!(c.us.get_roles(&Pagination::max(), tx.as_mut()).await.unwrap().len(), 14);
+
+    // legacy permission bitmask will migrate to roles.
+    sqlx::query("DROP TABLE roles").execute(tx.as_mut()).await.unwrap();
+    sqlx::query("DROP TABLE role_permissions").execute(tx.as_mut()).await.unwrap();
+    sqlx::query("DROP TABLE user_roles").execute(tx.as_mut()).await.unwrap();
+    sqlx::query("UPDATE users SET permission=? WHERE id=?").bind(MANAGE_AREA + ADD_ORDER).bind(p.user2.id).execute(tx.as_mut()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let us = UserSystem::new(c.ps.clone()).await;
+    let mut tx = c.ps.begin_tx(true).await.unwrap();
+    let user1 = us.get_user(p.user1.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user1.permissions, Permission::views());
+    let user2 = us.get_user(p.user2.id, ActionType::System, tx.as_mut()).await.unwrap().unwrap();
+    assert_eq!(user2.roles.as_ref().unwrap().len(), 3);
+    assert!(user2.permissions.contains(&Permission::ManageArea));
+    assert!(user2.permissions.contains(&Permission::AddOrder));
+    assert!(!user2.permissions.contains(&Permission::ManageSKU));
+    assert!(user2.permissions.contains(&Permission::ViewOrderPrice));
+}
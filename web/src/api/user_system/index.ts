@@ -6,6 +6,8 @@ import {
   GetTokenQuery,
   AuthenticatedUser,
   UserConfigure,
+  Role,
+  GetRolesQuery,
 } from "./models";
 
 export async function add_user(v: UserInfo): Promise<UserInfo> {
@@ -65,3 +67,10 @@ export async function update_user_configure(id: number, v: UserConfigure): Promi
   const resp = await web.put(`/us/users_configure/${id}`, v);
   return resp.data;
 }
+
+export async function get_roles(q: GetRolesQuery): Promise<ListSlice<Role>> {
+  const resp = await web.get(`/us/roles`, {
+    params: q,
+  });
+  return resp.data;
+}
@@ -11,10 +11,25 @@ export interface UserInfo {
   username: string;
   password: string;
   user_type: UserType;
-  permission: UserPermission;
+  // The roles are kept if it is not sent when update the user.
+  roles?: number[];
+  // Granted by the roles, it is ignored when add or update the user.
+  permissions: Permission[];
   is_connected: boolean;
 }
 
+export interface Role {
+  id: number;
+  name: string;
+  description: string;
+  permissions: Permission[];
+}
+
+export interface GetRolesQuery {
+  index: number;
+  limit: number;
+}
+
 export interface UserConfigureDefaults {
   order_type: OrderType;
   order_category_id: number;
@@ -48,16 +63,26 @@ export interface GetTokenQuery {
   password: string;
 }
 
-export enum UserPermission {
-  EMPTY = 0,
-  MANAGE_AREA = 1,
-  MANAGE_PERSON = 2,
-  MANAGE_WAREHOUSE = 4,
-  MANAGE_SKU = 8,
-  MANAGE_SKU_CATEGORY = 16,
-  ADD_ORDER = 32,
-  UPDATE_REMOVE_ORDER = 64,
-  MANAGE_ORDER_CATEGORY = 128,
-  ADD_ORDER_PAYMENT = 256,
-  UPDATE_REMOVE_ORDER_PAYMENT = 512,
+export enum Permission {
+  ManageArea = "ManageArea",
+  ManagePerson = "ManagePerson",
+  ManageWarehouse = "ManageWarehouse",
+  ManageSKU = "ManageSKU",
+  ManageSKUCategory = "ManageSKUCategory",
+  ManageOrderCategory = "ManageOrderCategory",
+  AddOrder = "AddOrder",
+  UpdateRemoveOrder = "UpdateRemoveOrder",
+  AddOrderPayment = "AddOrderPayment",
+  UpdateRemoveOrderPayment = "UpdateRemoveOrderPayment",
+  ViewArea = "ViewArea",
+  ViewPerson = "ViewPerson",
+  ViewWarehouse = "ViewWarehouse",
+  ViewSKU = "ViewSKU",
+  ViewSKUCategory = "ViewSKUCategory",
+  ViewOrderCategory = "ViewOrderCategory",
+  ViewOrder = "ViewOrder",
+  ViewOrderPrice = "ViewOrderPrice",
+  ViewOrderPayment = "ViewOrderPayment",
+  ViewInventory = "ViewInventory",
+  ViewStatistics = "ViewStatistics",
 }
@@ -79,8 +79,8 @@ export function getTitleByFormRow(row: FormRow): string {
       return t("common.orderItems");
     case "user_type":
       return t("common.userType");
-    case "permission":
-      return t("common.userPermission");
+    case "roles":
+      return t("common.userRoles");
     case "currency":
       return t("common.currency");
     case "color":
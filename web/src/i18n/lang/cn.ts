@@ -58,6 +58,7 @@ export default {
     result: "总结",
     simpleResult: "简要总结",
     userPermission: "用户权限",
+    userRoles: "用户角色",
     quantity: "数量",
     price: "单价",
     orderItem: "商品",
@@ -129,6 +130,17 @@ export default {
     updateRemoveOrder: "编辑和删除订单",
     addOrderPayment: "添加回款单",
     updateRemoveOrderPayment: "编辑和删除回款单",
+    viewArea: "查看区域",
+    viewPerson: "查看人员",
+    viewWarehouse: "查看仓库",
+    viewSKU: "查看SKU",
+    viewSKUCategory: "查看SKU类别",
+    viewOrderCategory: "查看订单类别",
+    viewOrder: "查看订单",
+    viewOrderPrice: "查看订单价格",
+    viewOrderPayment: "查看回款单",
+    viewInventory: "查看库存",
+    viewStatistics: "查看统计",
   },
   action: {
     confirm: "确认",
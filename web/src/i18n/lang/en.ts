@@ -58,6 +58,7 @@ export default {
     result: "Summary",
     simpleResult: "Brief summary",
     userPermission: "User Permission",
+    userRoles: "User Roles",
     quantity: "Quantity",
     price: "Price",
     orderItem: "Item",
@@ -129,6 +130,17 @@ export default {
     updateRemoveOrder: "Update and remove Order",
     addOrderPayment: "Add Order's Payment",
     updateRemoveOrderPayment: "Update and remove Order's Payment",
+    viewArea: "View Area",
+    viewPerson: "View Person",
+    viewWarehouse: "View Warehouse",
+    viewSKU: "View SKU",
+    viewSKUCategory: "View SKU Category",
+    viewOrderCategory: "View Order Category",
+    viewOrder: "View Order",
+    viewOrderPrice: "View Order's Price",
+    viewOrderPayment: "View Order's Payment",
+    viewInventory: "View Inventory",
+    viewStatistics: "View Statistics",
   },
   action: {
     confirm: "Confirm",
@@ -58,6 +58,7 @@ export default {
     result: "Ringkasan",
     simpleResult: "Ringkasan ringkas",
     userPermission: "Kebenaran pengguna",
+    userRoles: "Peranan pengguna",
     quantity: "Kuantiti",
     price: "Harga",
     orderItem: "Item pesanan",
@@ -129,6 +130,17 @@ export default {
     updateRemoveOrder: "Kemaskini dan Padam Pesanan",
     addOrderPayment: "Tambah Bayaran Pesanan",
     updateRemoveOrderPayment: "Kemaskini dan Padam Bayaran Pesanan",
+    viewArea: "Lihat Kawasan",
+    viewPerson: "Lihat Individu",
+    viewWarehouse: "Lihat Gudang",
+    viewSKU: "Lihat SKU",
+    viewSKUCategory: "Lihat Kategori SKU",
+    viewOrderCategory: "Lihat Kategori Pesanan",
+    viewOrder: "Lihat Pesanan",
+    viewOrderPrice: "Lihat Harga Pesanan",
+    viewOrderPayment: "Lihat Bayaran Pesanan",
+    viewInventory: "Lihat Inventori",
+    viewStatistics: "Lihat Statistik",
   },
   action: {
     confirm: "Sahkan",
@@ -1,5 +1,5 @@
 import { DefineComponent } from "vue";
-import { UserType } from "./api/user_system/models";
+import { Permission, UserType } from "./api/user_system/models";
 import {
   GuestOrderStatus,
   OrderCurrency,
@@ -173,6 +173,12 @@ export function getUserTypeText(u: UserType) {
   }
 }
 
+export function getPermissionText(p: Permission) {
+  const { t } = i18n.global;
+  // The keys of userPermission are the permissions in camel case.
+  return t(`userPermission.${p.charAt(0).toLowerCase()}${p.slice(1)}`);
+}
+
 export function getOrderCurrencyText(t: OrderCurrency) {
   return OrderCurrency[t] as string;
 }